@@ -0,0 +1,33 @@
+use std::env;
+use std::path::Path;
+
+fn main() {
+    let device = env::args().nth(1).unwrap_or_else(|| "/dev/video0".to_owned());
+
+    let mut camera = match gphoto::Camera::autodetect() {
+        Ok(c) => c,
+        Err(err) => panic!("error opening camera: {}", err),
+    };
+
+    let mut preview = match gphoto::FileMedia::create_mem() {
+        Ok(p) => p,
+        Err(err) => panic!("error allocating preview: {}", err),
+    };
+
+    let mut sink = match gphoto::V4l2Sink::open(Path::new(&device), 1024, 680) {
+        Ok(s) => s,
+        Err(err) => panic!("error opening {}: {}", device, err),
+    };
+
+    println!("streaming live view to {} ...", device);
+
+    loop {
+        if let Err(err) = camera.capture_preview(&mut preview) {
+            panic!("error capturing preview: {}", err);
+        }
+
+        if let Err(err) = sink.write_frame(&preview.get_data()) {
+            panic!("error writing frame: {}", err);
+        }
+    }
+}
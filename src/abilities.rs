@@ -13,8 +13,7 @@ use cstr_core::CStr;
 /// An `Abilities` object can be used to retrieve information about a camera's driver:
 ///
 /// ```no_run
-/// let mut context = gphoto::Context::new().unwrap();
-/// let mut camera = gphoto::Camera::autodetect(&mut context).unwrap();
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
 /// let abilities = camera.abilities();
 ///
 /// println!("      device type = {:?}", abilities.device_type());
@@ -22,6 +21,7 @@ use cstr_core::CStr;
 /// println!("    driver status = {:?}", abilities.driver_status());
 /// println!("       port types = {:?}", abilities.port_types());
 /// println!("           speeds = {:?}", abilities.speeds());
+/// println!("           serial = {:?}", abilities.supports_serial());
 /// println!("camera operations = {:?}", abilities.camera_operations());
 /// println!("  file operations = {:?}", abilities.file_operations());
 /// println!("folder operations = {:?}", abilities.folder_operations());
@@ -40,6 +40,7 @@ use cstr_core::CStr;
 ///     driver status = Production
 ///        port types = {USB}
 ///            speeds = []
+///            serial = false
 /// camera operations = {CaptureImage, TriggerCapture, Config, CapturePreview}
 ///   file operations = {Delete, Preview}
 /// folder operations = {MakeDirectory, RemoveDirectory, PutFile}
@@ -53,6 +54,12 @@ pub struct Abilities {
     inner: crate::gphoto2::CameraAbilities,
 }
 
+impl Clone for Abilities {
+    fn clone(&self) -> Self {
+        from_libgphoto2(to_libgphoto2(self))
+    }
+}
+
 impl Abilities {
     /// Returns the type of the device.
     pub fn device_type(&self) -> DeviceType {
@@ -67,6 +74,15 @@ impl Abilities {
         unsafe { String::from_utf8_lossy(CStr::from_ptr(self.inner.model.as_ptr()).to_bytes()) }
     }
 
+    /// Returns the vendor of the camera's model, i.e., the first word of the model's name, e.g.,
+    /// `"Nikon"` for `"Nikon DSC D750"`.
+    pub fn vendor(&self) -> Cow<'_, str> {
+        match self.model() {
+            Cow::Borrowed(model) => Cow::Borrowed(model.split(' ').next().unwrap_or(model)),
+            Cow::Owned(model) => Cow::Owned(model.split(' ').next().unwrap_or(&model).to_owned()),
+        }
+    }
+
     /// Returns the driver's stability status.
     pub fn driver_status(&self) -> DriverStatus {
         match self.inner.status {
@@ -118,6 +134,29 @@ impl Abilities {
             .collect()
     }
 
+    /// Returns `true` if the device can be connected through a serial port.
+    pub fn supports_serial(&self) -> bool {
+        self.inner.port & crate::gphoto2::GP_PORT_SERIAL != 0
+    }
+
+    /// Returns the fastest supported serial port speed.
+    ///
+    /// Returns `None` if the driver doesn't list any serial speeds, which is the case for devices
+    /// that don't support serial ports and for drivers that negotiate the speed themselves.
+    pub fn max_speed(&self) -> Option<usize> {
+        self.speeds().into_iter().max()
+    }
+
+    /// Returns the path of the camera driver library.
+    pub fn library(&self) -> Cow<'_, str> {
+        unsafe { String::from_utf8_lossy(CStr::from_ptr(self.inner.library.as_ptr()).to_bytes()) }
+    }
+
+    /// Returns the ID of the camera driver.
+    pub fn id(&self) -> Cow<'_, str> {
+        unsafe { String::from_utf8_lossy(CStr::from_ptr(self.inner.id.as_ptr()).to_bytes()) }
+    }
+
     /// Returns the camera operations supported by the device.
     pub fn camera_operations(&self) -> BTreeSet<CameraOperation> {
         let mut operations = BTreeSet::<CameraOperation>::new();
@@ -199,6 +238,57 @@ impl Abilities {
         operations
     }
 
+    /// Returns `true` if the device is a still camera.
+    pub fn is_camera(&self) -> bool {
+        self.device_type() == DeviceType::Camera
+    }
+
+    /// Returns `true` if the device is a camera that can capture images.
+    ///
+    /// This is `false` for devices that only give access to their storage, like phones and media
+    /// players in PTP mode.
+    pub fn can_capture(&self) -> bool {
+        let operations = self.camera_operations();
+
+        self.is_camera()
+            && (operations.contains(&CameraOperation::CaptureImage)
+                || operations.contains(&CameraOperation::TriggerCapture))
+    }
+
+    /// Returns a summary of the device's capture features.
+    ///
+    /// The summary is known before connecting to the camera, so applications can decide per model
+    /// whether to capture with `Camera::capture_image()` or `Camera::trigger_capture()`, and
+    /// whether to offer live view.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::CaptureFlow;
+    ///
+    /// for camera in gphoto::detect_cameras().unwrap() {
+    ///     let summary = camera.abilities().capture_summary();
+    ///
+    ///     match summary.preferred_flow() {
+    ///         Some(CaptureFlow::Trigger) => println!("{}: trigger and wait", camera.model()),
+    ///         Some(CaptureFlow::Capture) => println!("{}: capture", camera.model()),
+    ///         None => println!("{}: can't capture", camera.model()),
+    ///     }
+    /// }
+    /// ```
+    pub fn capture_summary(&self) -> CaptureSummary {
+        let operations = self.camera_operations();
+
+        CaptureSummary {
+            camera: self.is_camera(),
+            image: operations.contains(&CameraOperation::CaptureImage),
+            video: operations.contains(&CameraOperation::CaptureVideo),
+            audio: operations.contains(&CameraOperation::CaptureAudio),
+            preview: operations.contains(&CameraOperation::CapturePreview),
+            trigger: operations.contains(&CameraOperation::TriggerCapture),
+        }
+    }
+
     /// USB vendor ID.
     pub fn usb_vendor(&self) -> u16 {
         self.inner.usb_vendor as u16
@@ -225,6 +315,73 @@ impl Abilities {
     }
 }
 
+/// The capture features of a device, as reported by its driver.
+///
+/// Summaries are returned by `Abilities::capture_summary()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CaptureSummary {
+    camera: bool,
+    image: bool,
+    video: bool,
+    audio: bool,
+    preview: bool,
+    trigger: bool,
+}
+
+impl CaptureSummary {
+    /// Returns `true` if the device can capture an image and return the captured file.
+    pub fn image(&self) -> bool {
+        self.image
+    }
+
+    /// Returns `true` if the device can capture video.
+    pub fn video(&self) -> bool {
+        self.video
+    }
+
+    /// Returns `true` if the device can capture audio.
+    pub fn audio(&self) -> bool {
+        self.audio
+    }
+
+    /// Returns `true` if the device can capture previews, i.e., supports live view.
+    pub fn preview(&self) -> bool {
+        self.preview
+    }
+
+    /// Returns `true` if the device can trigger a capture without waiting for the file.
+    pub fn trigger(&self) -> bool {
+        self.trigger
+    }
+
+    /// Returns the preferred way to capture images with the device, or `None` if it is no camera
+    /// or can't capture images.
+    ///
+    /// Triggering is preferred, because it returns as soon as the shutter is released, so
+    /// captures can be fired in quick succession and the files are collected from events.
+    pub fn preferred_flow(&self) -> Option<CaptureFlow> {
+        if !self.camera {
+            None
+        } else if self.trigger {
+            Some(CaptureFlow::Trigger)
+        } else if self.image {
+            Some(CaptureFlow::Capture)
+        } else {
+            None
+        }
+    }
+}
+
+/// Ways to capture images.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum CaptureFlow {
+    /// Capture with `Camera::trigger_capture()` and collect the files from the camera's events.
+    Trigger,
+
+    /// Capture with `Camera::capture_image()`, which waits for the captured file.
+    Capture,
+}
+
 /// Types of devices.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
 pub enum DeviceType {
@@ -312,3 +469,7 @@ pub enum FolderOperation {
 pub fn from_libgphoto2(abilities: crate::gphoto2::CameraAbilities) -> Abilities {
     Abilities { inner: abilities }
 }
+
+pub(crate) fn to_libgphoto2(abilities: &Abilities) -> crate::gphoto2::CameraAbilities {
+    unsafe { core::ptr::read(&abilities.inner) }
+}
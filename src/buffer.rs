@@ -0,0 +1,127 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+/// The default number of idle buffers kept by a pool.
+const DEFAULT_CAPACITY: usize = 4;
+
+/// A pool of reusable byte buffers.
+///
+/// Buffers taken from the pool keep their allocated capacity when they are returned to it, so
+/// that capturing previews or downloading files of similar size doesn't allocate new memory for
+/// every frame or file.
+///
+/// A `BufferPool` is cheap to clone. Clones share the same buffers, so one pool can be shared by
+/// several streams or threads.
+///
+/// ## Example
+///
+/// ```no_run
+/// let pool = gphoto::BufferPool::new(8);
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut stream = camera.preview_stream().unwrap();
+///
+/// stream.set_buffer_pool(pool.clone());
+///
+/// for frame in stream.take(100) {
+///     let frame = frame.unwrap();
+///     println!("{} bytes", frame.len());
+///
+///     // the frame's buffer is returned to the pool when it's dropped
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BufferPool {
+    buffers: Arc<Mutex<Vec<Vec<u8>>>>,
+    capacity: usize,
+}
+
+impl BufferPool {
+    /// Creates a pool that keeps up to `capacity` idle buffers.
+    ///
+    /// Buffers returned to a full pool are deallocated.
+    pub fn new(capacity: usize) -> Self {
+        BufferPool {
+            buffers: Arc::new(Mutex::new(Vec::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Takes an empty buffer from the pool.
+    ///
+    /// A new buffer is allocated if the pool doesn't have any idle buffers.
+    pub fn get(&self) -> PooledBuffer {
+        let buffer = match self.buffers.lock() {
+            Ok(mut buffers) => buffers.pop().unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+
+        PooledBuffer {
+            buffer,
+            pool: self.clone(),
+        }
+    }
+
+    /// Returns the number of idle buffers in the pool.
+    pub fn idle(&self) -> usize {
+        match self.buffers.lock() {
+            Ok(buffers) => buffers.len(),
+            Err(_) => 0,
+        }
+    }
+
+    fn put(&self, mut buffer: Vec<u8>) {
+        buffer.clear();
+
+        if let Ok(mut buffers) = self.buffers.lock() {
+            if buffers.len() < self.capacity {
+                buffers.push(buffer);
+            }
+        }
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        BufferPool::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// A buffer borrowed from a `BufferPool`.
+///
+/// The buffer dereferences to a `Vec<u8>` and is returned to its pool when dropped. Use
+/// `into_vec()` to keep the buffer instead.
+pub struct PooledBuffer {
+    buffer: Vec<u8>,
+    pool: BufferPool,
+}
+
+impl PooledBuffer {
+    /// Takes ownership of the underlying `Vec`. The buffer is not returned to the pool.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        mem::take(&mut self.buffer)
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if self.buffer.capacity() > 0 {
+            self.pool.put(mem::take(&mut self.buffer));
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::camera::{Camera, CameraFile};
+use crate::event::CameraEvent;
+use crate::widget::WidgetValue;
+
+/// Names of the settings that enable long-exposure noise reduction in different drivers.
+const LENR_SETTINGS: &[&str] = &["longexpnr", "longexposurenr", "longexposurenoisereduction"];
+
+/// Values of the noise reduction settings that mean it is disabled.
+const LENR_OFF: &[&str] = &["off", "0", "disable", "disabled"];
+
+/// Names of the settings that open and close the shutter for bulb exposures, in the order they
+/// are tried.
+const BULB_SETTINGS: &[&str] = &["bulb", "eosremoterelease"];
+
+/// The time that the camera is given to process and report a bulb exposure, on top of the
+/// dark frame of long-exposure noise reduction.
+const PROCESSING_TIME: Duration = Duration::from_secs(30);
+
+/// How long to wait for the second file of a RAW+JPEG capture after the first.
+const PAIR_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl Camera {
+    /// Returns `true` if in-camera long-exposure noise reduction (LENR) is enabled.
+    ///
+    /// With LENR, the camera takes a dark frame of the same length after each long exposure and
+    /// subtracts it, so the capture takes twice the exposure time. Settings like `Auto`, which
+    /// enable LENR for long exposures only, count as enabled. Cameras that don't expose the
+    /// setting are assumed to have LENR disabled.
+    pub fn long_exposure_nr(&mut self) -> crate::Result<bool> {
+        for name in LENR_SETTINGS {
+            if let Ok(config) = self.single_config(name) {
+                return Ok(match config.root().value() {
+                    Some(WidgetValue::Toggle(enabled)) => enabled,
+                    Some(WidgetValue::Choice(value)) | Some(WidgetValue::Text(value)) => {
+                        !LENR_OFF.contains(&value.to_ascii_lowercase().as_str())
+                    }
+                    _ => false,
+                });
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Captures a bulb exposure.
+    ///
+    /// The shutter is held open for `exposure` and the files of the capture are returned, e.g.,
+    /// a RAW and a JPEG file. The camera's shutter speed must be set to bulb.
+    ///
+    /// When long-exposure noise reduction is enabled, the files are only reported after the
+    /// camera's dark frame, so the wait for them is extended by the exposure time.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// for file in camera.capture_bulb(Duration::from_secs(120)).unwrap() {
+    ///     println!("{}", file.path());
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the exposure could not be captured:
+    ///
+    /// * `NotSupported` if the camera doesn't support bulb exposures.
+    /// * `Timeout` if the camera didn't report a file in time.
+    pub fn capture_bulb(&mut self, exposure: Duration) -> crate::Result<Vec<CameraFile>> {
+        let dark_frame = if self.long_exposure_nr()? {
+            exposure
+        } else {
+            Duration::from_secs(0)
+        };
+
+        self.press_bulb(true)?;
+        thread::sleep(exposure);
+        self.release_bulb()?;
+
+        // Other events, e.g., property changes, don't extend the wait.
+        let mut deadline = Instant::now() + dark_frame + PROCESSING_TIME;
+        let mut files = Vec::new();
+
+        while Instant::now() < deadline {
+            match self.wait_event_until(deadline)? {
+                CameraEvent::FileAdded(file) => {
+                    // The second file of a RAW+JPEG capture follows shortly after the first.
+                    if files.is_empty() {
+                        deadline = Instant::now() + PAIR_TIMEOUT;
+                    }
+
+                    files.push(file);
+                }
+                CameraEvent::Timeout => break,
+                CameraEvent::CaptureComplete if !files.is_empty() => break,
+                _ => (),
+            }
+        }
+
+        if files.is_empty() {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_TIMEOUT,
+            ));
+        }
+
+        Ok(files)
+    }
+
+    /// Opens or closes the shutter for a bulb exposure.
+    fn press_bulb(&mut self, open: bool) -> crate::Result<()> {
+        for name in BULB_SETTINGS {
+            if let Ok(mut config) = self.single_config(name) {
+                config.set_value(name, bulb_value(name, open))?;
+                return self.set_single_config(&mut config);
+            }
+        }
+
+        Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        ))
+    }
+
+    /// Closes the shutter after a bulb exposure.
+    ///
+    /// A shutter that is left open ruins the exposure and drains the battery, so a failed release
+    /// is retried once and then tried with each setting, before the first error is returned.
+    fn release_bulb(&mut self) -> crate::Result<()> {
+        let err = match self.press_bulb(false) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+
+        if self.press_bulb(false).is_ok() {
+            return Ok(());
+        }
+
+        for name in BULB_SETTINGS {
+            if let Ok(mut config) = self.single_config(name) {
+                if config.set_value(name, bulb_value(name, false)).is_ok()
+                    && self.set_single_config(&mut config).is_ok()
+                {
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(err)
+    }
+}
+
+/// Returns the value of a bulb setting that opens or closes the shutter.
+fn bulb_value(name: &str, open: bool) -> WidgetValue {
+    match (name, open) {
+        ("eosremoterelease", true) => WidgetValue::Choice("Press Full".to_owned()),
+        ("eosremoterelease", false) => WidgetValue::Choice("Release Full".to_owned()),
+        (_, open) => WidgetValue::Toggle(open),
+    }
+}
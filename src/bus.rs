@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::camera::{Camera, CameraFile};
+use crate::download::DownloadOptions;
+
+/// Limits the number of concurrent heavy transfers per USB bus.
+///
+/// Cameras on the same USB bus share its bandwidth, so downloading from all cameras of a rig at
+/// once makes every transfer slow, and starves live view and captures. A scheduler admits a
+/// limited number of transfers per bus and queues the others, which improves the aggregate
+/// throughput of rigs where many cameras share a few host controllers.
+///
+/// Cameras are grouped by the bus number of their port path, e.g., `usb:001,005` and
+/// `usb:001,007` are on bus 1. Cameras on other ports, e.g., `ptpip:` network cameras, are each
+/// scheduled on their own.
+///
+/// Cloning a scheduler returns another handle to the same scheduler, so it can be shared between
+/// the threads that drive the cameras.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::thread;
+///
+/// use gphoto::{BusScheduler, DownloadOptions};
+///
+/// let scheduler = BusScheduler::new(2);
+///
+/// let threads: Vec<_> = gphoto::detect_cameras()
+///     .unwrap()
+///     .into_iter()
+///     .map(|detected| {
+///         let scheduler = scheduler.clone();
+///
+///         thread::spawn(move || {
+///             let mut camera = detected.open().unwrap();
+///             let file = camera.capture_image().unwrap();
+///
+///             scheduler
+///                 .download(&mut camera, &file, "photos".as_ref(), &DownloadOptions::new())
+///                 .unwrap();
+///         })
+///     })
+///     .collect();
+///
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BusScheduler {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    per_bus: usize,
+    active: Mutex<HashMap<String, usize>>,
+    available: Condvar,
+}
+
+/// A slot for a transfer on a bus of a `BusScheduler`.
+///
+/// The slot is released when the permit is dropped.
+pub struct BusPermit {
+    inner: Arc<Inner>,
+    bus: String,
+}
+
+impl Drop for BusPermit {
+    fn drop(&mut self) {
+        let mut active = self.inner.active();
+
+        if let Some(count) = active.get_mut(&self.bus) {
+            *count -= 1;
+
+            if *count == 0 {
+                active.remove(&self.bus);
+            }
+        }
+
+        drop(active);
+        self.inner.available.notify_all();
+    }
+}
+
+impl BusPermit {
+    /// The bus that the permit is for, e.g., `"usb:001"`.
+    pub fn bus(&self) -> &str {
+        &self.bus
+    }
+}
+
+impl BusScheduler {
+    /// Creates a scheduler that admits `per_bus` concurrent transfers per bus.
+    ///
+    /// A limit of 0 is treated as 1.
+    pub fn new(per_bus: usize) -> Self {
+        BusScheduler {
+            inner: Arc::new(Inner {
+                per_bus: per_bus.max(1),
+                active: Mutex::new(HashMap::new()),
+                available: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Returns the number of concurrent transfers that are admitted per bus.
+    pub fn per_bus(&self) -> usize {
+        self.inner.per_bus
+    }
+
+    /// Returns the number of transfers that are running on the bus of a port.
+    pub fn active(&self, port: &str) -> usize {
+        self.inner.active().get(&bus(port)).copied().unwrap_or(0)
+    }
+
+    /// Blocks until a transfer can start on the bus of a port, e.g., `"usb:001,005"`.
+    ///
+    /// The transfer should run while the returned permit is held.
+    pub fn acquire(&self, port: &str) -> BusPermit {
+        let bus = bus(port);
+        let mut active = self.inner.active();
+
+        while active.get(&bus).copied().unwrap_or(0) >= self.inner.per_bus {
+            active = match self.inner.available.wait(active) {
+                Ok(active) => active,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+
+        *active.entry(bus.clone()).or_insert(0) += 1;
+
+        BusPermit {
+            inner: self.inner.clone(),
+            bus,
+        }
+    }
+
+    /// Returns a permit for a transfer on the bus of a port, or `None` if the bus is busy.
+    pub fn try_acquire(&self, port: &str) -> Option<BusPermit> {
+        let bus = bus(port);
+        let mut active = self.inner.active();
+        let count = active.entry(bus.clone()).or_insert(0);
+
+        if *count >= self.inner.per_bus {
+            return None;
+        }
+
+        *count += 1;
+
+        Some(BusPermit {
+            inner: self.inner.clone(),
+            bus,
+        })
+    }
+
+    /// Downloads a file to a local directory once a transfer can start on the camera's bus.
+    ///
+    /// See `Camera::download_to_dir()` for details.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the errors of `Camera::download_to_dir()`.
+    pub fn download(
+        &self,
+        camera: &mut Camera,
+        file: &CameraFile,
+        dir: &Path,
+        options: &DownloadOptions,
+    ) -> crate::Result<Option<PathBuf>> {
+        let port = camera.port().path().into_owned();
+        let _permit = self.acquire(&port);
+
+        camera.download_to_dir(file, dir, options)
+    }
+}
+
+impl Inner {
+    fn active(&self) -> MutexGuard<'_, HashMap<String, usize>> {
+        match self.active.lock() {
+            Ok(active) => active,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Returns the bus of a port path, e.g., `"usb:001"` for `"usb:001,005"`.
+///
+/// Ports that aren't on a USB bus are their own bus.
+fn bus(port: &str) -> String {
+    match port.split_once(',') {
+        Some((bus, _)) if port.starts_with("usb:") => bus.to_owned(),
+        _ => port.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn groups_ports_by_bus() {
+        let cases = [
+            ("usb:001,005", "usb:001"),
+            ("usb:001,007", "usb:001"),
+            ("usb:002,003", "usb:002"),
+            ("usb:", "usb:"),
+            ("ptpip:192.168.1.10", "ptpip:192.168.1.10"),
+            ("serial:/dev/ttyS0,1", "serial:/dev/ttyS0,1"),
+        ];
+
+        for &(port, expected) in cases.iter() {
+            assert_eq!(bus(port), expected);
+        }
+    }
+
+    #[test]
+    fn limits_transfers_per_bus() {
+        let scheduler = BusScheduler::new(2);
+
+        let first = scheduler.try_acquire("usb:001,005").unwrap();
+        let second = scheduler.try_acquire("usb:001,007").unwrap();
+        assert!(scheduler.try_acquire("usb:001,009").is_none());
+        assert_eq!(scheduler.active("usb:001,005"), 2);
+
+        let other = scheduler.try_acquire("usb:002,003").unwrap();
+        assert_eq!(other.bus(), "usb:002");
+        assert_eq!(scheduler.active("usb:002,004"), 1);
+
+        drop(first);
+        assert_eq!(scheduler.active("usb:001,005"), 1);
+        assert!(scheduler.try_acquire("usb:001,009").is_some());
+
+        drop(second);
+        assert_eq!(scheduler.active("usb:001,005"), 0);
+    }
+
+    #[test]
+    fn treats_a_zero_limit_as_one() {
+        let scheduler = BusScheduler::new(0);
+        assert_eq!(scheduler.per_bus(), 1);
+
+        let _permit = scheduler.try_acquire("usb:001,005").unwrap();
+        assert!(scheduler.try_acquire("usb:001,005").is_none());
+    }
+
+    #[test]
+    fn blocks_until_a_permit_is_released() {
+        let scheduler = BusScheduler::new(1);
+        let permit = scheduler.acquire("usb:001,005");
+
+        let (sender, receiver) = mpsc::channel();
+        let waiting = scheduler.clone();
+
+        let thread = thread::spawn(move || {
+            let permit = waiting.acquire("usb:001,007");
+            sender.send(permit.bus().to_owned()).unwrap();
+        });
+
+        let timeout = Duration::from_millis(50);
+        assert!(receiver.recv_timeout(timeout).is_err());
+
+        drop(permit);
+        assert_eq!(receiver.recv().unwrap(), "usb:001");
+
+        thread.join().unwrap();
+        assert_eq!(scheduler.active("usb:001,005"), 0);
+    }
+}
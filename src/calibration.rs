@@ -0,0 +1,225 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::camera::Camera;
+use crate::download::{Collision, DownloadOptions};
+use crate::exposure::{self, SHUTTER_SETTINGS};
+
+/// Kinds of calibration frames for astrophotography.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum CalibrationKind {
+    /// Frames with the lens capped at the exposure of the light frames, which record thermal
+    /// noise and hot pixels.
+    Dark,
+
+    /// Frames with the lens capped at the shortest shutter speed, which record the sensor's
+    /// read-out offset.
+    Bias,
+
+    /// Frames of an evenly lit surface, which record vignetting and dust.
+    Flat,
+}
+
+impl CalibrationKind {
+    /// The name of the directory that frames of this kind are downloaded to.
+    pub fn dir_name(&self) -> &'static str {
+        match *self {
+            CalibrationKind::Dark => "darks",
+            CalibrationKind::Bias => "bias",
+            CalibrationKind::Flat => "flats",
+        }
+    }
+}
+
+/// A plan for capturing calibration frames.
+///
+/// Frames are captured in the order darks, bias, flats. Before each kind, the prompt is called
+/// with the kind, so that the application can ask the user to cap the lens or to set up the
+/// light source, and blocks until they confirm. Darks and flats are captured at the camera's
+/// current settings, which aren't changed in between; bias frames are captured at the shortest
+/// shutter speed. Autofocus is disabled while capturing, since it fails with a capped lens.
+///
+/// Frames are downloaded into a directory per kind, e.g., `darks/`.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{CalibrationKind, CalibrationPlan};
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+///
+/// let plan = CalibrationPlan::new().darks(20).bias(50);
+/// let paths = camera
+///     .capture_calibration(&plan, "session".as_ref(), |kind| {
+///         println!("cap the lens for {:?} frames and press enter", kind);
+///         let _ = std::io::stdin().read_line(&mut String::new());
+///     })
+///     .unwrap();
+///
+/// println!("captured {} frames", paths.len());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CalibrationPlan {
+    darks: usize,
+    bias: usize,
+    flats: usize,
+}
+
+impl CalibrationPlan {
+    /// Creates a plan without frames.
+    pub fn new() -> Self {
+        CalibrationPlan::default()
+    }
+
+    /// Sets the number of dark frames.
+    pub fn darks(mut self, count: usize) -> Self {
+        self.darks = count;
+        self
+    }
+
+    /// Sets the number of bias frames.
+    pub fn bias(mut self, count: usize) -> Self {
+        self.bias = count;
+        self
+    }
+
+    /// Sets the number of flat frames.
+    pub fn flats(mut self, count: usize) -> Self {
+        self.flats = count;
+        self
+    }
+}
+
+impl Camera {
+    /// Captures the calibration frames of a plan into a directory.
+    ///
+    /// Returns the kinds and local paths of the downloaded frames. See `CalibrationPlan` for
+    /// details. Autofocus is turned off during the calibration if the camera exposes it. The
+    /// camera's shutter speed, autofocus setting and capture options are restored afterwards.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if a frame could not be captured or downloaded:
+    ///
+    /// * `NotSupported` if bias frames are planned and the camera doesn't expose its shutter
+    ///   speed.
+    /// * `OSFailure` if a directory could not be created.
+    pub fn capture_calibration<F>(
+        &mut self,
+        plan: &CalibrationPlan,
+        dir: &Path,
+        mut prompt: F,
+    ) -> crate::Result<Vec<(CalibrationKind, PathBuf)>>
+    where
+        F: FnMut(CalibrationKind),
+    {
+        // Autofocus would hunt on the covered lens or the flat field, so it is turned off if the
+        // camera exposes it. The capture options must not turn it back on.
+        let options = self.capture_options().clone();
+        let mut calibration = options.clone();
+        calibration.autofocus = None;
+        self.set_capture_options(calibration);
+
+        let autofocus = self.turn_autofocus_off();
+
+        let mut paths = Vec::new();
+        let mut result = Ok(());
+
+        for &(kind, count) in &[
+            (CalibrationKind::Dark, plan.darks),
+            (CalibrationKind::Bias, plan.bias),
+            (CalibrationKind::Flat, plan.flats),
+        ] {
+            if count == 0 {
+                continue;
+            }
+
+            prompt(kind);
+
+            result = self.capture_calibration_frames(kind, count, dir, &mut paths);
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        if let Some((mut config, original)) = autofocus {
+            let name = config.root().name().into_owned();
+
+            if config.set_value(&name, original).is_ok() {
+                let _ = self.set_single_config(&mut config);
+            }
+        }
+
+        self.set_capture_options(options);
+
+        result.map(|()| paths)
+    }
+
+    fn capture_calibration_frames(
+        &mut self,
+        kind: CalibrationKind,
+        count: usize,
+        dir: &Path,
+        paths: &mut Vec<(CalibrationKind, PathBuf)>,
+    ) -> crate::Result<()> {
+        let dir = dir.join(kind.dir_name());
+
+        if fs::create_dir_all(&dir).is_err() {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            ));
+        }
+
+        // Bias frames are captured at the shortest shutter speed, which is restored afterwards.
+        let restore = if kind == CalibrationKind::Bias {
+            let mut config = exposure::find_config(self, SHUTTER_SETTINGS)?;
+            let original = config.root().value();
+
+            let fastest = exposure::choices(&config, exposure::parse_shutter)
+                .into_iter()
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            match fastest {
+                Some((fastest, _)) => exposure::set_choice(self, &mut config, &fastest)?,
+                None => {
+                    return Err(crate::error::from_libgphoto2(
+                        crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                    ))
+                }
+            }
+
+            original.map(|original| (config, original))
+        } else {
+            None
+        };
+
+        let options = DownloadOptions::new().collision(Collision::Rename);
+        let mut result = Ok(());
+
+        for _ in 0..count {
+            let downloaded = self
+                .capture_image()
+                .and_then(|file| self.download_to_dir(&file, &dir, &options));
+
+            match downloaded {
+                Ok(Some(path)) => paths.push((kind, path)),
+                Ok(None) => (),
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+
+        if let Some((mut config, original)) = restore {
+            let name = config.root().name().into_owned();
+
+            if config.set_value(&name, original).is_ok() {
+                let _ = self.set_single_config(&mut config);
+            }
+        }
+
+        result
+    }
+}
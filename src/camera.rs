@@ -3,40 +3,127 @@ use alloc::borrow::Cow;
 #[cfg(feature = "std")]
 use std::borrow::Cow;
 
+use core::cell::RefCell;
+use core::fmt;
 use core::mem::MaybeUninit;
+use core::ptr;
+use core::time::Duration;
 use cstr_core::{CStr, CString};
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
 use gphoto2_sys::CameraWidgetType;
-use libc::c_char;
+use libc::{c_char, c_int};
 
 use crate::abilities::Abilities;
+#[cfg(feature = "std")]
+use crate::buffer::{BufferPool, PooledBuffer};
+#[cfg(feature = "std")]
+use crate::capture::{BusyBackoff, Capture, CaptureId, CaptureOptions, CaptureTracker};
+#[cfg(feature = "std")]
+use crate::config::{CachedConfig, ConfigCache};
+#[cfg(feature = "std")]
+use crate::context::CancelHandle;
 use crate::context::Context;
-use crate::media::Media;
-use crate::port::Port;
+use crate::event::CameraEvent;
+#[cfg(feature = "std")]
+use crate::health::{HealthReport, STATUS_INTERVAL};
+#[cfg(feature = "std")]
+use crate::lifecycle::{CameraState, Lifecycle};
+use crate::media::{FileMedia, Media};
+use crate::port::{Port, PortIo};
+#[cfg(feature = "std")]
+use crate::preview::PreviewStream;
+#[cfg(feature = "std")]
+use crate::ratelimit::RateLimiter;
+#[cfg(feature = "std")]
+use crate::sidecar::CaptureSnapshots;
 use crate::storage::Storage;
+use crate::widget::Config;
+#[cfg(feature = "std")]
+use crate::widget::WidgetValue;
 
 use crate::handle::prelude::*;
 
 /// A structure representing a camera connected to the system.
 pub struct Camera {
-    camera: *mut crate::gphoto2::Camera,
-    context: Context,
+    pub(crate) camera: *mut crate::gphoto2::Camera,
+    pub(crate) context: Context,
+    #[cfg(feature = "std")]
+    pub(crate) config_cache: CachedConfig,
+    #[cfg(feature = "std")]
+    captures: CaptureTracker,
+    #[cfg(feature = "std")]
+    queued_events: VecDeque<CameraEvent>,
+    #[cfg(feature = "std")]
+    pub(crate) capture_snapshots: CaptureSnapshots,
+    #[cfg(feature = "std")]
+    pub(crate) capture_options: CaptureOptions,
+    #[cfg(feature = "std")]
+    pub(crate) capture_options_applied: bool,
+    #[cfg(feature = "std")]
+    pub(crate) session_tag: Option<String>,
+    #[cfg(feature = "std")]
+    pub(crate) schema_cache: Option<PathBuf>,
+    #[cfg(feature = "std")]
+    pub(crate) half_duplex: bool,
+    #[cfg(feature = "std")]
+    pub(crate) live_view: bool,
+    #[cfg(feature = "std")]
+    pub(crate) status_limiter: RateLimiter,
+    #[cfg(feature = "std")]
+    pub(crate) last_health: Option<HealthReport>,
+    #[cfg(feature = "std")]
+    pub(crate) lifecycle: Lifecycle,
+    abilities: RefCell<Option<Abilities>>,
+    manual: Option<String>,
+    about: Option<String>,
 }
 
 impl Drop for Camera {
     fn drop(&mut self) {
         unsafe {
             crate::gphoto2::gp_camera_unref(self.camera);
-            crate::gphoto2::gp_context_unref(self.context.context);
         }
     }
 }
 
+// The camera is owned exclusively by `Camera`, so it can be moved to another thread. Its context
+// may be shared with cameras on other threads, but the `GPContext` isn't written to after it is
+// created and is released only once, by the last handle. `libgphoto2` doesn't allow concurrent
+// calls for the same camera, so `Camera` isn't `Sync`; use `SharedCamera` to share a camera
+// between threads.
+unsafe impl Send for Camera {}
+
 impl Camera {
     /// Opens the first detected camera.
     pub fn autodetect() -> crate::Result<Self> {
-        let context = crate::Context::new()?;
+        Camera::autodetect_with(Context::new()?)
+    }
 
+    /// Opens the first detected camera, bound to a context.
+    ///
+    /// The camera reports its errors, status updates and messages to the context's callbacks,
+    /// and is cancelled together with the other cameras of the context.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let context = gphoto::Context::new().unwrap();
+    /// context.on_status(|text| println!("{}", text));
+    ///
+    /// let camera = gphoto::Camera::autodetect_in(&context).unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn autodetect_in(context: &Context) -> crate::Result<Self> {
+        Camera::autodetect_with(context.clone())
+    }
+
+    fn autodetect_with(context: Context) -> crate::Result<Self> {
         let mut ptr = MaybeUninit::uninit();
 
         let camera = unsafe {
@@ -47,18 +134,32 @@ impl Camera {
             ptr.assume_init()
         };
 
-        let mut camera = Camera { camera, context };
+        let mut camera = from_libgphoto2(camera, context);
 
         try_unsafe!(crate::gphoto2::gp_camera_init(
             camera.camera,
             camera.context.as_mut_ptr()
         ));
 
+        #[cfg(feature = "std")]
+        camera.set_state(CameraState::Initialized);
+
         Ok(camera)
     }
 
+    /// Returns a handle that cancels the camera's in-flight operation from another thread.
+    #[cfg(feature = "std")]
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.context.cancel_handle()
+    }
+
     /// Captures an image.
+    ///
+    /// The camera's `CaptureOptions` are applied before the capture.
     pub fn capture_image(&mut self) -> crate::Result<CameraFile> {
+        #[cfg(feature = "std")]
+        self.prepare_capture()?;
+
         let mut file_path = MaybeUninit::uninit();
 
         let file_path = unsafe {
@@ -69,14 +170,730 @@ impl Camera {
                 self.context.as_mut_ptr(),
             ) {
                 crate::gphoto2::GP_OK => (),
-                err => return Err(crate::error::from_libgphoto2(err)),
+                err => return self.track(Err(crate::error::from_libgphoto2(err))),
             }
             file_path.assume_init()
         };
-        unsafe {
-            crate::gphoto2::gp_camera_exit(self.camera, self.context.context);
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_capture();
+
+        let file = CameraFile { inner: file_path };
+
+        #[cfg(feature = "std")]
+        {
+            self.capture_snapshots.capture_complete();
+            self.capture_file_added(&file);
+        }
+
+        self.track(Ok(file))
+    }
+
+    /// Captures a sound recording.
+    ///
+    /// Only a few devices support recording sound; see `CameraOperation::CaptureAudio`.
+    ///
+    /// The camera's `CaptureOptions` are deliberately not applied: their capture target and
+    /// autofocus settings only affect image captures. Sound recordings aren't counted as captures
+    /// in the metrics either.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the recording could not be captured:
+    ///
+    /// * `NotSupported` if the device can't record sound.
+    pub fn capture_sound(&mut self) -> crate::Result<CameraFile> {
+        let mut file_path = MaybeUninit::uninit();
+
+        let file_path = unsafe {
+            match crate::gphoto2::gp_camera_capture(
+                self.camera,
+                crate::gphoto2::GP_CAPTURE_SOUND,
+                &mut *file_path.as_mut_ptr(),
+                self.context.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK => (),
+                err => return self.track(Err(crate::error::from_libgphoto2(err))),
+            }
+            file_path.assume_init()
+        };
+
+        self.track(Ok(CameraFile { inner: file_path }))
+    }
+
+    /// Triggers a capture without waiting for it to complete.
+    ///
+    /// The files produced by the capture are reported by `FileAdded` events. Each trigger is
+    /// assigned a `CaptureId`, which allows the files to be attributed to the trigger that produced
+    /// them with `wait_capture_complete()`, even when several captures are triggered in a burst.
+    ///
+    /// The camera's `CaptureOptions` are applied before the capture. While the camera reports
+    /// that it is busy, e.g., between the shots of a burst, the trigger is retried until the
+    /// options' busy deadline has passed.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the capture could not be triggered:
+    ///
+    /// * `NotSupported` if the camera does not support triggering captures.
+    /// * `CameraBusy` if the camera was not ready for another capture before the busy deadline.
+    #[cfg(feature = "std")]
+    pub fn trigger_capture(&mut self) -> crate::Result<CaptureId> {
+        self.prepare_capture()?;
+
+        let mut backoff = BusyBackoff::new(&self.capture_options);
+
+        loop {
+            let id = self.captures.trigger();
+
+            match unsafe {
+                crate::gphoto2::gp_camera_trigger_capture(self.camera, self.context.as_mut_ptr())
+            } {
+                crate::gphoto2::GP_OK => {
+                    #[cfg(feature = "metrics")]
+                    crate::metrics::record_capture();
+
+                    self.capture_snapshots.capture_complete();
+
+                    return self.track(Ok(id));
+                }
+                err => {
+                    self.captures.cancel_last();
+
+                    if err == crate::gphoto2::GP_ERROR_CAMERA_BUSY && backoff.wait() {
+                        continue;
+                    }
+
+                    return self.track(Err(crate::error::from_libgphoto2(err)));
+                }
+            }
+        }
+    }
+
+    /// Waits for the oldest pending triggered capture to complete.
+    ///
+    /// Returns the capture with the files that it produced. A capture is complete when the camera
+    /// reports `CaptureComplete`, or, for cameras that don't report it, when no further file has
+    /// been added for two seconds. Events that are received while waiting are kept and returned
+    /// by the following calls to `wait_event()`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// let first = camera.trigger_capture().unwrap();
+    /// let second = camera.trigger_capture().unwrap();
+    ///
+    /// for _ in 0..2 {
+    ///     let capture = camera.wait_capture_complete(Duration::from_secs(10)).unwrap();
+    ///     assert!(capture.id() == first || capture.id() == second);
+    ///
+    ///     for file in capture.files() {
+    ///         println!("{:?}: {}/{}", capture.id(), file.directory(), file.basename());
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the capture did not complete:
+    ///
+    /// * `InvalidInput` if there is no pending capture.
+    /// * `Timeout` if the capture did not produce any files before the timeout expired.
+    #[cfg(feature = "std")]
+    pub fn wait_capture_complete(&mut self, timeout: Duration) -> crate::Result<Capture> {
+        self.wait_capture_complete_until(Instant::now() + timeout)
+    }
+
+    /// Waits until `deadline` for the oldest pending triggered capture to complete.
+    ///
+    /// This is equivalent to `wait_capture_complete()`, but takes an absolute deadline instead of
+    /// a timeout, which is convenient when the deadline is computed by a scheduler.
+    #[cfg(feature = "std")]
+    pub fn wait_capture_complete_until(&mut self, deadline: Instant) -> crate::Result<Capture> {
+        const MAX_DRAINED_EVENTS: usize = 32;
+        const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+        if !self.captures.has_pending() {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            ));
+        }
+
+        // The number of files of the oldest capture and when it last changed.
+        let mut settling: Option<(usize, Instant)> = None;
+
+        while !self.captures.is_ready() {
+            let now = Instant::now();
+            let files = self.captures.oldest_files();
+
+            if files > 0 {
+                match settling {
+                    Some((count, since)) if count == files => {
+                        if now >= since + SETTLE_TIME {
+                            break;
+                        }
+                    }
+                    _ => settling = Some((files, now)),
+                }
+            }
+
+            if now >= deadline {
+                if files > 0 {
+                    break;
+                }
+
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_TIMEOUT,
+                ));
+            }
+
+            let until = match settling {
+                Some((_, since)) => deadline.min(since + SETTLE_TIME),
+                None => deadline,
+            };
+
+            let event = self.receive_event(until.saturating_duration_since(now))?;
+            self.queue_event(event);
+        }
+
+        // Collect files that were already reported, e.g., the second file of a RAW+JPEG capture.
+        for _ in 0..MAX_DRAINED_EVENTS {
+            match self.receive_event(Duration::from_millis(0))? {
+                CameraEvent::Timeout => break,
+                event => self.queue_event(event),
+            }
+        }
+
+        match self.captures.pop() {
+            Some(capture) => Ok(capture),
+            None => Err(crate::error::from_libgphoto2(crate::gphoto2::GP_ERROR)),
+        }
+    }
+
+    /// Waits for an event from the camera.
+    ///
+    /// Returns `CameraEvent::Timeout` if no event occurred before the timeout expired.
+    ///
+    /// Cameras that save more than one file per capture, e.g., RAW+JPEG, report a
+    /// `CameraEvent::FileAdded` for each file, so waiting for events after a capture catches the
+    /// files that the capture didn't return.
+    ///
+    /// Events that `wait_capture_complete()` received while waiting are returned first, in the
+    /// order they were received.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// loop {
+    ///     match camera.wait_event(Duration::from_secs(1)).unwrap() {
+    ///         gphoto::CameraEvent::FileAdded(file) => println!("new file: {}", file.basename()),
+    ///         gphoto::CameraEvent::Timeout => (),
+    ///         event => println!("{:?}", event),
+    ///     }
+    /// }
+    /// ```
+    pub fn wait_event(&mut self, timeout: Duration) -> crate::Result<CameraEvent> {
+        #[cfg(feature = "std")]
+        {
+            if let Some(event) = self.queued_events.pop_front() {
+                return Ok(event);
+            }
+        }
+
+        self.receive_event(timeout)
+    }
+
+    /// Keeps an event that was received while waiting for a capture for `wait_event()`.
+    #[cfg(feature = "std")]
+    pub(crate) fn queue_event(&mut self, event: CameraEvent) {
+        const MAX_QUEUED_EVENTS: usize = 256;
+
+        if let CameraEvent::Timeout = event {
+            return;
+        }
+
+        if self.queued_events.len() == MAX_QUEUED_EVENTS {
+            self.queued_events.pop_front();
+        }
+
+        self.queued_events.push_back(event);
+    }
+
+    /// Receives an event from the camera and attributes it to triggered captures.
+    pub(crate) fn receive_event(&mut self, timeout: Duration) -> crate::Result<CameraEvent> {
+        let timeout = timeout.as_millis().min(c_int::MAX as u128) as c_int;
+
+        let mut event_type = MaybeUninit::uninit();
+        let mut data = MaybeUninit::uninit();
+
+        let event = unsafe {
+            match crate::gphoto2::gp_camera_wait_for_event(
+                self.camera,
+                timeout,
+                &mut *event_type.as_mut_ptr(),
+                &mut *data.as_mut_ptr(),
+                self.context.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK => (),
+                err => return self.track(Err(crate::error::from_libgphoto2(err))),
+            }
+            crate::event::from_libgphoto2(event_type.assume_init(), data.assume_init())
+        };
+
+        #[cfg(feature = "std")]
+        match event {
+            CameraEvent::FileAdded(ref file) => {
+                self.captures.file_added(file);
+                self.capture_file_added(file);
+            }
+            CameraEvent::CaptureComplete => {
+                self.captures.capture_complete();
+                self.capture_snapshots.capture_complete();
+            }
+            _ => (),
+        }
+
+        self.track(Ok(event))
+    }
+
+    /// Waits until `deadline` for an event from the camera.
+    ///
+    /// This is equivalent to `wait_event()`, but takes an absolute deadline instead of a timeout.
+    /// Returns `CameraEvent::Timeout` if no event occurred before the deadline, including when the
+    /// deadline has already passed.
+    #[cfg(feature = "std")]
+    pub fn wait_event_until(&mut self, deadline: Instant) -> crate::Result<CameraEvent> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        self.wait_event(timeout)
+    }
+
+    /// Captures a preview image.
+    ///
+    /// The preview is a low-resolution frame from the camera's viewfinder or live view, usually a
+    /// JPEG image. The frame is written to `destination`, which is typically created with
+    /// `FileMedia::create_mem()` so that it can be reused for consecutive frames.
+    ///
+    /// Unlike other operations, capturing a preview does not close the connection to the camera,
+    /// so that live view stays active between frames.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the preview could not be captured:
+    ///
+    /// * `NotSupported` if the camera does not support capturing previews.
+    pub fn capture_preview<T: Media>(&mut self, destination: &mut T) -> crate::Result<()> {
+        try_unsafe!(crate::gphoto2::gp_camera_capture_preview(
+            self.camera,
+            destination.as_mut_ptr(),
+            self.context.as_mut_ptr()
+        ));
+
+        #[cfg(feature = "std")]
+        {
+            self.live_view = true;
+        }
+
+        Ok(())
+    }
+
+    /// Starts a stream of live view frames.
+    ///
+    /// See `PreviewStream` for details.
+    #[cfg(feature = "std")]
+    pub fn preview_stream(&mut self) -> crate::Result<PreviewStream<'_>> {
+        crate::preview::from_camera(self)
+    }
+
+    /// Retrieves the camera's configuration.
+    ///
+    /// The configuration is a tree of widgets that describe the camera's settings. See `Config`
+    /// for details.
+    ///
+    /// Unlike most other operations, configuring the camera does not close the connection to the
+    /// camera, so that ongoing operations like live view or movie recording aren't interrupted.
+    /// See `set_half_duplex()` for drivers that can't be configured during live view.
+    pub fn config(&mut self) -> crate::Result<Config> {
+        #[cfg(feature = "std")]
+        self.guard_live_view();
+
+        let mut ptr = MaybeUninit::uninit();
+
+        let root = unsafe {
+            match crate::gphoto2::gp_camera_get_config(
+                self.camera,
+                &mut *ptr.as_mut_ptr(),
+                self.context.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK => (),
+                err => return Err(crate::error::from_libgphoto2(err)),
+            }
+            ptr.assume_init()
+        };
+
+        Ok(crate::widget::config_from_libgphoto2(root))
+    }
+
+    /// Applies a configuration to the camera.
+    ///
+    /// Only the widgets that were changed in `config` are applied.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the configuration could not be applied:
+    ///
+    /// * `NotSupported` if the camera can not be configured.
+    /// * `InvalidInput` if one of the changed values is not accepted by the camera.
+    pub fn set_config(&mut self, config: &mut Config) -> crate::Result<()> {
+        #[cfg(feature = "std")]
+        self.guard_live_view();
+
+        try_unsafe!(crate::gphoto2::gp_camera_set_config(
+            self.camera,
+            config.as_mut_ptr(),
+            self.context.as_mut_ptr()
+        ));
+
+        #[cfg(feature = "std")]
+        self.config_cache.invalidate_all();
+
+        Ok(())
+    }
+
+    /// Retrieves a single widget of the camera's configuration.
+    ///
+    /// The returned `Config` has the named widget as its root. Returns a `NotSupported` error if
+    /// the driver can't retrieve single widgets.
+    pub(crate) fn single_config(&mut self, name: &str) -> crate::Result<Config> {
+        #[cfg(feature = "std")]
+        self.guard_live_view();
+
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        };
+
+        let mut ptr = MaybeUninit::uninit();
+
+        let widget = unsafe {
+            match crate::gphoto2::gp_camera_get_single_config(
+                self.camera,
+                name.as_ptr(),
+                &mut *ptr.as_mut_ptr(),
+                self.context.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK => (),
+                err => return Err(crate::error::from_libgphoto2(err)),
+            }
+            ptr.assume_init()
+        };
+
+        Ok(crate::widget::config_from_libgphoto2(widget))
+    }
+
+    /// Applies a single widget that was retrieved with `single_config()`.
+    ///
+    /// Returns a `NotSupported` error if the driver can't set single widgets.
+    pub(crate) fn set_single_config(&mut self, config: &mut Config) -> crate::Result<()> {
+        #[cfg(feature = "std")]
+        self.guard_live_view();
+
+        let name = match CString::new(config.root().name().as_bytes()) {
+            Ok(name) => name,
+            Err(_) => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        };
+
+        try_unsafe!(crate::gphoto2::gp_camera_set_single_config(
+            self.camera,
+            name.as_ptr(),
+            config.as_mut_ptr(),
+            self.context.as_mut_ptr()
+        ));
+
+        #[cfg(feature = "std")]
+        self.config_cache.invalidate(&config.root().name());
+
+        Ok(())
+    }
+
+    /// Sets the policy for caching the camera's configuration.
+    ///
+    /// Changing the policy discards the cached configuration. See `ConfigCache` for details.
+    #[cfg(feature = "std")]
+    pub fn set_config_cache(&mut self, policy: ConfigCache) {
+        self.config_cache.set_policy(policy);
+        self.config_cache.invalidate_all();
+    }
+
+    /// Returns the policy for caching the camera's configuration.
+    #[cfg(feature = "std")]
+    pub fn config_cache(&self) -> ConfigCache {
+        self.config_cache.policy()
+    }
+
+    /// Fetches the camera's configuration into the cache.
+    #[cfg(feature = "std")]
+    pub fn refresh_config(&mut self) -> crate::Result<()> {
+        let config = self.config()?;
+        self.config_cache.store(config);
+
+        Ok(())
+    }
+
+    /// Marks a single setting in the cached configuration as out of date.
+    ///
+    /// The next read of the setting fetches only that setting from the camera, if the driver
+    /// supports it, instead of the whole configuration.
+    #[cfg(feature = "std")]
+    pub fn invalidate_setting(&mut self, name: &str) {
+        self.config_cache.invalidate(name);
+    }
+
+    /// Returns the current value of a setting.
+    ///
+    /// The setting is looked up by name, e.g., `"iso"`, or by path, e.g.,
+    /// `"/main/imgsettings/iso"`. The value is read from the cached configuration when the cache
+    /// policy allows it. See `ConfigCache` for details.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the setting could not be read:
+    ///
+    /// * `InvalidInput` if the camera doesn't have a setting with that name or the setting
+    ///   doesn't have a value.
+    #[cfg(feature = "std")]
+    pub fn setting(&mut self, name: &str) -> crate::Result<WidgetValue> {
+        let leaf = crate::widget::leaf_name(name);
+
+        if !self.config_cache.is_fresh() {
+            self.refresh_config()?;
+        } else if leaf != name && self.config_cache.is_stale(leaf) {
+            // Drivers look up single widgets by name only, which can't tell apart widgets with the
+            // same name, so a path re-reads the whole configuration.
+            self.refresh_config()?;
+        } else if self.config_cache.is_stale(name) {
+            match self.single_config(name) {
+                Ok(widget) => {
+                    if let Some(value) = widget.root().value() {
+                        if let Some(config) = self.config_cache.config_mut() {
+                            config.update_value(name, &value)?;
+                        }
+                    }
+
+                    self.config_cache.validate(name);
+                }
+                Err(ref err) if err.kind() == crate::ErrorKind::NotSupported => {
+                    self.refresh_config()?;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        match self.config_cache.config().and_then(|config| config.value(name)) {
+            Some(value) => Ok(value),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            )),
+        }
+    }
+
+    /// Changes several settings with a single configuration update.
+    ///
+    /// The changes are made to the cached configuration (which is fetched first if the cache
+    /// policy requires it) and applied with one call to the camera, instead of one round trip per
+    /// setting. The changed settings are re-read from the camera the next time they are accessed
+    /// with `setting()`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::WidgetValue;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// camera.apply_config(&[
+    ///     ("iso", WidgetValue::Choice("400".to_owned())),
+    ///     ("shutterspeed", WidgetValue::Choice("1/125".to_owned())),
+    ///     ("f-number", WidgetValue::Choice("f/8".to_owned())),
+    /// ]).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the settings could not be changed:
+    ///
+    /// * `InvalidInput` if the camera doesn't have a setting with one of the names or one of the
+    ///   values doesn't match its setting's type. No changes are applied in that case.
+    /// * `NotSupported` if the camera can not be configured.
+    #[cfg(feature = "std")]
+    pub fn apply_config(&mut self, changes: &[(&str, WidgetValue)]) -> crate::Result<()> {
+        if !self.config_cache.is_fresh() {
+            self.refresh_config()?;
+        }
+
+        let mut config = match self.config_cache.take() {
+            Some(config) => config,
+            None => self.config()?,
+        };
+
+        for (name, value) in changes {
+            config.set_value(name, value.clone())?;
+        }
+
+        self.set_config(&mut config)?;
+        self.config_cache.store(config);
+
+        for (name, _) in changes {
+            self.config_cache.invalidate(crate::widget::leaf_name(name));
+        }
+
+        Ok(())
+    }
+
+    /// Changes several settings for the duration of a closure, and restores their previous values
+    /// afterwards, even if the closure fails.
+    ///
+    /// The settings are changed with `apply_config()`. This is useful for temporary changes that
+    /// shouldn't affect later operations, e.g., switching to small JPEGs for a few preview
+    /// captures.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::WidgetValue;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// let file = camera
+    ///     .with_settings(
+    ///         &[("imagequality", WidgetValue::Choice("JPEG Basic".to_owned()))],
+    ///         |camera| camera.capture_image(),
+    ///     )
+    ///     .unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the settings could not be changed or restored, or the
+    /// error of the closure. The closure's error takes precedence over an error while restoring
+    /// the settings:
+    ///
+    /// * `InvalidInput` if the camera doesn't have a setting with one of the names or one of the
+    ///   values doesn't match its setting's type. No changes are applied in that case.
+    /// * `NotSupported` if the camera can not be configured.
+    #[cfg(feature = "std")]
+    pub fn with_settings<F, T>(&mut self, changes: &[(&str, WidgetValue)], f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Camera) -> crate::Result<T>,
+    {
+        let mut previous = Vec::with_capacity(changes.len());
+
+        for (name, _) in changes {
+            previous.push((*name, self.setting(name)?));
+        }
+
+        self.apply_config(changes)?;
+
+        let result = f(self);
+        let restored = self.apply_config(&previous);
+
+        match result {
+            Ok(value) => restored.map(|_| value),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Reads a single setting as text, e.g., `"800"` for `"iso"`.
+    ///
+    /// Only the named setting is read from the camera if the driver supports it, instead of the
+    /// whole configuration tree. The value isn't cached. Toggles are returned as `"1"` or `"0"`,
+    /// and dates in seconds since the Unix epoch.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// println!("iso = {}", camera.config_value("iso").unwrap());
+    /// camera.set_config_value("iso", "800").unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the setting could not be read:
+    ///
+    /// * `InvalidInput` if the camera doesn't have a setting with that name or the setting
+    ///   doesn't have a value.
+    /// * `NotSupported` if the camera can not be configured.
+    #[cfg(feature = "std")]
+    pub fn config_value(&mut self, name: &str) -> crate::Result<String> {
+        let value = match self.single_config(name) {
+            Ok(config) => config.root().value(),
+            Err(ref err) if err.kind() == crate::ErrorKind::NotSupported => {
+                self.config()?.value(name)
+            }
+            Err(err) => return Err(err),
+        };
+
+        match value {
+            Some(WidgetValue::Text(value)) | Some(WidgetValue::Choice(value)) => Ok(value),
+            Some(WidgetValue::Range(value)) => Ok(value.to_string()),
+            Some(WidgetValue::Toggle(value)) => Ok(if value { "1" } else { "0" }.to_owned()),
+            Some(WidgetValue::Date(value)) => Ok(value.to_string()),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            )),
+        }
+    }
+
+    /// Changes a single setting to a value given as text, e.g., `"800"` for `"iso"`.
+    ///
+    /// The value is converted to the type of the setting: `"1"`, `"true"` or `"on"` for toggles,
+    /// numbers for ranges and dates (as a Unix timestamp), and text for text and choice settings.
+    /// Only the named setting is applied if the driver supports it, instead of the whole
+    /// configuration tree.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the setting could not be changed:
+    ///
+    /// * `InvalidInput` if the camera doesn't have a setting with that name or the value can't be
+    ///   converted to the setting's type.
+    /// * `NotSupported` if the camera can not be configured.
+    #[cfg(feature = "std")]
+    pub fn set_config_value(&mut self, name: &str, value: &str) -> crate::Result<()> {
+        match self.single_config(name) {
+            Ok(mut config) => {
+                let value = crate::sequence::parse_value(config.root().value(), value)?;
+
+                config.set_value(name, value)?;
+                self.set_single_config(&mut config)
+            }
+            Err(ref err) if err.kind() == crate::ErrorKind::NotSupported => {
+                let mut config = self.config()?;
+                let value = crate::sequence::parse_value(config.value(name), value)?;
+
+                config.set_value(name, value)?;
+                self.set_config(&mut config)
+            }
+            Err(err) => Err(err),
         }
-        Ok(CameraFile { inner: file_path })
     }
 
     /// Set a setting to a specific value
@@ -115,27 +932,107 @@ impl Camera {
         source: &CameraFile,
         destination: &mut T,
         file_type: Option<crate::CameraFileType>,
+    ) -> crate::Result<()> {
+        self.get_file(source, destination, file_type)?;
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_download();
+
+        Ok(())
+    }
+
+    /// Downloads a file from the camera without counting it as a download, e.g., for thumbnails.
+    pub(crate) fn get_file<T: Media>(
+        &mut self,
+        source: &CameraFile,
+        destination: &mut T,
+        file_type: Option<crate::CameraFileType>,
     ) -> crate::Result<()> {
         let file_type = if let Some(file_type) = file_type {
             file_type
         } else {
             crate::gphoto2::GP_FILE_TYPE_NORMAL
         };
-        try_unsafe! {
-            crate::gphoto2::gp_camera_file_get(self.camera,
-                                          source.inner.folder.as_ptr(),
-                                          source.inner.name.as_ptr(),
-                                          file_type,
-                                          destination.as_mut_ptr(),
-                                          self.context.as_mut_ptr())
-        };
-        unsafe {
-            crate::gphoto2::gp_camera_exit(self.camera, self.context.context);
+        match unsafe {
+            crate::gphoto2::gp_camera_file_get(
+                self.camera,
+                source.inner.folder.as_ptr(),
+                source.inner.name.as_ptr(),
+                file_type,
+                destination.as_mut_ptr(),
+                self.context.as_mut_ptr(),
+            )
+        } {
+            crate::gphoto2::GP_OK => (),
+            err => return self.track(Err(crate::error::from_libgphoto2(err))),
         }
 
+        self.track(Ok(()))
+    }
+
+    /// Uploads a file to the camera.
+    ///
+    /// The file is stored as `name` in `folder`. Depending on `file_type`, the driver stores the
+    /// data as a new file or, e.g., for `GP_FILE_TYPE_METADATA`, applies it to an existing file.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the file could not be uploaded:
+    ///
+    /// * `NotSupported` if the camera doesn't support uploading files.
+    /// * `DirectoryNotFound` if the folder doesn't exist.
+    /// * `InvalidInput` if the folder or name contains a NUL byte.
+    pub fn upload<T: Media>(
+        &mut self,
+        folder: &str,
+        name: &str,
+        source: &mut T,
+        file_type: Option<crate::CameraFileType>,
+    ) -> crate::Result<()> {
+        let (folder, name) = match (CString::new(folder), CString::new(name)) {
+            (Ok(folder), Ok(name)) => (folder, name),
+            _ => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        };
+
+        try_unsafe!(crate::gphoto2::gp_camera_folder_put_file(
+            self.camera,
+            folder.as_ptr(),
+            name.as_ptr(),
+            file_type.unwrap_or(crate::gphoto2::GP_FILE_TYPE_NORMAL),
+            source.as_mut_ptr(),
+            self.context.as_mut_ptr()
+        ));
+
         Ok(())
     }
 
+    /// Downloads a file from the camera into a buffer from `pool`.
+    ///
+    /// This is equivalent to downloading the file to memory with `download()`, but the file's
+    /// contents are copied into a reused buffer instead of a newly allocated one.
+    #[cfg(feature = "std")]
+    pub fn download_to_buffer(
+        &mut self,
+        source: &CameraFile,
+        file_type: Option<crate::CameraFileType>,
+        pool: &BufferPool,
+    ) -> crate::Result<PooledBuffer> {
+        let mut media = FileMedia::create_mem()?;
+        self.download(source, &mut media, file_type)?;
+
+        let mut buffer = pool.get();
+        media.get_data_into(&mut buffer);
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_downloaded_bytes(buffer.len() as u64);
+
+        Ok(buffer)
+    }
+
     /// Returns information about the port the camera is connected to.
     pub fn port(&self) -> Port {
         let mut ptr = MaybeUninit::uninit();
@@ -148,14 +1045,51 @@ impl Camera {
 
             ptr.assume_init()
         };
-        unsafe {
-            crate::gphoto2::gp_camera_exit(self.camera, self.context.context);
-        }
         crate::port::from_libgphoto2(self, port_info)
     }
 
+    /// Returns raw access to the port that the camera is connected to.
+    ///
+    /// This is an escape hatch for sending commands that the camera driver doesn't support. See
+    /// `PortIo` for details.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut port = camera.port_io().unwrap();
+    ///
+    /// port.set_timeout(Duration::from_secs(1)).unwrap();
+    ///
+    /// // PTP GetDeviceInfo command container
+    /// let command = [0x0c, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x10, 0x00, 0x00, 0x00, 0x00];
+    /// port.write(&command).unwrap();
+    ///
+    /// let mut response = [0; 512];
+    /// let len = port.read(&mut response).unwrap();
+    /// println!("{:02x?}", &response[..len]);
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the port can't be accessed:
+    ///
+    /// * `NotSupported` if the camera doesn't have an open port.
+    pub fn port_io(&mut self) -> crate::Result<PortIo<'_>> {
+        crate::port::port_io(self)
+    }
+
     /// Retrieves the camera's abilities.
+    ///
+    /// The abilities don't change while the camera is connected, so they are cached after the first
+    /// call.
     pub fn abilities(&self) -> Abilities {
+        if let Some(ref abilities) = *self.abilities.borrow() {
+            return abilities.clone();
+        }
+
         let mut abilities = MaybeUninit::uninit();
 
         let abilities = unsafe {
@@ -166,10 +1100,19 @@ impl Camera {
             abilities.assume_init()
         };
 
-        unsafe {
-            crate::gphoto2::gp_camera_exit(self.camera, self.context.context);
-        }
-        crate::abilities::from_libgphoto2(abilities)
+        let abilities = crate::abilities::from_libgphoto2(abilities);
+        *self.abilities.borrow_mut() = Some(abilities.clone());
+
+        abilities
+    }
+
+    /// Returns `true` if the device can capture images.
+    ///
+    /// Some devices that are detected as cameras only give access to their storage, e.g., phones
+    /// and media players in PTP mode. Checking this before capturing allows an application to
+    /// report such devices clearly instead of failing on the first capture.
+    pub fn is_capture_capable(&self) -> bool {
+        self.abilities().can_capture()
     }
 
     /// Retrieves information about the camera's storage.
@@ -187,17 +1130,14 @@ impl Camera {
                 self.context.as_mut_ptr(),
             ) {
                 crate::gphoto2::GP_OK => (),
-                err => return Err(crate::error::from_libgphoto2(err)),
+                err => return self.track(Err(crate::error::from_libgphoto2(err))),
             }
             (ptr.assume_init(), len.assume_init())
         };
         let storage = storage as *mut Storage;
         let length = len as usize;
 
-        unsafe {
-            crate::gphoto2::gp_camera_exit(self.camera, self.context.context);
-        }
-        Ok(unsafe { Vec::from_raw_parts(storage, length, length) })
+        self.track(Ok(unsafe { Vec::from_raw_parts(storage, length, length) }))
     }
 
     /// Returns the camera's summary.
@@ -226,15 +1166,12 @@ impl Camera {
             summary.assume_init()
         };
 
-        unsafe {
-            crate::gphoto2::gp_camera_exit(self.camera, self.context.context);
-        }
         util::camera_text_to_string(summary)
     }
 
     /// Returns the camera's manual.
     ///
-    /// The manual contains information about using the camera.
+    /// The manual contains information about using the camera. It is cached after the first call.
     ///
     /// ## Errors
     ///
@@ -243,6 +1180,10 @@ impl Camera {
     /// * `NotSupported` if there is no manual available for the camera.
     /// * `CorruptedData` if the summary is invalid UTF-8.
     pub fn manual(&mut self) -> crate::Result<String> {
+        if let Some(ref manual) = self.manual {
+            return Ok(manual.clone());
+        }
+
         let mut manual = MaybeUninit::uninit();
 
         let manual = unsafe {
@@ -257,15 +1198,16 @@ impl Camera {
             manual.assume_init()
         };
 
-        unsafe {
-            crate::gphoto2::gp_camera_exit(self.camera, self.context.context);
-        }
-        util::camera_text_to_string(manual)
+        let manual = util::camera_text_to_string(manual)?;
+        self.manual = Some(manual.clone());
+
+        Ok(manual)
     }
 
     /// Returns information about the camera driver.
     ///
     /// This text typically contains information about the driver's author, acknowledgements, etc.
+    /// It is cached after the first call.
     ///
     /// ## Errors
     ///
@@ -274,6 +1216,10 @@ impl Camera {
     /// * `NotSupported` if there is no about text available for the camera's driver.
     /// * `CorruptedData` if the summary is invalid UTF-8.
     pub fn about_driver(&mut self) -> crate::Result<String> {
+        if let Some(ref about) = self.about {
+            return Ok(about.clone());
+        }
+
         let mut about = MaybeUninit::uninit();
 
         let about = unsafe {
@@ -288,16 +1234,51 @@ impl Camera {
             about.assume_init()
         };
 
-        unsafe {
-            crate::gphoto2::gp_camera_exit(self.camera, self.context.context);
+        let about = util::camera_text_to_string(about)?;
+        self.about = Some(about.clone());
+
+        Ok(about)
+    }
+
+    /// Records the outcome of an operation in the camera's lifecycle state.
+    ///
+    /// With the `metrics` feature enabled, errors are also counted by their kind.
+    pub(crate) fn track<T>(&mut self, result: crate::Result<T>) -> crate::Result<T> {
+        #[cfg(feature = "std")]
+        self.record_outcome(result.as_ref().err());
+
+        #[cfg(feature = "metrics")]
+        {
+            if let Err(ref err) = result {
+                crate::metrics::record_error(err.kind());
+            }
         }
-        util::camera_text_to_string(about)
+
+        result
     }
 }
 
 /// A file stored on a camera's storage.
 pub struct CameraFile {
-    inner: crate::gphoto2::CameraFilePath,
+    pub(crate) inner: crate::gphoto2::CameraFilePath,
+}
+
+impl Clone for CameraFile {
+    fn clone(&self) -> Self {
+        // CameraFilePath only contains character arrays.
+        CameraFile {
+            inner: unsafe { ptr::read(&self.inner) },
+        }
+    }
+}
+
+impl fmt::Debug for CameraFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CameraFile")
+            .field("directory", &self.directory())
+            .field("basename", &self.basename())
+            .finish()
+    }
 }
 
 impl CameraFile {
@@ -310,10 +1291,86 @@ impl CameraFile {
     pub fn basename(&self) -> Cow<str> {
         unsafe { String::from_utf8_lossy(CStr::from_ptr(self.inner.name.as_ptr()).to_bytes()) }
     }
+
+    /// Returns the absolute path of the file on the camera.
+    pub fn path(&self) -> String {
+        let directory = self.directory();
+
+        if directory.ends_with('/') {
+            format!("{}{}", directory, self.basename())
+        } else {
+            format!("{}/{}", directory, self.basename())
+        }
+    }
+}
+
+/// Wraps a camera that has not been initialized yet.
+pub(crate) fn from_libgphoto2(camera: *mut crate::gphoto2::Camera, context: Context) -> Camera {
+    Camera {
+        camera,
+        context,
+        #[cfg(feature = "std")]
+        config_cache: CachedConfig::new(),
+        #[cfg(feature = "std")]
+        captures: CaptureTracker::new(),
+        #[cfg(feature = "std")]
+        queued_events: VecDeque::new(),
+        #[cfg(feature = "std")]
+        capture_snapshots: CaptureSnapshots::new(),
+        #[cfg(feature = "std")]
+        capture_options: CaptureOptions::new(),
+        #[cfg(feature = "std")]
+        capture_options_applied: true,
+        #[cfg(feature = "std")]
+        session_tag: None,
+        #[cfg(feature = "std")]
+        schema_cache: None,
+        #[cfg(feature = "std")]
+        half_duplex: false,
+        #[cfg(feature = "std")]
+        live_view: false,
+        #[cfg(feature = "std")]
+        status_limiter: RateLimiter::new(STATUS_INTERVAL),
+        #[cfg(feature = "std")]
+        last_health: None,
+        #[cfg(feature = "std")]
+        lifecycle: Lifecycle::new(),
+        abilities: RefCell::new(None),
+        manual: None,
+        about: None,
+    }
+}
+
+#[doc(hidden)]
+pub fn file_from_libgphoto2(path: crate::gphoto2::CameraFilePath) -> CameraFile {
+    CameraFile { inner: path }
+}
+
+/// Creates a `CameraFile` from a directory and a file name.
+///
+/// Names that don't fit into `CameraFilePath` are truncated.
+pub(crate) fn file_from_parts(directory: &str, name: &str) -> CameraFile {
+    let mut path: crate::gphoto2::CameraFilePath = unsafe { core::mem::zeroed() };
+
+    util::copy_to_c_buf(directory, &mut path.folder);
+    util::copy_to_c_buf(name, &mut path.name);
+
+    CameraFile { inner: path }
 }
 
 mod util {
     use cstr_core::CStr;
+    use libc::c_char;
+
+    pub fn copy_to_c_buf(s: &str, buf: &mut [c_char]) {
+        let len = s.len().min(buf.len() - 1);
+
+        for (dst, src) in buf.iter_mut().zip(s.bytes().take(len)) {
+            *dst = src as c_char;
+        }
+
+        buf[len] = 0;
+    }
 
     pub fn camera_text_to_string(camera_text: crate::gphoto2::CameraText) -> crate::Result<String> {
         let c_str = unsafe { CStr::from_ptr(camera_text.text.as_ptr()) };
@@ -0,0 +1,584 @@
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::camera::{Camera, CameraFile};
+use crate::widget::{Config, WidgetValue};
+
+/// Names of the settings that select the autofocus mode in different drivers.
+const AUTOFOCUS_SETTINGS: &[&str] = &["autofocus", "focusmode"];
+
+/// Values of the autofocus settings that enable autofocus.
+const AUTOFOCUS_ON: &[&str] = &["on", "auto", "automatic", "af-s", "one shot", "single"];
+
+/// Values of the autofocus settings that disable autofocus.
+const AUTOFOCUS_OFF: &[&str] = &["off", "manual", "mf"];
+
+/// The setting that drives the autofocus once.
+const AUTOFOCUS_DRIVE: &str = "autofocusdrive";
+
+/// The setting that selects where captured images are stored.
+const CAPTURE_TARGET: &str = "capturetarget";
+
+/// How long a triggered capture is retried while the camera is busy, by default.
+const BUSY_DEADLINE: Duration = Duration::from_secs(1);
+
+/// The delay before the first retry of a busy capture, which doubles with each further retry.
+const BUSY_RETRY_DELAY: Duration = Duration::from_millis(5);
+
+/// The longest delay between retries of a busy capture, so that long deadlines keep retrying.
+const MAX_BUSY_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Autofocus policies for captures.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Af {
+    /// Autofocus is enabled and driven before each capture, where the camera supports it.
+    Before,
+
+    /// Autofocus is disabled, so the focus stays where it was set.
+    Off,
+}
+
+/// Storage locations for captured images.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CaptureTarget {
+    /// Images are kept in the camera's RAM and deleted after they are downloaded.
+    Ram,
+
+    /// Images are written to the memory card.
+    Card,
+}
+
+/// Options that are applied by `Camera::capture_image()` and `Camera::trigger_capture()`.
+///
+/// Settings that aren't set are left as they are on the camera.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use gphoto::{Af, CaptureOptions, CaptureTarget};
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+///
+/// // Copy-stand work: fixed focus, no vibration from the previous shot.
+/// camera.set_capture_options(
+///     CaptureOptions::new()
+///         .autofocus(Af::Off)
+///         .settle(Duration::from_millis(500))
+///         .target(CaptureTarget::Card),
+/// );
+///
+/// for _ in 0..10 {
+///     camera.capture_image().unwrap();
+/// }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CaptureOptions {
+    pub(crate) autofocus: Option<Af>,
+    settle: Duration,
+    target: Option<CaptureTarget>,
+    busy_deadline: Duration,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        CaptureOptions {
+            autofocus: None,
+            settle: Duration::from_secs(0),
+            target: None,
+            busy_deadline: BUSY_DEADLINE,
+        }
+    }
+}
+
+impl CaptureOptions {
+    /// Creates the default options, which leave the camera's settings as they are and retry busy
+    /// triggers for up to one second.
+    pub fn new() -> Self {
+        CaptureOptions::default()
+    }
+
+    /// Sets the autofocus policy.
+    pub fn autofocus(mut self, autofocus: Af) -> Self {
+        self.autofocus = Some(autofocus);
+        self
+    }
+
+    /// Sets the time to wait before each capture, e.g., for vibrations to settle.
+    pub fn settle(mut self, settle: Duration) -> Self {
+        self.settle = settle;
+        self
+    }
+
+    /// Sets where captured images are stored.
+    pub fn target(mut self, target: CaptureTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Sets how long `Camera::trigger_capture()` retries while the camera is busy.
+    ///
+    /// Cameras briefly report busy between the shots of a burst. Triggers are retried after a
+    /// few milliseconds, with the delay doubling for each retry up to two seconds, until the
+    /// deadline has passed. A deadline of zero disables retries.
+    pub fn busy_deadline(mut self, deadline: Duration) -> Self {
+        self.busy_deadline = deadline;
+        self
+    }
+}
+
+/// The backoff of retries while the camera is busy.
+pub(crate) struct BusyBackoff {
+    deadline: Instant,
+    delay: Duration,
+}
+
+impl BusyBackoff {
+    pub fn new(options: &CaptureOptions) -> Self {
+        BusyBackoff {
+            deadline: Instant::now() + options.busy_deadline,
+            delay: BUSY_RETRY_DELAY,
+        }
+    }
+
+    /// Waits before the next retry.
+    ///
+    /// Returns `false` without waiting if the deadline has passed.
+    pub fn wait(&mut self) -> bool {
+        match self.next_delay(Instant::now()) {
+            Some(delay) => {
+                thread::sleep(delay);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the delay before the next retry at `now`, or `None` if the deadline has passed.
+    ///
+    /// The last delay is shortened to end at the deadline, so that the last retry starts at the
+    /// deadline instead of being given up.
+    fn next_delay(&mut self, now: Instant) -> Option<Duration> {
+        if now >= self.deadline {
+            return None;
+        }
+
+        let delay = self.delay.min(self.deadline - now);
+        self.delay = (self.delay * 2).min(MAX_BUSY_RETRY_DELAY);
+
+        Some(delay)
+    }
+}
+
+impl Camera {
+    /// Returns the options that are applied to captures.
+    pub fn capture_options(&self) -> &CaptureOptions {
+        &self.capture_options
+    }
+
+    /// Sets the options that are applied to captures.
+    ///
+    /// The settings are written to the camera by the next capture, so that an error is reported
+    /// by the capture. They are only written again when the options are changed; settings that
+    /// are changed on the camera in the meantime are not restored.
+    pub fn set_capture_options(&mut self, options: CaptureOptions) {
+        self.capture_options = options;
+        self.capture_options_applied = false;
+    }
+
+    /// Applies the capture options before a capture.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the options could not be applied:
+    ///
+    /// * `NotSupported` if the camera can't disable autofocus or select the capture target.
+    pub(crate) fn prepare_capture(&mut self) -> crate::Result<()> {
+        if !self.capture_options_applied {
+            if let Some(target) = self.capture_options.target {
+                self.set_capture_target(target)?;
+            }
+
+            if let Some(autofocus) = self.capture_options.autofocus {
+                self.set_autofocus(autofocus)?;
+            }
+
+            self.capture_options_applied = true;
+        }
+
+        if self.capture_options.autofocus == Some(Af::Before) {
+            // Cameras without the setting focus when the shutter is released.
+            if let Ok(mut config) = self.single_config(AUTOFOCUS_DRIVE) {
+                config.set_value(AUTOFOCUS_DRIVE, WidgetValue::Toggle(true))?;
+                self.set_single_config(&mut config)?;
+            }
+        }
+
+        if !self.capture_options.settle.is_zero() {
+            thread::sleep(self.capture_options.settle);
+        }
+
+        Ok(())
+    }
+
+    fn set_capture_target(&mut self, target: CaptureTarget) -> crate::Result<()> {
+        let mut config = self.single_config(CAPTURE_TARGET)?;
+
+        let keyword = match target {
+            CaptureTarget::Ram => "ram",
+            CaptureTarget::Card => "card",
+        };
+
+        let choice = find_choice(&config, |choice| choice.contains(keyword))?;
+
+        config.set_value(CAPTURE_TARGET, WidgetValue::Choice(choice))?;
+        self.set_single_config(&mut config)
+    }
+
+    fn set_autofocus(&mut self, autofocus: Af) -> crate::Result<()> {
+        let config = AUTOFOCUS_SETTINGS
+            .iter()
+            .find_map(|name| self.single_config(name).ok());
+
+        let mut config = match (config, autofocus) {
+            (Some(config), _) => config,
+            // Autofocus is the default of cameras that don't expose it.
+            (None, Af::Before) => return Ok(()),
+            (None, Af::Off) => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                ))
+            }
+        };
+
+        let name = config.root().name().into_owned();
+        let value = autofocus_value(&config, autofocus)?;
+
+        config.set_value(&name, value)?;
+        self.set_single_config(&mut config)
+    }
+
+    /// Turns autofocus off, if the camera exposes it, and returns the setting with its previous
+    /// value so that it can be restored.
+    ///
+    /// Returns `None` if autofocus could not be turned off.
+    pub(crate) fn turn_autofocus_off(&mut self) -> Option<(Config, WidgetValue)> {
+        let mut config = AUTOFOCUS_SETTINGS
+            .iter()
+            .find_map(|name| self.single_config(name).ok())?;
+
+        let name = config.root().name().into_owned();
+        let original = config.root().value()?;
+        let value = autofocus_value(&config, Af::Off).ok()?;
+
+        config.set_value(&name, value).ok()?;
+        self.set_single_config(&mut config).ok()?;
+
+        Some((config, original))
+    }
+}
+
+/// Returns the value of an autofocus setting that selects a policy.
+fn autofocus_value(config: &Config, autofocus: Af) -> crate::Result<WidgetValue> {
+    match config.root().value() {
+        Some(WidgetValue::Toggle(_)) => Ok(WidgetValue::Toggle(autofocus == Af::Before)),
+        _ => {
+            let accepted = match autofocus {
+                Af::Before => AUTOFOCUS_ON,
+                Af::Off => AUTOFOCUS_OFF,
+            };
+
+            Ok(WidgetValue::Choice(find_choice(config, |choice| {
+                accepted.contains(&choice)
+            })?))
+        }
+    }
+}
+
+/// Finds the choice of a setting whose lowercase value matches a predicate.
+fn find_choice<P>(config: &Config, predicate: P) -> crate::Result<String>
+where
+    P: Fn(&str) -> bool,
+{
+    match config
+        .root()
+        .choices()
+        .into_iter()
+        .find(|choice| predicate(&choice.to_ascii_lowercase()))
+    {
+        Some(choice) => Ok(choice),
+        None => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        )),
+    }
+}
+
+/// Identifies a capture started by `Camera::trigger_capture()`.
+///
+/// Capture IDs are assigned in increasing order for each camera.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+pub struct CaptureId(u64);
+
+impl CaptureId {
+    /// Returns the capture's sequence number.
+    pub fn sequence(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A completed capture and the files that it produced.
+///
+/// A single capture can produce several files, e.g., a RAW and a JPEG file.
+#[derive(Debug)]
+pub struct Capture {
+    id: CaptureId,
+    files: Vec<CameraFile>,
+    complete: bool,
+}
+
+impl Capture {
+    /// Returns the ID that was returned by the `trigger_capture()` call that started the capture.
+    pub fn id(&self) -> CaptureId {
+        self.id
+    }
+
+    /// Returns the files produced by the capture.
+    pub fn files(&self) -> &[CameraFile] {
+        &self.files
+    }
+
+    /// Consumes the capture, returning its files.
+    pub fn into_files(self) -> Vec<CameraFile> {
+        self.files
+    }
+}
+
+/// Tracks triggered captures and attributes events to them.
+///
+/// Cameras report `CaptureComplete` and `FileAdded` events in the order of the triggers, but the
+/// files of one capture can be reported before or after its `CaptureComplete` event. A file is
+/// attributed to the capture that already has a file with the same name stem (e.g. the RAW file
+/// of a RAW+JPEG pair), or else to the oldest capture that doesn't have any files yet.
+pub(crate) struct CaptureTracker {
+    next_sequence: u64,
+    pending: VecDeque<Capture>,
+}
+
+impl CaptureTracker {
+    pub fn new() -> Self {
+        CaptureTracker {
+            next_sequence: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn trigger(&mut self) -> CaptureId {
+        let id = CaptureId(self.next_sequence);
+        self.next_sequence += 1;
+
+        self.pending.push_back(Capture {
+            id,
+            files: Vec::new(),
+            complete: false,
+        });
+
+        id
+    }
+
+    /// Forgets the most recent trigger, e.g., because triggering failed.
+    pub fn cancel_last(&mut self) {
+        self.pending.pop_back();
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn file_added(&mut self, file: &CameraFile) {
+        let file_stem = stem(&file.basename()).to_owned();
+
+        let index = self
+            .pending
+            .iter()
+            .position(|capture| {
+                capture
+                    .files
+                    .iter()
+                    .any(|other| file_stem == stem(&other.basename()))
+            })
+            .or_else(|| {
+                self.pending
+                    .iter()
+                    .position(|capture| capture.files.is_empty())
+            })
+            .or_else(|| self.pending.iter().position(|capture| !capture.complete));
+
+        if let Some(index) = index {
+            self.pending[index].files.push(file.clone());
+        }
+    }
+
+    pub fn capture_complete(&mut self) {
+        if let Some(capture) = self.pending.iter_mut().find(|capture| !capture.complete) {
+            capture.complete = true;
+        }
+    }
+
+    /// Returns the number of files that the oldest capture has produced so far.
+    pub fn oldest_files(&self) -> usize {
+        self.pending
+            .front()
+            .map_or(0, |capture| capture.files.len())
+    }
+
+    /// Returns `true` if the oldest capture is complete and has produced files.
+    pub fn is_ready(&self) -> bool {
+        match self.pending.front() {
+            Some(capture) => capture.complete && !capture.files.is_empty(),
+            None => false,
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Capture> {
+        self.pending.pop_front()
+    }
+}
+
+fn stem(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(index) => &name[..index],
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::camera::file_from_parts;
+
+    fn names(capture: &Capture) -> Vec<String> {
+        capture
+            .files()
+            .iter()
+            .map(|file| file.basename().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn attributes_files_to_captures() {
+        let mut tracker = CaptureTracker::new();
+
+        let first = tracker.trigger();
+        let second = tracker.trigger();
+        assert!(first < second);
+
+        tracker.file_added(&file_from_parts("/DCIM", "IMG_0001.CR2"));
+        tracker.file_added(&file_from_parts("/DCIM", "IMG_0002.CR2"));
+        // The JPEG of a RAW+JPEG capture belongs to the capture of its RAW file.
+        tracker.file_added(&file_from_parts("/DCIM", "IMG_0001.JPG"));
+        assert_eq!(tracker.oldest_files(), 2);
+
+        assert!(!tracker.is_ready());
+        tracker.capture_complete();
+        assert!(tracker.is_ready());
+
+        let capture = tracker.pop().unwrap();
+        assert_eq!(capture.id(), first);
+        assert_eq!(names(&capture), ["IMG_0001.CR2", "IMG_0001.JPG"]);
+
+        assert!(!tracker.is_ready());
+        tracker.capture_complete();
+
+        let capture = tracker.pop().unwrap();
+        assert_eq!(capture.id(), second);
+        assert_eq!(names(&capture), ["IMG_0002.CR2"]);
+        assert!(!tracker.has_pending());
+    }
+
+    #[test]
+    fn attributes_unmatched_files_to_the_oldest_incomplete_capture() {
+        let mut tracker = CaptureTracker::new();
+
+        tracker.trigger();
+        tracker.trigger();
+        tracker.file_added(&file_from_parts("/DCIM", "IMG_0001.JPG"));
+        tracker.file_added(&file_from_parts("/DCIM", "IMG_0002.JPG"));
+        tracker.capture_complete();
+        tracker.file_added(&file_from_parts("/DCIM", "IMG_0003.JPG"));
+
+        let capture = tracker.pop().unwrap();
+        assert_eq!(names(&capture), ["IMG_0001.JPG"]);
+
+        let capture = tracker.pop().unwrap();
+        assert_eq!(names(&capture), ["IMG_0002.JPG", "IMG_0003.JPG"]);
+    }
+
+    #[test]
+    fn ignores_files_without_captures() {
+        let mut tracker = CaptureTracker::new();
+
+        tracker.file_added(&file_from_parts("/DCIM", "IMG_0001.JPG"));
+        tracker.capture_complete();
+        assert!(!tracker.has_pending());
+
+        let first = tracker.trigger();
+        tracker.cancel_last();
+        assert!(!tracker.has_pending());
+
+        // Sequence numbers of canceled triggers aren't reused.
+        assert_eq!(tracker.trigger().sequence(), first.sequence() + 1);
+        assert_eq!(tracker.oldest_files(), 0);
+        assert!(!tracker.is_ready());
+    }
+
+    #[test]
+    fn strips_extensions() {
+        let cases = [
+            ("IMG_0001.JPG", "IMG_0001"),
+            ("IMG_0001", "IMG_0001"),
+            ("archive.tar.gz", "archive.tar"),
+            (".hidden", ""),
+        ];
+
+        for &(name, expected) in cases.iter() {
+            assert_eq!(stem(name), expected);
+        }
+    }
+
+    #[test]
+    fn caps_busy_retry_delays_at_the_deadline() {
+        let start = Instant::now();
+        let mut backoff = BusyBackoff {
+            deadline: start + Duration::from_secs(10),
+            delay: BUSY_RETRY_DELAY,
+        };
+
+        let mut now = start;
+        let mut delays = Vec::new();
+
+        while let Some(delay) = backoff.next_delay(now) {
+            delays.push(delay.as_millis());
+            now += delay;
+        }
+
+        assert_eq!(
+            delays,
+            [5, 10, 20, 40, 80, 160, 320, 640, 1280, 2000, 2000, 2000, 1445]
+        );
+        assert_eq!(now, start + Duration::from_secs(10));
+    }
+
+    #[test]
+    fn disables_busy_retries_without_a_deadline() {
+        let now = Instant::now();
+        let mut backoff = BusyBackoff {
+            deadline: now,
+            delay: BUSY_RETRY_DELAY,
+        };
+
+        assert_eq!(backoff.next_delay(now), None);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::camera::Camera;
+use crate::storage::Storage;
+use crate::widget::{Config, WidgetValue};
+
+/// The prefix of the vendor settings that contain the serial numbers of memory cards.
+///
+/// Cameras with several slots append the slot number to the name, e.g., `cardserialnumber2`.
+const CARD_SERIAL_SETTING: &str = "cardserialnumber";
+
+/// Information about a storage and the physical card behind it.
+///
+/// Card information is read with `Camera::card_info()`.
+pub struct CardInfo {
+    storage: Storage,
+    serial_number: Option<String>,
+}
+
+impl CardInfo {
+    /// The storage's information as reported by the camera.
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    /// The card's serial number.
+    ///
+    /// Returns `None` if the camera doesn't expose the serial numbers of its cards, which is the
+    /// case for most drivers.
+    pub fn serial_number(&self) -> Option<&str> {
+        self.serial_number.as_deref()
+    }
+}
+
+impl Camera {
+    /// Reads information about the camera's storages and cards.
+    ///
+    /// This combines the storage information with the card serial numbers that some drivers
+    /// expose as vendor settings, so that fleet tools can track which physical cards are in
+    /// which bodies.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// for card in camera.card_info().unwrap() {
+    ///     let storage = card.storage();
+    ///
+    ///     println!("{:?} ({:?})", storage.label(), card.serial_number());
+    ///     println!("    removable = {:?}", storage.is_removable());
+    ///     println!("     writable = {:?}", storage.is_writable());
+    ///     println!("   filesystem = {:?}", storage.filesystem_type());
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the storage information could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't report information about its storages.
+    pub fn card_info(&mut self) -> crate::Result<Vec<CardInfo>> {
+        let storages = self.storage()?;
+        let config = self.config().ok();
+        let count = storages.len();
+
+        Ok(storages
+            .into_iter()
+            .enumerate()
+            .map(|(index, storage)| {
+                let serial_number = config
+                    .as_ref()
+                    .and_then(|config| card_serial(config, index + 1, count));
+
+                CardInfo {
+                    storage,
+                    serial_number,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Reads the serial number of the card in a slot from the vendor settings.
+fn card_serial(config: &Config, slot: usize, slots: usize) -> Option<String> {
+    let numbered = format!("{}{}", CARD_SERIAL_SETTING, slot);
+
+    let value = match config.value(&numbered) {
+        Some(value) => value,
+        None if slots == 1 => config.value(CARD_SERIAL_SETTING)?,
+        None => return None,
+    };
+
+    match value {
+        WidgetValue::Text(serial) | WidgetValue::Choice(serial) if !serial.trim().is_empty() => {
+            Some(serial.trim().to_owned())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A source of time for scheduling.
+///
+/// Helpers that schedule camera operations take a `Clock` instead of reading the system time
+/// directly, so that their schedules can be tested deterministically and simulated faster than
+/// real time with a `SimulatedClock`. `SystemClock` is used by default.
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Blocks for the given duration.
+    fn sleep(&self, duration: Duration);
+
+    /// Blocks until the given time.
+    ///
+    /// Returns immediately if the time has already passed.
+    fn sleep_until(&self, deadline: Instant) {
+        let now = self.now();
+
+        if deadline > now {
+            self.sleep(deadline - now);
+        }
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+}
+
+/// The system's monotonic clock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A clock whose time only advances when it is told to.
+///
+/// Sleeping on a simulated clock advances its time by the sleep duration and returns
+/// immediately, so a schedule that spans hours runs in milliseconds.
+///
+/// ## Example
+///
+/// ```
+/// use std::time::Duration;
+/// use gphoto::{Clock, SimulatedClock};
+///
+/// let clock = SimulatedClock::new();
+/// let start = clock.now();
+///
+/// clock.sleep(Duration::from_secs(3600));
+/// assert_eq!(clock.now() - start, Duration::from_secs(3600));
+/// ```
+#[derive(Debug)]
+pub struct SimulatedClock {
+    now: Mutex<Instant>,
+}
+
+impl SimulatedClock {
+    /// Creates a simulated clock that starts at the current system time.
+    pub fn new() -> Self {
+        SimulatedClock {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Advances the clock's time.
+    pub fn advance(&self, duration: Duration) {
+        match self.now.lock() {
+            Ok(mut now) => *now += duration,
+            Err(poisoned) => *poisoned.into_inner() += duration,
+        }
+    }
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        SimulatedClock::new()
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Instant {
+        match self.now.lock() {
+            Ok(now) => *now,
+            Err(poisoned) => *poisoned.into_inner(),
+        }
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// Converts days since the Unix epoch to a date in the proleptic Gregorian calendar.
+///
+/// Returns the year, the month (1-12) and the day of the month (1-31), computed with Howard
+/// Hinnant's `civil_from_days` algorithm, which doesn't need a calendar library.
+pub(crate) fn civil_date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_days_to_dates() {
+        let cases = [
+            (0, (1970, 1, 1)),
+            (-1, (1969, 12, 31)),
+            (59, (1970, 3, 1)),
+            (11016, (2000, 2, 29)),
+            (11017, (2000, 3, 1)),
+            (-25508, (1900, 3, 1)),
+            (47540, (2100, 2, 28)),
+            (20088, (2024, 12, 31)),
+            (-135_140, (1600, 1, 1)),
+        ];
+
+        for &(days, date) in cases.iter() {
+            assert_eq!(civil_date(days), date, "{}", days);
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::widget::{Config, WidgetValue};
+
+/// Policies for caching a camera's configuration.
+///
+/// Fetching a camera's configuration is slow on many drivers, because the whole configuration
+/// tree is rebuilt even when only a single setting is needed. A cache policy lets repeated reads
+/// with `Camera::setting()` be served from a cached copy of the tree instead.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// camera.set_config_cache(gphoto::ConfigCache::Ttl(Duration::from_secs(2)));
+///
+/// // only the first read fetches the configuration from the camera
+/// for _ in 0..10 {
+///     println!("iso = {:?}", camera.setting("iso").unwrap());
+/// }
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ConfigCache {
+    /// The configuration is fetched from the camera for every read.
+    #[default]
+    Disabled,
+
+    /// The cached configuration is refreshed once it is older than the given duration.
+    Ttl(Duration),
+
+    /// The cached configuration is only refreshed by `Camera::refresh_config()`.
+    Manual,
+}
+
+/// A cached configuration tree.
+///
+/// Individual widgets can be invalidated, which causes only that widget to be fetched again on
+/// the next read.
+pub(crate) struct CachedConfig {
+    policy: ConfigCache,
+    config: Option<Config>,
+    fetched: Option<Instant>,
+    stale: BTreeSet<String>,
+}
+
+impl CachedConfig {
+    pub fn new() -> Self {
+        CachedConfig {
+            policy: ConfigCache::default(),
+            config: None,
+            fetched: None,
+            stale: BTreeSet::new(),
+        }
+    }
+
+    pub fn policy(&self) -> ConfigCache {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: ConfigCache) {
+        self.policy = policy;
+    }
+
+    /// Returns `true` if the cached tree can be used according to the cache policy.
+    pub fn is_fresh(&self) -> bool {
+        if self.config.is_none() {
+            return false;
+        }
+
+        match self.policy {
+            ConfigCache::Disabled => false,
+            ConfigCache::Ttl(ttl) => match self.fetched {
+                Some(fetched) => fetched.elapsed() < ttl,
+                None => false,
+            },
+            ConfigCache::Manual => true,
+        }
+    }
+
+    pub fn store(&mut self, config: Config) {
+        self.config = Some(config);
+        self.fetched = Some(Instant::now());
+        self.stale.clear();
+    }
+
+    pub fn config(&self) -> Option<&Config> {
+        self.config.as_ref()
+    }
+
+    pub fn config_mut(&mut self) -> Option<&mut Config> {
+        self.config.as_mut()
+    }
+
+    pub fn take(&mut self) -> Option<Config> {
+        self.fetched = None;
+        self.stale.clear();
+        self.config.take()
+    }
+
+    pub fn is_stale(&self, name: &str) -> bool {
+        self.stale.contains(name)
+    }
+
+    pub fn invalidate(&mut self, name: &str) {
+        self.stale.insert(name.to_owned());
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.config = None;
+        self.fetched = None;
+        self.stale.clear();
+    }
+
+    pub fn validate(&mut self, name: &str) {
+        self.stale.remove(name);
+    }
+}
+
+/// A change of a camera setting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingChanged {
+    /// The name of the setting, e.g., `"iso"`.
+    pub name: String,
+
+    /// The path of the setting, e.g., `"/main/imgsettings/iso"`.
+    ///
+    /// Settings are identified by their paths, because some cameras report several settings with
+    /// the same name in different sections.
+    pub path: String,
+
+    /// The previous value, or `None` if the setting didn't exist before.
+    pub old: Option<WidgetValue>,
+
+    /// The new value, or `None` if the setting no longer exists.
+    pub new: Option<WidgetValue>,
+}
+
+impl SettingChanged {
+    fn new(path: &str, old: Option<WidgetValue>, new: Option<WidgetValue>) -> Self {
+        SettingChanged {
+            name: path.rsplit('/').next().unwrap_or(path).to_owned(),
+            path: path.to_owned(),
+            old,
+            new,
+        }
+    }
+}
+
+/// Watches a camera's configuration for changes.
+///
+/// A `ConfigWatcher` detects settings that were changed on the camera itself, e.g., when the
+/// photographer turns a dial on the body, by periodically fetching the configuration and comparing
+/// it to the previous one.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut watcher = gphoto::ConfigWatcher::new(Duration::from_secs(1));
+///
+/// loop {
+///     for change in watcher.wait(&mut camera).unwrap() {
+///         println!("{}: {:?} -> {:?}", change.name, change.old, change.new);
+///     }
+/// }
+/// ```
+pub struct ConfigWatcher {
+    interval: Duration,
+    last_poll: Option<Instant>,
+    values: Option<BTreeMap<String, WidgetValue>>,
+}
+
+impl ConfigWatcher {
+    /// Creates a watcher that polls the configuration at the given interval.
+    pub fn new(interval: Duration) -> Self {
+        ConfigWatcher {
+            interval,
+            last_poll: None,
+            values: None,
+        }
+    }
+
+    /// Returns the polling interval.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Fetches the configuration and returns the settings that changed since the last poll.
+    ///
+    /// The first poll records the current configuration and doesn't report any changes.
+    pub fn poll(&mut self, camera: &mut Camera) -> crate::Result<Vec<SettingChanged>> {
+        let config = camera.config()?;
+        self.last_poll = Some(Instant::now());
+
+        let values: BTreeMap<String, WidgetValue> = config
+            .dump()
+            .widgets()
+            .into_iter()
+            .filter_map(|widget| {
+                let value = widget.value()?.clone();
+                Some((widget.path().to_owned(), value))
+            })
+            .collect();
+
+        let changes = match self.values {
+            Some(ref old) => diff(old, &values),
+            None => Vec::new(),
+        };
+
+        self.values = Some(values);
+
+        Ok(changes)
+    }
+
+    /// Waits until the polling interval has elapsed since the last poll, then polls.
+    pub fn wait(&mut self, camera: &mut Camera) -> crate::Result<Vec<SettingChanged>> {
+        if let Some(last_poll) = self.last_poll {
+            let elapsed = last_poll.elapsed();
+
+            if elapsed < self.interval {
+                thread::sleep(self.interval - elapsed);
+            }
+        }
+
+        self.poll(camera)
+    }
+}
+
+fn diff(
+    old: &BTreeMap<String, WidgetValue>,
+    new: &BTreeMap<String, WidgetValue>,
+) -> Vec<SettingChanged> {
+    let mut changes = Vec::new();
+
+    for (path, old_value) in old {
+        match new.get(path) {
+            Some(new_value) if new_value == old_value => (),
+            new_value => changes.push(SettingChanged::new(
+                path,
+                Some(old_value.clone()),
+                new_value.cloned(),
+            )),
+        }
+    }
+
+    for (path, new_value) in new {
+        if !old.contains_key(path) {
+            changes.push(SettingChanged::new(path, None, Some(new_value.clone())));
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(settings: &[(&str, &str)]) -> BTreeMap<String, WidgetValue> {
+        settings
+            .iter()
+            .map(|&(path, value)| (path.to_owned(), WidgetValue::Choice(value.to_owned())))
+            .collect()
+    }
+
+    #[test]
+    fn diffs_settings_by_path() {
+        let old = values(&[
+            ("/main/imgsettings/iso", "100"),
+            ("/main/capturesettings/aperture", "5.6"),
+            ("/main/capturesettings/shutterspeed", "1/125"),
+            ("/main/settings/mode", "Manual"),
+            ("/main/other/mode", "Single"),
+        ]);
+        let new = values(&[
+            ("/main/imgsettings/iso", "400"),
+            ("/main/capturesettings/aperture", "5.6"),
+            ("/main/settings/mode", "Manual"),
+            ("/main/other/mode", "Continuous"),
+            ("/main/imgsettings/whitebalance", "Auto"),
+        ]);
+
+        let cases = [
+            ("/main/imgsettings/iso", "iso", Some("100"), Some("400")),
+            (
+                "/main/capturesettings/shutterspeed",
+                "shutterspeed",
+                Some("1/125"),
+                None,
+            ),
+            (
+                "/main/other/mode",
+                "mode",
+                Some("Single"),
+                Some("Continuous"),
+            ),
+            (
+                "/main/imgsettings/whitebalance",
+                "whitebalance",
+                None,
+                Some("Auto"),
+            ),
+        ];
+
+        // The unchanged settings aren't reported, even if they share a name with a changed one.
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), cases.len(), "{:?}", changes);
+
+        for &(path, name, old, new) in cases.iter() {
+            let choice = |value: Option<&str>| value.map(|v| WidgetValue::Choice(v.to_owned()));
+            let expected = SettingChanged {
+                name: name.to_owned(),
+                path: path.to_owned(),
+                old: choice(old),
+                new: choice(new),
+            };
+
+            assert!(changes.contains(&expected), "{:?}", expected);
+        }
+    }
+}
@@ -1,32 +1,382 @@
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+use cstr_core::CStr;
+#[cfg(feature = "std")]
+use libc::{c_char, c_void};
+
 use crate::handle::{Handle, HandleMut};
 
+/// A callback that receives the messages of a context.
+#[cfg(feature = "std")]
+type Callback = Box<dyn Fn(&str) + Send>;
+
+/// The messages that drivers report to a context, and the callbacks that receive them.
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct Messages {
+    last_error: Mutex<Option<String>>,
+    on_error: Mutex<Option<Callback>>,
+    on_status: Mutex<Option<Callback>>,
+    on_message: Mutex<Option<Callback>>,
+    on_idle: Mutex<Option<Box<dyn FnMut() + Send>>>,
+}
+
 /// A `libgphoto2` library context.
-pub(crate) struct Context {
+///
+/// Drivers report errors, status updates and messages for the user to a context, and check it for
+/// cancellation. Each camera is bound to a context: `Camera::autodetect()` and similar functions
+/// create a new context per camera, while `Camera::autodetect_in()` and
+/// `DetectedCamera::open_in()` bind the camera to a given context. This allows subsystems to be
+/// isolated from each other, e.g., a diagnostics camera whose context logs every message, and
+/// production cameras whose context is quiet.
+///
+/// Cloning a context returns another handle to the same context. Cameras that are bound to the
+/// same context share its callbacks, its most recent error and its cancellation requests.
+///
+/// ## Example
+///
+/// ```no_run
+/// let diagnostics = gphoto::Context::new().unwrap();
+///
+/// diagnostics.on_error(|text| eprintln!("error: {}", text));
+/// diagnostics.on_status(|text| eprintln!("status: {}", text));
+/// diagnostics.on_message(|text| eprintln!("message: {}", text));
+///
+/// let camera = gphoto::Camera::autodetect_in(&diagnostics).unwrap();
+/// ```
+pub struct Context {
     pub(crate) context: *mut crate::gphoto2::GPContext,
+    #[cfg(feature = "std")]
+    inner: Arc<Inner>,
+}
+
+/// The state that is shared by the handles of a context.
+///
+/// The `GPContext` is released when the last handle is dropped. Its reference count isn't
+/// atomic, so handles share the `Inner` instead of referencing the `GPContext` themselves.
+#[cfg(feature = "std")]
+struct Inner {
+    context: *mut crate::gphoto2::GPContext,
+    cancel: Arc<AtomicBool>,
+    messages: Messages,
+}
+
+#[cfg(feature = "std")]
+impl Drop for Inner {
+    fn drop(&mut self) {
+        unsafe {
+            crate::gphoto2::gp_context_unref(self.context);
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            crate::gphoto2::gp_context_unref(self.context);
+        }
+    }
+}
+
+// All callbacks are registered when the context is created, so the `GPContext` is only read
+// afterwards, and the callbacks only access data that is synchronized.
+#[cfg(feature = "std")]
+unsafe impl Send for Inner {}
+#[cfg(feature = "std")]
+unsafe impl Sync for Inner {}
+#[cfg(feature = "std")]
+unsafe impl Send for Context {}
+
+#[cfg(feature = "std")]
+impl Clone for Context {
+    fn clone(&self) -> Self {
+        Context {
+            context: self.context,
+            inner: self.inner.clone(),
+        }
+    }
 }
 
 impl Context {
     /// Creates a new context.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the context could not be created:
+    ///
+    /// * `Other` if `libgphoto2` ran out of memory.
     pub fn new() -> crate::Result<Context> {
         let ptr = unsafe { crate::gphoto2::gp_context_new() };
 
         if !ptr.is_null() {
-            Ok(Context { context: ptr })
+            #[cfg(feature = "std")]
+            let inner = Arc::new(Inner {
+                context: ptr,
+                cancel: Arc::new(AtomicBool::new(false)),
+                messages: Messages::default(),
+            });
+
+            // The data is owned by the `Inner`, which releases the `GPContext` when it is dropped,
+            // so it outlives the callback registrations. The callbacks do nothing until they are
+            // set, and setting them only swaps the boxed closures, so the `GPContext` is never
+            // written to while it may be in use on another thread.
+            #[cfg(feature = "std")]
+            unsafe {
+                let messages = &inner.messages as *const Messages as *mut c_void;
+
+                crate::gphoto2::gp_context_set_cancel_func(
+                    ptr,
+                    Some(cancel_func),
+                    Arc::as_ptr(&inner.cancel) as *mut c_void,
+                );
+                crate::gphoto2::gp_context_set_error_func(ptr, Some(error_func), messages);
+                crate::gphoto2::gp_context_set_status_func(ptr, Some(status_func), messages);
+                crate::gphoto2::gp_context_set_message_func(ptr, Some(message_func), messages);
+                crate::gphoto2::gp_context_set_idle_func(ptr, Some(idle_func), messages);
+            }
+
+            Ok(Context {
+                context: ptr,
+                #[cfg(feature = "std")]
+                inner,
+            })
         } else {
             Err(crate::error::from_libgphoto2(
                 crate::gphoto2::GP_ERROR_NO_MEMORY,
             ))
         }
     }
-}
 
-/*impl Drop for Context {
-    fn drop(&mut self) {
-        unsafe {
-            crate::gphoto2::gp_context_unref(self.context);
+    /// Sets a callback that receives the error messages of drivers.
+    ///
+    /// The most recent error is also kept for `Camera::last_error()`.
+    #[cfg(feature = "std")]
+    pub fn on_error<F: Fn(&str) + Send + 'static>(&self, callback: F) {
+        *lock(&self.inner.messages.on_error) = Some(Box::new(callback));
+    }
+
+    /// Sets a callback that receives the status updates of drivers, e.g., `"Downloading..."`.
+    #[cfg(feature = "std")]
+    pub fn on_status<F: Fn(&str) + Send + 'static>(&self, callback: F) {
+        *lock(&self.inner.messages.on_status) = Some(Box::new(callback));
+    }
+
+    /// Sets a callback that receives messages that drivers address to the user.
+    #[cfg(feature = "std")]
+    pub fn on_message<F: Fn(&str) + Send + 'static>(&self, callback: F) {
+        *lock(&self.inner.messages.on_message) = Some(Box::new(callback));
+    }
+
+    /// Sets a callback that is called regularly while a driver waits for the camera, e.g., between
+    /// the blocks of a transfer.
+    ///
+    /// This allows single-threaded GUI applications to process their events during long blocking
+    /// calls, e.g., by running an iteration of their event loop. The callback runs on the thread
+    /// that called into the camera, and must not use cameras of the same context.
+    ///
+    /// Like the other callbacks, the callback may set the callbacks of the context, and panics in
+    /// it are dropped. While it runs, e.g., on behalf of a camera on another thread, it isn't
+    /// called again.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let context = gphoto::Context::new().unwrap();
+    /// let ticks = Arc::new(AtomicUsize::new(0));
+    ///
+    /// let counter = ticks.clone();
+    /// context.on_idle(move || {
+    ///     // Pump the UI's event loop here.
+    ///     counter.fetch_add(1, Ordering::Relaxed);
+    /// });
+    ///
+    /// let mut camera = gphoto::Camera::autodetect_in(&context).unwrap();
+    /// camera.capture_image().unwrap();
+    /// println!("idle {} times", ticks.load(Ordering::Relaxed));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn on_idle<F: FnMut() + Send + 'static>(&self, callback: F) {
+        *lock(&self.inner.messages.on_idle) = Some(Box::new(callback));
+    }
+
+    /// Returns a handle that cancels operations running in this context.
+    #[cfg(feature = "std")]
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle {
+            cancel: self.inner.cancel.clone(),
         }
     }
-}*/
+
+    /// Returns the most recent error message reported by a driver in this context.
+    #[cfg(feature = "std")]
+    pub(crate) fn last_error(&self) -> Option<String> {
+        lock(&self.inner.messages.last_error).clone()
+    }
+}
+
+/// A handle for cancelling a camera's in-flight operation from another thread.
+///
+/// Cancelling is cooperative: `libgphoto2` checks for cancellation between the blocks of a
+/// transfer and while waiting for the camera, so a cancelled operation stops at the next such
+/// point and fails with a `Cancel` error. This is the building block for timeouts on blocking
+/// calls, e.g., a future that runs a download on a worker thread can cancel it when it is dropped.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let cancel = camera.cancel_handle();
+///
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_secs(5));
+///     cancel.cancel();
+/// });
+///
+/// match camera.wait_event(Duration::from_secs(60)) {
+///     Err(ref err) if err.kind() == gphoto::ErrorKind::Cancel => println!("cancelled"),
+///     result => println!("{:?}", result),
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct CancelHandle {
+    cancel: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "std")]
+impl CancelHandle {
+    /// Requests cancellation of the camera's current operation.
+    ///
+    /// The request is consumed by the first operation that checks for it. If no operation is in
+    /// progress, the next operation is cancelled as soon as it checks for cancellation; use
+    /// `reset()` to withdraw a request that is no longer needed.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+
+    /// Withdraws a pending cancellation request.
+    pub fn reset(&self) {
+        self.cancel.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if a cancellation request is pending.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(feature = "std")]
+extern "C" fn cancel_func(
+    _context: *mut crate::gphoto2::GPContext,
+    data: *mut c_void,
+) -> crate::gphoto2::GPContextFeedback {
+    let cancel = unsafe { &*(data as *const AtomicBool) };
+
+    if cancel.swap(false, Ordering::SeqCst) {
+        crate::gphoto2::GP_CONTEXT_FEEDBACK_CANCEL
+    } else {
+        crate::gphoto2::GP_CONTEXT_FEEDBACK_OK
+    }
+}
+
+#[cfg(feature = "std")]
+extern "C" fn error_func(
+    _context: *mut crate::gphoto2::GPContext,
+    text: *const c_char,
+    data: *mut c_void,
+) {
+    let messages = unsafe { &*(data as *const Messages) };
+
+    if let Some(text) = unsafe { to_string(text) } {
+        call(&messages.on_error, |callback| callback(&text));
+
+        *lock(&messages.last_error) = Some(text);
+    }
+}
+
+#[cfg(feature = "std")]
+extern "C" fn status_func(
+    _context: *mut crate::gphoto2::GPContext,
+    text: *const c_char,
+    data: *mut c_void,
+) {
+    let messages = unsafe { &*(data as *const Messages) };
+
+    if let Some(text) = unsafe { to_string(text) } {
+        call(&messages.on_status, |callback| callback(&text));
+    }
+}
+
+#[cfg(feature = "std")]
+extern "C" fn message_func(
+    _context: *mut crate::gphoto2::GPContext,
+    text: *const c_char,
+    data: *mut c_void,
+) {
+    let messages = unsafe { &*(data as *const Messages) };
+
+    if let Some(text) = unsafe { to_string(text) } {
+        call(&messages.on_message, |callback| callback(&text));
+    }
+}
+
+#[cfg(feature = "std")]
+extern "C" fn idle_func(_context: *mut crate::gphoto2::GPContext, data: *mut c_void) {
+    let messages = unsafe { &*(data as *const Messages) };
+
+    call(&messages.on_idle, |callback| callback());
+}
+
+/// Calls the callback in a slot, if one is set.
+///
+/// The callback is taken out of its slot while it runs, so that it can set the callbacks of its
+/// context without deadlocking; a callback that it sets replaces it. A panic in the callback is
+/// dropped, because it must not unwind into `libgphoto2`.
+#[cfg(feature = "std")]
+fn call<C: ?Sized, F: FnOnce(&mut C)>(slot: &Mutex<Option<Box<C>>>, call: F) {
+    let mut callback = match lock(slot).take() {
+        Some(callback) => callback,
+        None => return,
+    };
+
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| call(&mut *callback)));
+
+    let mut slot = lock(slot);
+
+    if slot.is_none() {
+        *slot = Some(callback);
+    }
+}
+
+#[cfg(feature = "std")]
+unsafe fn to_string(text: *const c_char) -> Option<String> {
+    if text.is_null() {
+        None
+    } else {
+        Some(String::from_utf8_lossy(CStr::from_ptr(text).to_bytes()).into_owned())
+    }
+}
+
+#[cfg(feature = "std")]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
 
 #[doc(hidden)]
 impl Handle<crate::gphoto2::GPContext> for Context {
@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+
+use crate::camera::Camera;
+use crate::widget::{Config, WidgetType, WidgetValue};
+
+/// The Canon EOS setting that packs all custom functions into one value.
+const CANON_CUSTOM_FUNCTIONS: &str = "customfuncex";
+
+/// A custom function inside Canon's packed value, as a position in its list of words.
+struct PackedFunction {
+    id: u32,
+    offset: usize,
+    count: usize,
+}
+
+impl Camera {
+    /// Reads the camera's custom functions.
+    ///
+    /// Custom functions are the body-level behaviors that cameras hide in a separate menu, like
+    /// exposure step sizes or button assignments. They are returned as a map from names to
+    /// values, so that a map read from one body can be applied to others with
+    /// `set_custom_functions()`.
+    ///
+    /// The map contains:
+    ///
+    /// * For Canon EOS cameras, each function packed into the `customfuncex` setting, named
+    ///   `customfuncex.<id>` with the hexadecimal function ID. Its value is the text of the
+    ///   function's hexadecimal parameters, separated by commas.
+    /// * For drivers that group custom functions in a section of the configuration, like Nikon
+    ///   cameras' custom settings, each setting in that section under its own name.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// for (name, value) in camera.custom_functions().unwrap() {
+    ///     println!("{} = {:?}", name, value);
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera's configuration could not be read:
+    ///
+    /// * `NotSupported` if the camera can not be configured.
+    pub fn custom_functions(&mut self) -> crate::Result<BTreeMap<String, WidgetValue>> {
+        let config = self.config()?;
+        let mut functions = BTreeMap::new();
+
+        if let Some(WidgetValue::Text(packed)) = config.value(CANON_CUSTOM_FUNCTIONS) {
+            if let Some(words) = parse_words(&packed) {
+                for function in unpack(&words).unwrap_or_default() {
+                    let values = &words[function.offset..function.offset + function.count];
+
+                    functions.insert(
+                        format!("{}.{:x}", CANON_CUSTOM_FUNCTIONS, function.id),
+                        WidgetValue::Text(format_words(values)),
+                    );
+                }
+            }
+        }
+
+        for section in custom_sections(&config) {
+            for widget in config.get(&section).into_iter().flat_map(|s| s.children()) {
+                if widget.is_readonly() {
+                    continue;
+                }
+
+                if let Some(value) = widget.value() {
+                    functions.insert(widget.name().into_owned(), value);
+                }
+            }
+        }
+
+        Ok(functions)
+    }
+
+    /// Changes custom functions.
+    ///
+    /// `functions` uses the names and values returned by `custom_functions()`; functions that
+    /// aren't in the map are left unchanged. All changes are applied with a single configuration
+    /// update.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut reference = gphoto::Camera::autodetect().unwrap();
+    /// let functions = reference.custom_functions().unwrap();
+    ///
+    /// for detected in gphoto::detect_cameras().unwrap() {
+    ///     detected.open().unwrap().set_custom_functions(&functions).unwrap();
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the custom functions could not be changed:
+    ///
+    /// * `InvalidInput` if the camera doesn't have one of the functions or a value doesn't match
+    ///   the function. No changes are applied in that case.
+    /// * `NotSupported` if the camera can not be configured.
+    pub fn set_custom_functions(
+        &mut self,
+        functions: &BTreeMap<String, WidgetValue>,
+    ) -> crate::Result<()> {
+        let mut config = self.config()?;
+        let prefix = format!("{}.", CANON_CUSTOM_FUNCTIONS);
+        let mut packed = None;
+
+        for (name, value) in functions {
+            if let Some(id) = name.strip_prefix(&prefix) {
+                if packed.is_none() {
+                    packed = match config.value(CANON_CUSTOM_FUNCTIONS) {
+                        Some(WidgetValue::Text(packed)) => parse_words(&packed),
+                        _ => None,
+                    };
+                }
+
+                let words = match packed {
+                    Some(ref mut words) => words,
+                    None => return Err(invalid_input()),
+                };
+
+                let values = match (u32::from_str_radix(id, 16), value) {
+                    (Ok(id), WidgetValue::Text(values)) => {
+                        parse_words(values).map(|values| (id, values))
+                    }
+                    _ => None,
+                };
+
+                match values {
+                    Some((id, values)) => repack(words, id, &values)?,
+                    None => return Err(invalid_input()),
+                }
+            } else {
+                config.set_value(name, value.clone())?;
+            }
+        }
+
+        if let Some(words) = packed {
+            config.set_value(
+                CANON_CUSTOM_FUNCTIONS,
+                WidgetValue::Text(format_words(&words)),
+            )?;
+        }
+
+        self.set_config(&mut config)
+    }
+}
+
+/// Returns the names of the configuration sections that contain custom functions.
+fn custom_sections(config: &Config) -> Vec<String> {
+    config
+        .widgets()
+        .into_iter()
+        .filter(|widget| widget.widget_type() == WidgetType::Section)
+        .filter(|widget| {
+            widget.name().to_ascii_lowercase().contains("custom")
+                || widget.label().to_ascii_lowercase().contains("custom")
+        })
+        .map(|widget| widget.name().into_owned())
+        .collect()
+}
+
+/// Parses a list of hexadecimal words separated by commas.
+fn parse_words(text: &str) -> Option<Vec<u32>> {
+    text.split(',')
+        .map(|word| word.trim())
+        .filter(|word| !word.is_empty())
+        .map(|word| u32::from_str_radix(word, 16).ok())
+        .collect()
+}
+
+fn format_words(words: &[u32]) -> String {
+    words
+        .iter()
+        .map(|word| format!("{:x}", word))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Locates the functions in Canon's packed custom function value.
+///
+/// The value starts with its size and the number of groups. Each group starts with its ID, its
+/// size and the number of functions in it, followed by the functions. Each function is its ID,
+/// the number of its parameters and the parameters.
+fn unpack(words: &[u32]) -> Option<Vec<PackedFunction>> {
+    let mut functions = Vec::new();
+    let groups = *words.get(1)?;
+    let mut index = 2;
+
+    for _ in 0..groups {
+        let count = *words.get(index + 2)?;
+        index += 3;
+
+        for _ in 0..count {
+            let id = *words.get(index)?;
+            let count = *words.get(index + 1)? as usize;
+
+            if index + 2 + count > words.len() {
+                return None;
+            }
+
+            functions.push(PackedFunction {
+                id,
+                offset: index + 2,
+                count,
+            });
+
+            index += 2 + count;
+        }
+    }
+
+    Some(functions)
+}
+
+/// Replaces the parameters of a function in Canon's packed custom function value.
+fn repack(words: &mut [u32], id: u32, values: &[u32]) -> crate::Result<()> {
+    let functions = unpack(words).unwrap_or_default();
+
+    match functions.iter().find(|function| function.id == id) {
+        Some(function) if function.count == values.len() => {
+            words[function.offset..function.offset + function.count].copy_from_slice(values);
+            Ok(())
+        }
+        _ => Err(invalid_input()),
+    }
+}
+
+fn invalid_input() -> crate::Error {
+    crate::error::from_libgphoto2(crate::gphoto2::GP_ERROR_BAD_PARAMETERS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Size, 1 group: ID 1, size, 2 functions: 0x101 with [0], 0x102 with [1, 2].
+    const PACKED: &[u32] = &[0x30, 1, 1, 0x24, 2, 0x101, 1, 0, 0x102, 2, 1, 2];
+
+    #[test]
+    fn unpacks_functions() {
+        let functions: Vec<_> = unpack(PACKED)
+            .unwrap()
+            .iter()
+            .map(|function| (function.id, function.offset, function.count))
+            .collect();
+
+        assert_eq!(vec![(0x101, 7, 1), (0x102, 10, 2)], functions);
+        assert_eq!(0, unpack(&[0x8, 0]).unwrap().len());
+    }
+
+    #[test]
+    fn rejects_truncated_values() {
+        let cases: &[&[u32]] = &[
+            &[],
+            &[0x30],
+            &[0x30, 1],
+            &[0x30, 1, 1, 0x24],
+            &[0x30, 1, 1, 0x24, 2, 0x101],
+            &PACKED[..PACKED.len() - 1],
+        ];
+
+        for words in cases {
+            assert!(unpack(words).is_none(), "{:?}", words);
+        }
+    }
+
+    #[test]
+    fn repacks_functions() {
+        let cases = [
+            (
+                0x101,
+                vec![1],
+                Some(vec![0x30, 1, 1, 0x24, 2, 0x101, 1, 1, 0x102, 2, 1, 2]),
+            ),
+            (
+                0x102,
+                vec![3, 4],
+                Some(vec![0x30, 1, 1, 0x24, 2, 0x101, 1, 0, 0x102, 2, 3, 4]),
+            ),
+            (0x102, vec![3], None),
+            (0x103, vec![0], None),
+        ];
+
+        for (id, values, expected) in cases {
+            let mut words = PACKED.to_vec();
+            let result = repack(&mut words, id, &values);
+
+            match expected {
+                Some(expected) => {
+                    assert!(result.is_ok(), "{:x}", id);
+                    assert_eq!(expected, words);
+                }
+                None => {
+                    assert_eq!(crate::ErrorKind::InvalidInput, result.unwrap_err().kind());
+                    assert_eq!(PACKED, &words[..]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_words() {
+        assert_eq!(Some(PACKED.to_vec()), parse_words(&format_words(PACKED)));
+        assert_eq!(Some(vec![0xa, 0xb]), parse_words(" a, b ,"));
+        assert_eq!(Some(vec![]), parse_words(""));
+        assert_eq!(None, parse_words("a,g"));
+    }
+}
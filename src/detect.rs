@@ -0,0 +1,595 @@
+use core::mem::MaybeUninit;
+use cstr_core::CString;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::abilities::{Abilities, CameraOperation};
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::lifecycle::CameraState;
+use crate::list::List;
+use crate::port::Port;
+
+use crate::handle::prelude::*;
+
+/// A camera that was found by `detect_cameras()`.
+///
+/// A detected camera isn't opened until `open()` is called, so detecting cameras doesn't claim
+/// their USB devices.
+pub struct DetectedCamera {
+    model: String,
+    port: String,
+    abilities: Abilities,
+    usb_vendor: Option<u16>,
+}
+
+impl DetectedCamera {
+    /// Returns the name of the camera's model.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Returns the path of the port that the camera is connected to, e.g., `"usb:001,004"`.
+    pub fn port(&self) -> &str {
+        &self.port
+    }
+
+    /// Returns the abilities of the camera's driver.
+    pub fn abilities(&self) -> &Abilities {
+        &self.abilities
+    }
+
+    /// Opens the camera.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera could not be opened:
+    ///
+    /// * `InvalidInput` if the camera's port is no longer available.
+    pub fn open(&self) -> crate::Result<Camera> {
+        open(&self.abilities, &self.port, Context::new()?)
+    }
+
+    /// Opens the camera, bound to a context.
+    ///
+    /// See `Camera::autodetect_in()` for details.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera could not be opened:
+    ///
+    /// * `InvalidInput` if the camera's port is no longer available.
+    pub fn open_in(&self, context: &Context) -> crate::Result<Camera> {
+        open(&self.abilities, &self.port, context.clone())
+    }
+}
+
+/// Detects the cameras that are connected to the system.
+///
+/// ## Example
+///
+/// ```no_run
+/// for camera in gphoto::detect_cameras().unwrap() {
+///     println!("{} on {} ({})", camera.model(), camera.port(), camera.stable_id());
+/// }
+/// ```
+pub fn detect_cameras() -> crate::Result<Vec<DetectedCamera>> {
+    let _serialized = crate::threading::serialize();
+
+    let mut context = Context::new()?;
+    let abilities = AbilitiesList::load(&mut context)?;
+    let ports = PortInfoList::load()?;
+    let mut list = List::new()?;
+
+    let result = unsafe {
+        crate::gphoto2::gp_abilities_list_detect(
+            abilities.list,
+            ports.list,
+            list.as_mut_ptr(),
+            context.as_mut_ptr(),
+        )
+    };
+
+    match result {
+        crate::gphoto2::GP_OK => (),
+        err => return Err(crate::error::from_libgphoto2(err)),
+    }
+
+    let mut cameras = Vec::new();
+
+    for i in 0..list.len() {
+        let model = list.name(i).into_owned();
+
+        if let Some(camera_abilities) = abilities.lookup(&model) {
+            let port = list.value(i).into_owned();
+
+            cameras.push(DetectedCamera {
+                usb_vendor: usb_vendor(&port),
+                port,
+                model,
+                abilities: camera_abilities,
+            });
+        }
+    }
+
+    Ok(cameras)
+}
+
+/// Detects the connected cameras that support an operation.
+///
+/// This is useful to ignore devices that are detected as cameras but can't be used for the task
+/// at hand, e.g., phones or card readers that speak PTP but can't capture previews.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::CameraOperation;
+///
+/// let cameras = gphoto::cameras_with(CameraOperation::CapturePreview).unwrap();
+///
+/// if let Some(detected) = cameras.first() {
+///     let mut camera = detected.open().unwrap();
+/// }
+/// ```
+pub fn cameras_with(operation: CameraOperation) -> crate::Result<Vec<DetectedCamera>> {
+    let mut cameras = detect_cameras()?;
+    cameras.retain(|camera| camera.abilities.camera_operations().contains(&operation));
+
+    Ok(cameras)
+}
+
+/// Detects the connected cameras that pass a filter.
+///
+/// ## Example
+///
+/// ```no_run
+/// let filter = gphoto::DetectFilter::new()
+///     .ignore_model("iPhone")
+///     .ignore_usb_vendor(0x04e8);
+///
+/// for camera in gphoto::detect_cameras_filtered(&filter).unwrap() {
+///     println!("{} on {}", camera.model(), camera.port());
+/// }
+/// ```
+pub fn detect_cameras_filtered(filter: &DetectFilter) -> crate::Result<Vec<DetectedCamera>> {
+    let mut cameras = detect_cameras()?;
+    cameras.retain(|camera| filter.matches(camera));
+
+    Ok(cameras)
+}
+
+/// A filter that excludes devices from detection.
+///
+/// Filters are useful to ignore devices that speak PTP but aren't the cameras that an application
+/// is meant to control, e.g., smartphones that are plugged into a photo booth to charge.
+#[derive(Debug, Clone, Default)]
+pub struct DetectFilter {
+    models: Vec<String>,
+    usb_vendors: Vec<u16>,
+    ports: Vec<String>,
+}
+
+impl DetectFilter {
+    /// Creates a filter that doesn't exclude any devices.
+    pub fn new() -> Self {
+        DetectFilter::default()
+    }
+
+    /// Excludes devices whose model name contains `model`.
+    ///
+    /// The comparison ignores ASCII case.
+    pub fn ignore_model(mut self, model: &str) -> Self {
+        self.models.push(model.to_ascii_lowercase());
+        self
+    }
+
+    /// Excludes USB devices with a vendor ID.
+    ///
+    /// The vendor ID is read from the device in sysfs, so devices that are handled by a generic
+    /// class driver, like most PTP devices, are excluded too. Where sysfs isn't available, the
+    /// vendor ID of the device's driver is used, which generic class drivers don't have.
+    pub fn ignore_usb_vendor(mut self, vendor: u16) -> Self {
+        self.usb_vendors.push(vendor);
+        self
+    }
+
+    /// Excludes devices whose port path starts with `port`.
+    ///
+    /// For example, `"usb:001,"` excludes all devices on the first USB bus and `"ptpip:"` excludes
+    /// all network cameras.
+    pub fn ignore_port(mut self, port: &str) -> Self {
+        self.ports.push(port.to_owned());
+        self
+    }
+
+    /// Returns `true` if a detected camera passes the filter.
+    pub fn matches(&self, camera: &DetectedCamera) -> bool {
+        let model = camera.model.to_ascii_lowercase();
+        let usb_vendor = camera
+            .usb_vendor
+            .unwrap_or_else(|| camera.abilities.usb_vendor());
+
+        let ignored_model = self.models.iter().any(|ignored| model.contains(ignored.as_str()));
+        let ignored_vendor = usb_vendor != 0 && self.usb_vendors.contains(&usb_vendor);
+        let ignored_port = self
+            .ports
+            .iter()
+            .any(|ignored| camera.port.starts_with(ignored.as_str()));
+
+        !(ignored_model || ignored_vendor || ignored_port)
+    }
+}
+
+/// Returns the vendor ID of the device on a USB port like `"usb:001,004"` from sysfs.
+///
+/// Returns `None` if the port isn't a USB port or the device isn't found, e.g., on other
+/// platforms.
+fn usb_vendor(port: &str) -> Option<u16> {
+    let (bus, device) = port.strip_prefix("usb:")?.split_once(',')?;
+    let bus: u32 = bus.parse().ok()?;
+    let device: u32 = device.parse().ok()?;
+
+    let read = |dir: &Path, name: &str| fs::read_to_string(dir.join(name)).ok();
+
+    fs::read_dir("/sys/bus/usb/devices")
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|dir| {
+            read(dir, "busnum").and_then(|busnum| busnum.trim().parse().ok()) == Some(bus)
+                && read(dir, "devnum").and_then(|devnum| devnum.trim().parse().ok()) == Some(device)
+        })
+        .and_then(|dir| read(&dir, "idVendor"))
+        .and_then(|vendor| u16::from_str_radix(vendor.trim(), 16).ok())
+}
+
+impl Camera {
+    /// Opens a camera by its model name and port path.
+    ///
+    /// This opens a camera without detecting it first, e.g., a network camera on a `ptpip:` port
+    /// or a camera that was remembered from an earlier session.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera could not be opened:
+    ///
+    /// * `ModelNotFound` if `libgphoto2` doesn't have a driver for the model.
+    /// * `InvalidInput` if the port doesn't exist.
+    pub fn open(model: &str, port: &str) -> crate::Result<Self> {
+        let mut context = Context::new()?;
+        let abilities = AbilitiesList::load(&mut context).map(|list| list.lookup(model));
+
+        match abilities? {
+            Some(abilities) => open(&abilities, port, context),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_MODEL_NOT_FOUND,
+            )),
+        }
+    }
+
+    /// Opens a simulated camera that serves the files in a local directory.
+    ///
+    /// The simulated camera uses `libgphoto2`'s directory driver, which presents the directory as
+    /// the camera's storage. It supports listing and downloading files, so code that processes a
+    /// camera's files can be developed and tested without hardware. It can't capture images.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    ///
+    /// let mut camera = gphoto::Camera::simulated(Path::new("testdata/DCIM")).unwrap();
+    ///
+    /// for file in camera.fs().walk("/").unwrap() {
+    ///     println!("{}", file.path());
+    /// }
+    /// ```
+    pub fn simulated(source_dir: &Path) -> crate::Result<Self> {
+        let source_dir = match source_dir.to_str() {
+            Some(source_dir) => source_dir,
+            None => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        };
+
+        Camera::open("Directory Browse", &format!("disk:{}", source_dir))
+    }
+
+    /// Opens the first detected camera that passes a filter.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if no camera could be opened:
+    ///
+    /// * `ModelNotFound` if no detected camera passes the filter.
+    pub fn autodetect_filtered(filter: &DetectFilter) -> crate::Result<Self> {
+        match detect_cameras_filtered(filter)?.first() {
+            Some(camera) => camera.open(),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_MODEL_NOT_FOUND,
+            )),
+        }
+    }
+}
+
+impl Abilities {
+    /// Returns the abilities of all camera models that `libgphoto2` has a driver for.
+    ///
+    /// Loading the drivers is slow, so the list should be loaded once and filtered, e.g., with
+    /// `Abilities::vendors()` and `Abilities::filter_by_vendor()`.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the drivers could not be loaded:
+    ///
+    /// * `Other` if `libgphoto2`'s camera drivers could not be found or loaded.
+    pub fn list() -> crate::Result<Vec<Abilities>> {
+        let mut context = Context::new()?;
+
+        Ok(AbilitiesList::load(&mut context)?.all())
+    }
+
+    /// Returns the abilities of the camera models of a vendor, e.g., `"Nikon"`.
+    ///
+    /// Vendors are compared case-insensitively with `Abilities::vendor()`. This allows
+    /// configuration UIs to offer a model picker per vendor instead of a list of all models.
+    ///
+    /// Each call loads the drivers; to query several vendors, load the list once with
+    /// `Abilities::list()` and filter it with `Abilities::filter_by_vendor()`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// for abilities in gphoto::Abilities::list_by_vendor("Nikon").unwrap() {
+    ///     println!("{}", abilities.model());
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the errors of `Abilities::list()`.
+    pub fn list_by_vendor(vendor: &str) -> crate::Result<Vec<Abilities>> {
+        Ok(Abilities::filter_by_vendor(&Abilities::list()?, vendor))
+    }
+
+    /// Returns the abilities of the camera models of a vendor from a list of abilities.
+    ///
+    /// Vendors are compared like with `Abilities::list_by_vendor()`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let models = gphoto::Abilities::list().unwrap();
+    ///
+    /// for vendor in gphoto::Abilities::vendors(&models) {
+    ///     let vendor_models = gphoto::Abilities::filter_by_vendor(&models, &vendor);
+    ///     println!("{}: {} models", vendor, vendor_models.len());
+    /// }
+    /// ```
+    pub fn filter_by_vendor(models: &[Abilities], vendor: &str) -> Vec<Abilities> {
+        models
+            .iter()
+            .filter(|abilities| abilities.vendor().eq_ignore_ascii_case(vendor))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the vendors of the camera models in a list of abilities, sorted and without
+    /// duplicates.
+    pub fn vendors(models: &[Abilities]) -> Vec<String> {
+        let vendors: BTreeSet<String> = models
+            .iter()
+            .map(|abilities| abilities.vendor().into_owned())
+            .collect();
+
+        vendors.into_iter().collect()
+    }
+}
+
+/// Opens a camera with a driver on a port.
+fn open(abilities: &Abilities, port: &str, context: Context) -> crate::Result<Camera> {
+    let mut ports = PortInfoList::load()?;
+    let port = ports.lookup(port)?;
+
+    let mut ptr = MaybeUninit::uninit();
+
+    let camera = unsafe {
+        match crate::gphoto2::gp_camera_new(&mut *ptr.as_mut_ptr()) {
+            crate::gphoto2::GP_OK => (),
+            err => return Err(crate::error::from_libgphoto2(err)),
+        }
+        ptr.assume_init()
+    };
+
+    let mut camera = crate::camera::from_libgphoto2(camera, context);
+
+    try_unsafe!(crate::gphoto2::gp_camera_set_abilities(
+        camera.camera,
+        crate::abilities::to_libgphoto2(abilities)
+    ));
+    try_unsafe!(crate::gphoto2::gp_camera_set_port_info(camera.camera, port));
+    try_unsafe!(crate::gphoto2::gp_camera_init(
+        camera.camera,
+        camera.context.as_mut_ptr()
+    ));
+
+    camera.set_state(CameraState::Initialized);
+
+    Ok(camera)
+}
+
+/// The list of camera drivers known to `libgphoto2`.
+pub(crate) struct AbilitiesList {
+    list: *mut crate::gphoto2::CameraAbilitiesList,
+}
+
+impl Drop for AbilitiesList {
+    fn drop(&mut self) {
+        unsafe {
+            crate::gphoto2::gp_abilities_list_free(self.list);
+        }
+    }
+}
+
+impl AbilitiesList {
+    pub(crate) fn load(context: &mut Context) -> crate::Result<Self> {
+        let mut ptr = MaybeUninit::uninit();
+
+        let list = unsafe {
+            match crate::gphoto2::gp_abilities_list_new(&mut *ptr.as_mut_ptr()) {
+                crate::gphoto2::GP_OK => (),
+                err => return Err(crate::error::from_libgphoto2(err)),
+            }
+            AbilitiesList {
+                list: ptr.assume_init(),
+            }
+        };
+
+        try_unsafe!(crate::gphoto2::gp_abilities_list_load(
+            list.list,
+            context.as_mut_ptr()
+        ));
+
+        Ok(list)
+    }
+
+    fn lookup(&self, model: &str) -> Option<Abilities> {
+        let model = CString::new(model).ok()?;
+        let mut abilities = MaybeUninit::uninit();
+
+        unsafe {
+            let index = crate::gphoto2::gp_abilities_list_lookup_model(self.list, model.as_ptr());
+
+            if index < 0 {
+                return None;
+            }
+
+            match crate::gphoto2::gp_abilities_list_get_abilities(
+                self.list,
+                index,
+                &mut *abilities.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK => Some(crate::abilities::from_libgphoto2(
+                    abilities.assume_init(),
+                )),
+                _ => None,
+            }
+        }
+    }
+
+    /// Returns the abilities of all camera models.
+    pub(crate) fn all(&self) -> Vec<Abilities> {
+        let count = unsafe { crate::gphoto2::gp_abilities_list_count(self.list) };
+
+        (0..count.max(0))
+            .filter_map(|index| {
+                let mut abilities = MaybeUninit::uninit();
+
+                unsafe {
+                    match crate::gphoto2::gp_abilities_list_get_abilities(
+                        self.list,
+                        index,
+                        &mut *abilities.as_mut_ptr(),
+                    ) {
+                        crate::gphoto2::GP_OK => {
+                            Some(crate::abilities::from_libgphoto2(abilities.assume_init()))
+                        }
+                        _ => None,
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// The list of ports known to `libgphoto2`.
+pub(crate) struct PortInfoList {
+    list: *mut crate::gphoto2::GPPortInfoList,
+}
+
+impl Drop for PortInfoList {
+    fn drop(&mut self) {
+        unsafe {
+            crate::gphoto2::gp_port_info_list_free(self.list);
+        }
+    }
+}
+
+impl PortInfoList {
+    pub(crate) fn load() -> crate::Result<Self> {
+        let mut ptr = MaybeUninit::uninit();
+
+        let list = unsafe {
+            match crate::gphoto2::gp_port_info_list_new(&mut *ptr.as_mut_ptr()) {
+                crate::gphoto2::GP_OK => (),
+                err => return Err(crate::error::from_libgphoto2(err)),
+            }
+            PortInfoList {
+                list: ptr.assume_init(),
+            }
+        };
+
+        match unsafe { crate::gphoto2::gp_port_info_list_load(list.list) } {
+            // The number of loaded ports is returned on success.
+            n if n >= 0 => Ok(list),
+            err => Err(crate::error::from_libgphoto2(err)),
+        }
+    }
+
+    /// Looks up a port by its path.
+    ///
+    /// The returned port info is owned by the list.
+    pub(crate) fn lookup(&mut self, path: &str) -> crate::Result<crate::gphoto2::GPPortInfo> {
+        let c_path = match CString::new(path) {
+            Ok(path) => path,
+            Err(_) => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        };
+
+        let index =
+            unsafe { crate::gphoto2::gp_port_info_list_lookup_path(self.list, c_path.as_ptr()) };
+
+        if index < 0 {
+            return Err(crate::error::from_libgphoto2(index));
+        }
+
+        let mut info = MaybeUninit::uninit();
+
+        match unsafe {
+            crate::gphoto2::gp_port_info_list_get_info(self.list, index, &mut *info.as_mut_ptr())
+        } {
+            crate::gphoto2::GP_OK => Ok(unsafe { info.assume_init() }),
+            err => Err(crate::error::from_libgphoto2(err)),
+        }
+    }
+
+    /// Returns all ports in the list.
+    pub(crate) fn ports(&self) -> Vec<Port<'_>> {
+        let count = unsafe { crate::gphoto2::gp_port_info_list_count(self.list) };
+
+        (0..count.max(0))
+            .filter_map(|index| {
+                let mut info = MaybeUninit::uninit();
+
+                match unsafe {
+                    crate::gphoto2::gp_port_info_list_get_info(
+                        self.list,
+                        index,
+                        &mut *info.as_mut_ptr(),
+                    )
+                } {
+                    crate::gphoto2::GP_OK => Some(crate::port::from_port_info(self, unsafe {
+                        info.assume_init()
+                    })),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
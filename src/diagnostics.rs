@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::abilities::Abilities;
+use crate::context::Context;
+use crate::detect::{detect_cameras, AbilitiesList, DetectedCamera, PortInfoList};
+use crate::json::{self, JsonObject};
+use crate::port::PortType;
+use crate::version::{libgphoto2_version, LibraryVersion};
+
+/// A report about the `libgphoto2` installation and the connected cameras.
+///
+/// The report contains the library's version, the loaded camera drivers (camlibs) and I/O
+/// libraries (iolibs), the detected ports, and the detected cameras with the abilities of their
+/// drivers. It is the equivalent of `gphoto2 --version --list-ports --auto-detect --abilities`,
+/// meant to be attached to bug reports from devices in the field.
+///
+/// The report is formatted as text by `Display` and as JSON by `to_json()`.
+///
+/// ## Example
+///
+/// ```no_run
+/// let diagnostics = gphoto::diagnostics().unwrap();
+///
+/// println!("{}", diagnostics);
+/// std::fs::write("diagnostics.json", diagnostics.to_json()).unwrap();
+/// ```
+pub struct Diagnostics {
+    version: LibraryVersion,
+    camlibs: Vec<String>,
+    iolibs: Vec<String>,
+    ports: Vec<PortSummary>,
+    cameras: Vec<DetectedCamera>,
+}
+
+/// A port that is known to `libgphoto2`.
+#[derive(Debug, Clone)]
+pub struct PortSummary {
+    port_type: PortType,
+    name: String,
+    path: String,
+    library: String,
+}
+
+impl PortSummary {
+    /// The type of the port.
+    pub fn port_type(&self) -> PortType {
+        self.port_type
+    }
+
+    /// The name of the port, e.g., `"Universal Serial Bus"`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The path of the port, e.g., `"usb:001,004"`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The path of the I/O library that handles the port.
+    pub fn library(&self) -> &str {
+        &self.library
+    }
+}
+
+/// Collects a diagnostics report.
+///
+/// ## Errors
+///
+/// This function returns an error if the drivers, ports or cameras could not be listed.
+pub fn diagnostics() -> crate::Result<Diagnostics> {
+    let mut context = Context::new()?;
+    let abilities = AbilitiesList::load(&mut context);
+
+    let camlibs: BTreeSet<String> = abilities?
+        .all()
+        .iter()
+        .map(|abilities| abilities.library().into_owned())
+        .collect();
+
+    let ports: Vec<PortSummary> = PortInfoList::load()?
+        .ports()
+        .iter()
+        .map(|port| PortSummary {
+            port_type: port.port_type(),
+            name: port.name().into_owned(),
+            path: port.path().into_owned(),
+            library: port.library().into_owned(),
+        })
+        .collect();
+
+    let iolibs: BTreeSet<String> = ports.iter().map(|port| port.library.clone()).collect();
+
+    Ok(Diagnostics {
+        version: libgphoto2_version(),
+        camlibs: camlibs.into_iter().collect(),
+        iolibs: iolibs.into_iter().collect(),
+        ports,
+        cameras: detect_cameras()?,
+    })
+}
+
+impl Diagnostics {
+    /// The version of `libgphoto2`.
+    pub fn library_version(&self) -> &LibraryVersion {
+        &self.version
+    }
+
+    /// The paths of the loaded camera drivers.
+    pub fn camlibs(&self) -> &[String] {
+        &self.camlibs
+    }
+
+    /// The paths of the loaded I/O libraries.
+    pub fn iolibs(&self) -> &[String] {
+        &self.iolibs
+    }
+
+    /// The ports that are known to `libgphoto2`.
+    pub fn ports(&self) -> &[PortSummary] {
+        &self.ports
+    }
+
+    /// The detected cameras.
+    pub fn cameras(&self) -> &[DetectedCamera] {
+        &self.cameras
+    }
+
+    /// Formats the report as a JSON object.
+    pub fn to_json(&self) -> String {
+        let version = JsonObject::new()
+            .string("version", self.version.version())
+            .string("camlibs", self.version.camlibs())
+            .string("compiler", self.version.compiler())
+            .string("ltdl", self.version.ltdl())
+            .string("exif", self.version.exif())
+            .finish();
+
+        let ports = self.ports.iter().map(|port| {
+            JsonObject::new()
+                .string("type", &format!("{:?}", port.port_type))
+                .string("name", &port.name)
+                .string("path", &port.path)
+                .string("library", &port.library)
+                .finish()
+        });
+
+        let cameras = self.cameras.iter().map(|camera| {
+            JsonObject::new()
+                .string("model", camera.model())
+                .string("port", camera.port())
+                .string("stable_id", camera.stable_id().as_str())
+                .raw("abilities", &abilities_json(camera.abilities()))
+                .finish()
+        });
+
+        JsonObject::new()
+            .raw("libgphoto2", &version)
+            .raw("camlibs", &strings_json(&self.camlibs))
+            .raw("iolibs", &strings_json(&self.iolibs))
+            .raw("ports", &json::array(ports))
+            .raw("cameras", &json::array(cameras))
+            .finish()
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            fmt,
+            "libgphoto2 {} ({}, {}, {}, {})",
+            self.version.version(),
+            self.version.camlibs(),
+            self.version.compiler(),
+            self.version.ltdl(),
+            self.version.exif()
+        )?;
+
+        writeln!(fmt, "camlibs ({}):", self.camlibs.len())?;
+        for camlib in &self.camlibs {
+            writeln!(fmt, "  {}", camlib)?;
+        }
+
+        writeln!(fmt, "iolibs ({}):", self.iolibs.len())?;
+        for iolib in &self.iolibs {
+            writeln!(fmt, "  {}", iolib)?;
+        }
+
+        writeln!(fmt, "ports ({}):", self.ports.len())?;
+        for port in &self.ports {
+            writeln!(
+                fmt,
+                "  {:<24} {:?}: {}",
+                port.path, port.port_type, port.name
+            )?;
+        }
+
+        writeln!(fmt, "cameras ({}):", self.cameras.len())?;
+        for camera in &self.cameras {
+            let abilities = camera.abilities();
+
+            writeln!(fmt, "  {} on {}", camera.model(), camera.port())?;
+            writeln!(fmt, "    stable id         = {}", camera.stable_id())?;
+            writeln!(
+                fmt,
+                "    driver            = {} ({:?})",
+                abilities.id(),
+                abilities.driver_status()
+            )?;
+            writeln!(fmt, "    library           = {}", abilities.library())?;
+            writeln!(
+                fmt,
+                "    usb id            = {:04x}:{:04x}",
+                abilities.usb_vendor(),
+                abilities.usb_product()
+            )?;
+            writeln!(fmt, "    port types        = {:?}", abilities.port_types())?;
+            writeln!(
+                fmt,
+                "    camera operations = {:?}",
+                abilities.camera_operations()
+            )?;
+            writeln!(
+                fmt,
+                "    file operations   = {:?}",
+                abilities.file_operations()
+            )?;
+            writeln!(
+                fmt,
+                "    folder operations = {:?}",
+                abilities.folder_operations()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn abilities_json(abilities: &Abilities) -> String {
+    JsonObject::new()
+        .string("id", &abilities.id())
+        .string("library", &abilities.library())
+        .string("status", &format!("{:?}", abilities.driver_status()))
+        .number("usb_vendor", u64::from(abilities.usb_vendor()))
+        .number("usb_product", u64::from(abilities.usb_product()))
+        .raw("port_types", &debug_json(abilities.port_types()))
+        .raw(
+            "camera_operations",
+            &debug_json(abilities.camera_operations()),
+        )
+        .raw("file_operations", &debug_json(abilities.file_operations()))
+        .raw(
+            "folder_operations",
+            &debug_json(abilities.folder_operations()),
+        )
+        .finish()
+}
+
+/// Encodes the debug representations of values, e.g., the variants of an enum, as an array.
+fn debug_json<I>(values: I) -> String
+where
+    I: IntoIterator,
+    I::Item: fmt::Debug,
+{
+    json::array(
+        values
+            .into_iter()
+            .map(|value| json::string(&format!("{:?}", value))),
+    )
+}
+
+fn strings_json(strings: &[String]) -> String {
+    json::array(strings.iter().map(|s| json::string(s)))
+}
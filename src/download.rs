@@ -0,0 +1,599 @@
+#[cfg(unix)]
+use core::mem::MaybeUninit;
+#[cfg(unix)]
+use cstr_core::CString;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use libc::c_char;
+
+use crate::camera::{Camera, CameraFile};
+use crate::fileinfo::FileInfo;
+use crate::handle::prelude::*;
+use crate::media::FileMedia;
+use crate::sidecar::SidecarFormat;
+use crate::tag::tagged_name;
+
+/// The size of the chunks that bandwidth-limited downloads are read in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The number of numeric suffixes that are tried to find a free file name.
+const MAX_SUFFIX: u32 = 10_000;
+
+/// Policies for downloading a file whose name is already taken in the destination directory.
+///
+/// Cameras reuse file names like `IMG_0001.JPG` after their file counter is reset or wraps
+/// around, so names are not unique over the lifetime of a camera.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Collision {
+    /// Fail with a `FileExists` error.
+    Fail,
+
+    /// Replace the existing file once the new download is complete.
+    Overwrite,
+
+    /// Download to a new name with a numeric suffix, e.g., `IMG_0001_1.JPG`.
+    Rename,
+
+    /// Skip the download if the existing file has the same size as the file on the camera.
+    ///
+    /// Otherwise, the file is downloaded to a new name like with `Rename`.
+    SkipIfSameSize,
+}
+
+/// Options for downloading files to a local directory.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{Collision, DownloadOptions};
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let file = camera.capture_image().unwrap();
+///
+/// let options = DownloadOptions::new().collision(Collision::Rename);
+///
+/// if let Some(path) = camera.download_to_dir(&file, "photos".as_ref(), &options).unwrap() {
+///     println!("downloaded to {}", path.display());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    collision: Collision,
+    file_type: Option<crate::CameraFileType>,
+    verify: bool,
+    bandwidth_limit: Option<u64>,
+    sidecar: Option<SidecarFormat>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        DownloadOptions {
+            collision: Collision::Fail,
+            file_type: None,
+            verify: false,
+            bandwidth_limit: None,
+            sidecar: None,
+        }
+    }
+}
+
+impl DownloadOptions {
+    /// Creates the default options.
+    ///
+    /// By default, downloads fail if the file name is taken and the normal file is downloaded.
+    pub fn new() -> Self {
+        DownloadOptions::default()
+    }
+
+    /// Sets the policy for file names that are already taken.
+    pub fn collision(mut self, collision: Collision) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    /// Sets the type of file to download, e.g., `GP_FILE_TYPE_PREVIEW`.
+    pub fn file_type(mut self, file_type: crate::CameraFileType) -> Self {
+        self.file_type = Some(file_type);
+        self
+    }
+
+    /// Enables or disables verifying downloads.
+    ///
+    /// When enabled, the size of each downloaded file is compared to the size that the camera
+    /// reports for it. On a mismatch, the file is downloaded once more, and if the sizes still
+    /// differ, the download fails with a `CorruptedData` error instead of leaving a truncated
+    /// file behind. Files whose size the camera doesn't report can't be verified.
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Limits the average download speed to a number of bytes per second.
+    ///
+    /// Limited downloads are read from the camera in chunks, with pauses in between, so that
+    /// offloading images over a shared USB hub leaves bandwidth for other cameras, e.g., for
+    /// their live view. Drivers that can't read files in chunks download at full speed. Downloads
+    /// aren't limited by default.
+    pub fn bandwidth_limit(mut self, bytes_per_second: u64) -> Self {
+        self.bandwidth_limit = Some(bytes_per_second);
+        self
+    }
+
+    /// Writes a metadata sidecar in a format next to each downloaded file.
+    ///
+    /// The sidecar records the camera's identity and the time the file was taken, and the
+    /// settings at capture time if they were recorded with `Camera::record_capture_settings()`.
+    /// See `Sidecar` for details. An existing sidecar is only replaced with `Collision::Overwrite`.
+    /// No sidecars are written by default.
+    pub fn sidecar(mut self, format: SidecarFormat) -> Self {
+        self.sidecar = Some(format);
+        self
+    }
+}
+
+impl Camera {
+    /// Downloads a file to a local directory.
+    ///
+    /// The file keeps its name from the camera, prefixed with the camera's session tag if one is
+    /// set. If the name is already taken in `dir`, the
+    /// options' `Collision` policy decides what happens. This function returns the path that the
+    /// file was downloaded to, or `None` if the download was skipped.
+    ///
+    /// The file is written to a temporary file in `dir` first, which is renamed once the download
+    /// is complete. A failed download therefore leaves no partial file behind, and with
+    /// `Collision::Overwrite`, the existing file is kept until it's replaced by the complete
+    /// download. If a sidecar is enabled but could not be written, the downloaded file is kept.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the file could not be downloaded:
+    ///
+    /// * `InvalidInput` if the file's name from the camera isn't a plain file name, e.g., because
+    ///   it contains a path separator or is `..`.
+    /// * `FileExists` if the name is taken and the policy is `Collision::Fail`, or no free name
+    ///   with a numeric suffix was found.
+    /// * `OSFailure` if the downloaded file could not be renamed, e.g., to replace an existing
+    ///   file.
+    /// * `FileNotFound` if the file doesn't exist on the camera.
+    /// * `CorruptedData` if verification is enabled and the downloaded file's size doesn't match.
+    /// * `FileExists` if a sidecar is enabled and already exists, unless the policy is
+    ///   `Collision::Overwrite`.
+    /// * `OSFailure` if a sidecar is enabled and could not be written.
+    pub fn download_to_dir(
+        &mut self,
+        file: &CameraFile,
+        dir: &Path,
+        options: &DownloadOptions,
+    ) -> crate::Result<Option<PathBuf>> {
+        match self.download_destination(file, dir, options)? {
+            Some(path) => self.download_to_destination(file, &path, options).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the local path that `download_to_dir()` downloads a file to, or `None` if the
+    /// options' `Collision` policy skips the download.
+    pub(crate) fn download_destination(
+        &mut self,
+        file: &CameraFile,
+        dir: &Path,
+        options: &DownloadOptions,
+    ) -> crate::Result<Option<PathBuf>> {
+        let name = tagged_name(self.session_tag(), &file.basename());
+
+        // The name comes from the device, so it must not escape `dir`.
+        if !is_plain_name(&name) {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            ));
+        }
+
+        let path = dir.join(&name);
+
+        if !path.exists() {
+            return Ok(Some(path));
+        }
+
+        match options.collision {
+            Collision::Fail => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_FILE_EXISTS,
+            )),
+            Collision::Overwrite => Ok(Some(path)),
+            Collision::Rename => free_path(dir, &name).map(Some),
+            Collision::SkipIfSameSize => {
+                let local_size = local_size(&path);
+
+                if local_size.is_some() && local_size == self.file_info(file)?.size() {
+                    return Ok(None);
+                }
+
+                free_path(dir, &name).map(Some)
+            }
+        }
+    }
+
+    /// Downloads a file to a local path that was chosen with `download_destination()`.
+    ///
+    /// The file is downloaded to a temporary file next to `path`, which is renamed to `path` once
+    /// the download is complete and verified, replacing a file that exists there.
+    pub(crate) fn download_to_destination(
+        &mut self,
+        file: &CameraFile,
+        path: &Path,
+        options: &DownloadOptions,
+    ) -> crate::Result<PathBuf> {
+        let partial = partial_path(path);
+
+        // A download that was interrupted, e.g., by a crash, leaves its temporary file behind.
+        if partial.exists() && fs::remove_file(&partial).is_err() {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            ));
+        }
+
+        self.download_with_options(file, &partial, options)?;
+
+        if options.verify {
+            let info = self.file_info(file)?;
+
+            if let Some(expected) = expected_size(&info, options.file_type) {
+                if local_size(&partial) != Some(expected) {
+                    // Transfer errors on flaky connections are usually transient.
+                    let _ = fs::remove_file(&partial);
+                    self.download_with_options(file, &partial, options)?;
+
+                    if local_size(&partial) != Some(expected) {
+                        let _ = fs::remove_file(&partial);
+
+                        return Err(crate::error::from_libgphoto2(
+                            crate::gphoto2::GP_ERROR_CORRUPTED_DATA,
+                        ));
+                    }
+                }
+            }
+        }
+
+        if fs::rename(&partial, path).is_err() {
+            let _ = fs::remove_file(&partial);
+
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            ));
+        }
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_download();
+
+        if let Some(format) = options.sidecar {
+            let overwrite = options.collision == Collision::Overwrite;
+            self.sidecar(file)?.write(path, format, overwrite)?;
+        }
+
+        Ok(path.to_path_buf())
+    }
+
+    /// Downloads a file to a new local file, which is removed if the download fails.
+    ///
+    /// The file isn't counted as a download, so that a download that is retried is counted once.
+    pub(crate) fn download_to_path(
+        &mut self,
+        file: &CameraFile,
+        path: &Path,
+        file_type: Option<crate::CameraFileType>,
+    ) -> crate::Result<()> {
+        let mut media = FileMedia::create(path)?;
+
+        if let Err(err) = self.get_file(file, &mut media, file_type) {
+            drop(media);
+            let _ = fs::remove_file(path);
+
+            return Err(err);
+        }
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_downloaded_bytes(local_size(path).unwrap_or(0));
+
+        Ok(())
+    }
+}
+
+impl Camera {
+    /// Downloads a file to a new local file with the type and bandwidth limit of the options.
+    fn download_with_options(
+        &mut self,
+        file: &CameraFile,
+        path: &Path,
+        options: &DownloadOptions,
+    ) -> crate::Result<()> {
+        let limit = match options.bandwidth_limit {
+            Some(limit) => limit.max(1),
+            None => return self.download_to_path(file, path, options.file_type),
+        };
+
+        match self.download_paced(file, path, options.file_type, limit) {
+            Err(ref err) if err.kind() == crate::ErrorKind::NotSupported => {
+                self.download_to_path(file, path, options.file_type)
+            }
+            result => self.track(result),
+        }
+    }
+
+    /// Downloads a file to a new local file in chunks, pausing between chunks so that the average
+    /// speed stays below `limit` bytes per second.
+    ///
+    /// Reading stops at the size that the camera reports for the file, or at the first chunk that
+    /// is shorter than requested if the size isn't known.
+    ///
+    /// Fails with `NotSupported` without creating the file if the driver can't read files in
+    /// chunks. Otherwise, the file is removed if the download fails. The outcome is tracked by the
+    /// caller, so that falling back to a download in one piece isn't recorded as an error.
+    fn download_paced(
+        &mut self,
+        file: &CameraFile,
+        path: &Path,
+        file_type: Option<crate::CameraFileType>,
+        limit: u64,
+    ) -> crate::Result<()> {
+        // Without a known size, the end of the file is detected by a short read.
+        let total = match self.file_info(file) {
+            Ok(info) => expected_size(&info, file_type),
+            Err(_) => None,
+        };
+
+        let file_type = file_type.unwrap_or(crate::gphoto2::GP_FILE_TYPE_NORMAL);
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut local: Option<fs::File> = None;
+        let mut offset = 0u64;
+        let start = Instant::now();
+
+        loop {
+            let mut size = buffer.len() as u64;
+
+            let result = unsafe {
+                crate::gphoto2::gp_camera_file_read(
+                    self.camera,
+                    file.inner.folder.as_ptr(),
+                    file.inner.name.as_ptr(),
+                    file_type,
+                    offset,
+                    buffer.as_mut_ptr() as *mut c_char,
+                    &mut size,
+                    self.context.as_mut_ptr(),
+                )
+            };
+
+            if result != crate::gphoto2::GP_OK {
+                if local.is_some() {
+                    let _ = fs::remove_file(path);
+                }
+
+                return Err(crate::error::from_libgphoto2(result));
+            }
+
+            let size = size.min(buffer.len() as u64);
+
+            if local.is_none() {
+                match OpenOptions::new().write(true).create_new(true).open(path) {
+                    Ok(created) => local = Some(created),
+                    Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                        return Err(crate::error::from_libgphoto2(
+                            crate::gphoto2::GP_ERROR_FILE_EXISTS,
+                        ))
+                    }
+                    Err(_) => {
+                        return Err(crate::error::from_libgphoto2(
+                            crate::gphoto2::GP_ERROR_OS_FAILURE,
+                        ))
+                    }
+                }
+            }
+
+            if let Some(ref mut local) = local {
+                if local.write_all(&buffer[..size as usize]).is_err() {
+                    let _ = fs::remove_file(path);
+
+                    return Err(crate::error::from_libgphoto2(
+                        crate::gphoto2::GP_ERROR_OS_FAILURE,
+                    ));
+                }
+            }
+
+            offset += size;
+
+            let end = match total {
+                Some(total) => offset >= total || size == 0,
+                None => size < buffer.len() as u64,
+            };
+
+            if end {
+                break;
+            }
+
+            let due = Duration::from_secs_f64(offset as f64 / limit as f64);
+            let elapsed = start.elapsed();
+
+            if elapsed < due {
+                thread::sleep(due - elapsed);
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_downloaded_bytes(offset);
+
+        Ok(())
+    }
+}
+
+/// Returns the size of the data of a file that is downloaded with a file type, if it's known.
+fn expected_size(info: &FileInfo, file_type: Option<crate::CameraFileType>) -> Option<u64> {
+    match file_type {
+        None | Some(crate::gphoto2::GP_FILE_TYPE_NORMAL) => info.size(),
+        Some(crate::gphoto2::GP_FILE_TYPE_PREVIEW) => info.preview_size(),
+        Some(_) => None,
+    }
+}
+
+/// Checks that the filesystem of a directory has room for `required` bytes.
+///
+/// Returns a `NoSpace` error if it doesn't, or an `OSFailure` error if the free space could not be
+/// determined. On platforms where the free space can't be queried, the check passes.
+pub(crate) fn ensure_space(dir: &Path, required: u64) -> crate::Result<()> {
+    match available_space(dir)? {
+        Some(available) if available < required => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NO_SPACE,
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the number of bytes that are available to unprivileged users on the filesystem of a
+/// directory.
+#[cfg(unix)]
+fn available_space(dir: &Path) -> crate::Result<Option<u64>> {
+    let path = match dir.to_str().and_then(|path| CString::new(path).ok()) {
+        Some(path) => path,
+        None => {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            ))
+        }
+    };
+
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            ));
+        }
+
+        stat.assume_init()
+    };
+
+    Ok(Some(
+        (stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64),
+    ))
+}
+
+/// Returns `None`, because the free space can only be queried on Unix platforms.
+#[cfg(not(unix))]
+fn available_space(_dir: &Path) -> crate::Result<Option<u64>> {
+    Ok(None)
+}
+
+/// Returns the temporary path that a download to `path` is written to, e.g., `.IMG_0001.JPG.part`.
+///
+/// The name starts with a dot, so that partial downloads are hidden from file browsers.
+pub(crate) fn partial_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".part");
+
+    path.with_file_name(name)
+}
+
+fn local_size(path: &Path) -> Option<u64> {
+    fs::metadata(path).map(|metadata| metadata.len()).ok()
+}
+
+/// Returns the first path in `dir` of the form `stem_N.ext` that doesn't exist.
+///
+/// Fails with `FileExists` if the first `MAX_SUFFIX` names are all taken.
+pub(crate) fn free_path(dir: &Path, name: &str) -> crate::Result<PathBuf> {
+    let (stem, extension) = match name.rfind('.') {
+        Some(index) if index > 0 => name.split_at(index),
+        _ => (name, ""),
+    };
+
+    match (1..=MAX_SUFFIX)
+        .map(|n| dir.join(format!("{}_{}{}", stem, n, extension)))
+        .find(|path| !path.exists())
+    {
+        Some(path) => Ok(path),
+        None => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_FILE_EXISTS,
+        )),
+    }
+}
+
+/// Returns `true` if a name is a single, normal path component, e.g., `IMG_0001.JPG`.
+pub(crate) fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) && !name.contains('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_names() {
+        let cases = [
+            ("IMG_0001.JPG", true),
+            ("A1234_IMG_0001.JPG", true),
+            (".hidden", true),
+            ("", false),
+            (".", false),
+            ("..", false),
+            ("DCIM/IMG_0001.JPG", false),
+            ("/IMG_0001.JPG", false),
+            ("IMG_0001.JPG/", false),
+        ];
+
+        for &(name, plain) in cases.iter() {
+            assert_eq!(is_plain_name(name), plain, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn finds_free_paths() {
+        let dir = std::env::temp_dir().join(format!("gphoto-download-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for name in &["IMG_0001.JPG", "IMG_0001_1.JPG", "README", "archive.tar.gz"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let cases = [
+            ("IMG_0001.JPG", "IMG_0001_2.JPG"),
+            ("IMG_0002.JPG", "IMG_0002_1.JPG"),
+            ("README", "README_1"),
+            ("archive.tar.gz", "archive.tar_1.gz"),
+            (".hidden", ".hidden_1"),
+        ];
+
+        let paths: Vec<_> = cases
+            .iter()
+            .map(|&(name, _)| free_path(&dir, name).unwrap())
+            .collect();
+        let _ = fs::remove_dir_all(&dir);
+
+        for (&(_, expected), path) in cases.iter().zip(paths) {
+            assert_eq!(path, dir.join(expected));
+        }
+    }
+
+    #[test]
+    fn hides_partial_downloads() {
+        let cases = [
+            ("photos/IMG_0001.JPG", "photos/.IMG_0001.JPG.part"),
+            ("A1234_IMG_0001.JPG", ".A1234_IMG_0001.JPG.part"),
+            ("/tmp/photos/MVI_0002", "/tmp/photos/.MVI_0002.part"),
+        ];
+
+        for &(path, partial) in cases.iter() {
+            assert_eq!(partial_path(Path::new(path)), Path::new(partial));
+        }
+    }
+}
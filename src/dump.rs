@@ -0,0 +1,249 @@
+use std::fmt;
+
+use crate::json::{self, JsonObject};
+use crate::widget::{Config, Widget, WidgetType, WidgetValue};
+
+/// A copy of a widget of a camera's configuration tree, including its children.
+///
+/// Dumps are created with `Config::dump()`. Unlike a `Widget`, a dump doesn't borrow the
+/// configuration, so it can be kept, compared to a later dump, or sent elsewhere, e.g., to build
+/// a UI for a camera model that isn't known in advance.
+///
+/// A dump is formatted by `Display` like the output of `gphoto2 --list-all-config`, and as JSON by
+/// `to_json()`.
+///
+/// ## Example
+///
+/// ```no_run
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let dump = camera.config().unwrap().dump();
+///
+/// println!("{}", dump);
+/// std::fs::write("config.json", dump.to_json()).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetDump {
+    path: String,
+    name: String,
+    label: String,
+    info: String,
+    id: i32,
+    widget_type: WidgetType,
+    readonly: bool,
+    value: Option<WidgetValue>,
+    choices: Vec<String>,
+    range: Option<(f32, f32, f32)>,
+    children: Vec<WidgetDump>,
+}
+
+impl WidgetDump {
+    /// The widget's path, e.g., `"/main/imgsettings/iso"`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The widget's name, e.g., `"iso"`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The widget's human-readable label, e.g., `"ISO Speed"`.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The widget's help text.
+    pub fn info(&self) -> &str {
+        &self.info
+    }
+
+    /// The widget's numeric ID.
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// The type of the widget.
+    pub fn widget_type(&self) -> WidgetType {
+        self.widget_type
+    }
+
+    /// Returns `true` if the widget's value can not be changed.
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// The widget's value at the time of the dump.
+    pub fn value(&self) -> Option<&WidgetValue> {
+        self.value.as_ref()
+    }
+
+    /// The choices of a radio or menu widget.
+    pub fn choices(&self) -> &[String] {
+        &self.choices
+    }
+
+    /// The minimum, maximum, and increment of a range widget.
+    pub fn range(&self) -> Option<(f32, f32, f32)> {
+        self.range
+    }
+
+    /// The widget's children.
+    pub fn children(&self) -> &[WidgetDump] {
+        &self.children
+    }
+
+    /// Returns the widget and its descendants, in depth-first order.
+    pub fn widgets(&self) -> Vec<&WidgetDump> {
+        let mut widgets = Vec::new();
+        let mut pending = vec![self];
+
+        while let Some(widget) = pending.pop() {
+            pending.extend(widget.children.iter().rev());
+            widgets.push(widget);
+        }
+
+        widgets
+    }
+
+    /// Formats the widget and its descendants as a JSON object.
+    ///
+    /// Values are encoded as strings, numbers or booleans depending on the widget's type, and
+    /// `null` for widgets without a value.
+    pub fn to_json(&self) -> String {
+        let value = match self.value {
+            Some(WidgetValue::Text(ref value)) | Some(WidgetValue::Choice(ref value)) => {
+                json::string(value)
+            }
+            Some(WidgetValue::Range(value)) => float_json(value),
+            Some(WidgetValue::Toggle(value)) => value.to_string(),
+            Some(WidgetValue::Date(value)) => value.to_string(),
+            None => String::from("null"),
+        };
+
+        let range = match self.range {
+            Some((min, max, increment)) => JsonObject::new()
+                .raw("min", &float_json(min))
+                .raw("max", &float_json(max))
+                .raw("increment", &float_json(increment))
+                .finish(),
+            None => String::from("null"),
+        };
+
+        JsonObject::new()
+            .string("path", &self.path)
+            .string("name", &self.name)
+            .string("label", &self.label)
+            .string("info", &self.info)
+            .raw("id", &self.id.to_string())
+            .string("type", type_name(self.widget_type))
+            .raw("readonly", if self.readonly { "true" } else { "false" })
+            .raw("value", &value)
+            .raw(
+                "choices",
+                &json::array(self.choices.iter().map(|choice| json::string(choice))),
+            )
+            .raw("range", &range)
+            .raw(
+                "children",
+                &json::array(self.children.iter().map(|child| child.to_json())),
+            )
+            .finish()
+    }
+}
+
+impl fmt::Display for WidgetDump {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for widget in self.widgets() {
+            if widget.value.is_none() && widget.widget_type != WidgetType::Button {
+                continue;
+            }
+
+            writeln!(fmt, "{}", widget.path)?;
+            writeln!(fmt, "Label: {}", widget.label)?;
+            writeln!(fmt, "Readonly: {}", widget.readonly as u8)?;
+            writeln!(fmt, "Type: {}", type_name(widget.widget_type))?;
+
+            match widget.value {
+                Some(WidgetValue::Text(ref value)) | Some(WidgetValue::Choice(ref value)) => {
+                    writeln!(fmt, "Current: {}", value)?
+                }
+                Some(WidgetValue::Range(value)) => writeln!(fmt, "Current: {}", value)?,
+                Some(WidgetValue::Toggle(value)) => writeln!(fmt, "Current: {}", value as u8)?,
+                Some(WidgetValue::Date(value)) => writeln!(fmt, "Current: {}", value)?,
+                None => (),
+            }
+
+            if let Some((min, max, increment)) = widget.range {
+                writeln!(fmt, "Bottom: {}", min)?;
+                writeln!(fmt, "Top: {}", max)?;
+                writeln!(fmt, "Step: {}", increment)?;
+            }
+
+            for (index, choice) in widget.choices.iter().enumerate() {
+                writeln!(fmt, "Choice: {} {}", index, choice)?;
+            }
+
+            writeln!(fmt, "END")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Copies the configuration tree, with the current values of all widgets.
+    ///
+    /// See `WidgetDump` for details.
+    pub fn dump(&self) -> WidgetDump {
+        dump(&self.root(), "")
+    }
+}
+
+fn dump(widget: &Widget, parent: &str) -> WidgetDump {
+    let name = widget.name().into_owned();
+    let path = format!("{}/{}", parent, name);
+
+    let children = widget
+        .children()
+        .iter()
+        .map(|child| dump(child, &path))
+        .collect();
+
+    WidgetDump {
+        name,
+        label: widget.label().into_owned(),
+        info: widget.info().into_owned(),
+        id: widget.id(),
+        widget_type: widget.widget_type(),
+        readonly: widget.is_readonly(),
+        value: widget.value(),
+        choices: widget.choices(),
+        range: widget.range(),
+        children,
+        path,
+    }
+}
+
+/// Returns the name of a widget type as printed by `gphoto2 --list-all-config`.
+pub(crate) fn type_name(widget_type: WidgetType) -> &'static str {
+    match widget_type {
+        WidgetType::Window => "WINDOW",
+        WidgetType::Section => "SECTION",
+        WidgetType::Text => "TEXT",
+        WidgetType::Range => "RANGE",
+        WidgetType::Toggle => "TOGGLE",
+        WidgetType::Radio => "RADIO",
+        WidgetType::Menu => "MENU",
+        WidgetType::Button => "BUTTON",
+        WidgetType::Date => "DATE",
+    }
+}
+
+/// Encodes a number as JSON, or `null` if it isn't finite.
+fn float_json(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        String::from("null")
+    }
+}
@@ -15,7 +15,11 @@ use libc::c_int;
 pub type Result<T> = StdResult<T, Error>;
 
 /// Types of errors reported by gphoto2.
+///
+/// New kinds may be added when `libgphoto2` reports errors that callers want to tell apart, so
+/// matches on this type need a wildcard arm.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
 pub enum ErrorKind {
     /// A parameter was incorrect.
     InvalidInput,
@@ -59,6 +63,9 @@ pub enum ErrorKind {
     /// Not enough space when uploading a file.
     NoSpace,
 
+    /// Operation timed out.
+    Timeout,
+
     /// An unspecified error occured.
     Other,
 }
@@ -87,11 +94,45 @@ impl Error {
             crate::gphoto2::GP_ERROR_CAMERA_ERROR => ErrorKind::CameraError,
             crate::gphoto2::GP_ERROR_OS_FAILURE => ErrorKind::OSFailure,
             crate::gphoto2::GP_ERROR_NO_SPACE => ErrorKind::NoSpace,
+            crate::gphoto2::GP_ERROR_TIMEOUT => ErrorKind::Timeout,
             crate::gphoto2::GP_ERROR => ErrorKind::Other,
             _ => ErrorKind::Other,
         }
     }
 
+    /// Returns `true` if the operation might succeed when it is tried again.
+    ///
+    /// This is a heuristic: errors from a busy camera, timeouts and I/O errors are usually
+    /// transient, while errors like `NotSupported` or `InvalidInput` fail the same way each time.
+    /// After a disconnect (see `is_disconnect()`), the camera has to be reinitialized before the
+    /// operation is tried again.
+    pub fn is_retryable(&self) -> bool {
+        match self.err {
+            crate::gphoto2::GP_ERROR_CAMERA_BUSY
+            | crate::gphoto2::GP_ERROR_IO_LOCK
+            | crate::gphoto2::GP_ERROR_TIMEOUT => true,
+            _ => self.is_disconnect(),
+        }
+    }
+
+    /// Returns `true` if the error means that the connection to the camera was lost.
+    ///
+    /// This includes I/O errors on the port, e.g., because the camera was unplugged or turned
+    /// off. Timeouts aren't disconnects: the crate also reports a `Timeout` when a connected
+    /// camera didn't produce a file in time, e.g., in `Camera::wait_capture_complete()`.
+    pub fn is_disconnect(&self) -> bool {
+        matches!(
+            self.err,
+            crate::gphoto2::GP_ERROR_IO
+                | crate::gphoto2::GP_ERROR_IO_INIT
+                | crate::gphoto2::GP_ERROR_IO_READ
+                | crate::gphoto2::GP_ERROR_IO_WRITE
+                | crate::gphoto2::GP_ERROR_IO_USB_CLEAR_HALT
+                | crate::gphoto2::GP_ERROR_IO_USB_FIND
+                | crate::gphoto2::GP_ERROR_IO_USB_CLAIM
+        )
+    }
+
     /// Returns an error message.
     pub fn message(&self) -> &'static str {
         unsafe {
@@ -129,3 +170,28 @@ macro_rules! try_unsafe {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_errors() {
+        let cases = [
+            (crate::gphoto2::GP_ERROR_CAMERA_BUSY, true, false),
+            (crate::gphoto2::GP_ERROR_IO_LOCK, true, false),
+            (crate::gphoto2::GP_ERROR_TIMEOUT, true, false),
+            (crate::gphoto2::GP_ERROR_IO, true, true),
+            (crate::gphoto2::GP_ERROR_IO_USB_FIND, true, true),
+            (crate::gphoto2::GP_ERROR_NOT_SUPPORTED, false, false),
+            (crate::gphoto2::GP_ERROR_BAD_PARAMETERS, false, false),
+        ];
+
+        for &(code, retryable, disconnect) in cases.iter() {
+            let err = from_libgphoto2(code);
+
+            assert_eq!(err.is_retryable(), retryable, "{:?}", err.kind());
+            assert_eq!(err.is_disconnect(), disconnect, "{:?}", err.kind());
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use core::ptr;
+use cstr_core::CStr;
+use libc::{c_char, c_void};
+
+use crate::camera::CameraFile;
+
+/// Events reported by a camera.
+///
+/// Events are retrieved with `Camera::wait_event()`.
+#[derive(Debug)]
+pub enum CameraEvent {
+    /// No event occurred before the timeout expired.
+    Timeout,
+
+    /// A file was added to the camera's storage, e.g., by a capture.
+    FileAdded(CameraFile),
+
+    /// A folder was added to the camera's storage.
+    ///
+    /// The `CameraFile`'s directory is the parent folder and its basename is the new folder's name.
+    FolderAdded(CameraFile),
+
+    /// A file on the camera's storage was changed, e.g., its protection or rating was changed on
+    /// the camera's controls.
+    FileChanged(CameraFile),
+
+    /// A capture has completed.
+    CaptureComplete,
+
+    /// A device property was changed, e.g., a setting was changed on the camera's controls.
+    ///
+    /// This is decoded from the driver's description of an event that isn't understood by
+    /// `libgphoto2`. `code` is the PTP property code. Older versions of the `ptp2` driver don't
+    /// report the name of the setting and its new value.
+    VendorPropertyChanged {
+        code: u16,
+        name: Option<String>,
+        value: Option<String>,
+    },
+
+    /// An event that isn't understood by `libgphoto2`, with the driver's description of it.
+    Unknown(String),
+}
+
+#[doc(hidden)]
+pub unsafe fn from_libgphoto2(
+    event_type: crate::gphoto2::CameraEventType,
+    data: *mut c_void,
+) -> CameraEvent {
+    let event = match event_type {
+        crate::gphoto2::GP_EVENT_TIMEOUT => CameraEvent::Timeout,
+        crate::gphoto2::GP_EVENT_CAPTURE_COMPLETE => CameraEvent::CaptureComplete,
+        crate::gphoto2::GP_EVENT_FILE_ADDED => {
+            CameraEvent::FileAdded(crate::camera::file_from_libgphoto2(ptr::read(
+                data as *const crate::gphoto2::CameraFilePath,
+            )))
+        }
+        crate::gphoto2::GP_EVENT_FOLDER_ADDED => {
+            CameraEvent::FolderAdded(crate::camera::file_from_libgphoto2(ptr::read(
+                data as *const crate::gphoto2::CameraFilePath,
+            )))
+        }
+        crate::gphoto2::GP_EVENT_FILE_CHANGED => {
+            CameraEvent::FileChanged(crate::camera::file_from_libgphoto2(ptr::read(
+                data as *const crate::gphoto2::CameraFilePath,
+            )))
+        }
+        _ => {
+            if data.is_null() {
+                CameraEvent::Unknown(String::new())
+            } else {
+                decode_unknown(
+                    String::from_utf8_lossy(CStr::from_ptr(data as *const c_char).to_bytes())
+                        .into_owned(),
+                )
+            }
+        }
+    };
+
+    // The event data is allocated by libgphoto2 and owned by the caller.
+    if !data.is_null() {
+        libc::free(data);
+    }
+
+    event
+}
+
+/// Decodes the well-known descriptions of events that aren't understood by `libgphoto2`.
+///
+/// The `ptp2` driver describes property changes as `PTP Property d101 changed`, optionally
+/// followed by `, "name" to "value"`.
+fn decode_unknown(description: String) -> CameraEvent {
+    let rest = match description.strip_prefix("PTP Property ") {
+        Some(rest) => rest,
+        None => return CameraEvent::Unknown(description),
+    };
+
+    let (code, rest) = match rest.find(" changed") {
+        Some(index) => (&rest[..index], &rest[index + " changed".len()..]),
+        None => return CameraEvent::Unknown(description),
+    };
+
+    let code = match u16::from_str_radix(code, 16) {
+        Ok(code) => code,
+        Err(_) => return CameraEvent::Unknown(description),
+    };
+
+    let (name, value) = match rest
+        .strip_prefix(", \"")
+        .and_then(|rest| rest.split_once("\" to \""))
+    {
+        Some((name, value)) => (
+            Some(name.to_owned()),
+            Some(value.strip_suffix('"').unwrap_or(value).to_owned()),
+        ),
+        None => (None, None),
+    };
+
+    CameraEvent::VendorPropertyChanged { code, name, value }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_property_changes() {
+        let cases: &[(&str, u16, Option<&str>, Option<&str>)] = &[
+            ("PTP Property d101 changed", 0xd101, None, None),
+            ("PTP Property 5007 changed", 0x5007, None, None),
+            (
+                "PTP Property d10c changed, \"ISO Speed\" to \"400\"",
+                0xd10c,
+                Some("ISO Speed"),
+                Some("400"),
+            ),
+            (
+                "PTP Property d10c changed, \"Name\" to \"a \" to \"b\"",
+                0xd10c,
+                Some("Name"),
+                Some("a \" to \"b"),
+            ),
+            (
+                "PTP Property d10c changed, \"ISO Speed\"",
+                0xd10c,
+                None,
+                None,
+            ),
+        ];
+
+        for &(description, code, name, value) in cases {
+            match decode_unknown(description.to_owned()) {
+                CameraEvent::VendorPropertyChanged {
+                    code: decoded_code,
+                    name: decoded_name,
+                    value: decoded_value,
+                } => {
+                    assert_eq!(code, decoded_code, "{}", description);
+                    assert_eq!(name, decoded_name.as_deref(), "{}", description);
+                    assert_eq!(value, decoded_value.as_deref(), "{}", description);
+                }
+                event => panic!("{}: {:?}", description, event),
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_other_descriptions() {
+        let cases = [
+            "",
+            "Unknown PTP Event c10d",
+            "PTP Property d101",
+            "PTP Property xyz changed",
+            "PTP Property 12345 changed",
+            "ptp property d101 changed",
+        ];
+
+        for description in cases {
+            match decode_unknown(description.to_owned()) {
+                CameraEvent::Unknown(decoded) => assert_eq!(description, decoded),
+                event => panic!("{}: {:?}", description, event),
+            }
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BinaryHeap, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+use crate::camera::{Camera, CameraFile};
+use crate::health::HealthReport;
+use crate::shared::Priority;
+use crate::threading::{threading_model, ThreadingModel};
+use crate::widget::WidgetValue;
+
+type Job = Box<dyn FnOnce(&mut HashMap<u64, Camera>) + Send>;
+
+/// The callers that wait for a pending read of each setting.
+type SettingWaiters = HashMap<String, Vec<Sender<crate::Result<WidgetValue>>>>;
+
+/// The executor that is shared by all cameras in the `Serialized` threading model.
+static GLOBAL: OnceLock<Executor> = OnceLock::new();
+
+/// The source of camera IDs, which are unique across executors.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The source of job sequence numbers, which order jobs of the same priority.
+static NEXT_JOB: AtomicU64 = AtomicU64::new(0);
+
+/// A job in an executor's queue.
+struct Queued {
+    priority: Priority,
+    sequence: u64,
+    job: Job,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    // The greatest job runs first: the highest priority, and of those the oldest job.
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// A background thread that owns cameras and runs all operations on them.
+///
+/// Cameras that are opened on an executor are never touched by other threads: their operations
+/// are sent to the executor's thread through a channel and run one at a time. Their handles,
+/// `RemoteCamera`s, are `Send` and `Sync`, so they can be used from any thread, e.g., from async
+/// tasks that are moved between the threads of a runtime.
+///
+/// Queued operations run by priority: `High` priority operations, e.g., captures, run before
+/// `Normal` priority operations that were queued earlier, so that a user interface stays
+/// responsive while downloads are queued. Concurrent reads of the same setting are coalesced into
+/// a single read.
+///
+/// The executor's thread exits when the executor and all of its cameras were dropped.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::thread;
+///
+/// let executor = gphoto::Executor::new().unwrap();
+///
+/// let handles: Vec<_> = gphoto::detect_cameras()
+///     .unwrap()
+///     .into_iter()
+///     .map(|detected| {
+///         let camera = executor.open(move || detected.open()).unwrap();
+///         thread::spawn(move || camera.capture_image())
+///     })
+///     .collect();
+///
+/// for handle in handles {
+///     println!("{:?}", handle.join().unwrap());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Executor {
+    sender: Sender<Queued>,
+}
+
+impl Executor {
+    /// Starts an executor thread.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the thread could not be started:
+    ///
+    /// * `OSFailure` if the operating system refused to start a thread.
+    pub fn new() -> crate::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<Queued>();
+
+        let started = thread::Builder::new()
+            .name("gphoto-executor".to_owned())
+            .spawn(move || {
+                let mut cameras = HashMap::new();
+                let mut queue = BinaryHeap::new();
+
+                loop {
+                    if queue.is_empty() {
+                        match receiver.recv() {
+                            Ok(queued) => queue.push(queued),
+                            Err(_) => break,
+                        }
+                    }
+
+                    queue.extend(receiver.try_iter());
+
+                    if let Some(queued) = queue.pop() {
+                        let job = queued.job;
+
+                        // Jobs are serialized with the calls of `SharedCamera`s and camera
+                        // detection in the `Serialized` threading model.
+                        let _serialized = crate::threading::serialize();
+
+                        // A panicking job is reported to its caller; the other cameras live on.
+                        let _ = panic::catch_unwind(AssertUnwindSafe(|| job(&mut cameras)));
+                    }
+                }
+            });
+
+        match started {
+            Ok(_) => Ok(Executor { sender }),
+            Err(_) => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            )),
+        }
+    }
+
+    /// Returns the executor that is shared by all cameras in the `Serialized` threading model.
+    ///
+    /// The executor is started on first use.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the executor's thread could not be started:
+    ///
+    /// * `OSFailure` if the operating system refused to start a thread.
+    pub fn global() -> crate::Result<&'static Executor> {
+        if let Some(executor) = GLOBAL.get() {
+            return Ok(executor);
+        }
+
+        let executor = Executor::new()?;
+
+        // If another thread won the race, its executor is used and this one exits.
+        Ok(GLOBAL.get_or_init(|| executor))
+    }
+
+    /// Opens a camera on the executor's thread.
+    ///
+    /// `open` is called on the executor's thread, e.g., `Camera::autodetect` or a closure that
+    /// opens a `DetectedCamera`.
+    pub fn open<F>(&self, open: F) -> crate::Result<RemoteCamera>
+    where
+        F: FnOnce() -> crate::Result<Camera> + Send + 'static,
+    {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+        run(&self.sender, Priority::Normal, move |cameras| {
+            let camera = open()?;
+            cameras.insert(id, camera);
+
+            Ok(())
+        })?;
+
+        Ok(RemoteCamera {
+            inner: Arc::new(Remote {
+                id,
+                sender: self.sender.clone(),
+                settings: Arc::new(Mutex::new(HashMap::new())),
+            }),
+        })
+    }
+}
+
+/// A handle of a camera that is owned by an `Executor`.
+///
+/// Operations on the camera are run on the executor's thread, and the calling thread blocks until
+/// they complete. Cloning a `RemoteCamera` returns another handle to the same camera; the camera
+/// is closed when the last handle is dropped.
+#[derive(Clone)]
+pub struct RemoteCamera {
+    inner: Arc<Remote>,
+}
+
+struct Remote {
+    id: u64,
+    sender: Sender<Queued>,
+    settings: Arc<Mutex<SettingWaiters>>,
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        let id = self.id;
+
+        let _ = self.sender.send(Queued {
+            priority: Priority::Normal,
+            sequence: NEXT_JOB.fetch_add(1, Ordering::Relaxed),
+            job: Box::new(move |cameras| {
+                cameras.remove(&id);
+            }),
+        });
+    }
+}
+
+impl RemoteCamera {
+    /// Opens the first detected camera on an executor that is selected by the threading model.
+    ///
+    /// In the `Serialized` threading model, the camera is opened on the global executor, so that
+    /// all calls into `libgphoto2` run on one thread. In the `PerCamera` model, the camera gets an
+    /// executor of its own.
+    pub fn autodetect() -> crate::Result<Self> {
+        match threading_model() {
+            ThreadingModel::Serialized => Executor::global()?.open(Camera::autodetect),
+            ThreadingModel::PerCamera => Executor::new()?.open(Camera::autodetect),
+        }
+    }
+
+    /// Runs a function with the camera on the executor's thread and returns its result.
+    ///
+    /// The function is queued with `Normal` priority. If the function panics, the panic is resumed
+    /// on the calling thread. The function must not call other cameras of the same executor, which
+    /// would wait for itself.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the function's error, or:
+    ///
+    /// * `OSFailure` if the executor's thread has exited.
+    pub fn call<F, T>(&self, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Camera) -> crate::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.call_with_priority(Priority::Normal, f)
+    }
+
+    /// Runs a function with the camera on the executor's thread with a priority.
+    ///
+    /// Queued functions with `High` priority run before queued functions with `Normal` priority.
+    /// See `call()` for details.
+    pub fn call_with_priority<F, T>(&self, priority: Priority, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Camera) -> crate::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let id = self.inner.id;
+
+        run(&self.inner.sender, priority, move |cameras| {
+            match cameras.get_mut(&id) {
+                Some(camera) => f(camera),
+                None => Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_OS_FAILURE,
+                )),
+            }
+        })
+    }
+
+    /// Captures an image.
+    ///
+    /// The capture is queued with `High` priority.
+    pub fn capture_image(&self) -> crate::Result<CameraFile> {
+        self.call_with_priority(Priority::High, |camera| camera.capture_image())
+    }
+
+    /// Returns the current value of a setting.
+    ///
+    /// If a read of the same setting is already queued, this call waits for its result instead
+    /// of queueing another read.
+    pub fn setting(&self, name: &str) -> crate::Result<WidgetValue> {
+        let (reply, result) = mpsc::channel();
+
+        let queued = {
+            let mut settings = lock(&self.inner.settings);
+            let waiters = settings.entry(name.to_owned()).or_default();
+            waiters.push(reply);
+
+            waiters.len() > 1
+        };
+
+        if !queued {
+            let id = self.inner.id;
+            let key = name.to_owned();
+            let settings = self.inner.settings.clone();
+
+            let sent = self.inner.sender.send(Queued {
+                priority: Priority::Normal,
+                sequence: NEXT_JOB.fetch_add(1, Ordering::Relaxed),
+                job: Box::new(move |cameras| {
+                    // Callers that arrive from now on queue a new read, which sees later changes.
+                    let waiters = lock(&settings).remove(&key).unwrap_or_default();
+
+                    let value = match cameras.get_mut(&id) {
+                        Some(camera) => {
+                            panic::catch_unwind(AssertUnwindSafe(|| camera.setting(&key)))
+                                .unwrap_or_else(|_| Err(os_failure()))
+                        }
+                        None => Err(os_failure()),
+                    };
+
+                    for waiter in waiters {
+                        let _ = waiter.send(match value {
+                            Ok(ref value) => Ok(value.clone()),
+                            Err(ref err) => Err(crate::Error { err: err.err }),
+                        });
+                    }
+                }),
+            });
+
+            if sent.is_err() {
+                lock(&self.inner.settings).remove(name);
+            }
+        }
+
+        match result.recv() {
+            Ok(value) => value,
+            Err(_) => Err(os_failure()),
+        }
+    }
+
+    /// Checks the camera's health.
+    pub fn health(&self) -> crate::Result<HealthReport> {
+        self.call(|camera| Ok(camera.health()))
+    }
+}
+
+/// Runs a job on an executor's thread and waits for its result.
+fn run<F, T>(sender: &Sender<Queued>, priority: Priority, f: F) -> crate::Result<T>
+where
+    F: FnOnce(&mut HashMap<u64, Camera>) -> crate::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let (reply, result) = mpsc::channel();
+
+    let job: Job = Box::new(move |cameras| {
+        let _ = reply.send(panic::catch_unwind(AssertUnwindSafe(|| f(cameras))));
+    });
+
+    let queued = Queued {
+        priority,
+        sequence: NEXT_JOB.fetch_add(1, Ordering::Relaxed),
+        job,
+    };
+
+    if sender.send(queued).is_err() {
+        return Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_OS_FAILURE,
+        ));
+    }
+
+    match result.recv() {
+        Ok(Ok(result)) => result,
+        Ok(Err(panic)) => panic::resume_unwind(panic),
+        Err(_) => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_OS_FAILURE,
+        )),
+    }
+}
+
+fn lock(settings: &Mutex<SettingWaiters>) -> std::sync::MutexGuard<'_, SettingWaiters> {
+    // The map is consistent after every operation, so a panic while it was locked doesn't matter.
+    match settings.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn os_failure() -> crate::Error {
+    crate::error::from_libgphoto2(crate::gphoto2::GP_ERROR_OS_FAILURE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_jobs_by_priority_and_age() {
+        let jobs = [
+            (Priority::Normal, 0),
+            (Priority::High, 1),
+            (Priority::Normal, 2),
+            (Priority::High, 3),
+            (Priority::Normal, 4),
+        ];
+
+        let mut queue = BinaryHeap::new();
+
+        // Pushed out of order, so that the order isn't just the order of insertion.
+        for &(priority, sequence) in jobs.iter().rev() {
+            let job: Job = Box::new(|_| ());
+            queue.push(Queued {
+                priority,
+                sequence,
+                job,
+            });
+        }
+
+        let popped: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|queued| (queued.priority, queued.sequence))
+            .collect();
+
+        assert_eq!(
+            popped,
+            [
+                (Priority::High, 1),
+                (Priority::High, 3),
+                (Priority::Normal, 0),
+                (Priority::Normal, 2),
+                (Priority::Normal, 4),
+            ]
+        );
+    }
+}
@@ -0,0 +1,613 @@
+use std::time::Duration;
+
+use crate::camera::{Camera, CameraFile};
+use crate::widget::{Config, WidgetValue};
+
+/// Names of the settings that select the shutter speed in different drivers.
+pub(crate) const SHUTTER_SETTINGS: &[&str] = &["shutterspeed", "shutterspeed2", "eos-shutterspeed"];
+
+/// Names of the settings that select the ISO speed in different drivers.
+pub(crate) const ISO_SETTINGS: &[&str] = &["iso", "isospeed"];
+
+/// The default largest exposure change between two frames, in stops.
+const MAX_STEP: f64 = 1.0 / 3.0;
+
+/// Decides how the exposure should change between frames.
+///
+/// A controller returns the exposure correction for the next frame in stops: positive values
+/// make the next frame brighter, negative values darker. Controllers usually meter the scene,
+/// e.g., with an external light sensor, or with the `Histogram` of a preview frame or of the last
+/// captured image.
+///
+/// Closures that take the camera and return the correction are controllers.
+pub trait ExposureController {
+    /// Returns the exposure correction for the next frame, in stops.
+    fn correction(&mut self, camera: &mut Camera) -> crate::Result<f64>;
+}
+
+impl<F> ExposureController for F
+where
+    F: FnMut(&mut Camera) -> crate::Result<f64>,
+{
+    fn correction(&mut self, camera: &mut Camera) -> crate::Result<f64> {
+        self(camera)
+    }
+}
+
+/// The distribution of brightness in an image.
+///
+/// Images are decoded by the application; the histogram is built from the luma (brightness) of
+/// their pixels.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bins: [u64; 256],
+    total: u64,
+}
+
+impl Histogram {
+    /// Builds the histogram of 8-bit luma values.
+    pub fn from_luma(luma: &[u8]) -> Self {
+        let mut bins = [0; 256];
+
+        for &value in luma {
+            bins[usize::from(value)] += 1;
+        }
+
+        Histogram {
+            bins,
+            total: luma.len() as u64,
+        }
+    }
+
+    /// The number of pixels for each luma value.
+    pub fn bins(&self) -> &[u64; 256] {
+        &self.bins
+    }
+
+    /// The mean luma, from 0 to 255.
+    pub fn mean(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        let sum: u64 = (0..256u64)
+            .map(|value| value * self.bins[value as usize])
+            .sum();
+
+        sum as f64 / self.total as f64
+    }
+
+    /// The fraction of pixels that are clipped at full brightness.
+    pub fn clipped(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        self.bins[255] as f64 / self.total as f64
+    }
+
+    /// Returns the correction in stops that brings the mean luma to `target`.
+    ///
+    /// Luma is gamma-encoded, so the correction is approximate, but it converges when it is
+    /// applied over several frames.
+    pub fn correction(&self, target: f64) -> f64 {
+        // Avoid an infinite correction for black frames.
+        let mean = self.mean().max(1.0);
+
+        (to_linear(target) / to_linear(mean)).log2()
+    }
+}
+
+/// Converts a gamma-encoded luma value to linear light.
+fn to_linear(luma: f64) -> f64 {
+    (luma / 255.0).powf(2.2)
+}
+
+/// The exposure that was selected for a frame by `ExposureRamp::step()`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExposureStep {
+    correction: f64,
+    shutter: String,
+    iso: String,
+}
+
+impl ExposureStep {
+    /// The correction that was applied, in stops, after limiting it to the ramp's maximum step.
+    pub fn correction(&self) -> f64 {
+        self.correction
+    }
+
+    /// The selected shutter speed, as named by the camera, e.g., `"1/125"`.
+    pub fn shutter(&self) -> &str {
+        &self.shutter
+    }
+
+    /// The selected ISO speed, as named by the camera, e.g., `"800"`.
+    pub fn iso(&self) -> &str {
+        &self.iso
+    }
+}
+
+/// Ramps the exposure between frames of a sequence, e.g., for day-to-night timelapses.
+///
+/// Before each frame, `step()` asks the controller for a correction, limits it to the maximum
+/// step to avoid flicker, and selects the camera's shutter speed and ISO speed that come closest
+/// to the new exposure. Longer shutter speeds are preferred over higher ISO speeds, up to the
+/// longest allowed shutter speed, which should leave time for the interval between frames.
+///
+/// The camera must be in manual exposure mode.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use gphoto::ExposureRamp;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+///
+/// // Meter with the application's light sensor: keep the scene at 1000 lux.
+/// let read_lux = || 250.0_f64;
+/// let mut ramp = ExposureRamp::new(move |_: &mut gphoto::Camera| -> gphoto::Result<f64> {
+///     Ok((1000.0 / read_lux()).log2())
+/// })
+/// .max_shutter(Duration::from_secs(8))
+/// .iso_range(100, 3200);
+///
+/// for _ in 0..1000 {
+///     let step = ramp.step(&mut camera).unwrap();
+///     println!("{} ISO {}", step.shutter(), step.iso());
+///
+///     camera.capture_image().unwrap();
+///     std::thread::sleep(Duration::from_secs(10));
+/// }
+/// ```
+///
+/// See `Timelapse::capture_ramped()` to ramp the exposure of a scheduled timelapse.
+pub struct ExposureRamp<C> {
+    controller: C,
+    max_step: f64,
+    max_shutter: Option<Duration>,
+    min_iso: u32,
+    max_iso: u32,
+}
+
+impl<C: ExposureController> ExposureRamp<C> {
+    /// Creates a ramp that is driven by a controller.
+    pub fn new(controller: C) -> Self {
+        ExposureRamp {
+            controller,
+            max_step: MAX_STEP,
+            max_shutter: None,
+            min_iso: 0,
+            max_iso: u32::MAX,
+        }
+    }
+
+    /// Sets the largest exposure change between two frames, in stops.
+    ///
+    /// The default is a third of a stop. Steps that aren't finite, e.g., `f64::NAN`, are
+    /// ignored.
+    pub fn max_step(mut self, stops: f64) -> Self {
+        if stops.is_finite() {
+            self.max_step = stops.abs();
+        }
+
+        self
+    }
+
+    /// Sets the longest shutter speed that may be selected.
+    ///
+    /// By default, all shutter speeds of the camera may be selected, except bulb.
+    pub fn max_shutter(mut self, shutter: Duration) -> Self {
+        self.max_shutter = Some(shutter);
+        self
+    }
+
+    /// Sets the range of ISO speeds that may be selected.
+    pub fn iso_range(mut self, min: u32, max: u32) -> Self {
+        self.min_iso = min;
+        self.max_iso = max;
+        self
+    }
+
+    /// Returns the controller.
+    pub fn controller(&mut self) -> &mut C {
+        &mut self.controller
+    }
+
+    /// Adjusts the camera's exposure for the next frame.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the controller's error, or an error if the exposure could not be
+    /// changed:
+    ///
+    /// * `NotSupported` if the camera doesn't expose its shutter speed or ISO speed, or if their
+    ///   current values aren't numeric, e.g., `"Auto"`.
+    pub fn step(&mut self, camera: &mut Camera) -> crate::Result<ExposureStep> {
+        let correction = self
+            .controller
+            .correction(camera)?
+            .clamp(-self.max_step, self.max_step);
+
+        let mut shutter_config = find_config(camera, SHUTTER_SETTINGS)?;
+        let mut iso_config = find_config(camera, ISO_SETTINGS)?;
+
+        let current_shutter = current_value(&shutter_config, parse_shutter)?;
+        let current_iso = current_value(&iso_config, parse_iso)?;
+        let target = exposure(current_shutter.1, current_iso.1) + correction;
+
+        let max_shutter = self
+            .max_shutter
+            .map_or(f64::INFINITY, |shutter| shutter.as_secs_f64());
+        let shutters: Vec<(String, f64)> = choices(&shutter_config, parse_shutter)
+            .into_iter()
+            .filter(|&(_, seconds)| seconds <= max_shutter)
+            .collect();
+        let isos: Vec<(String, f64)> = choices(&iso_config, parse_iso)
+            .into_iter()
+            .filter(|&(_, iso)| iso >= f64::from(self.min_iso) && iso <= f64::from(self.max_iso))
+            .collect();
+
+        // Exposures within a sixth of a stop are equally good; of those, the lowest ISO speed
+        // wins, and then the shutter speed that is closest to the current one.
+        let best = shutters
+            .iter()
+            .flat_map(|shutter| isos.iter().map(move |iso| (shutter, iso)))
+            .min_by_key(|(shutter, iso)| {
+                let error = (exposure(shutter.1, iso.1) - target).abs();
+                let jump = (shutter.1.log2() - current_shutter.1.log2()).abs();
+
+                (
+                    (error * 6.0).round() as i64,
+                    iso.1 as u64,
+                    (jump * 6.0).round() as i64,
+                )
+            });
+
+        let (shutter, iso) = match best {
+            Some((shutter, iso)) => (shutter.0.clone(), iso.0.clone()),
+            None => (current_shutter.0.clone(), current_iso.0.clone()),
+        };
+
+        if shutter != current_shutter.0 {
+            set_choice(camera, &mut shutter_config, &shutter)?;
+        }
+
+        if iso != current_iso.0 {
+            set_choice(camera, &mut iso_config, &iso)?;
+        }
+
+        Ok(ExposureStep {
+            correction,
+            shutter,
+            iso,
+        })
+    }
+}
+
+/// The shutter speeds and ISO speeds of an exposure sweep.
+///
+/// By default, all numeric shutter speeds and ISO speeds of the camera are included.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ExposureRange {
+    shutter: Option<(Duration, Duration)>,
+    iso: Option<(u32, u32)>,
+}
+
+impl ExposureRange {
+    /// Creates a range that includes all shutter speeds and ISO speeds.
+    pub fn new() -> Self {
+        ExposureRange::default()
+    }
+
+    /// Limits the range to shutter speeds from `min` to `max`.
+    pub fn shutter(mut self, min: Duration, max: Duration) -> Self {
+        self.shutter = Some((min, max));
+        self
+    }
+
+    /// Limits the range to ISO speeds from `min` to `max`.
+    pub fn iso(mut self, min: u32, max: u32) -> Self {
+        self.iso = Some((min, max));
+        self
+    }
+
+    fn contains_shutter(&self, seconds: f64) -> bool {
+        match self.shutter {
+            // Shutter speeds like 1/3 s are rounded by the camera.
+            Some((min, max)) => {
+                seconds >= min.as_secs_f64() * 0.99 && seconds <= max.as_secs_f64() * 1.01
+            }
+            None => true,
+        }
+    }
+
+    fn contains_iso(&self, iso: f64) -> bool {
+        match self.iso {
+            Some((min, max)) => iso >= f64::from(min) && iso <= f64::from(max),
+            None => true,
+        }
+    }
+}
+
+/// An image that was captured by an exposure sweep, with its settings.
+#[derive(Debug, Clone)]
+pub struct SweepFrame {
+    file: CameraFile,
+    shutter: String,
+    iso: String,
+}
+
+impl SweepFrame {
+    /// The captured file.
+    pub fn file(&self) -> &CameraFile {
+        &self.file
+    }
+
+    /// The shutter speed of the image, as named by the camera, e.g., `"1/125"`.
+    pub fn shutter(&self) -> &str {
+        &self.shutter
+    }
+
+    /// The ISO speed of the image, as named by the camera, e.g., `"800"`.
+    pub fn iso(&self) -> &str {
+        &self.iso
+    }
+
+    /// The shutter speed of the image.
+    pub fn exposure_time(&self) -> Option<Duration> {
+        parse_shutter(&self.shutter).map(Duration::from_secs_f64)
+    }
+}
+
+impl Camera {
+    /// Captures an image at each combination of shutter speed and ISO speed in a range.
+    ///
+    /// The images are captured from the shortest to the longest shutter speed, and at each
+    /// shutter speed from the lowest to the highest ISO speed. The camera's shutter speed and ISO
+    /// speed are restored afterwards, also if the sweep fails. The camera must be in manual
+    /// exposure mode.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use gphoto::ExposureRange;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// let range = ExposureRange::new()
+    ///     .shutter(Duration::from_millis(1), Duration::from_secs(1))
+    ///     .iso(100, 1600);
+    ///
+    /// for frame in camera.exposure_sweep(&range).unwrap() {
+    ///     println!("{} at {} ISO {}", frame.file().path(), frame.shutter(), frame.iso());
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if a setting could not be changed or an image could not
+    /// be captured:
+    ///
+    /// * `NotSupported` if the camera doesn't expose its shutter speed or ISO speed.
+    pub fn exposure_sweep(&mut self, range: &ExposureRange) -> crate::Result<Vec<SweepFrame>> {
+        let mut shutter_config = find_config(self, SHUTTER_SETTINGS)?;
+        let mut iso_config = find_config(self, ISO_SETTINGS)?;
+
+        let original_shutter = shutter_config.root().value();
+        let original_iso = iso_config.root().value();
+
+        let mut shutters: Vec<(String, f64)> = choices(&shutter_config, parse_shutter)
+            .into_iter()
+            .filter(|&(_, seconds)| range.contains_shutter(seconds))
+            .collect();
+        let mut isos: Vec<(String, f64)> = choices(&iso_config, parse_iso)
+            .into_iter()
+            .filter(|&(_, iso)| range.contains_iso(iso))
+            .collect();
+
+        shutters.sort_by(|a, b| a.1.total_cmp(&b.1));
+        isos.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let mut sweep = || -> crate::Result<Vec<SweepFrame>> {
+            let mut frames = Vec::with_capacity(shutters.len() * isos.len());
+
+            for (shutter, _) in &shutters {
+                set_choice(self, &mut shutter_config, shutter)?;
+
+                for (iso, _) in &isos {
+                    set_choice(self, &mut iso_config, iso)?;
+
+                    frames.push(SweepFrame {
+                        file: self.capture_image()?,
+                        shutter: shutter.clone(),
+                        iso: iso.clone(),
+                    });
+                }
+            }
+
+            Ok(frames)
+        };
+
+        let result = sweep();
+
+        for (mut config, original) in [
+            (shutter_config, original_shutter),
+            (iso_config, original_iso),
+        ] {
+            if let Some(original) = original {
+                let name = config.root().name().into_owned();
+
+                if config.set_value(&name, original).is_ok() {
+                    let _ = self.set_single_config(&mut config);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Selects a choice of a setting.
+pub(crate) fn set_choice(
+    camera: &mut Camera,
+    config: &mut Config,
+    choice: &str,
+) -> crate::Result<()> {
+    let name = config.root().name().into_owned();
+
+    config.set_value(&name, WidgetValue::Choice(choice.to_owned()))?;
+    camera.set_single_config(config)
+}
+
+/// The exposure of a shutter speed and ISO speed in stops, relative to 1 s at ISO 1.
+fn exposure(shutter: f64, iso: f64) -> f64 {
+    shutter.log2() + iso.log2()
+}
+
+pub(crate) fn find_config(camera: &mut Camera, names: &[&str]) -> crate::Result<Config> {
+    for name in names {
+        if let Ok(config) = camera.single_config(name) {
+            return Ok(config);
+        }
+    }
+
+    Err(crate::error::from_libgphoto2(
+        crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+    ))
+}
+
+fn current_value(config: &Config, parse: fn(&str) -> Option<f64>) -> crate::Result<(String, f64)> {
+    match config.root().value() {
+        Some(WidgetValue::Choice(value)) | Some(WidgetValue::Text(value)) => match parse(&value) {
+            Some(number) => Ok((value, number)),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        },
+        _ => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        )),
+    }
+}
+
+/// Returns the choices of a setting that have a numeric value.
+pub(crate) fn choices(config: &Config, parse: fn(&str) -> Option<f64>) -> Vec<(String, f64)> {
+    config
+        .root()
+        .choices()
+        .into_iter()
+        .filter_map(|choice| parse(&choice).map(|number| (choice, number)))
+        .collect()
+}
+
+/// Parses shutter speeds like `"1/125"`, `"0.3"`, `"30"`, `"2.5s"` or `30"` into seconds.
+pub(crate) fn parse_shutter(value: &str) -> Option<f64> {
+    let value = value.trim().trim_end_matches(['s', '"']);
+
+    let seconds = match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            numerator.trim().parse::<f64>().ok()? / denominator.trim().parse::<f64>().ok()?
+        }
+        None => value.parse().ok()?,
+    };
+
+    if seconds > 0.0 && seconds.is_finite() {
+        Some(seconds)
+    } else {
+        None
+    }
+}
+
+/// Parses ISO speeds like `"800"` or `"ISO 800"`.
+fn parse_iso(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let value = value.strip_prefix("ISO").unwrap_or(value).trim();
+
+    match value.parse::<u32>() {
+        Ok(iso) if iso > 0 => Some(f64::from(iso)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_shutter_speeds() {
+        let cases = [
+            ("1/125", Some(1.0 / 125.0)),
+            (" 1/4 ", Some(0.25)),
+            ("30\"", Some(30.0)),
+            ("2.5s", Some(2.5)),
+            ("0.5", Some(0.5)),
+            ("bulb", None),
+            ("0", None),
+            ("1/0", None),
+            ("", None),
+        ];
+
+        for &(value, seconds) in cases.iter() {
+            assert_eq!(parse_shutter(value), seconds, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn parses_iso_speeds() {
+        let cases = [
+            ("800", Some(800.0)),
+            ("ISO 800", Some(800.0)),
+            ("ISO100", Some(100.0)),
+            ("auto", None),
+            ("Auto ISO", None),
+            ("0", None),
+            ("", None),
+        ];
+
+        for &(value, iso) in cases.iter() {
+            assert_eq!(parse_iso(value), iso, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn corrects_histograms() {
+        let cases = [
+            (vec![118; 64], 118.0, 0.0),
+            (vec![236; 64], 118.0, -2.2),
+            (vec![59; 64], 118.0, 2.2),
+            // A black frame is corrected as if its mean were 1.
+            (vec![0; 64], 118.0, 2.2 * 118f64.log2()),
+            (Vec::new(), 118.0, 2.2 * 118f64.log2()),
+        ];
+
+        for &(ref luma, target, stops) in cases.iter() {
+            let correction = Histogram::from_luma(luma).correction(target);
+
+            assert!(correction.is_finite(), "{:?}", luma.first());
+            assert!((correction - stops).abs() < 1e-9, "{:?}", luma.first());
+        }
+    }
+
+    #[test]
+    fn ignores_non_finite_steps() {
+        let controller = |_: &mut Camera| -> crate::Result<f64> { Ok(0.0) };
+
+        let cases = [
+            (1.0, 1.0),
+            (-0.5, 0.5),
+            (f64::NAN, MAX_STEP),
+            (f64::INFINITY, MAX_STEP),
+            (f64::NEG_INFINITY, MAX_STEP),
+        ];
+
+        for &(stops, max_step) in cases.iter() {
+            let ramp = ExposureRamp::new(controller).max_step(stops);
+            assert_eq!(ramp.max_step, max_step, "{}", stops);
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use core::convert::TryFrom;
+use core::mem::MaybeUninit;
+use cstr_core::CStr;
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::camera::{Camera, CameraFile};
+use crate::handle::prelude::*;
+
+/// Information about a file on a camera.
+///
+/// Each field is optional, because drivers report different subsets of the information.
+///
+/// ## Example
+///
+/// ```no_run
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let file = camera.capture_image().unwrap();
+/// let info = camera.file_info(&file).unwrap();
+///
+/// println!("     size = {:?}", info.size());
+/// println!("mime type = {:?}", info.mime_type());
+/// println!("    width = {:?}", info.width());
+/// println!("   height = {:?}", info.height());
+/// println!(" modified = {:?}", info.mtime());
+/// ```
+pub struct FileInfo {
+    inner: crate::gphoto2::CameraFileInfo,
+    file: CameraFile,
+}
+
+impl FileInfo {
+    /// The file that the information is about.
+    pub fn file(&self) -> &CameraFile {
+        &self.file
+    }
+
+    /// The file's size in bytes.
+    pub fn size(&self) -> Option<u64> {
+        if self.inner.file.fields & crate::gphoto2::GP_FILE_INFO_SIZE != 0 {
+            Some(self.inner.file.size)
+        } else {
+            None
+        }
+    }
+
+    /// The file's MIME type, e.g., `image/jpeg`.
+    pub fn mime_type(&self) -> Option<Cow<'_, str>> {
+        if self.inner.file.fields & crate::gphoto2::GP_FILE_INFO_TYPE != 0 {
+            Some(unsafe {
+                String::from_utf8_lossy(CStr::from_ptr(self.inner.file.type_.as_ptr()).to_bytes())
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The width of the image in pixels.
+    pub fn width(&self) -> Option<u32> {
+        if self.inner.file.fields & crate::gphoto2::GP_FILE_INFO_WIDTH != 0 {
+            Some(self.inner.file.width)
+        } else {
+            None
+        }
+    }
+
+    /// The height of the image in pixels.
+    pub fn height(&self) -> Option<u32> {
+        if self.inner.file.fields & crate::gphoto2::GP_FILE_INFO_HEIGHT != 0 {
+            Some(self.inner.file.height)
+        } else {
+            None
+        }
+    }
+
+    /// The time that the file was last modified.
+    ///
+    /// Cameras store times in their local time zone without a zone offset, so this is only
+    /// accurate if the camera's clock is set to UTC.
+    pub fn mtime(&self) -> Option<SystemTime> {
+        if self.inner.file.fields & crate::gphoto2::GP_FILE_INFO_MTIME != 0 {
+            // Times before the epoch are never valid on a camera.
+            u64::try_from(self.inner.file.mtime)
+                .ok()
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the file has already been downloaded.
+    pub fn is_downloaded(&self) -> Option<bool> {
+        if self.inner.file.fields & crate::gphoto2::GP_FILE_INFO_STATUS != 0 {
+            Some(self.inner.file.status == crate::gphoto2::GP_FILE_STATUS_DOWNLOADED)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the file can be deleted.
+    pub fn is_deletable(&self) -> Option<bool> {
+        if self.inner.file.fields & crate::gphoto2::GP_FILE_INFO_PERMISSIONS != 0 {
+            Some(self.inner.file.permissions & crate::gphoto2::GP_FILE_PERM_DELETE != 0)
+        } else {
+            None
+        }
+    }
+
+    /// The size of the file's preview in bytes.
+    pub fn preview_size(&self) -> Option<u64> {
+        if self.inner.preview.fields & crate::gphoto2::GP_FILE_INFO_SIZE != 0 {
+            Some(self.inner.preview.size)
+        } else {
+            None
+        }
+    }
+}
+
+impl Camera {
+    /// Retrieves information about a file.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the information could not be retrieved:
+    ///
+    /// * `FileNotFound` if the file doesn't exist.
+    /// * `NotSupported` if the driver doesn't provide file information.
+    pub fn file_info(&mut self, file: &CameraFile) -> crate::Result<FileInfo> {
+        let mut info = MaybeUninit::uninit();
+
+        let info = unsafe {
+            match crate::gphoto2::gp_camera_file_get_info(
+                self.camera,
+                file.inner.folder.as_ptr(),
+                file.inner.name.as_ptr(),
+                &mut *info.as_mut_ptr(),
+                self.context.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK => (),
+                err => return Err(crate::error::from_libgphoto2(err)),
+            }
+            info.assume_init()
+        };
+
+        Ok(FileInfo {
+            inner: info,
+            file: file.clone(),
+        })
+    }
+}
+
+impl FileInfo {
+    /// Creates the information of a file for which the driver reports no fields.
+    #[cfg(test)]
+    pub(crate) fn empty(file: CameraFile) -> Self {
+        FileInfo {
+            // The information is a plain C struct, and its fields are marked as unset.
+            inner: unsafe { core::mem::zeroed() },
+            file,
+        }
+    }
+}
+
+/// A declarative filter for files on a camera.
+///
+/// Filters select files by their information, so that only part of a card needs to be
+/// transferred. A file matches if it meets all of the filter's criteria. Criteria that depend on
+/// information that the camera doesn't report for a file don't match that file.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::{Duration, SystemTime};
+///
+/// use gphoto::{DownloadOptions, FileFilter};
+///
+/// let filter = FileFilter::new()
+///     .after(SystemTime::now() - Duration::from_secs(24 * 60 * 60))
+///     .extension("cr2")
+///     .extension("nef")
+///     .min_size(1024);
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let downloaded = camera
+///     .fs()
+///     .download_matching("/", |info| filter.matches(info), "photos".as_ref(), &DownloadOptions::new())
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    after: Option<SystemTime>,
+    before: Option<SystemTime>,
+    extensions: Vec<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+}
+
+impl FileFilter {
+    /// Creates a filter that matches all files.
+    pub fn new() -> Self {
+        FileFilter::default()
+    }
+
+    /// Matches files that were modified at or after a time.
+    pub fn after(mut self, time: SystemTime) -> Self {
+        self.after = Some(time);
+        self
+    }
+
+    /// Matches files that were modified before a time.
+    pub fn before(mut self, time: SystemTime) -> Self {
+        self.before = Some(time);
+        self
+    }
+
+    /// Matches files with an extension.
+    ///
+    /// The extension is given without the leading dot and compared ignoring ASCII case. If
+    /// several extensions are added, files with any of them match.
+    pub fn extension(mut self, extension: &str) -> Self {
+        self.extensions.push(extension.to_ascii_lowercase());
+        self
+    }
+
+    /// Matches files of at least `bytes` bytes.
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.min_size = Some(bytes);
+        self
+    }
+
+    /// Matches files of at most `bytes` bytes.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Returns `true` if a file matches the filter.
+    pub fn matches(&self, info: &FileInfo) -> bool {
+        self.matches_parts(&info.file().basename(), info.mtime(), info.size())
+    }
+
+    /// Returns `true` if a file with a name, modification time and size matches the filter.
+    fn matches_parts(&self, name: &str, mtime: Option<SystemTime>, size: Option<u64>) -> bool {
+        if let Some(after) = self.after {
+            match mtime {
+                Some(mtime) if mtime >= after => (),
+                _ => return false,
+            }
+        }
+
+        if let Some(before) = self.before {
+            match mtime {
+                Some(mtime) if mtime < before => (),
+                _ => return false,
+            }
+        }
+
+        if let Some(min_size) = self.min_size {
+            match size {
+                Some(size) if size >= min_size => (),
+                _ => return false,
+            }
+        }
+
+        if let Some(max_size) = self.max_size {
+            match size {
+                Some(size) if size <= max_size => (),
+                _ => return false,
+            }
+        }
+
+        if !self.extensions.is_empty() {
+            let name = name.to_ascii_lowercase();
+
+            let extension = match name.rfind('.') {
+                Some(index) => &name[index + 1..],
+                None => return false,
+            };
+
+            if !self.extensions.iter().any(|ext| ext == extension) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_files() {
+        let time = |seconds| Some(UNIX_EPOCH + Duration::from_secs(seconds));
+        let day = 24 * 60 * 60;
+
+        let filter = FileFilter::new()
+            .after(UNIX_EPOCH + Duration::from_secs(day))
+            .before(UNIX_EPOCH + Duration::from_secs(2 * day))
+            .extension("CR2")
+            .extension("nef")
+            .min_size(10)
+            .max_size(100);
+
+        let cases = [
+            ("IMG_0001.CR2", time(day), Some(10), true),
+            ("IMG_0001.cr2", time(2 * day - 1), Some(100), true),
+            ("DSC_0001.NEF", time(day + 1), Some(50), true),
+            ("IMG_0001.JPG", time(day), Some(50), false),
+            ("CR2", time(day), Some(50), false),
+            ("IMG_0001.CR2", time(day - 1), Some(50), false),
+            ("IMG_0001.CR2", time(2 * day), Some(50), false),
+            ("IMG_0001.CR2", None, Some(50), false),
+            ("IMG_0001.CR2", time(day), Some(9), false),
+            ("IMG_0001.CR2", time(day), Some(101), false),
+            ("IMG_0001.CR2", time(day), None, false),
+        ];
+
+        for &(name, mtime, size, matches) in cases.iter() {
+            assert_eq!(
+                filter.matches_parts(name, mtime, size),
+                matches,
+                "{} {:?} {:?}",
+                name,
+                mtime,
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn matches_everything_by_default() {
+        let filter = FileFilter::new();
+
+        assert!(filter.matches_parts("IMG_0001.JPG", None, None));
+        assert!(filter.matches_parts("README", Some(UNIX_EPOCH), Some(0)));
+    }
+}
@@ -0,0 +1,557 @@
+use cstr_core::CString;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::vec;
+
+use crate::buffer::{BufferPool, PooledBuffer};
+use crate::camera::{Camera, CameraFile};
+use crate::download::DownloadOptions;
+use crate::fileinfo::FileInfo;
+use crate::hooks::Hooks;
+use crate::journal::{journal_error, Journal};
+use crate::list::List;
+use crate::media::FileMedia;
+use crate::storage::Storage;
+use crate::widget::{WidgetType, WidgetValue};
+
+use crate::handle::prelude::*;
+
+/// Names of the settings that select the card slot for new captures.
+const SLOT_SETTINGS: &[&str] = &["cardslot", "recordingmedia"];
+
+/// Access to the filesystem of a camera's storage.
+///
+/// ## Example
+///
+/// A `CameraFs` can be used to list the files on each of a camera's storages, e.g., the two
+/// memory cards of a dual-slot camera:
+///
+/// ```no_run
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+///
+/// for storage in camera.storage().unwrap() {
+///     println!("[{}]", storage.label().unwrap_or_default());
+///
+///     for file in camera.fs().storage_files(&storage).unwrap() {
+///         println!("{}", file.path());
+///     }
+/// }
+/// ```
+pub struct CameraFs<'a> {
+    camera: &'a mut Camera,
+}
+
+impl<'a> CameraFs<'a> {
+    /// Lists the files in a folder.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the folder could not be listed:
+    ///
+    /// * `DirectoryNotFound` if the folder doesn't exist.
+    pub fn list_files(&mut self, folder: &str) -> crate::Result<Vec<CameraFile>> {
+        let c_folder = to_cstring(folder)?;
+        let mut list = List::new()?;
+
+        try_unsafe!(crate::gphoto2::gp_camera_folder_list_files(
+            self.camera.camera,
+            c_folder.as_ptr(),
+            list.as_mut_ptr(),
+            self.camera.context.as_mut_ptr()
+        ));
+
+        Ok(list
+            .names()
+            .iter()
+            .map(|name| crate::camera::file_from_parts(folder, name))
+            .collect())
+    }
+
+    /// Lists the subfolders of a folder.
+    ///
+    /// Returns the absolute paths of the subfolders.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the folder could not be listed:
+    ///
+    /// * `DirectoryNotFound` if the folder doesn't exist.
+    pub fn list_folders(&mut self, folder: &str) -> crate::Result<Vec<String>> {
+        let c_folder = to_cstring(folder)?;
+        let mut list = List::new()?;
+
+        try_unsafe!(crate::gphoto2::gp_camera_folder_list_folders(
+            self.camera.camera,
+            c_folder.as_ptr(),
+            list.as_mut_ptr(),
+            self.camera.context.as_mut_ptr()
+        ));
+
+        Ok(list.names().iter().map(|name| join(folder, name)).collect())
+    }
+
+    /// Lists all files in a folder and its subfolders.
+    pub fn walk(&mut self, root: &str) -> crate::Result<Vec<CameraFile>> {
+        let mut files = Vec::new();
+        let mut pending = vec![root.to_owned()];
+
+        while let Some(folder) = pending.pop() {
+            files.extend(self.list_files(&folder)?);
+
+            let mut folders = self.list_folders(&folder)?;
+            folders.reverse();
+            pending.extend(folders);
+        }
+
+        Ok(files)
+    }
+
+    /// Computes statistics about the files in a folder.
+    ///
+    /// Subfolders are not included. The statistics are computed from the information that the
+    /// camera reports for each file, so no file data is transferred.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut fs = camera.fs();
+    ///
+    /// for folder in fs.list_folders("/store_00010001/DCIM").unwrap() {
+    ///     let stats = fs.folder_stats(&folder).unwrap();
+    ///     println!("{}: {} files, {} bytes", folder, stats.files(), stats.total_size());
+    /// }
+    /// ```
+    pub fn folder_stats(&mut self, folder: &str) -> crate::Result<FolderStats> {
+        let mut stats = FolderStats {
+            files: 0,
+            total_size: 0,
+            newest: None,
+        };
+
+        for file in self.list_files(folder)? {
+            let info = self.camera.file_info(&file)?;
+
+            stats.files += 1;
+            stats.total_size += info.size().unwrap_or(0);
+
+            stats.newest = stats.newest.max(info.mtime());
+        }
+
+        Ok(stats)
+    }
+
+    /// Downloads the thumbnails of the files in a folder.
+    ///
+    /// Only the previews that the camera stores with each file are transferred, which is much
+    /// faster than downloading the files themselves, so culling applications can show a contact
+    /// sheet of a folder of RAW files in seconds. Subfolders are not included.
+    ///
+    /// The thumbnails are downloaded one at a time as the iterator is advanced. They are stored in
+    /// buffers from a `BufferPool`, so a caller that drops each thumbnail after rendering it
+    /// doesn't allocate per file.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut fs = camera.fs();
+    ///
+    /// for thumbnail in fs.thumbnails("/store_00010001/DCIM/100CANON").unwrap() {
+    ///     let (file, data) = thumbnail.unwrap();
+    ///     println!("{}: {} bytes", file.path(), data.len());
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the folder could not be listed:
+    ///
+    /// * `DirectoryNotFound` if the folder doesn't exist.
+    ///
+    /// The iterator returns an error for files whose thumbnail could not be downloaded, e.g.,
+    /// `NotSupported` for files without a thumbnail, and continues with the next file.
+    pub fn thumbnails(&mut self, folder: &str) -> crate::Result<Thumbnails<'_>> {
+        let files = self.list_files(folder)?;
+
+        Ok(Thumbnails {
+            camera: self.camera,
+            files: files.into_iter(),
+            media: FileMedia::create_mem()?,
+            pool: BufferPool::default(),
+        })
+    }
+
+    /// Downloads the files below a folder that match a predicate.
+    ///
+    /// The folder and its subfolders are walked and the predicate is called with the information
+    /// about each file, so that files can be selected by name, size or date without downloading
+    /// them. The matching files are downloaded to `dest_dir` with `Camera::download_to_dir()`.
+    ///
+    /// Returns the paths of the downloaded files. Files that were skipped because of the
+    /// options' `Collision` policy are not included.
+    ///
+    /// Before the first file is downloaded, the total size of the matching files is compared to
+    /// the free space of the destination's filesystem, so that a download that can't complete
+    /// fails early instead of filling the disk.
+    ///
+    /// ## Example
+    ///
+    /// Downloading only the RAW files captured in the last 24 hours:
+    ///
+    /// ```no_run
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// use gphoto::{Collision, DownloadOptions};
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let since = SystemTime::now() - Duration::from_secs(24 * 60 * 60);
+    /// let options = DownloadOptions::new().collision(Collision::SkipIfSameSize);
+    ///
+    /// let downloaded = camera
+    ///     .fs()
+    ///     .download_matching(
+    ///         "/",
+    ///         |info| {
+    ///             info.file().basename().to_ascii_uppercase().ends_with(".CR2")
+    ///                 && info.mtime().map_or(false, |mtime| mtime >= since)
+    ///         },
+    ///         "photos".as_ref(),
+    ///         &options,
+    ///     )
+    ///     .unwrap();
+    ///
+    /// println!("downloaded {} files", downloaded.len());
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if a folder could not be listed or a file could not be
+    /// downloaded:
+    ///
+    /// * `DirectoryNotFound` if the folder doesn't exist.
+    /// * `FileExists` if a file's name is taken and the policy is `Collision::Fail`.
+    /// * `NoSpace` if the destination doesn't have room for the matching files.
+    ///
+    /// Files that were downloaded before the error are kept.
+    pub fn download_matching<F>(
+        &mut self,
+        root: &str,
+        filter: F,
+        dest_dir: &Path,
+        options: &DownloadOptions,
+    ) -> crate::Result<Vec<PathBuf>>
+    where
+        F: Fn(&FileInfo) -> bool,
+    {
+        self.download_matching_with_hooks(root, filter, dest_dir, options, &mut Hooks::new())
+    }
+
+    /// Downloads the files below a folder that match a predicate and runs hooks for each of them.
+    ///
+    /// This works like `download_matching()`, but runs the pipeline of hooks after each file is
+    /// downloaded. Hooks that fail don't stop the download; their failures are recorded in the
+    /// pipeline.
+    pub fn download_matching_with_hooks<F>(
+        &mut self,
+        root: &str,
+        filter: F,
+        dest_dir: &Path,
+        options: &DownloadOptions,
+        hooks: &mut Hooks,
+    ) -> crate::Result<Vec<PathBuf>>
+    where
+        F: Fn(&FileInfo) -> bool,
+    {
+        let mut matching = Vec::new();
+        let mut required = 0;
+
+        for file in self.walk(root)? {
+            let info = self.camera.file_info(&file)?;
+
+            if filter(&info) {
+                required += info.size().unwrap_or(0);
+                matching.push(info);
+            }
+        }
+
+        crate::download::ensure_space(dest_dir, required)?;
+
+        let mut downloaded = Vec::new();
+
+        for info in matching {
+            if let Some(path) = self
+                .camera
+                .download_to_dir(info.file(), dest_dir, options)?
+            {
+                hooks.run(&path, &info);
+                downloaded.push(path);
+            }
+        }
+
+        Ok(downloaded)
+    }
+
+    /// Downloads the files below a folder that match a predicate, runs hooks for each of them, and
+    /// records the transfers in a journal.
+    ///
+    /// This works like `download_matching_with_hooks()`, but skips the files that the journal
+    /// records as completed, as long as their size on the camera is unchanged and the local file
+    /// still exists. Each remaining file is recorded as planned with its local path before it's
+    /// downloaded, and as completed or failed afterwards, so an interrupted offload resumes where
+    /// it stopped. See `Journal` for details.
+    ///
+    /// A file whose last entry is planned was being downloaded when the offload was interrupted,
+    /// e.g., by a power loss. It's downloaded again to the local path that was planned for it,
+    /// regardless of the `Collision` policy, so a copy that was completed but not recorded is
+    /// replaced instead of kept twice.
+    ///
+    /// Returns the paths of the files that were downloaded by this call.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the errors of `download_matching()`, and:
+    ///
+    /// * `OSFailure` if the journal could not be read or written.
+    pub fn download_matching_journaled<F>(
+        &mut self,
+        root: &str,
+        filter: F,
+        dest_dir: &Path,
+        options: &DownloadOptions,
+        hooks: &mut Hooks,
+        journal: &mut dyn Journal,
+    ) -> crate::Result<Vec<PathBuf>>
+    where
+        F: Fn(&FileInfo) -> bool,
+    {
+        let entries = journal.entries().map_err(|_| journal_error())?;
+        let completed = crate::journal::completed(&entries);
+        let interrupted = crate::journal::interrupted(&entries);
+
+        let mut matching = Vec::new();
+        let mut required = 0;
+
+        for file in self.walk(root)? {
+            let info = self.camera.file_info(&file)?;
+
+            if !filter(&info) {
+                continue;
+            }
+
+            if let Some(&(size, local)) = completed.get(file.path().as_str()) {
+                if size == info.size() && local.exists() {
+                    continue;
+                }
+            }
+
+            required += info.size().unwrap_or(0);
+            matching.push(info);
+        }
+
+        crate::download::ensure_space(dest_dir, required)?;
+
+        let mut downloaded = Vec::new();
+
+        for info in matching {
+            let planned = interrupted.get(info.file().path().as_str()).copied();
+
+            let path = self
+                .camera
+                .download_journaled(&info, dest_dir, options, hooks, journal, planned)?;
+            downloaded.extend(path);
+        }
+
+        Ok(downloaded)
+    }
+
+    /// Lists all files on a storage.
+    ///
+    /// The files are listed starting from the storage's base directory, or from the root folder if
+    /// the camera doesn't report a base directory.
+    pub fn storage_files(&mut self, storage: &Storage) -> crate::Result<Vec<CameraFile>> {
+        match storage.base_dir() {
+            Some(base_dir) => self.walk(&base_dir),
+            None => self.walk("/"),
+        }
+    }
+}
+
+/// An iterator over the thumbnails of the files in a folder.
+///
+/// This iterator is created with `CameraFs::thumbnails()`.
+pub struct Thumbnails<'a> {
+    camera: &'a mut Camera,
+    files: vec::IntoIter<CameraFile>,
+    media: FileMedia,
+    pool: BufferPool,
+}
+
+impl<'a> Thumbnails<'a> {
+    /// Sets the pool that thumbnail buffers are taken from.
+    pub fn set_buffer_pool(&mut self, pool: BufferPool) {
+        self.pool = pool;
+    }
+}
+
+impl<'a> Iterator for Thumbnails<'a> {
+    type Item = crate::Result<(CameraFile, PooledBuffer)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let file = self.files.next()?;
+
+        if let Err(err) = self.camera.get_file(
+            &file,
+            &mut self.media,
+            Some(crate::gphoto2::GP_FILE_TYPE_PREVIEW),
+        ) {
+            return Some(Err(err));
+        }
+
+        let mut thumbnail = self.pool.get();
+        self.media.get_data_into(&mut thumbnail);
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_downloaded_bytes(thumbnail.len() as u64);
+
+        Some(Ok((file, thumbnail)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.files.size_hint()
+    }
+}
+
+/// Statistics about the files in a folder.
+///
+/// Folder statistics are computed with `CameraFs::folder_stats()`.
+#[derive(Debug, Clone, Copy)]
+pub struct FolderStats {
+    files: usize,
+    total_size: u64,
+    newest: Option<SystemTime>,
+}
+
+impl FolderStats {
+    /// Returns the number of files.
+    pub fn files(&self) -> usize {
+        self.files
+    }
+
+    /// Returns the total size of the files in bytes.
+    ///
+    /// Files whose size isn't reported by the camera are counted as empty.
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// Returns the modification time of the newest file.
+    pub fn newest(&self) -> Option<SystemTime> {
+        self.newest
+    }
+}
+
+impl Camera {
+    /// Returns access to the camera's filesystem.
+    pub fn fs(&mut self) -> CameraFs<'_> {
+        CameraFs { camera: self }
+    }
+
+    /// Returns the card slots that new captures can be stored in.
+    ///
+    /// Returns an empty `Vec` if the camera doesn't allow selecting a card slot.
+    pub fn capture_slots(&mut self) -> crate::Result<Vec<String>> {
+        let config = self.config()?;
+
+        Ok(SLOT_SETTINGS
+            .iter()
+            .find_map(|name| config.get(name))
+            .map(|widget| widget.choices())
+            .unwrap_or_default())
+    }
+
+    /// Selects the card slot that new captures are stored in.
+    ///
+    /// Slots are numbered starting from 1. This uses the `cardslot` or `recordingmedia` setting,
+    /// depending on the camera. Note that a camera's backup or overflow recording modes may still
+    /// store files on other cards.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the slot could not be selected:
+    ///
+    /// * `NotSupported` if the camera doesn't allow selecting a card slot.
+    /// * `InvalidInput` if the camera doesn't have the given slot.
+    pub fn set_capture_slot(&mut self, slot: u32) -> crate::Result<()> {
+        let mut config = self.config()?;
+
+        let name = match SLOT_SETTINGS.iter().find(|name| config.get(name).is_some()) {
+            Some(name) => *name,
+            None => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                ))
+            }
+        };
+
+        let value = match config.get(name) {
+            Some(widget) => match widget.widget_type() {
+                WidgetType::Radio | WidgetType::Menu => {
+                    let choices = widget.choices();
+
+                    choices
+                        .iter()
+                        .find(|choice| slot_number(choice) == Some(slot))
+                        .or_else(|| choices.get((slot as usize).wrapping_sub(1)))
+                        .map(|choice| WidgetValue::Choice(choice.clone()))
+                }
+                WidgetType::Text => Some(WidgetValue::Text(slot.to_string())),
+                WidgetType::Range => Some(WidgetValue::Range(slot as f32)),
+                _ => None,
+            },
+            None => None,
+        };
+
+        match value {
+            Some(value) => config.set_value(name, value)?,
+            None => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        }
+
+        self.set_config(&mut config)
+    }
+}
+
+/// Joins a folder and a name into an absolute path.
+pub(crate) fn join(folder: &str, name: &str) -> String {
+    if folder.ends_with('/') {
+        format!("{}{}", folder, name)
+    } else {
+        format!("{}/{}", folder, name)
+    }
+}
+
+fn to_cstring(s: &str) -> crate::Result<CString> {
+    match CString::new(s) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+        )),
+    }
+}
+
+/// Returns the number at the end of a slot name like `"Card 2"` or `"SD1"`.
+fn slot_number(choice: &str) -> Option<u32> {
+    let digits = choice
+        .trim_end()
+        .bytes()
+        .rev()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+
+    let choice = choice.trim_end();
+    choice[choice.len() - digits..].parse().ok()
+}
@@ -0,0 +1,188 @@
+use crate::camera::Camera;
+use crate::exposure::{find_config, set_choice};
+use crate::widget::WidgetValue;
+
+/// Names of the settings that contain the flash mode in different drivers.
+const FLASH_MODE_SETTINGS: &[&str] = &["flashmode", "canonflashmode"];
+
+/// The setting that raises the built-in flash of Nikon cameras.
+const POPUP_FLASH: &str = "popupflash";
+
+/// Sync modes of a flash.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FlashSync {
+    /// The flash fires at the start of the exposure (front-curtain sync).
+    Front,
+
+    /// The flash fires at the end of the exposure (rear-curtain sync), so that motion trails
+    /// appear behind moving subjects.
+    Rear,
+
+    /// Front-curtain sync with a slow shutter speed, so that the background is exposed too.
+    Slow,
+
+    /// Rear-curtain sync with a slow shutter speed.
+    SlowRear,
+}
+
+impl FlashSync {
+    /// Classifies a choice of the flash mode setting, e.g., `"Slow rear-curtain sync"`.
+    fn from_choice(choice: &str) -> Self {
+        let choice = choice.to_ascii_lowercase();
+
+        match (choice.contains("slow"), choice.contains("rear")) {
+            (true, true) => FlashSync::SlowRear,
+            (false, true) => FlashSync::Rear,
+            (true, false) => FlashSync::Slow,
+            (false, false) => FlashSync::Front,
+        }
+    }
+}
+
+impl Camera {
+    /// Returns the flash's sync mode.
+    ///
+    /// Flash modes that don't name a sync mode, e.g., `"Fill flash"` or `"Off"`, are reported as
+    /// `FlashSync::Front`.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the flash mode could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't have a flash mode setting.
+    pub fn flash_sync(&mut self) -> crate::Result<FlashSync> {
+        let config = find_config(self, FLASH_MODE_SETTINGS)?;
+
+        match config.root().value() {
+            Some(WidgetValue::Choice(value)) | Some(WidgetValue::Text(value)) => {
+                Ok(FlashSync::from_choice(&value))
+            }
+            _ => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+
+    /// Changes the flash's sync mode.
+    ///
+    /// The flash mode is set to the first of its choices with the sync mode that doesn't turn the
+    /// flash off or enable red-eye reduction, if there is one.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::FlashSync;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// camera.set_builtin_flash(true).unwrap();
+    /// camera.set_flash_sync(FlashSync::SlowRear).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the flash mode could not be changed:
+    ///
+    /// * `NotSupported` if the camera doesn't have a flash mode setting, or the setting doesn't
+    ///   have a choice for the sync mode.
+    pub fn set_flash_sync(&mut self, sync: FlashSync) -> crate::Result<()> {
+        let mut config = find_config(self, FLASH_MODE_SETTINGS)?;
+
+        let choice = config
+            .root()
+            .choices()
+            .into_iter()
+            .filter(|choice| FlashSync::from_choice(choice) == sync && !is_off(choice))
+            .min_by_key(|choice| choice.to_ascii_lowercase().contains("red"));
+
+        match choice {
+            Some(choice) => set_choice(self, &mut config, &choice),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+
+    /// Enables or disables rear-curtain sync.
+    ///
+    /// This is a shortcut for `set_flash_sync()` with `FlashSync::Rear` or `FlashSync::Front`.
+    pub fn set_rear_curtain_sync(&mut self, enabled: bool) -> crate::Result<()> {
+        self.set_flash_sync(if enabled {
+            FlashSync::Rear
+        } else {
+            FlashSync::Front
+        })
+    }
+
+    /// Returns `true` if the built-in flash is enabled, i.e., the flash mode isn't off.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the flash mode could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't have a flash mode setting.
+    pub fn builtin_flash(&mut self) -> crate::Result<bool> {
+        let config = find_config(self, FLASH_MODE_SETTINGS)?;
+
+        match config.root().value() {
+            Some(WidgetValue::Choice(value)) | Some(WidgetValue::Text(value)) => {
+                Ok(!is_off(&value))
+            }
+            _ => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+
+    /// Enables or disables the built-in flash.
+    ///
+    /// Enabling the flash raises it on cameras with a pop-up flash that can be raised remotely,
+    /// and selects front-curtain sync if the flash mode was off. Disabling it selects the flash
+    /// mode's off choice; a raised flash has to be lowered by hand.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the flash could not be changed:
+    ///
+    /// * `NotSupported` if the camera doesn't have a flash mode setting, or the setting doesn't
+    ///   have an off choice.
+    pub fn set_builtin_flash(&mut self, enabled: bool) -> crate::Result<()> {
+        if enabled {
+            if let Ok(mut config) = self.single_config(POPUP_FLASH) {
+                if config
+                    .set_value(POPUP_FLASH, WidgetValue::Toggle(true))
+                    .is_ok()
+                {
+                    self.set_single_config(&mut config)?;
+                }
+            }
+
+            if !self.builtin_flash()? {
+                self.set_flash_sync(FlashSync::Front)?;
+            }
+
+            return Ok(());
+        }
+
+        let mut config = find_config(self, FLASH_MODE_SETTINGS)?;
+
+        match config
+            .root()
+            .choices()
+            .into_iter()
+            .find(|choice| is_off(choice))
+        {
+            Some(choice) => set_choice(self, &mut config, &choice),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+}
+
+/// Returns `true` if a choice of the flash mode setting turns the flash off.
+fn is_off(choice: &str) -> bool {
+    let choice = choice.to_ascii_lowercase();
+
+    choice == "off" || choice.contains("flash off") || choice.contains("no flash")
+}
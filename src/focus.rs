@@ -0,0 +1,337 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::camera::{Camera, CameraFile};
+#[cfg(feature = "image")]
+use crate::media::FileMedia;
+use crate::widget::WidgetValue;
+
+/// The setting that drives the focus of a lens in live view.
+const MANUAL_FOCUS_DRIVE: &str = "manualfocusdrive";
+
+/// The distances of focus steps for drivers with a numeric focus drive, e.g., Nikon.
+const RANGE_STEPS: [f32; 3] = [10.0, 100.0, 1000.0];
+
+/// The time that the lens is given to complete a focus step.
+const FOCUS_SETTLE: Duration = Duration::from_millis(300);
+
+/// The width that preview frames are scaled down to for measuring contrast.
+#[cfg(feature = "image")]
+const CONTRAST_WIDTH: u32 = 320;
+
+/// Directions to drive the focus of a lens.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FocusDirection {
+    /// Towards the closest focus distance.
+    Near,
+
+    /// Towards infinity.
+    Far,
+}
+
+/// Sizes of the steps that a lens's focus is driven by.
+///
+/// These are the steps of the `manualfocusdrive` setting, e.g., `Near 1` to `Near 3` on Canon
+/// cameras. How far each step moves the focus depends on the lens; `calibrate_focus()` measures
+/// it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FocusStep {
+    /// The smallest step.
+    Small,
+
+    /// A medium step.
+    Medium,
+
+    /// The largest step.
+    Large,
+}
+
+impl FocusStep {
+    fn index(self) -> usize {
+        match self {
+            FocusStep::Small => 0,
+            FocusStep::Medium => 1,
+            FocusStep::Large => 2,
+        }
+    }
+}
+
+/// The measured focus travel of a lens's focus steps, in units of small steps.
+///
+/// Calibrations are measured with `Camera::calibrate_focus()`. A calibration converts a focus
+/// spacing into the steps that drive it, so that the frames of a focus stack are evenly spaced
+/// regardless of the lens.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FocusCalibration {
+    medium: f64,
+    large: f64,
+}
+
+impl FocusCalibration {
+    /// Creates a calibration from known step sizes, in units of small steps.
+    pub fn new(medium: f64, large: f64) -> Self {
+        FocusCalibration {
+            medium: medium.max(1.0),
+            large: large.max(1.0),
+        }
+    }
+
+    /// The travel of a medium step, in small steps.
+    pub fn medium(&self) -> f64 {
+        self.medium
+    }
+
+    /// The travel of a large step, in small steps.
+    pub fn large(&self) -> f64 {
+        self.large
+    }
+
+    /// Returns the steps that move the focus by `spacing` small steps.
+    ///
+    /// Large steps are used first, so that a spacing is driven with as few steps as possible.
+    pub fn steps(&self, spacing: f64) -> Vec<FocusStep> {
+        let mut remaining = spacing.max(0.0);
+        let mut steps = Vec::new();
+
+        for &(step, size) in &[
+            (FocusStep::Large, self.large),
+            (FocusStep::Medium, self.medium),
+        ] {
+            while remaining + 0.5 >= size {
+                steps.push(step);
+                remaining -= size;
+            }
+        }
+
+        for _ in 0..remaining.max(0.0).round() as usize {
+            steps.push(FocusStep::Small);
+        }
+
+        steps
+    }
+}
+
+impl Camera {
+    /// Drives the lens's focus by one step.
+    ///
+    /// The camera must be in live view, e.g., by capturing a preview frame first, and the lens
+    /// must be in autofocus mode.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the focus could not be driven:
+    ///
+    /// * `NotSupported` if the camera can't drive the focus.
+    pub fn drive_focus(&mut self, direction: FocusDirection, step: FocusStep) -> crate::Result<()> {
+        let mut config = self.single_config(MANUAL_FOCUS_DRIVE)?;
+
+        let value = match config.root().value() {
+            Some(WidgetValue::Range(_)) => {
+                let distance = RANGE_STEPS[step.index()];
+
+                WidgetValue::Range(match direction {
+                    FocusDirection::Near => -distance,
+                    FocusDirection::Far => distance,
+                })
+            }
+            _ => {
+                let name = match direction {
+                    FocusDirection::Near => "near",
+                    FocusDirection::Far => "far",
+                };
+                let number = (step.index() + 1).to_string();
+
+                match config.root().choices().into_iter().find(|choice| {
+                    let choice = choice.to_ascii_lowercase();
+                    choice.starts_with(name) && choice.ends_with(number.as_str())
+                }) {
+                    Some(choice) => WidgetValue::Choice(choice),
+                    None => {
+                        return Err(crate::error::from_libgphoto2(
+                            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                        ))
+                    }
+                }
+            }
+        };
+
+        config.set_value(MANUAL_FOCUS_DRIVE, value)?;
+        self.set_single_config(&mut config)?;
+
+        thread::sleep(FOCUS_SETTLE);
+
+        Ok(())
+    }
+
+    /// Captures a focus stack.
+    ///
+    /// `frames` images are captured, and the focus is driven by `steps` between them. The steps
+    /// of an even spacing are computed by `FocusCalibration::steps()`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::{FocusCalibration, FocusDirection};
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// let calibration = FocusCalibration::new(8.0, 60.0);
+    /// let steps = calibration.steps(12.0);
+    ///
+    /// let files = camera.focus_bracket(20, FocusDirection::Far, &steps).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if an image could not be captured or the focus could not
+    /// be driven:
+    ///
+    /// * `NotSupported` if the camera can't drive the focus.
+    pub fn focus_bracket(
+        &mut self,
+        frames: usize,
+        direction: FocusDirection,
+        steps: &[FocusStep],
+    ) -> crate::Result<Vec<CameraFile>> {
+        let mut files = Vec::with_capacity(frames);
+
+        for frame in 0..frames {
+            if frame > 0 {
+                for &step in steps {
+                    self.drive_focus(direction, step)?;
+                }
+            }
+
+            files.push(self.capture_image()?);
+        }
+
+        Ok(files)
+    }
+
+    /// Measures the focus travel of the lens's medium and large steps.
+    ///
+    /// The travel is measured by the contrast of preview frames: the contrast after a medium step
+    /// is compared to the contrast after each of `samples` small steps, and likewise for a large
+    /// step and medium steps. Before calibrating, focus on a flat, textured target, e.g., a page
+    /// of text, so that the contrast falls steadily while the focus moves away from it. The focus
+    /// is returned to the target after each measurement.
+    ///
+    /// This function is only available with the `image` feature enabled.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the calibration could not be measured:
+    ///
+    /// * `NotSupported` if the camera can't capture previews or drive the focus.
+    /// * `CorruptedData` if a preview frame isn't a valid JPEG image.
+    #[cfg(feature = "image")]
+    pub fn calibrate_focus(&mut self, samples: usize) -> crate::Result<FocusCalibration> {
+        let samples = samples.max(2);
+        let mut media = FileMedia::create_mem()?;
+
+        let small = self.contrast_curve(&mut media, FocusStep::Small, samples)?;
+        let medium = self.contrast_curve(&mut media, FocusStep::Medium, samples)?;
+
+        let medium_travel = position(&small, self.contrast_after(&mut media, FocusStep::Medium)?);
+        let large_travel = position(&medium, self.contrast_after(&mut media, FocusStep::Large)?);
+
+        Ok(FocusCalibration::new(
+            medium_travel,
+            large_travel * medium_travel,
+        ))
+    }
+
+    /// Measures the contrast before and after each of `samples` steps towards infinity, and
+    /// returns the focus to the start.
+    #[cfg(feature = "image")]
+    fn contrast_curve(
+        &mut self,
+        media: &mut FileMedia,
+        step: FocusStep,
+        samples: usize,
+    ) -> crate::Result<Vec<f64>> {
+        let mut curve = vec![self.preview_contrast(media)?];
+
+        for _ in 0..samples {
+            self.drive_focus(FocusDirection::Far, step)?;
+            curve.push(self.preview_contrast(media)?);
+        }
+
+        for _ in 0..samples {
+            self.drive_focus(FocusDirection::Near, step)?;
+        }
+
+        Ok(curve)
+    }
+
+    /// Measures the contrast after one step towards infinity, and returns the focus to the start.
+    #[cfg(feature = "image")]
+    fn contrast_after(&mut self, media: &mut FileMedia, step: FocusStep) -> crate::Result<f64> {
+        self.drive_focus(FocusDirection::Far, step)?;
+        let contrast = self.preview_contrast(media);
+        self.drive_focus(FocusDirection::Near, step)?;
+
+        contrast
+    }
+
+    #[cfg(feature = "image")]
+    fn preview_contrast(&mut self, media: &mut FileMedia) -> crate::Result<f64> {
+        self.capture_preview(media)?;
+
+        match image::load_from_memory_with_format(&media.get_data(), image::ImageFormat::Jpeg) {
+            Ok(image) => Ok(contrast(
+                &image.thumbnail(CONTRAST_WIDTH, CONTRAST_WIDTH).to_luma8(),
+            )),
+            Err(_) => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_CORRUPTED_DATA,
+            )),
+        }
+    }
+}
+
+/// Measures the sharpness of an image as the mean squared brightness gradient.
+#[cfg(feature = "image")]
+fn contrast(image: &image::GrayImage) -> f64 {
+    let (width, height) = image.dimensions();
+
+    if width < 2 || height < 2 {
+        return 0.0;
+    }
+
+    let mut sum = 0.0;
+
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let pixel = f64::from(image.get_pixel(x, y)[0]);
+            let dx = f64::from(image.get_pixel(x + 1, y)[0]) - pixel;
+            let dy = f64::from(image.get_pixel(x, y + 1)[0]) - pixel;
+
+            sum += dx * dx + dy * dy;
+        }
+    }
+
+    sum / f64::from((width - 1) * (height - 1))
+}
+
+/// Returns the number of steps along a falling contrast curve at which `contrast` is reached.
+///
+/// The position is interpolated between samples. Contrasts beyond the end of the curve are
+/// placed at its end.
+#[cfg(feature = "image")]
+fn position(curve: &[f64], contrast: f64) -> f64 {
+    for index in 1..curve.len() {
+        let (before, after) = (curve[index - 1], curve[index]);
+
+        if contrast >= after {
+            if before <= after {
+                return index as f64;
+            }
+
+            let fraction = ((before - contrast) / (before - after)).clamp(0.0, 1.0);
+
+            return (index - 1) as f64 + fraction;
+        }
+    }
+
+    (curve.len() - 1) as f64
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use crate::camera::Camera;
+use crate::widget::WidgetValue;
+
+/// The default minimum interval between status queries.
+pub(crate) const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A summary of a camera's health.
+///
+/// Health reports are created with `Camera::health()`.
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    connected: bool,
+    battery_level: Option<String>,
+    free_kbytes: Option<u64>,
+    last_error: Option<String>,
+}
+
+impl HealthReport {
+    /// Returns `true` if the camera responded to the health check.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// The camera's battery level as reported by the driver, e.g., `"75%"` or `"Full"`.
+    ///
+    /// Returns `None` if the camera doesn't report its battery level.
+    pub fn battery_level(&self) -> Option<&str> {
+        self.battery_level.as_deref()
+    }
+
+    /// The free space on all of the camera's storages in kilobytes.
+    ///
+    /// Returns `None` if the camera doesn't report the free space of any storage.
+    pub fn free_kbytes(&self) -> Option<u64> {
+        self.free_kbytes
+    }
+
+    /// The most recent error message reported by the camera's driver.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Returns `true` if the camera is connected and has free space left.
+    pub fn is_healthy(&self) -> bool {
+        self.connected && self.free_kbytes != Some(0)
+    }
+}
+
+impl Camera {
+    /// Checks the camera's health.
+    ///
+    /// This queries the camera's storages, which also checks that the camera responds, and its
+    /// battery level. It is meant to be called from the health check of a camera service, so it
+    /// doesn't fail: a camera that doesn't respond is reported as not connected.
+    ///
+    /// The camera is queried at most once per status interval (one second by default); more
+    /// frequent calls return the previous report, so that polling the health doesn't delay other
+    /// operations. See `set_status_interval()`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let health = camera.health();
+    ///
+    /// if !health.is_healthy() {
+    ///     println!("unhealthy: {:?}", health);
+    /// }
+    /// ```
+    pub fn health(&mut self) -> HealthReport {
+        if !self.status_limiter.ready() {
+            if let Some(ref health) = self.last_health {
+                return health.clone();
+            }
+        }
+
+        let health = self.query_health();
+        self.last_health = Some(health.clone());
+
+        health
+    }
+
+    /// Sets the minimum interval between the status queries of `health()`.
+    pub fn set_status_interval(&mut self, interval: Duration) {
+        self.status_limiter.set_interval(interval);
+    }
+
+    fn query_health(&mut self) -> HealthReport {
+        let (connected, free_kbytes) = match self.storage() {
+            Ok(storages) => (
+                true,
+                storages
+                    .iter()
+                    .filter_map(|storage| storage.free_kbytes())
+                    .fold(None, |total, free| Some(total.unwrap_or(0) + free)),
+            ),
+            Err(_) => (false, None),
+        };
+
+        let battery_level = if connected {
+            self.battery_level()
+        } else {
+            None
+        };
+
+        HealthReport {
+            connected,
+            battery_level,
+            free_kbytes,
+            last_error: self.context.last_error(),
+        }
+    }
+
+    fn battery_level(&mut self) -> Option<String> {
+        let config = self.single_config("batterylevel").ok()?;
+        let value = config.root().value()?;
+
+        match value {
+            WidgetValue::Text(level) | WidgetValue::Choice(level) => Some(level),
+            WidgetValue::Range(level) => Some(format!("{}%", level)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use crate::fileinfo::FileInfo;
+
+/// The error type of hooks.
+pub type HookError = Box<dyn StdError + Send + Sync>;
+
+/// A step that runs after a file was downloaded.
+///
+/// Hooks receive the path of the local file and the information about the file on the camera.
+/// They are used for work like generating thumbnails, uploading files or recording them in a
+/// database.
+///
+/// Closures with the signature of `run()` are hooks.
+pub trait Hook {
+    /// Processes a downloaded file.
+    fn run(&mut self, path: &Path, info: &FileInfo) -> Result<(), HookError>;
+
+    /// Returns the hook's name, which identifies the hook in failures.
+    fn name(&self) -> &str {
+        "hook"
+    }
+}
+
+impl<F> Hook for F
+where
+    F: FnMut(&Path, &FileInfo) -> Result<(), HookError>,
+{
+    fn run(&mut self, path: &Path, info: &FileInfo) -> Result<(), HookError> {
+        self(path, info)
+    }
+}
+
+/// An ordered pipeline of hooks.
+///
+/// The hooks run in the order that they were added. Failing hooks are isolated: an error or a
+/// panic in one hook is recorded as a `HookFailure` and the remaining hooks still run, so that a
+/// failing upload doesn't stop a capture loop.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{DownloadOptions, Hooks};
+///
+/// let mut hooks = Hooks::new();
+///
+/// hooks.add(|path: &std::path::Path, info: &gphoto::FileInfo| {
+///     println!("downloaded {} ({:?} bytes)", path.display(), info.size());
+///     Ok(())
+/// });
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let options = DownloadOptions::new();
+///
+/// camera
+///     .fs()
+///     .download_matching_with_hooks("/", |_| true, "photos".as_ref(), &options, &mut hooks)
+///     .unwrap();
+///
+/// for failure in hooks.take_failures() {
+///     println!("{}", failure);
+/// }
+/// ```
+#[derive(Default)]
+pub struct Hooks {
+    hooks: Vec<Box<dyn Hook + Send>>,
+    failures: Vec<HookFailure>,
+}
+
+impl Hooks {
+    /// Creates an empty pipeline.
+    pub fn new() -> Self {
+        Hooks::default()
+    }
+
+    /// Adds a hook to the end of the pipeline.
+    pub fn add<H: Hook + Send + 'static>(&mut self, hook: H) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Adds a hook to the end of the pipeline as a named channel.
+    ///
+    /// Channels are hooks that deliver each downloaded file to a destination, e.g., a backup
+    /// directory with `DirSink` or object storage with `S3Sink`. The channel's name replaces the
+    /// hook's name in failures, so that failures of several destinations of the same kind can be
+    /// told apart.
+    pub fn add_channel<H: Hook + Send + 'static>(&mut self, name: &str, hook: H) {
+        self.add(Channel {
+            name: name.to_owned(),
+            hook,
+        });
+    }
+
+    /// Returns the number of hooks in the pipeline.
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    /// Returns `true` if the pipeline doesn't have any hooks.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Runs all hooks for a downloaded file.
+    ///
+    /// Returns `true` if all hooks succeeded. Failures are recorded and can be retrieved with
+    /// `take_failures()`.
+    pub fn run(&mut self, path: &Path, info: &FileInfo) -> bool {
+        let mut succeeded = true;
+
+        for hook in &mut self.hooks {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| hook.run(path, info)));
+
+            let error = match result {
+                Ok(Ok(())) => continue,
+                Ok(Err(error)) => error,
+                Err(_) => "hook panicked".into(),
+            };
+
+            succeeded = false;
+
+            self.failures.push(HookFailure {
+                hook: hook.name().to_owned(),
+                path: path.to_owned(),
+                error,
+            });
+        }
+
+        succeeded
+    }
+
+    /// Returns the failures recorded since the last call and clears them.
+    pub fn take_failures(&mut self) -> Vec<HookFailure> {
+        std::mem::take(&mut self.failures)
+    }
+}
+
+/// A hook with the name of a channel.
+struct Channel<H> {
+    name: String,
+    hook: H,
+}
+
+impl<H: Hook> Hook for Channel<H> {
+    fn run(&mut self, path: &Path, info: &FileInfo) -> Result<(), HookError> {
+        self.hook.run(path, info)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A hook that failed to process a file.
+#[derive(Debug)]
+pub struct HookFailure {
+    hook: String,
+    path: PathBuf,
+    error: HookError,
+}
+
+impl HookFailure {
+    /// The name of the hook that failed.
+    pub fn hook(&self) -> &str {
+        &self.hook
+    }
+
+    /// The path of the file that the hook failed to process.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The error returned by the hook.
+    pub fn error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        &*self.error
+    }
+}
+
+impl fmt::Display for HookFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} failed for {}: {}",
+            self.hook,
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn isolates_failing_hooks() {
+        let info = FileInfo::empty(crate::camera::file_from_parts("/DCIM", "IMG_0001.JPG"));
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let mut hooks = Hooks::new();
+
+        let log = ran.clone();
+        hooks.add_channel(
+            "error",
+            move |_: &Path, _: &FileInfo| -> Result<(), HookError> {
+                log.lock().unwrap().push("error");
+                Err("upload failed".into())
+            },
+        );
+
+        let log = ran.clone();
+        hooks.add_channel(
+            "panic",
+            move |_: &Path, _: &FileInfo| -> Result<(), HookError> {
+                log.lock().unwrap().push("panic");
+                panic!("thumbnail failed");
+            },
+        );
+
+        let log = ran.clone();
+        hooks.add(
+            move |path: &Path, info: &FileInfo| -> Result<(), HookError> {
+                assert_eq!(path, Path::new("photos/IMG_0001.JPG"));
+                assert_eq!(info.file().basename(), "IMG_0001.JPG");
+                log.lock().unwrap().push("last");
+                Ok(())
+            },
+        );
+
+        assert!(!hooks.run(Path::new("photos/IMG_0001.JPG"), &info));
+        assert_eq!(*ran.lock().unwrap(), ["error", "panic", "last"]);
+
+        let failures = hooks.take_failures();
+        let failed: Vec<_> = failures
+            .iter()
+            .map(|failure| (failure.hook(), failure.error().to_string()))
+            .collect();
+
+        assert_eq!(
+            failed,
+            [
+                ("error", "upload failed".to_owned()),
+                ("panic", "hook panicked".to_owned()),
+            ]
+        );
+        assert_eq!(failures[0].path(), Path::new("photos/IMG_0001.JPG"));
+        assert!(hooks.take_failures().is_empty());
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The states of a transfer that are recorded in a `Journal`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferState {
+    /// The file was selected for transfer to a local path.
+    Planned(PathBuf),
+
+    /// The file was downloaded to a local path.
+    Completed(PathBuf),
+
+    /// The transfer failed with an error message.
+    Failed(String),
+}
+
+/// A record of a transfer in a `Journal`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    time: SystemTime,
+    file: String,
+    size: Option<u64>,
+    state: TransferState,
+}
+
+impl JournalEntry {
+    /// Creates an entry with the current time for a file on the camera, e.g.,
+    /// `"/store_00010001/DCIM/100CANON/IMG_0001.JPG"`.
+    pub fn new(file: &str, size: Option<u64>, state: TransferState) -> Self {
+        JournalEntry {
+            time: SystemTime::now(),
+            file: file.to_owned(),
+            size,
+            state,
+        }
+    }
+
+    /// The time at which the entry was recorded.
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// The path of the file on the camera.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The size of the file on the camera, if the camera reports it.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// The state of the transfer.
+    pub fn state(&self) -> &TransferState {
+        &self.state
+    }
+}
+
+/// An append-only record of transfers.
+///
+/// `CameraFs::download_matching_journaled()` records each file that it plans to download, and
+/// whether the download completed or failed. The journal lets an interrupted card offload resume
+/// with the files that aren't completed yet, and lets operators audit that every file made it off
+/// the card. `Camera::wait_event_journaled()` records the files that a camera reports while
+/// tethered, so that `Camera::resume_journaled()` can download the files that a crash left behind.
+/// A `Tether` records its downloads in the journal set with `TetherOptions::journal()`.
+///
+/// `FileJournal` stores the journal in a local file; other backends, e.g., a database, implement
+/// this trait.
+pub trait Journal {
+    /// Appends an entry to the journal.
+    ///
+    /// The entry should be durable when this method returns.
+    fn append(&mut self, entry: &JournalEntry) -> io::Result<()>;
+
+    /// Returns all entries of the journal in the order they were appended.
+    fn entries(&mut self) -> io::Result<Vec<JournalEntry>>;
+}
+
+/// A `Journal` that is stored in a local text file.
+///
+/// Each entry is appended as a line of tab-separated fields: the time in seconds since the Unix
+/// epoch, the state (`planned`, `completed` or `failed`), the file's size (empty if unknown), the
+/// file's path on the camera, and the local path that the file is planned for or was downloaded to,
+/// or the error message. Tabs, line breaks and backslashes in the fields are escaped with a
+/// backslash, e.g., `\t`. Lines that can't be parsed, e.g., a line that was cut short by a power
+/// loss, are ignored.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{DownloadOptions, FileJournal, Hooks};
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut journal = FileJournal::open("offload.journal".as_ref()).unwrap();
+/// let options = DownloadOptions::new();
+///
+/// // Files that were completed by an earlier, interrupted run are skipped.
+/// camera
+///     .fs()
+///     .download_matching_journaled(
+///         "/",
+///         |_| true,
+///         "photos".as_ref(),
+///         &options,
+///         &mut Hooks::new(),
+///         &mut journal,
+///     )
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct FileJournal {
+    path: PathBuf,
+    file: File,
+}
+
+impl FileJournal {
+    /// Opens a journal file, creating it if it doesn't exist.
+    ///
+    /// If the last line was cut short, e.g., by a power loss, it is terminated, so that the next
+    /// entry starts on a line of its own.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the file could not be opened.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        if file.seek(SeekFrom::End(0))? > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+                file.sync_data()?;
+            }
+        }
+
+        Ok(FileJournal {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    /// Returns the path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Journal for FileJournal {
+    fn append(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        let seconds = entry
+            .time
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or(0);
+
+        let (state, detail) = match entry.state {
+            TransferState::Planned(ref path) => ("planned", path.display().to_string()),
+            TransferState::Completed(ref path) => ("completed", path.display().to_string()),
+            TransferState::Failed(ref message) => ("failed", message.clone()),
+        };
+
+        let size = entry.size.map(|size| size.to_string()).unwrap_or_default();
+
+        writeln!(
+            self.file,
+            "{}\t{}\t{}\t{}\t{}",
+            seconds,
+            state,
+            size,
+            escape(&entry.file),
+            escape(&detail)
+        )?;
+
+        self.file.sync_data()
+    }
+
+    fn entries(&mut self) -> io::Result<Vec<JournalEntry>> {
+        let reader = BufReader::new(File::open(&self.path)?);
+        let mut entries = Vec::new();
+
+        for line in reader.lines() {
+            if let Some(entry) = parse(&line?) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Returns the files whose last entry is `Completed` by their path on the camera, with the size
+/// they had on the camera and their local path.
+pub(crate) fn completed(entries: &[JournalEntry]) -> HashMap<&str, (Option<u64>, &Path)> {
+    let mut completed = HashMap::new();
+
+    for entry in entries {
+        match entry.state {
+            TransferState::Completed(ref path) => {
+                completed.insert(entry.file.as_str(), (entry.size, path.as_path()));
+            }
+            _ => {
+                completed.remove(entry.file.as_str());
+            }
+        }
+    }
+
+    completed
+}
+
+/// Returns the files whose last entry isn't `Completed`, in the order they were first recorded.
+pub(crate) fn unfinished(entries: &[JournalEntry]) -> Vec<&str> {
+    let completed = completed(entries);
+    let mut seen = HashSet::new();
+
+    entries
+        .iter()
+        .map(|entry| entry.file.as_str())
+        .filter(|file| !completed.contains_key(file) && seen.insert(*file))
+        .collect()
+}
+
+/// Returns the files whose last entry is `Planned`, i.e., whose transfer was interrupted before
+/// it completed or failed, by their path on the camera, with the local path they were planned for.
+pub(crate) fn interrupted(entries: &[JournalEntry]) -> HashMap<&str, &Path> {
+    let mut interrupted = HashMap::new();
+
+    for entry in entries {
+        match entry.state {
+            TransferState::Planned(ref path) => {
+                interrupted.insert(entry.file.as_str(), path.as_path());
+            }
+            _ => {
+                interrupted.remove(entry.file.as_str());
+            }
+        }
+    }
+
+    interrupted
+}
+
+/// The error for a journal that could not be read or written.
+pub(crate) fn journal_error() -> crate::Error {
+    crate::error::from_libgphoto2(crate::gphoto2::GP_ERROR_OS_FAILURE)
+}
+
+/// Parses a line of a `FileJournal`.
+fn parse(line: &str) -> Option<JournalEntry> {
+    let mut fields = line.splitn(5, '\t');
+
+    let seconds: u64 = fields.next()?.parse().ok()?;
+    let state = fields.next()?;
+
+    let size = match fields.next()? {
+        "" => None,
+        size => Some(size.parse().ok()?),
+    };
+
+    let file = unescape(fields.next()?);
+    let detail = unescape(fields.next()?);
+
+    let state = match state {
+        "planned" => TransferState::Planned(PathBuf::from(detail)),
+        "completed" => TransferState::Completed(PathBuf::from(detail)),
+        "failed" => TransferState::Failed(detail),
+        _ => return None,
+    };
+
+    Some(JournalEntry {
+        time: UNIX_EPOCH.checked_add(Duration::from_secs(seconds))?,
+        file,
+        size,
+        state,
+    })
+}
+
+/// Escapes the characters that separate fields and lines of a `FileJournal`, and backslashes.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Reverses `escape()`.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries() {
+        let cases = [
+            (
+                "1700000000\tplanned\t1024\t/DCIM/IMG_0001.JPG\tphotos/IMG_0001.JPG",
+                Some((
+                    1_700_000_000,
+                    Some(1024),
+                    TransferState::Planned(PathBuf::from("photos/IMG_0001.JPG")),
+                )),
+            ),
+            (
+                "1700000000\tcompleted\t\t/DCIM/IMG_0001.JPG\tphotos/IMG_0001.JPG",
+                Some((
+                    1_700_000_000,
+                    None,
+                    TransferState::Completed(PathBuf::from("photos/IMG_0001.JPG")),
+                )),
+            ),
+            (
+                "0\tfailed\t7\t/DCIM/IMG_0001.JPG\tI/O problem",
+                Some((0, Some(7), TransferState::Failed("I/O problem".to_owned()))),
+            ),
+            ("1700000000\tplanned\t1024\t/DCIM/IMG_0001.JPG", None),
+            ("1700000000\tunknown\t1024\t/DCIM/IMG_0001.JPG\ta.JPG", None),
+            ("1700000000\tplanned\tbig\t/DCIM/IMG_0001.JPG\ta.JPG", None),
+            ("soon\tplanned\t1024\t/DCIM/IMG_0001.JPG\ta.JPG", None),
+            (
+                "18446744073709551615\tplanned\t\t/DCIM/IMG_0001.JPG\ta.JPG",
+                None,
+            ),
+            ("", None),
+        ];
+
+        for &(line, ref expected) in cases.iter() {
+            let entry = parse(line);
+
+            let actual = entry
+                .as_ref()
+                .map(|entry| (entry.time, entry.size, entry.state.clone()));
+            let expected = expected.as_ref().map(|&(seconds, size, ref state)| {
+                (
+                    UNIX_EPOCH + Duration::from_secs(seconds),
+                    size,
+                    state.clone(),
+                )
+            });
+
+            assert_eq!(actual, expected, "{:?}", line);
+
+            if let Some(entry) = entry {
+                assert_eq!(entry.file, "/DCIM/IMG_0001.JPG");
+            }
+        }
+    }
+
+    #[test]
+    fn escapes_separators() {
+        let cases = [
+            ("IMG_0001.JPG", "IMG_0001.JPG"),
+            ("a\tb", "a\\tb"),
+            ("a\nb\r", "a\\nb\\r"),
+            ("a\\tb", "a\\\\tb"),
+            ("a\\", "a\\\\"),
+            ("", ""),
+        ];
+
+        for &(text, escaped) in cases.iter() {
+            assert_eq!(escape(text), escaped, "{:?}", text);
+            assert_eq!(unescape(escaped), text, "{:?}", escaped);
+        }
+    }
+
+    #[test]
+    fn finds_unfinished_transfers() {
+        let planned = |path: &str| TransferState::Planned(PathBuf::from(path));
+        let done = |path: &str| TransferState::Completed(PathBuf::from(path));
+        let entries = vec![
+            JournalEntry::new("/a.JPG", None, planned("photos/a.JPG")),
+            JournalEntry::new("/a.JPG", None, done("photos/a.JPG")),
+            JournalEntry::new("/b.JPG", None, planned("photos/b.JPG")),
+            JournalEntry::new("/b.JPG", None, TransferState::Failed("busy".into())),
+            JournalEntry::new("/c.JPG", None, planned("photos/c.JPG")),
+            JournalEntry::new("/d.JPG", None, planned("photos/d.JPG")),
+            JournalEntry::new("/d.JPG", None, done("photos/d.JPG")),
+            JournalEntry::new("/d.JPG", None, planned("photos/d_1.JPG")),
+        ];
+
+        assert_eq!(unfinished(&entries), ["/b.JPG", "/c.JPG", "/d.JPG"]);
+
+        let mut interrupted: Vec<_> = interrupted(&entries).into_iter().collect();
+        interrupted.sort_unstable();
+        assert_eq!(
+            interrupted,
+            [
+                ("/c.JPG", Path::new("photos/c.JPG")),
+                ("/d.JPG", Path::new("photos/d_1.JPG")),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trips_entries() {
+        let path = std::env::temp_dir().join(format!("gphoto-journal-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // A line that was cut short by a power loss.
+        std::fs::write(&path, "1700000000\tplanned\t10").unwrap();
+
+        let entries = vec![
+            JournalEntry::new(
+                "/DCIM/a\tb.JPG",
+                Some(10),
+                TransferState::Planned(PathBuf::from("photos/a\tb.JPG")),
+            ),
+            JournalEntry::new(
+                "/DCIM/a.JPG",
+                None,
+                TransferState::Completed(PathBuf::from("photos/a.JPG")),
+            ),
+            JournalEntry::new(
+                "/DCIM/b.JPG",
+                Some(3),
+                TransferState::Failed("no\nspace".into()),
+            ),
+        ];
+
+        let mut journal = FileJournal::open(&path).unwrap();
+
+        for entry in &entries {
+            journal.append(entry).unwrap();
+        }
+
+        let read = journal.entries().unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let files: Vec<_> = read.iter().map(|entry| entry.file.as_str()).collect();
+        assert_eq!(files, ["/DCIM/a\tb.JPG", "/DCIM/a.JPG", "/DCIM/b.JPG"]);
+
+        for (read, entry) in read.iter().zip(&entries) {
+            assert_eq!(read.size, entry.size, "{:?}", entry.file);
+            assert_eq!(read.state, entry.state, "{:?}", entry.file);
+        }
+    }
+}
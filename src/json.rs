@@ -0,0 +1,121 @@
+use std::fmt::Write;
+
+/// Builds a flat JSON object.
+pub(crate) struct JsonObject {
+    json: String,
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        JsonObject {
+            json: String::from("{"),
+        }
+    }
+
+    pub fn string(mut self, key: &str, value: &str) -> Self {
+        self.key(key);
+        push_string(&mut self.json, value);
+        self
+    }
+
+    pub fn number(mut self, key: &str, value: u64) -> Self {
+        self.key(key);
+        let _ = write!(self.json, "{}", value);
+        self
+    }
+
+    #[cfg(feature = "mqtt")]
+    pub fn boolean(mut self, key: &str, value: bool) -> Self {
+        self.key(key);
+        self.json.push_str(if value { "true" } else { "false" });
+        self
+    }
+
+    pub fn optional_string(self, key: &str, value: Option<&str>) -> Self {
+        match value {
+            Some(value) => self.string(key, value),
+            None => self.null(key),
+        }
+    }
+
+    #[cfg(any(feature = "mqtt", feature = "webhook"))]
+    pub fn optional_number(self, key: &str, value: Option<u64>) -> Self {
+        match value {
+            Some(value) => self.number(key, value),
+            None => self.null(key),
+        }
+    }
+
+    /// Adds a value that is already encoded as JSON, e.g., an array or a nested object.
+    pub fn raw(mut self, key: &str, json: &str) -> Self {
+        self.key(key);
+        self.json.push_str(json);
+        self
+    }
+
+    pub fn null(mut self, key: &str) -> Self {
+        self.key(key);
+        self.json.push_str("null");
+        self
+    }
+
+    pub fn finish(mut self) -> String {
+        self.json.push('}');
+        self.json
+    }
+
+    fn key(&mut self, key: &str) {
+        if self.json.len() > 1 {
+            self.json.push(',');
+        }
+
+        push_string(&mut self.json, key);
+        self.json.push(':');
+    }
+}
+
+/// Encodes values that are already encoded as JSON as an array.
+pub(crate) fn array<I>(values: I) -> String
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut json = String::from("[");
+
+    for (index, value) in values.into_iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+
+        json.push_str(&value);
+    }
+
+    json.push(']');
+    json
+}
+
+/// Encodes a string as JSON.
+pub(crate) fn string(value: &str) -> String {
+    let mut json = String::new();
+    push_string(&mut json, value);
+    json
+}
+
+fn push_string(json: &mut String, value: &str) {
+    json.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+}
@@ -0,0 +1,134 @@
+use crate::camera::Camera;
+use crate::widget::{Config, WidgetValue};
+
+/// Names of the settings that contain the lens name in different drivers.
+const NAME_SETTINGS: &[&str] = &["lensname", "lensid"];
+
+/// Names of the settings that contain the aperture in different drivers.
+const APERTURE_SETTINGS: &[&str] = &["f-number", "aperture"];
+
+/// Information about the lens mounted on a camera.
+///
+/// Lens information is read with `Camera::lens_info()`. Each field is optional, because drivers
+/// report different subsets of the information and some lenses don't report anything.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LensInfo {
+    name: Option<String>,
+    focal_length: Option<f32>,
+    focal_range: Option<(f32, f32)>,
+    aperture_range: Option<(f32, f32)>,
+}
+
+impl LensInfo {
+    /// The lens name, e.g., `"EF24-105mm f/4L IS USM"`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The current focal length in millimeters.
+    pub fn focal_length(&self) -> Option<f32> {
+        self.focal_length
+    }
+
+    /// The shortest and longest focal lengths of the lens in millimeters.
+    ///
+    /// Both are the same for prime lenses.
+    pub fn focal_range(&self) -> Option<(f32, f32)> {
+        self.focal_range
+    }
+
+    /// The widest and narrowest apertures that can be set, as f-numbers.
+    ///
+    /// The range is derived from the aperture values that the camera offers, so it depends on the
+    /// current focal length for lenses with a variable maximum aperture.
+    pub fn aperture_range(&self) -> Option<(f32, f32)> {
+        self.aperture_range
+    }
+}
+
+impl Camera {
+    /// Reads information about the mounted lens.
+    ///
+    /// The information is read from the camera's settings, so it is available before any image
+    /// is captured and doesn't require downloading a file to read its EXIF data.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let lens = camera.lens_info().unwrap();
+    ///
+    /// println!("          lens = {:?}", lens.name());
+    /// println!("  focal length = {:?}", lens.focal_length());
+    /// println!("   focal range = {:?}", lens.focal_range());
+    /// println!("aperture range = {:?}", lens.aperture_range());
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera's configuration could not be read:
+    ///
+    /// * `NotSupported` if the camera can not be configured.
+    pub fn lens_info(&mut self) -> crate::Result<LensInfo> {
+        let config = self.config()?;
+
+        let name = NAME_SETTINGS
+            .iter()
+            .filter_map(|name| match config.value(name) {
+                Some(WidgetValue::Text(value)) | Some(WidgetValue::Choice(value)) => Some(value),
+                _ => None,
+            })
+            .find(|value| !value.is_empty());
+
+        let focal_range = match (
+            number_setting(&config, "minfocallength"),
+            number_setting(&config, "maxfocallength"),
+        ) {
+            (Some(min), Some(max)) => Some((min, max)),
+            _ => None,
+        };
+
+        let aperture_range = APERTURE_SETTINGS.iter().find_map(|name| {
+            let widget = config.get(name)?;
+
+            widget
+                .choices()
+                .iter()
+                .filter_map(|choice| parse_number(choice))
+                .fold(None, |range, aperture| match range {
+                    None => Some((aperture, aperture)),
+                    Some((min, max)) => Some((aperture.min(min), aperture.max(max))),
+                })
+        });
+
+        Ok(LensInfo {
+            name,
+            focal_length: number_setting(&config, "focallength"),
+            focal_range,
+            aperture_range,
+        })
+    }
+}
+
+/// Reads a numeric setting that may be a range, a choice or text.
+fn number_setting(config: &Config, name: &str) -> Option<f32> {
+    match config.value(name)? {
+        WidgetValue::Range(value) => Some(value),
+        WidgetValue::Text(value) | WidgetValue::Choice(value) => parse_number(&value),
+        _ => None,
+    }
+}
+
+/// Parses the number in values like `"50"`, `"50 mm"` and `"f/2.8"`.
+fn parse_number(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let value = value.strip_prefix("f/").unwrap_or(value);
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+
+    value[..end]
+        .parse()
+        .ok()
+        .filter(|number: &f32| *number > 0.0)
+}
@@ -1,26 +1,267 @@
 pub use crate::abilities::{
-    Abilities, CameraOperation, DeviceType, DriverStatus, FileOperation, FolderOperation,
+    Abilities, CameraOperation, CaptureFlow, CaptureSummary, DeviceType, DriverStatus,
+    FileOperation, FolderOperation,
 };
+#[cfg(feature = "std")]
+pub use crate::buffer::{BufferPool, PooledBuffer};
+#[cfg(feature = "std")]
+pub use crate::bus::{BusPermit, BusScheduler};
+#[cfg(feature = "std")]
+pub use crate::calibration::{CalibrationKind, CalibrationPlan};
 pub use crate::camera::{Camera, CameraFile};
+#[cfg(feature = "std")]
+pub use crate::capture::{Af, Capture, CaptureId, CaptureOptions, CaptureTarget};
+#[cfg(feature = "std")]
+pub use crate::card::CardInfo;
+#[cfg(feature = "std")]
+pub use crate::clock::{Clock, SimulatedClock, SystemClock};
+#[cfg(feature = "std")]
+pub use crate::config::{ConfigCache, ConfigWatcher, SettingChanged};
+#[cfg(feature = "std")]
+pub use crate::context::CancelHandle;
+pub use crate::context::Context;
+#[cfg(feature = "std")]
+pub use crate::detect::{
+    cameras_with, detect_cameras, detect_cameras_filtered, DetectFilter, DetectedCamera,
+};
+#[cfg(feature = "std")]
+pub use crate::diagnostics::{diagnostics, Diagnostics, PortSummary};
+#[cfg(feature = "std")]
+pub use crate::download::{Collision, DownloadOptions};
+#[cfg(feature = "std")]
+pub use crate::dump::WidgetDump;
 pub use crate::error::{Error, ErrorKind, Result};
+pub use crate::event::CameraEvent;
+#[cfg(feature = "std")]
+pub use crate::executor::{Executor, RemoteCamera};
+#[cfg(feature = "std")]
+pub use crate::exposure::{
+    ExposureController, ExposureRamp, ExposureRange, ExposureStep, Histogram, SweepFrame,
+};
+#[cfg(feature = "std")]
+pub use crate::fileinfo::{FileFilter, FileInfo};
+#[cfg(feature = "std")]
+pub use crate::filesys::{CameraFs, FolderStats, Thumbnails};
+#[cfg(feature = "std")]
+pub use crate::flash::FlashSync;
+#[cfg(feature = "std")]
+pub use crate::focus::{FocusCalibration, FocusDirection, FocusStep};
+#[cfg(feature = "std")]
+pub use crate::health::HealthReport;
+#[cfg(feature = "std")]
+pub use crate::hooks::{Hook, HookError, HookFailure, Hooks};
+#[cfg(feature = "std")]
+pub use crate::journal::{FileJournal, Journal, JournalEntry, TransferState};
+#[cfg(feature = "std")]
+pub use crate::lens::LensInfo;
+#[cfg(feature = "std")]
+pub use crate::lifecycle::{CameraState, StateTransition};
+#[cfg(feature = "manifest")]
+pub use crate::manifest::{Manifest, ManifestEntry};
 pub use crate::media::{FileMedia, Media};
-pub use crate::port::{Port, PortType};
-pub use crate::storage::{AccessType, FilesystemType, Storage, StorageType};
+#[cfg(feature = "std")]
+pub use crate::metadata::{Metadata, MetadataIter};
+#[cfg(feature = "metrics")]
+pub use crate::metrics::{metrics, Metrics};
+#[cfg(feature = "mqtt")]
+pub use crate::mqtt::MqttPublisher;
+#[cfg(feature = "std")]
+pub use crate::numbering::FileNumbering;
+#[cfg(feature = "image")]
+pub use crate::overlay::Overlay;
+pub use crate::port::{Port, PortIo, PortType};
+#[cfg(feature = "std")]
+pub use crate::preview::PreviewStream;
+#[cfg(feature = "std")]
+pub use crate::probe::{ProbeIssue, ProbeReport};
+#[cfg(feature = "std")]
+pub use crate::provision::{
+    Profile, ProvisionItem, ProvisionOutcome, ProvisionReport, Provisioner,
+};
+#[cfg(feature = "std")]
+pub use crate::ratelimit::RateLimiter;
+#[cfg(feature = "std")]
+pub use crate::recovery::Recovery;
+#[cfg(feature = "upload")]
+pub use crate::s3::S3Sink;
+#[cfg(feature = "std")]
+pub use crate::schema::{ConfigSchema, WidgetSchema};
+#[cfg(feature = "std")]
+pub use crate::sequence::{Action, Plan, Sequence, SequenceEvent};
+#[cfg(feature = "std")]
+pub use crate::settings::{library_setting, set_library_setting};
+#[cfg(feature = "std")]
+pub use crate::shared::{CameraGuard, Priority, SharedCamera};
+#[cfg(feature = "std")]
+pub use crate::shutdown::{Shutdown, ShutdownReport, ShutdownSignal};
+#[cfg(feature = "std")]
+pub use crate::sidecar::{GpsFix, Sidecar, SidecarFormat};
+#[cfg(feature = "std")]
+pub use crate::sink::DirSink;
+#[cfg(feature = "std")]
+pub use crate::snapshot::StateSnapshot;
+#[cfg(feature = "std")]
+pub use crate::stableid::StableId;
+#[cfg(feature = "std")]
+pub use crate::status::{CameraStatus, CaptureCounters};
+#[cfg(feature = "std")]
+pub use crate::stopmotion::StopMotion;
+pub use crate::storage::{AccessType, FilesystemType, Storage, StorageFields, StorageType};
+#[cfg(feature = "std")]
+pub use crate::tag::TagDecoder;
+#[cfg(feature = "std")]
+pub use crate::tether::{Tether, TetherOptions};
+#[cfg(feature = "std")]
+pub use crate::threading::{set_threading_model, threading_model, ThreadingModel};
+#[cfg(feature = "std")]
+pub use crate::timelapse::{
+    Frame, JitterStats, RampedFrame, SimulatedFrame, SimulatedTimelapse, Timelapse,
+};
+#[cfg(feature = "image")]
+pub use crate::trigger::MotionTrigger;
+#[cfg(feature = "std")]
+pub use crate::trigger::Trigger;
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+pub use crate::trigger::{Edge, GpioTrigger};
 pub use crate::version::{libgphoto2_version, LibraryVersion};
+#[cfg(feature = "std")]
+pub use crate::video::Mode;
+#[cfg(feature = "webhook")]
+pub use crate::webhook::Webhook;
+pub use crate::widget::{Config, Widget, WidgetType, WidgetValue};
+#[cfg(all(feature = "v4l2", target_os = "linux"))]
+pub use crate::v4l2::V4l2Sink;
 pub use gphoto2::CameraFileType;
 
-pub(crate) use crate::context::Context;
 pub(crate) use gphoto2_sys as gphoto2;
 
 #[macro_use]
 mod error;
 mod abilities;
+#[cfg(feature = "std")]
+mod buffer;
+#[cfg(feature = "std")]
+mod bulb;
+#[cfg(feature = "std")]
+mod bus;
+#[cfg(feature = "std")]
+mod calibration;
 mod camera;
+#[cfg(feature = "std")]
+mod capture;
+#[cfg(feature = "std")]
+mod card;
+#[cfg(feature = "std")]
+mod clock;
+#[cfg(feature = "std")]
+mod config;
 mod context;
+#[cfg(feature = "std")]
+mod customfunc;
+#[cfg(feature = "std")]
+mod detect;
+#[cfg(feature = "std")]
+mod diagnostics;
+#[cfg(feature = "std")]
+mod download;
+#[cfg(feature = "std")]
+mod dump;
+mod event;
+#[cfg(feature = "std")]
+mod executor;
+#[cfg(feature = "std")]
+mod exposure;
+#[cfg(feature = "std")]
+mod fileinfo;
+#[cfg(feature = "std")]
+mod filesys;
+#[cfg(feature = "std")]
+mod flash;
+#[cfg(feature = "std")]
+mod focus;
+#[cfg(feature = "std")]
+mod health;
+#[cfg(feature = "std")]
+mod hooks;
+#[cfg(feature = "std")]
+mod journal;
+#[cfg(feature = "std")]
+mod lens;
+#[cfg(feature = "std")]
+mod lifecycle;
+#[cfg(feature = "manifest")]
+mod manifest;
 mod media;
+#[cfg(feature = "std")]
+mod metadata;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "std")]
+mod numbering;
+#[cfg(feature = "image")]
+mod overlay;
 mod port;
+#[cfg(feature = "std")]
+mod preview;
+#[cfg(feature = "std")]
+mod probe;
+#[cfg(feature = "std")]
+mod provision;
+#[cfg(feature = "std")]
+mod ptp;
+#[cfg(feature = "std")]
+mod ratelimit;
+#[cfg(feature = "std")]
+mod recovery;
+#[cfg(feature = "upload")]
+mod s3;
+#[cfg(feature = "std")]
+mod schema;
+#[cfg(feature = "std")]
+mod sequence;
+#[cfg(feature = "std")]
+mod settings;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "std")]
+mod shutdown;
+#[cfg(feature = "std")]
+mod sidecar;
+#[cfg(feature = "std")]
+mod sink;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod stableid;
+#[cfg(feature = "std")]
+mod status;
+#[cfg(feature = "std")]
+mod stopmotion;
 mod storage;
+#[cfg(feature = "std")]
+mod tag;
+#[cfg(feature = "std")]
+mod tether;
+#[cfg(feature = "std")]
+mod threading;
+#[cfg(feature = "std")]
+mod timelapse;
+#[cfg(feature = "std")]
+mod trigger;
+#[cfg(all(feature = "v4l2", target_os = "linux"))]
+mod v4l2;
 mod version;
+#[cfg(feature = "std")]
+mod video;
+#[cfg(feature = "webhook")]
+mod webhook;
+mod widget;
 
 // internal
 mod handle;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod list;
@@ -0,0 +1,150 @@
+use crate::camera::Camera;
+use crate::error::{Error, ErrorKind};
+
+/// The stage of a camera's lifecycle.
+///
+/// A camera moves through its lifecycle as follows:
+///
+/// ```text
+/// Detected -> Initialized -> Session -> Error -> Reinitializing -> Initialized -> ...
+/// ```
+///
+/// Operations that fail because the connection was lost move the camera to `Error`. Other
+/// failures, e.g., a capture that failed because autofocus didn't lock, don't change its state.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum CameraState {
+    /// The camera was found on a port, but its driver hasn't been initialized.
+    Detected,
+
+    /// The camera's driver was initialized, but no operation has completed yet.
+    Initialized,
+
+    /// At least one operation completed since the camera was initialized.
+    Session,
+
+    /// The connection to the camera was lost.
+    Error,
+
+    /// The camera's driver is being reinitialized.
+    Reinitializing,
+}
+
+/// A change of a camera's lifecycle state.
+///
+/// Transitions are reported to the listener set with `Camera::on_state_change()`.
+#[derive(Debug, Clone, Copy)]
+pub struct StateTransition {
+    from: CameraState,
+    to: CameraState,
+    error: Option<ErrorKind>,
+}
+
+impl StateTransition {
+    /// The state that the camera left.
+    pub fn from(&self) -> CameraState {
+        self.from
+    }
+
+    /// The state that the camera entered.
+    pub fn to(&self) -> CameraState {
+        self.to
+    }
+
+    /// The kind of error that caused the transition, if any.
+    pub fn error(&self) -> Option<ErrorKind> {
+        self.error
+    }
+}
+
+type Listener = Box<dyn FnMut(&StateTransition) + Send>;
+
+/// Tracks a camera's lifecycle state and notifies a listener about transitions.
+pub(crate) struct Lifecycle {
+    state: CameraState,
+    listener: Option<Listener>,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Lifecycle {
+            state: CameraState::Detected,
+            listener: None,
+        }
+    }
+
+    pub fn state(&self) -> CameraState {
+        self.state
+    }
+
+    pub fn transition(&mut self, to: CameraState, error: Option<ErrorKind>) {
+        if self.state == to {
+            return;
+        }
+
+        let transition = StateTransition {
+            from: self.state,
+            to,
+            error,
+        };
+
+        self.state = to;
+
+        if let Some(ref mut listener) = self.listener {
+            listener(&transition);
+        }
+    }
+}
+
+impl Camera {
+    /// Returns the camera's lifecycle state.
+    pub fn state(&self) -> CameraState {
+        self.lifecycle.state()
+    }
+
+    /// Sets a listener that is called after each change of the camera's lifecycle state.
+    ///
+    /// This allows supervisory code to react to lost connections and reinitializations in one
+    /// place, whether they were caused by a capture, a download, a health check or recovery.
+    /// The listener replaces any previous listener.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::CameraState;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// camera.on_state_change(|transition| {
+    ///     if transition.to() == CameraState::Error {
+    ///         println!("camera disconnected: {:?}", transition.error());
+    ///     }
+    /// });
+    /// ```
+    pub fn on_state_change<F>(&mut self, listener: F)
+    where
+        F: FnMut(&StateTransition) + Send + 'static,
+    {
+        self.lifecycle.listener = Some(Box::new(listener));
+    }
+
+    pub(crate) fn set_state(&mut self, state: CameraState) {
+        self.lifecycle.transition(state, None);
+    }
+
+    /// Records the outcome of an operation on the camera.
+    pub(crate) fn record_outcome(&mut self, err: Option<&Error>) {
+        match err {
+            None => {
+                if self.lifecycle.state() == CameraState::Initialized {
+                    self.lifecycle.transition(CameraState::Session, None);
+                }
+            }
+            Some(err) => {
+                if err.is_disconnect() {
+                    self.lifecycle
+                        .transition(CameraState::Error, Some(err.kind()));
+                }
+            }
+        }
+    }
+}
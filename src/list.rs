@@ -0,0 +1,80 @@
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+
+use core::mem::MaybeUninit;
+use cstr_core::CStr;
+use libc::c_int;
+
+/// A list of names and values returned by `libgphoto2`.
+pub(crate) struct List {
+    list: *mut crate::gphoto2::CameraList,
+}
+
+impl Drop for List {
+    fn drop(&mut self) {
+        unsafe {
+            crate::gphoto2::gp_list_unref(self.list);
+        }
+    }
+}
+
+impl List {
+    pub fn new() -> crate::Result<Self> {
+        let mut ptr = MaybeUninit::uninit();
+
+        match unsafe { crate::gphoto2::gp_list_new(&mut *ptr.as_mut_ptr()) } {
+            crate::gphoto2::GP_OK => Ok(List {
+                list: unsafe { ptr.assume_init() },
+            }),
+            err => Err(crate::error::from_libgphoto2(err)),
+        }
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut crate::gphoto2::CameraList {
+        self.list
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { crate::gphoto2::gp_list_count(self.list).max(0) as usize }
+    }
+
+    pub fn name(&self, index: usize) -> Cow<'_, str> {
+        let mut name = MaybeUninit::uninit();
+
+        unsafe {
+            match crate::gphoto2::gp_list_get_name(
+                self.list,
+                index as c_int,
+                &mut *name.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK if !name.assume_init().is_null() => {
+                    String::from_utf8_lossy(CStr::from_ptr(name.assume_init()).to_bytes())
+                }
+                _ => Cow::Borrowed(""),
+            }
+        }
+    }
+
+    pub fn value(&self, index: usize) -> Cow<'_, str> {
+        let mut value = MaybeUninit::uninit();
+
+        unsafe {
+            match crate::gphoto2::gp_list_get_value(
+                self.list,
+                index as c_int,
+                &mut *value.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK if !value.assume_init().is_null() => {
+                    String::from_utf8_lossy(CStr::from_ptr(value.assume_init()).to_bytes())
+                }
+                _ => Cow::Borrowed(""),
+            }
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        (0..self.len()).map(|i| self.name(i).into_owned()).collect()
+    }
+}
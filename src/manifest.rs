@@ -0,0 +1,190 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::fileinfo::FileInfo;
+use crate::hooks::{Hook, HookError};
+use crate::json::{self, JsonObject};
+
+/// The size of the blocks that files are hashed in.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// A list of downloaded files with their sizes and hashes, which downstream systems can verify.
+///
+/// Each entry records the local path of a file, its size, the BLAKE3 hash of the local copy, and
+/// the file's path on the camera. A manifest is built while files are downloaded by adding it to
+/// a pipeline of post-download hooks, and written out once the offload is complete.
+///
+/// Cloning a manifest returns another handle to the same list, so a clone can be added to the
+/// pipeline while the original is kept to write the manifest.
+///
+/// A manifest is formatted by `Display` in the format of `b3sum`, so it can be verified with
+/// `b3sum --check` from the download directory, and as JSON with all fields by `to_json()`. It
+/// is only available with the `manifest` feature.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{DownloadOptions, Hooks, Manifest};
+///
+/// let manifest = Manifest::new();
+///
+/// let mut hooks = Hooks::new();
+/// hooks.add(manifest.clone());
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let options = DownloadOptions::new().verify(true);
+///
+/// camera
+///     .fs()
+///     .download_matching_with_hooks("/", |_| true, "photos".as_ref(), &options, &mut hooks)
+///     .unwrap();
+///
+/// std::fs::write("photos/manifest.json", manifest.to_json()).unwrap();
+/// std::fs::write("photos/manifest.b3", manifest.to_string()).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    entries: Arc<Mutex<Vec<ManifestEntry>>>,
+}
+
+/// A file in a `Manifest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    path: PathBuf,
+    size: u64,
+    hash: String,
+    camera_path: String,
+}
+
+impl ManifestEntry {
+    /// The path of the local copy of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The size of the local copy in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The BLAKE3 hash of the local copy, as lowercase hexadecimal.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// The path of the file on the camera.
+    pub fn camera_path(&self) -> &str {
+        &self.camera_path
+    }
+}
+
+impl Manifest {
+    /// Creates an empty manifest.
+    pub fn new() -> Self {
+        Manifest::default()
+    }
+
+    /// Hashes a local file and adds it to the manifest.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the file could not be read.
+    pub fn add(&self, path: &Path, camera_path: &str) -> io::Result<()> {
+        let size = fs::metadata(path)?.len();
+        let hash = hash_file(path)?;
+
+        self.entries().push(ManifestEntry {
+            path: path.to_path_buf(),
+            size,
+            hash,
+            camera_path: camera_path.to_owned(),
+        });
+
+        Ok(())
+    }
+
+    /// Returns the entries of the manifest, in the order they were added.
+    pub fn to_vec(&self) -> Vec<ManifestEntry> {
+        self.entries().clone()
+    }
+
+    /// Returns the number of files in the manifest.
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    /// Returns `true` if the manifest doesn't contain any files.
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    /// Formats the manifest as a JSON array of objects with the fields `file` (the local file's
+    /// name), `path`, `size`, `blake3` and `camera_path`.
+    pub fn to_json(&self) -> String {
+        json::array(self.entries().iter().map(|entry| {
+            JsonObject::new()
+                .string("file", &file_name(&entry.path))
+                .string("path", &entry.path.display().to_string())
+                .number("size", entry.size)
+                .string("blake3", &entry.hash)
+                .string("camera_path", &entry.camera_path)
+                .finish()
+        }))
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Vec<ManifestEntry>> {
+        match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for entry in self.entries().iter() {
+            writeln!(fmt, "{}  {}", entry.hash, file_name(&entry.path))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Hook for Manifest {
+    fn run(&mut self, path: &Path, info: &FileInfo) -> Result<(), HookError> {
+        self.add(path, &info.file().path())?;
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "manifest"
+    }
+}
+
+/// Returns the BLAKE3 hash of a file as lowercase hexadecimal.
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; BLOCK_SIZE];
+
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            n => {
+                hasher.update(&buffer[..n]);
+            }
+        }
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
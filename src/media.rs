@@ -6,7 +6,7 @@ use std::path::Path;
 #[cfg(feature = "std")]
 use std::slice;
 
-use libc::c_ulong;
+use libc::{c_char, c_ulong};
 
 /// A trait for types that can store media.
 pub trait Media {
@@ -116,6 +116,35 @@ impl FileMedia {
 
         unsafe { slice::from_raw_parts(ptr as *const u8, len as usize).to_vec() }
     }
+
+    /// Copies the media's data into an existing buffer.
+    ///
+    /// The buffer is cleared first. Unlike `get_data()`, this doesn't allocate if the buffer
+    /// already has enough capacity, which makes it suitable for reusing buffers from a
+    /// `BufferPool`.
+    pub fn get_data_into(&mut self, buffer: &mut Vec<u8>) {
+        let mut ptr = MaybeUninit::uninit();
+        let mut len: c_ulong = 0;
+
+        let ptr = unsafe {
+            crate::gphoto2::gp_file_get_data_and_size(self.file, &mut *ptr.as_mut_ptr(), &mut len);
+            ptr.assume_init()
+        };
+
+        buffer.clear();
+        buffer.extend_from_slice(unsafe { slice::from_raw_parts(ptr as *const u8, len as usize) });
+    }
+
+    /// Appends data to the media.
+    pub fn append(&mut self, data: &[u8]) -> crate::Result<()> {
+        try_unsafe!(crate::gphoto2::gp_file_append(
+            self.file,
+            data.as_ptr() as *const c_char,
+            data.len() as c_ulong
+        ));
+
+        Ok(())
+    }
 }
 
 impl Media for FileMedia {
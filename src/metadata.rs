@@ -0,0 +1,327 @@
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+
+use crate::camera::{Camera, CameraFile};
+use crate::media::FileMedia;
+
+/// Metadata of a file on a camera.
+///
+/// Some drivers expose properties of a file, like its rating or protection flag, only as a
+/// metadata blob. `Metadata` parses the common formats of these blobs into key-value pairs:
+/// lines of the form `key=value` or `key: value`, and XML elements that contain only text, like
+/// the object properties reported by the `ptp2` driver. The unparsed blob is available from
+/// `raw()`.
+///
+/// Metadata is retrieved with `Camera::metadata()`.
+#[derive(Debug, Clone)]
+pub struct Metadata {
+    raw: Vec<u8>,
+    values: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Parses a metadata blob.
+    pub fn parse(raw: Vec<u8>) -> Self {
+        let values = parse(&String::from_utf8_lossy(&raw));
+
+        Metadata { raw, values }
+    }
+
+    /// Returns the value of a key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Returns an iterator over the key-value pairs, sorted by key.
+    pub fn iter(&self) -> MetadataIter<'_> {
+        MetadataIter {
+            inner: self.values.iter(),
+        }
+    }
+
+    /// Returns the number of key-value pairs.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if no key-value pairs were found.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the metadata blob as reported by the driver.
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+}
+
+/// An iterator over the key-value pairs of `Metadata`.
+pub struct MetadataIter<'a> {
+    inner: btree_map::Iter<'a, String, String>,
+}
+
+impl<'a> Iterator for MetadataIter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+impl Camera {
+    /// Retrieves the metadata of a file.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let file = camera.capture_image().unwrap();
+    ///
+    /// for (key, value) in camera.metadata(&file).unwrap().iter() {
+    ///     println!("{} = {}", key, value);
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the metadata could not be retrieved:
+    ///
+    /// * `NotSupported` if the driver doesn't provide metadata.
+    /// * `FileNotFound` if the file doesn't exist.
+    pub fn metadata(&mut self, file: &CameraFile) -> crate::Result<Metadata> {
+        let mut media = FileMedia::create_mem()?;
+        self.get_file(
+            file,
+            &mut media,
+            Some(crate::gphoto2::GP_FILE_TYPE_METADATA),
+        )?;
+
+        Ok(Metadata::parse(media.get_data()))
+    }
+}
+
+impl Camera {
+    /// Returns the star rating of a file.
+    ///
+    /// Ratings range from 0 (not rated) to 5 stars. This uses the `Rating` property that MTP
+    /// devices report in a file's metadata, which is scaled from 0 to 100.
+    ///
+    /// Returns `None` if the file's metadata doesn't contain a rating.
+    pub fn rating(&mut self, file: &CameraFile) -> crate::Result<Option<u8>> {
+        let metadata = self.metadata(file)?;
+
+        Ok(metadata
+            .get(RATING)
+            .and_then(|rating| rating.parse::<u32>().ok())
+            .map(stars_from_rating))
+    }
+
+    /// Sets the star rating of a file.
+    ///
+    /// Ratings range from 0 (not rated) to 5 stars. See `rating()` for details.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let file = camera.capture_image().unwrap();
+    ///
+    /// // mark the capture as a keeper
+    /// camera.set_rating(&file, 5).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the rating could not be set:
+    ///
+    /// * `InvalidInput` if the rating is greater than 5.
+    /// * `NotSupported` if the driver doesn't support changing metadata.
+    pub fn set_rating(&mut self, file: &CameraFile, stars: u8) -> crate::Result<()> {
+        if stars > 5 {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            ));
+        }
+
+        let xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<{0}>{1}</{0}>\n",
+            RATING,
+            rating_from_stars(stars)
+        );
+
+        let mut media = FileMedia::create_mem()?;
+        media.append(xml.as_bytes())?;
+
+        self.upload(
+            &file.directory(),
+            &file.basename(),
+            &mut media,
+            Some(crate::gphoto2::GP_FILE_TYPE_METADATA),
+        )
+    }
+}
+
+/// The name of the rating property in the metadata of MTP devices.
+const RATING: &str = "Rating";
+
+/// The MTP ratings that correspond to 0 to 5 stars.
+const STAR_RATINGS: [u32; 6] = [0, 1, 25, 50, 75, 99];
+
+fn rating_from_stars(stars: u8) -> u32 {
+    STAR_RATINGS[stars as usize]
+}
+
+fn stars_from_rating(rating: u32) -> u8 {
+    STAR_RATINGS
+        .iter()
+        .rposition(|&min| rating >= min)
+        .unwrap_or(0) as u8
+}
+
+fn parse(text: &str) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+
+    if text.trim_start().starts_with('<') {
+        parse_xml(text, &mut values);
+    } else {
+        for line in text.lines() {
+            let separator = line.find(['=', ':']);
+
+            if let Some(index) = separator {
+                let key = line[..index].trim();
+
+                if !key.is_empty() {
+                    values.insert(key.to_owned(), line[index + 1..].trim().to_owned());
+                }
+            }
+        }
+    }
+
+    values
+}
+
+/// Collects the elements that contain only text, e.g., `<dc02>3</dc02>`.
+fn parse_xml(text: &str, values: &mut BTreeMap<String, String>) {
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with(['/', '?', '!']) || tag.ends_with('/') {
+            continue;
+        }
+
+        let name = tag.split_whitespace().next().unwrap_or(tag);
+        let close = format!("</{}>", name);
+
+        if let Some(content_end) = rest.find('<') {
+            if rest[content_end..].starts_with(&close) {
+                values.insert(name.to_owned(), rest[..content_end].trim().to_owned());
+                rest = &rest[content_end + close.len()..];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_ratings_to_stars() {
+        for stars in 0..=5 {
+            assert_eq!(stars, stars_from_rating(rating_from_stars(stars)));
+        }
+
+        let cases = [
+            (0, 0),
+            (1, 1),
+            (10, 1),
+            (24, 1),
+            (25, 2),
+            (49, 2),
+            (50, 3),
+            (74, 3),
+            (75, 4),
+            (98, 4),
+            (99, 5),
+            (100, 5),
+        ];
+
+        for &(rating, stars) in cases.iter() {
+            assert_eq!(stars, stars_from_rating(rating), "{:?}", rating);
+        }
+    }
+
+    #[test]
+    fn parses_key_value_pairs() {
+        let cases: &[(&str, &[(&str, &str)])] = &[
+            ("", &[]),
+            ("rating=3\n", &[("rating", "3")]),
+            (
+                "Rating: 3\r\nProtected : yes\n",
+                &[("Protected", "yes"), ("Rating", "3")],
+            ),
+            ("time: 12:30:00", &[("time", "12:30:00")]),
+            ("label = a=b", &[("label", "a=b")]),
+            ("no separator\n=value\n : value", &[]),
+            ("rating=1\nrating=2", &[("rating", "2")]),
+        ];
+
+        for (text, expected) in cases {
+            let expected: BTreeMap<_, _> = expected
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+
+            assert_eq!(expected, parse(text), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parses_xml_elements() {
+        let cases: &[(&str, &[(&str, &str)])] = &[
+            ("<x></x>", &[("x", "")]),
+            ("<dc02>3</dc02>", &[("dc02", "3")]),
+            (
+                concat!(
+                    "<?xml version=\"1.0\"?>\n",
+                    "<x>\n<dc02> 3 </dc02>\n<dc44 type=\"str\">name</dc44>\n</x>",
+                ),
+                &[("dc02", "3"), ("dc44", "name")],
+            ),
+            ("<x><empty/><dc02>1</dc02></x>", &[("dc02", "1")]),
+            ("<x><a><b>1</b></a></x>", &[("b", "1")]),
+            ("<x><a>1</b></x>", &[]),
+            ("<!-- comment --><dc02>1", &[]),
+            ("<dc02", &[]),
+        ];
+
+        for (text, expected) in cases {
+            let expected: BTreeMap<_, _> = expected
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+
+            assert_eq!(expected, parse(text), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn keeps_raw_blob() {
+        let metadata = Metadata::parse(b"rating=\xff3".to_vec());
+
+        assert_eq!(b"rating=\xff3", metadata.raw());
+        assert_eq!(Some("\u{fffd}3"), metadata.get("rating"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::ErrorKind;
+
+/// All kinds of errors, in the order of their counters.
+const ERROR_KINDS: [ErrorKind; 16] = [
+    ErrorKind::InvalidInput,
+    ErrorKind::NotSupported,
+    ErrorKind::CorruptedData,
+    ErrorKind::ModelNotFound,
+    ErrorKind::FileExists,
+    ErrorKind::DirectoryExists,
+    ErrorKind::DirectoryNotFound,
+    ErrorKind::FileNotFound,
+    ErrorKind::CameraBusy,
+    ErrorKind::PathNotAbsolute,
+    ErrorKind::Cancel,
+    ErrorKind::CameraError,
+    ErrorKind::OSFailure,
+    ErrorKind::NoSpace,
+    ErrorKind::Timeout,
+    ErrorKind::Other,
+];
+
+static CAPTURES: AtomicU64 = AtomicU64::new(0);
+static DOWNLOADS: AtomicU64 = AtomicU64::new(0);
+static DOWNLOADED_BYTES: AtomicU64 = AtomicU64::new(0);
+static RECONNECTS: AtomicU64 = AtomicU64::new(0);
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+static ERRORS: [AtomicU64; 16] = [ZERO; 16];
+
+/// A snapshot of the crate's counters.
+///
+/// The counters are global to the process and count the operations of all cameras since the
+/// process started. They are only collected with the `metrics` feature.
+///
+/// ## Example
+///
+/// Serving the counters to Prometheus:
+///
+/// ```no_run
+/// use std::io::{Read, Write};
+/// use std::net::TcpListener;
+///
+/// let listener = TcpListener::bind("0.0.0.0:9100").unwrap();
+///
+/// for stream in listener.incoming() {
+///     let mut stream = stream.unwrap();
+///     let _ = stream.read(&mut [0; 1024]);
+///
+///     let body = gphoto::metrics().to_prometheus();
+///     write!(stream, "HTTP/1.0 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n\r\n{}", body)
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metrics {
+    captures: u64,
+    downloads: u64,
+    downloaded_bytes: u64,
+    reconnects: u64,
+    errors: [u64; 16],
+}
+
+/// Returns a snapshot of the crate's counters.
+pub fn metrics() -> Metrics {
+    let mut errors = [0; 16];
+
+    for (count, counter) in errors.iter_mut().zip(ERRORS.iter()) {
+        *count = counter.load(Ordering::Relaxed);
+    }
+
+    Metrics {
+        captures: CAPTURES.load(Ordering::Relaxed),
+        downloads: DOWNLOADS.load(Ordering::Relaxed),
+        downloaded_bytes: DOWNLOADED_BYTES.load(Ordering::Relaxed),
+        reconnects: RECONNECTS.load(Ordering::Relaxed),
+        errors,
+    }
+}
+
+impl Metrics {
+    /// The number of image captures, including triggered captures.
+    pub fn captures(&self) -> u64 {
+        self.captures
+    }
+
+    /// The number of files downloaded from cameras.
+    ///
+    /// Thumbnails and metadata aren't counted, and a download that is retried is counted once.
+    pub fn downloads(&self) -> u64 {
+        self.downloads
+    }
+
+    /// The number of bytes downloaded to directories and buffers.
+    pub fn downloaded_bytes(&self) -> u64 {
+        self.downloaded_bytes
+    }
+
+    /// The number of times that a camera was reinitialized after losing its connection.
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+
+    /// The number of errors of a kind that operations on cameras, e.g., captures, downloads or
+    /// waiting for events, have failed with.
+    pub fn errors(&self, kind: ErrorKind) -> u64 {
+        match ERROR_KINDS.iter().position(|&k| k == kind) {
+            Some(index) => self.errors[index],
+            None => 0,
+        }
+    }
+
+    /// The number of errors of all kinds.
+    pub fn total_errors(&self) -> u64 {
+        self.errors.iter().sum()
+    }
+
+    /// Formats the counters in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut text = String::new();
+
+        let counters = [
+            ("gphoto_captures_total", "Captures.", self.captures),
+            (
+                "gphoto_downloads_total",
+                "Files downloaded.",
+                self.downloads,
+            ),
+            (
+                "gphoto_downloaded_bytes_total",
+                "Bytes downloaded.",
+                self.downloaded_bytes,
+            ),
+            (
+                "gphoto_reconnects_total",
+                "Camera reinitializations.",
+                self.reconnects,
+            ),
+        ];
+
+        for (name, help, value) in &counters {
+            let _ = writeln!(text, "# HELP {} {}", name, help);
+            let _ = writeln!(text, "# TYPE {} counter", name);
+            let _ = writeln!(text, "{} {}", name, value);
+        }
+
+        let _ = writeln!(text, "# HELP gphoto_errors_total Errors by kind.");
+        let _ = writeln!(text, "# TYPE gphoto_errors_total counter");
+
+        for (kind, count) in ERROR_KINDS.iter().zip(self.errors.iter()) {
+            let _ = writeln!(text, "gphoto_errors_total{{kind=\"{:?}\"}} {}", kind, count);
+        }
+
+        text
+    }
+}
+
+pub(crate) fn record_capture() {
+    CAPTURES.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_download() {
+    DOWNLOADS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_downloaded_bytes(bytes: u64) {
+    DOWNLOADED_BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn record_reconnect() {
+    RECONNECTS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_error(kind: ErrorKind) {
+    if let Some(index) = ERROR_KINDS.iter().position(|&k| k == kind) {
+        ERRORS[index].fetch_add(1, Ordering::Relaxed);
+    }
+}
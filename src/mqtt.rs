@@ -0,0 +1,340 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::event::CameraEvent;
+use crate::health::HealthReport;
+use crate::json::JsonObject;
+
+/// The timeout of connecting to the broker and of each write.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The keep-alive interval that is requested from the broker.
+///
+/// The broker closes connections that have been silent for 1.5 times as long.
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+/// A publisher of camera events and health reports to an MQTT broker.
+///
+/// Messages are published as JSON to a topic tree per camera:
+///
+/// * `cameras/<id>/events` for camera events, e.g.,
+///   `{"event":"file_added","path":"/store_00010001/DCIM/100CANON/IMG_0001.JPG"}`.
+/// * `cameras/<id>/health` for health reports, e.g.,
+///   `{"connected":true,"healthy":true,"battery_level":"75%","free_kbytes":30833088,"last_error":null}`.
+///   Health reports are retained, so that a dashboard that subscribes later receives the latest
+///   report.
+///
+/// The publisher speaks MQTT 3.1.1 over plain TCP and publishes with QoS 0. Before a message is
+/// published on a connection that has been idle for longer than the keep-alive interval of 60
+/// seconds, the connection is checked with a ping. If the connection is lost, it reconnects once
+/// per message. It is only available with the `mqtt` feature.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use gphoto::MqttPublisher;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut publisher = MqttPublisher::connect("broker.local:1883", "booth-1").unwrap();
+///
+/// loop {
+///     let event = camera.wait_event(Duration::from_secs(10)).unwrap();
+///     publisher.publish_event(&event).unwrap();
+///     publisher.publish_health(&camera.health()).unwrap();
+/// }
+/// ```
+pub struct MqttPublisher {
+    address: String,
+    client_id: String,
+    topic: String,
+    stream: Option<TcpStream>,
+    last_packet: Instant,
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        if let Some(ref mut stream) = self.stream {
+            let _ = stream.write_all(&[0xe0, 0x00]);
+        }
+    }
+}
+
+impl MqttPublisher {
+    /// Connects to a broker to publish the messages of a camera.
+    ///
+    /// `address` is the broker's host and port, e.g., `broker.local:1883`. `camera_id` identifies
+    /// the camera in the topic tree; characters that aren't allowed in topic levels are replaced.
+    /// The client identifier is derived from a hash of `camera_id`, so that it is unique per
+    /// camera and short enough for all brokers.
+    pub fn connect(address: &str, camera_id: &str) -> io::Result<Self> {
+        let camera_id: String = camera_id
+            .chars()
+            .map(|c| match c {
+                '/' | '+' | '#' => '_',
+                c => c,
+            })
+            .collect();
+
+        // 23 characters, the longest client identifier that all brokers accept.
+        let client_id = format!("gphoto-{:016x}", fnv1a(camera_id.as_bytes()));
+
+        let mut publisher = MqttPublisher {
+            address: address.to_owned(),
+            client_id,
+            topic: format!("cameras/{}", camera_id),
+            stream: None,
+            last_packet: Instant::now(),
+        };
+
+        publisher.reconnect()?;
+
+        Ok(publisher)
+    }
+
+    /// Publishes a camera event to `cameras/<id>/events`.
+    ///
+    /// `CameraEvent::Timeout` is not published.
+    pub fn publish_event(&mut self, event: &CameraEvent) -> io::Result<()> {
+        let payload = match *event {
+            CameraEvent::Timeout => return Ok(()),
+            CameraEvent::FileAdded(ref file) => JsonObject::new()
+                .string("event", "file_added")
+                .string("path", &file.path()),
+            CameraEvent::FolderAdded(ref folder) => JsonObject::new()
+                .string("event", "folder_added")
+                .string("path", &folder.path()),
+            CameraEvent::FileChanged(ref file) => JsonObject::new()
+                .string("event", "file_changed")
+                .string("path", &file.path()),
+            CameraEvent::CaptureComplete => JsonObject::new().string("event", "capture_complete"),
+            CameraEvent::VendorPropertyChanged {
+                code,
+                ref name,
+                ref value,
+            } => JsonObject::new()
+                .string("event", "property_changed")
+                .number("code", u64::from(code))
+                .optional_string("name", name.as_deref())
+                .optional_string("value", value.as_deref()),
+            CameraEvent::Unknown(ref description) => JsonObject::new()
+                .string("event", "unknown")
+                .string("description", description),
+        };
+
+        self.publish("events", payload.finish().as_bytes(), false)
+    }
+
+    /// Publishes a health report to `cameras/<id>/health`.
+    pub fn publish_health(&mut self, health: &HealthReport) -> io::Result<()> {
+        let payload = JsonObject::new()
+            .boolean("connected", health.is_connected())
+            .boolean("healthy", health.is_healthy())
+            .optional_string("battery_level", health.battery_level())
+            .optional_number("free_kbytes", health.free_kbytes())
+            .optional_string("last_error", health.last_error())
+            .finish();
+
+        self.publish("health", payload.as_bytes(), true)
+    }
+
+    /// Publishes a message to a subtopic of the camera's topic, e.g., `"events"`.
+    ///
+    /// Retained messages are stored by the broker and delivered to new subscribers.
+    pub fn publish(&mut self, subtopic: &str, payload: &[u8], retain: bool) -> io::Result<()> {
+        let topic = format!("{}/{}", self.topic, subtopic);
+
+        let mut body = Vec::with_capacity(2 + topic.len() + payload.len());
+        push_string(&mut body, &topic);
+        body.extend_from_slice(payload);
+
+        let header = if retain { 0x31 } else { 0x30 };
+        let packet = packet(header, &body)?;
+
+        // Writes to a connection that the broker has closed can succeed, so an idle connection is
+        // checked first.
+        let idle = self.last_packet.elapsed() >= KEEP_ALIVE;
+
+        let written = match self.stream {
+            Some(ref mut stream) => {
+                (!idle || ping(stream).is_ok()) && stream.write_all(&packet).is_ok()
+            }
+            None => false,
+        };
+
+        if !written {
+            self.reconnect()?;
+
+            if let Some(ref mut stream) = self.stream {
+                stream.write_all(&packet)?;
+            }
+        }
+
+        self.last_packet = Instant::now();
+
+        Ok(())
+    }
+
+    fn reconnect(&mut self) -> io::Result<()> {
+        self.stream = None;
+
+        let address = match self.address.to_socket_addrs()?.next() {
+            Some(address) => address,
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, "broker not found")),
+        };
+
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        // Protocol name and level, clean session, keep alive, client identifier.
+        let mut body = Vec::new();
+        push_string(&mut body, "MQTT");
+        body.extend_from_slice(&[0x04, 0x02]);
+        body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+        push_string(&mut body, &self.client_id);
+
+        stream.write_all(&packet(0x10, &body)?)?;
+
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("broker refused connection with code {}", connack[3]),
+            ));
+        }
+
+        self.stream = Some(stream);
+        self.last_packet = Instant::now();
+
+        Ok(())
+    }
+}
+
+/// Sends a ping to the broker and waits for its response.
+fn ping(stream: &mut TcpStream) -> io::Result<()> {
+    stream.write_all(&[0xc0, 0x00])?;
+
+    let mut pingresp = [0; 2];
+    stream.read_exact(&mut pingresp)?;
+
+    if pingresp != [0xd0, 0x00] {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected response to ping",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Encodes a control packet with its fixed header.
+fn packet(header: u8, body: &[u8]) -> io::Result<Vec<u8>> {
+    // The remaining length is encoded in up to four bytes of seven bits.
+    if body.len() >= 1 << 28 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "message is too large",
+        ));
+    }
+
+    let mut packet = Vec::with_capacity(body.len() + 5);
+    packet.push(header);
+
+    let mut length = body.len();
+
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+
+        if length > 0 {
+            byte |= 0x80;
+        }
+
+        packet.push(byte);
+
+        if length == 0 {
+            break;
+        }
+    }
+
+    packet.extend_from_slice(body);
+
+    Ok(packet)
+}
+
+/// Returns the 64-bit FNV-1a hash of bytes, which is stable across releases and platforms.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Appends a length-prefixed UTF-8 string.
+///
+/// Strings that don't fit the length are truncated at the last character that fits, so that the
+/// string stays valid UTF-8.
+fn push_string(buffer: &mut Vec<u8>, s: &str) {
+    let mut end = s.len().min(u16::MAX as usize);
+
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let bytes = &s.as_bytes()[..end];
+
+    buffer.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_remaining_lengths() {
+        let cases: [(usize, &[u8]); 7] = [
+            (0, &[0x00]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (16383, &[0xff, 0x7f]),
+            (16384, &[0x80, 0x80, 0x01]),
+            (2_097_151, &[0xff, 0xff, 0x7f]),
+            (2_097_152, &[0x80, 0x80, 0x80, 0x01]),
+        ];
+
+        for &(length, encoded) in cases.iter() {
+            let packet = packet(0x30, &vec![0; length]).unwrap();
+
+            assert_eq!(packet[0], 0x30, "{}", length);
+            assert_eq!(&packet[1..1 + encoded.len()], encoded, "{}", length);
+            assert_eq!(packet.len(), 1 + encoded.len() + length, "{}", length);
+        }
+    }
+
+    #[test]
+    fn truncates_strings_at_char_boundaries() {
+        let max = u16::MAX as usize;
+
+        let cases = [
+            ("gphoto/status".to_owned(), 13),
+            ("a".repeat(max), max),
+            ("a".repeat(max + 1), max),
+            // The last character would be cut in half at the limit.
+            ("a".repeat(max - 1) + "\u{e9}", max - 1),
+            ("\u{20ac}".repeat(max / 3 + 1), max),
+        ];
+
+        for &(ref s, length) in cases.iter() {
+            let mut buffer = Vec::new();
+            push_string(&mut buffer, s);
+
+            assert_eq!(&buffer[..2], &(length as u16).to_be_bytes(), "{}", s.len());
+            assert_eq!(&buffer[2..], &s.as_bytes()[..length], "{}", s.len());
+        }
+    }
+}
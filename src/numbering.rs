@@ -0,0 +1,121 @@
+use crate::camera::Camera;
+use crate::widget::{Config, WidgetValue};
+
+/// Names of the settings that control file numbering in different drivers.
+const SETTINGS: &[&str] = &["filenrsequencing", "filenumbering"];
+
+/// Values of the file numbering settings that mean continuous numbering.
+const CONTINUOUS: &[&str] = &["on", "continuous"];
+
+/// Values of the file numbering settings that mean numbering is reset.
+const RESET: &[&str] = &["off", "reset", "auto reset"];
+
+/// Modes of numbering the files that a camera captures.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FileNumbering {
+    /// Numbering continues across folders and cards.
+    Continuous,
+
+    /// Numbering restarts at `0001` when a new folder is created or a card is inserted.
+    Reset,
+}
+
+impl Camera {
+    /// Returns the camera's file numbering mode.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the mode could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't expose its file numbering mode.
+    pub fn file_numbering(&mut self) -> crate::Result<FileNumbering> {
+        let config = self.file_numbering_config()?;
+
+        match config.root().value() {
+            Some(WidgetValue::Toggle(true)) => Ok(FileNumbering::Continuous),
+            Some(WidgetValue::Toggle(false)) => Ok(FileNumbering::Reset),
+            Some(WidgetValue::Choice(value)) | Some(WidgetValue::Text(value)) => {
+                let value = value.to_ascii_lowercase();
+
+                if CONTINUOUS.contains(&value.as_str()) {
+                    Ok(FileNumbering::Continuous)
+                } else if RESET.contains(&value.as_str()) {
+                    Ok(FileNumbering::Reset)
+                } else {
+                    Err(crate::error::from_libgphoto2(
+                        crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                    ))
+                }
+            }
+            _ => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+
+    /// Sets the camera's file numbering mode.
+    ///
+    /// Provisioning a fleet of cameras with `FileNumbering::Reset` and a freshly formatted card
+    /// puts all of them in a known state, where the first capture is numbered `0001`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::FileNumbering;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// camera.set_file_numbering(FileNumbering::Reset).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the mode could not be set:
+    ///
+    /// * `NotSupported` if the camera doesn't expose its file numbering mode or doesn't support
+    ///   the requested mode.
+    pub fn set_file_numbering(&mut self, numbering: FileNumbering) -> crate::Result<()> {
+        let mut config = self.file_numbering_config()?;
+        let root = config.root();
+        let name = root.name().into_owned();
+
+        let value = match root.value() {
+            Some(WidgetValue::Toggle(_)) => {
+                WidgetValue::Toggle(numbering == FileNumbering::Continuous)
+            }
+            _ => {
+                let accepted = match numbering {
+                    FileNumbering::Continuous => CONTINUOUS,
+                    FileNumbering::Reset => RESET,
+                };
+
+                match root
+                    .choices()
+                    .into_iter()
+                    .find(|choice| accepted.contains(&choice.to_ascii_lowercase().as_str()))
+                {
+                    Some(choice) => WidgetValue::Choice(choice),
+                    None => {
+                        return Err(crate::error::from_libgphoto2(
+                            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                        ))
+                    }
+                }
+            }
+        };
+
+        config.set_value(&name, value)?;
+        self.set_single_config(&mut config)
+    }
+
+    fn file_numbering_config(&mut self) -> crate::Result<Config> {
+        for name in SETTINGS {
+            if let Ok(config) = self.single_config(name) {
+                return Ok(config);
+            }
+        }
+
+        Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        ))
+    }
+}
@@ -0,0 +1,222 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, Rgb, RgbImage};
+
+/// The default color of grid lines and crop guides.
+const LINE_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// The opacity of the area outside of crop guides.
+const MASK_OPACITY: f32 = 0.5;
+
+/// Composites guides onto live view frames before they are shown.
+///
+/// An overlay can draw:
+///
+/// * an alignment grid, e.g., the rule of thirds,
+/// * a ghost image, usually the previous frame of a stop-motion sequence or the shot that is being
+///   matched, blended at a low opacity (onion skinning),
+/// * a crop guide that darkens the parts of the frame outside of a target aspect ratio.
+///
+/// This type is only available with the `image` feature enabled.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::Overlay;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let previous = image::open("frame_0041.jpg").unwrap().to_rgb8();
+///
+/// let overlay = Overlay::new()
+///     .grid(3, 3)
+///     .ghost(previous, 0.3)
+///     .crop_guide(16, 9);
+///
+/// let mut stream = camera.preview_stream().unwrap();
+/// let frame = stream.next_frame().unwrap();
+/// let composited = overlay.apply_jpeg(&frame, 80).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    grid: Option<(u32, u32)>,
+    ghost: Option<(RgbImage, f32)>,
+    crop: Option<(u32, u32)>,
+    color: Rgb<u8>,
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Overlay {
+            grid: None,
+            ghost: None,
+            crop: None,
+            color: LINE_COLOR,
+        }
+    }
+}
+
+impl Overlay {
+    /// Creates an overlay that doesn't draw anything.
+    pub fn new() -> Self {
+        Overlay::default()
+    }
+
+    /// Draws a grid that divides the frame into columns and rows.
+    pub fn grid(mut self, columns: u32, rows: u32) -> Self {
+        self.grid = Some((columns.max(1), rows.max(1)));
+        self
+    }
+
+    /// Blends an image onto the frame with an opacity from 0 to 1.
+    ///
+    /// The image is scaled to the size of the frame.
+    pub fn ghost(mut self, image: RgbImage, opacity: f32) -> Self {
+        self.ghost = Some((image, opacity.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// Replaces the ghost image, e.g., after each frame of a stop-motion sequence.
+    ///
+    /// The opacity of the previous ghost image is kept, or 0.3 if there was none.
+    pub fn set_ghost(&mut self, image: RgbImage) {
+        let opacity = self.ghost.as_ref().map_or(0.3, |&(_, opacity)| opacity);
+        self.ghost = Some((image, opacity));
+    }
+
+    /// Removes the ghost image.
+    pub fn clear_ghost(&mut self) {
+        self.ghost = None;
+    }
+
+    /// Darkens the parts of the frame outside of the largest centered area with an aspect ratio.
+    pub fn crop_guide(mut self, width: u32, height: u32) -> Self {
+        self.crop = Some((width.max(1), height.max(1)));
+        self
+    }
+
+    /// Sets the color of grid lines and crop guides.
+    pub fn color(mut self, color: Rgb<u8>) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Draws the overlay onto an image.
+    pub fn apply(&self, image: &mut RgbImage) {
+        let (width, height) = image.dimensions();
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        if let Some((ref ghost, opacity)) = self.ghost {
+            let scaled;
+            let ghost = if ghost.dimensions() == (width, height) {
+                ghost
+            } else {
+                scaled = imageops::resize(ghost, width, height, FilterType::Triangle);
+                &scaled
+            };
+
+            for (pixel, ghost) in image.pixels_mut().zip(ghost.pixels()) {
+                for channel in 0..3 {
+                    pixel[channel] = blend(pixel[channel], ghost[channel], opacity);
+                }
+            }
+        }
+
+        if let Some((aspect_width, aspect_height)) = self.crop {
+            let (left, top, right, bottom) = crop_area(width, height, aspect_width, aspect_height);
+
+            for (x, y, pixel) in image.enumerate_pixels_mut() {
+                if x < left || x >= right || y < top || y >= bottom {
+                    for channel in 0..3 {
+                        pixel[channel] = blend(pixel[channel], 0, MASK_OPACITY);
+                    }
+                } else if x == left || x == right - 1 || y == top || y == bottom - 1 {
+                    *pixel = self.color;
+                }
+            }
+        }
+
+        if let Some((columns, rows)) = self.grid {
+            for column in 1..columns {
+                let x = (u64::from(width) * u64::from(column) / u64::from(columns)) as u32;
+
+                for y in 0..height {
+                    image.put_pixel(x, y, self.color);
+                }
+            }
+
+            for row in 1..rows {
+                let y = (u64::from(height) * u64::from(row) / u64::from(rows)) as u32;
+
+                for x in 0..width {
+                    image.put_pixel(x, y, self.color);
+                }
+            }
+        }
+    }
+
+    /// Decodes a JPEG preview frame, draws the overlay and encodes it as JPEG again.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be processed:
+    ///
+    /// * `CorruptedData` if the frame isn't a valid JPEG image.
+    /// * `OSFailure` if the result could not be encoded.
+    pub fn apply_jpeg(&self, frame: &[u8], quality: u8) -> crate::Result<Vec<u8>> {
+        let mut image = match image::load_from_memory_with_format(frame, ImageFormat::Jpeg) {
+            Ok(image) => image.to_rgb8(),
+            Err(_) => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_CORRUPTED_DATA,
+                ))
+            }
+        };
+
+        self.apply(&mut image);
+
+        let mut jpeg = Vec::new();
+
+        match JpegEncoder::new_with_quality(&mut jpeg, quality).encode_image(&image) {
+            Ok(()) => Ok(jpeg),
+            Err(_) => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            )),
+        }
+    }
+}
+
+fn blend(base: u8, top: u8, opacity: f32) -> u8 {
+    (f32::from(base) * (1.0 - opacity) + f32::from(top) * opacity).round() as u8
+}
+
+/// Returns the bounds `(left, top, right, bottom)` of the largest centered area of an image with
+/// an aspect ratio.
+fn crop_area(
+    width: u32,
+    height: u32,
+    aspect_width: u32,
+    aspect_height: u32,
+) -> (u32, u32, u32, u32) {
+    let (width, height) = (u64::from(width), u64::from(height));
+    let (aspect_width, aspect_height) = (u64::from(aspect_width), u64::from(aspect_height));
+
+    let (crop_width, crop_height) = if width * aspect_height > height * aspect_width {
+        (height * aspect_width / aspect_height, height)
+    } else {
+        (width, width * aspect_height / aspect_width)
+    };
+
+    let (crop_width, crop_height) = (crop_width.max(1), crop_height.max(1));
+    let left = (width - crop_width) / 2;
+    let top = (height - crop_height) / 2;
+
+    (
+        left as u32,
+        top as u32,
+        (left + crop_width) as u32,
+        (top + crop_height) as u32,
+    )
+}
@@ -2,8 +2,9 @@
 use allow::borrow::Cow;
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
+use core::time::Duration;
 use cstr_core::CStr;
-use libc::c_void;
+use libc::{c_char, c_int, c_void};
 #[cfg(feature = "std")]
 use std::borrow::Cow;
 
@@ -114,6 +115,99 @@ impl<'a> Port<'a> {
             String::from_utf8_lossy(CStr::from_ptr(path).to_bytes())
         }
     }
+
+    /// Returns the path of the I/O library that handles the port.
+    pub fn library(&self) -> Cow<'_, str> {
+        let mut library = MaybeUninit::uninit();
+
+        unsafe {
+            assert_eq!(
+                crate::gphoto2::GP_OK,
+                crate::gphoto2::gp_port_info_get_library_filename(
+                    self.inner,
+                    &mut *library.as_mut_ptr()
+                )
+            );
+            let library = library.assume_init();
+            String::from_utf8_lossy(CStr::from_ptr(library).to_bytes())
+        }
+    }
+}
+
+/// Raw access to the port that a camera is connected to.
+///
+/// `PortIo` allows sending commands that the camera driver doesn't support, e.g., vendor
+/// extensions of a camera's protocol. The data is written to and read from the port as is, so the
+/// caller is responsible for framing it according to the camera's protocol. Interleaving raw I/O
+/// with the driver's own communication can confuse the driver if a transaction is left incomplete.
+///
+/// A `PortIo` is created with `Camera::port_io()`. The port's timeout is restored when it is
+/// dropped.
+pub struct PortIo<'a> {
+    port: *mut crate::gphoto2::GPPort,
+    timeout: c_int,
+    __phantom: PhantomData<&'a mut crate::camera::Camera>,
+}
+
+impl<'a> Drop for PortIo<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            crate::gphoto2::gp_port_set_timeout(self.port, self.timeout);
+        }
+    }
+}
+
+impl<'a> PortIo<'a> {
+    /// Sets the timeout for reads and writes.
+    pub fn set_timeout(&mut self, timeout: Duration) -> crate::Result<()> {
+        let timeout = timeout.as_millis().min(c_int::MAX as u128) as c_int;
+
+        try_unsafe!(crate::gphoto2::gp_port_set_timeout(self.port, timeout));
+
+        Ok(())
+    }
+
+    /// Writes data to the port.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the data could not be written:
+    ///
+    /// * `Timeout` if the write didn't complete before the timeout expired.
+    /// * `NotSupported` if the port doesn't support writing.
+    pub fn write(&mut self, data: &[u8]) -> crate::Result<usize> {
+        let size = data.len().min(c_int::MAX as usize) as c_int;
+
+        match unsafe {
+            crate::gphoto2::gp_port_write(self.port, data.as_ptr() as *const c_char, size)
+        } {
+            n if n >= 0 => Ok(n as usize),
+            err => Err(crate::error::from_libgphoto2(err)),
+        }
+    }
+
+    /// Reads data from the port.
+    ///
+    /// Returns the number of bytes read.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if no data could be read:
+    ///
+    /// * `Timeout` if no data arrived before the timeout expired.
+    /// * `NotSupported` if the port doesn't support reading.
+    pub fn read(&mut self, buffer: &mut [u8]) -> crate::Result<usize> {
+        let size = buffer.len().min(c_int::MAX as usize) as c_int;
+
+        match unsafe {
+            crate::gphoto2::gp_port_read(self.port, buffer.as_mut_ptr() as *mut c_char, size)
+        } {
+            n if n >= 0 => Ok(n as usize),
+            err => Err(crate::error::from_libgphoto2(err)),
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -123,3 +217,37 @@ pub fn from_libgphoto2(_camera: &crate::camera::Camera, ptr: crate::gphoto2::GPP
         __phantom: PhantomData,
     }
 }
+
+/// Wraps a port info that is owned by `owner`, e.g., a port info list.
+pub(crate) fn from_port_info<T>(_owner: &T, ptr: crate::gphoto2::GPPortInfo) -> Port<'_> {
+    Port {
+        inner: ptr,
+        __phantom: PhantomData,
+    }
+}
+
+pub(crate) fn port_io(camera: &mut crate::camera::Camera) -> crate::Result<PortIo<'_>> {
+    let port = unsafe { (*camera.camera).port };
+
+    if port.is_null() {
+        return Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        ));
+    }
+
+    let mut timeout = MaybeUninit::uninit();
+
+    let timeout = unsafe {
+        match crate::gphoto2::gp_port_get_timeout(port, &mut *timeout.as_mut_ptr()) {
+            crate::gphoto2::GP_OK => (),
+            err => return Err(crate::error::from_libgphoto2(err)),
+        }
+        timeout.assume_init()
+    };
+
+    Ok(PortIo {
+        port,
+        timeout,
+        __phantom: PhantomData,
+    })
+}
@@ -0,0 +1,508 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::buffer::{BufferPool, PooledBuffer};
+use crate::camera::Camera;
+use crate::error::ErrorKind;
+use crate::media::FileMedia;
+use crate::tag::TagDecoder;
+use crate::widget::WidgetValue;
+
+/// Weight given to the newest sample in the moving averages.
+const SMOOTHING: f64 = 0.2;
+
+/// The slowest polling interval that the stream will back off to, unless the frame rate is
+/// limited to a slower rate.
+const MAX_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The longest interval that a frame rate limit is converted to, about 136 years, which keeps the
+/// interval arithmetic from overflowing.
+const MAX_FPS_INTERVAL: Duration = Duration::from_secs(u32::MAX as u64);
+
+/// The polling interval that the stream backs off to first when the camera is busy.
+const BUSY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The setting that raises the mirror and enables live view on many cameras.
+const VIEWFINDER: &str = "viewfinder";
+
+/// A stream of live view frames.
+///
+/// A `PreviewStream` repeatedly captures preview frames from a camera. It measures the frame rate
+/// that is actually achieved and adapts its polling interval to it: when the camera or USB bus
+/// can't deliver frames as fast as requested, the stream backs off instead of busy-looping the
+/// camera.
+///
+/// ## Example
+///
+/// ```no_run
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut stream = camera.preview_stream().unwrap();
+///
+/// stream.set_max_fps(30.0);
+///
+/// for frame in stream.by_ref().take(100) {
+///     let frame = frame.unwrap();
+///     println!("{} bytes", frame.len());
+/// }
+///
+/// println!("achieved {:.1} fps", stream.fps());
+/// stream.shutdown().unwrap();
+/// ```
+///
+/// Dropping the stream ends live view like `shutdown()`, but ignores errors.
+///
+/// Some drivers stall or crash when the configuration is read while live view is active. Use
+/// `pause()` to leave live view around such operations, or enable `Camera::set_half_duplex()` to
+/// have the camera do so for every configuration access.
+pub struct PreviewStream<'a> {
+    camera: &'a mut Camera,
+    media: FileMedia,
+    pool: BufferPool,
+    min_interval: Duration,
+    interval: Duration,
+    last_capture: Option<Instant>,
+    last_frame: Option<Instant>,
+    frames: u64,
+    fps: f64,
+    tag_decoder: Option<Box<dyn TagDecoder + Send>>,
+    finished: bool,
+}
+
+impl<'a> Drop for PreviewStream<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.end_live_view();
+        }
+    }
+}
+
+impl<'a> PreviewStream<'a> {
+    /// Captures the next preview frame.
+    ///
+    /// This function blocks until the polling interval has elapsed since the previous capture. The
+    /// frame is stored in a buffer from the stream's `BufferPool`.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be captured:
+    ///
+    /// * `NotSupported` if the camera does not support capturing previews.
+    /// * `CameraBusy` if the camera is busy. The stream backs off before the next capture.
+    pub fn next_frame(&mut self) -> crate::Result<PooledBuffer> {
+        if let Some(last_capture) = self.last_capture {
+            let elapsed = last_capture.elapsed();
+
+            if elapsed < self.interval {
+                thread::sleep(self.interval - elapsed);
+            }
+        }
+
+        let started = Instant::now();
+        let result = self.camera.capture_preview(&mut self.media);
+        let capture_time = started.elapsed();
+
+        self.last_capture = Some(started);
+
+        match result {
+            Ok(()) => {
+                self.record_frame(started);
+                self.adapt(capture_time);
+
+                let mut frame = self.pool.get();
+                self.media.get_data_into(&mut frame);
+
+                if let Some(ref mut decoder) = self.tag_decoder {
+                    if let Some(tag) = decoder.decode(&frame) {
+                        self.camera.set_session_tag(Some(&tag));
+                    }
+                }
+
+                Ok(frame)
+            }
+            Err(err) => {
+                if err.kind() == ErrorKind::CameraBusy {
+                    self.interval = busy_interval(self.interval, self.min_interval);
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Leaves live view, calls a function with the camera, and resumes the stream.
+    ///
+    /// Live view is entered again by the next frame. The pause isn't counted towards the measured
+    /// frame rate.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut stream = camera.preview_stream().unwrap();
+    ///
+    /// let frame = stream.next_frame().unwrap();
+    /// let iso = stream.pause(|camera| camera.setting("iso")).unwrap();
+    /// let frame = stream.next_frame().unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the error of `f`.
+    pub fn pause<F, T>(&mut self, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Camera) -> crate::Result<T>,
+    {
+        self.camera.leave_live_view();
+        self.last_capture = None;
+        self.last_frame = None;
+
+        f(self.camera)
+    }
+
+    /// Sets the pool that frame buffers are taken from.
+    ///
+    /// By default, each stream has its own small pool. Frames are returned to the pool when they
+    /// are dropped, so a stream whose frames are dropped promptly doesn't allocate per frame.
+    pub fn set_buffer_pool(&mut self, pool: BufferPool) {
+        self.pool = pool;
+    }
+
+    /// Passes each frame to a decoder that sets the camera's session tag.
+    ///
+    /// See `TagDecoder` for details.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut stream = camera.preview_stream().unwrap();
+    ///
+    /// stream.set_tag_decoder(|_frame: &[u8]| -> Option<String> {
+    ///     // decode a QR code with the application's decoder...
+    ///     None
+    /// });
+    ///
+    /// for frame in stream.by_ref().take(100) {
+    ///     frame.unwrap();
+    /// }
+    /// ```
+    pub fn set_tag_decoder<D: TagDecoder + Send + 'static>(&mut self, decoder: D) {
+        self.tag_decoder = Some(Box::new(decoder));
+    }
+
+    /// Limits the frame rate that the stream polls the camera for.
+    ///
+    /// By default, the stream polls as fast as the camera delivers frames. A rate that isn't
+    /// positive removes the limit. When the camera is slow or busy, the stream backs off to one
+    /// frame per second, or to the limit if it is slower.
+    pub fn set_max_fps(&mut self, fps: f64) {
+        self.min_interval = fps_interval(fps);
+        self.interval = self.interval.max(self.min_interval);
+    }
+
+    /// Returns the measured frame rate in frames per second.
+    ///
+    /// The frame rate is a moving average over the most recent frames. It is `0.0` until at least
+    /// two frames have been captured.
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    /// Returns the number of frames captured by the stream.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns the current polling interval.
+    ///
+    /// This is the minimum time between the start of two consecutive captures, as adapted to the
+    /// rate that the camera can sustain.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Stops the stream and takes the camera out of live view.
+    ///
+    /// Capturing previews raises the mirror and powers the sensor on many cameras. Shutting the
+    /// stream down lowers the mirror again, so that the camera is left ready for a normal capture
+    /// or for the next client.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera could not leave live view.
+    pub fn shutdown(mut self) -> crate::Result<()> {
+        self.finished = true;
+        self.end_live_view()
+    }
+
+    fn end_live_view(&mut self) -> crate::Result<()> {
+        if !self.camera.live_view {
+            return Ok(());
+        }
+
+        match self.camera.set_viewfinder(false) {
+            // Cameras without a viewfinder setting end live view on their own.
+            Err(ref err) if err.kind() == crate::ErrorKind::NotSupported => {
+                self.camera.live_view = false;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn record_frame(&mut self, captured: Instant) {
+        if let Some(last_frame) = self.last_frame {
+            let period = captured.duration_since(last_frame).as_secs_f64();
+
+            if period > 0.0 {
+                let fps = 1.0 / period;
+
+                self.fps = if self.fps == 0.0 {
+                    fps
+                } else {
+                    self.fps + (fps - self.fps) * SMOOTHING
+                };
+            }
+        }
+
+        self.last_frame = Some(captured);
+        self.frames += 1;
+    }
+
+    fn adapt(&mut self, capture_time: Duration) {
+        self.interval = adapted_interval(self.interval, capture_time, self.min_interval);
+    }
+}
+
+/// Returns the polling interval of a frame rate limit, or zero if the rate isn't positive.
+fn fps_interval(fps: f64) -> Duration {
+    if fps > 0.0 {
+        Duration::from_secs_f64((1.0 / fps).min(MAX_FPS_INTERVAL.as_secs_f64()))
+    } else {
+        Duration::from_secs(0)
+    }
+}
+
+/// Returns the slowest polling interval that a stream with a minimum interval backs off to.
+fn max_interval(min_interval: Duration) -> Duration {
+    MAX_INTERVAL.max(min_interval)
+}
+
+/// Returns the polling interval after the camera reported that it is busy.
+fn busy_interval(interval: Duration, min_interval: Duration) -> Duration {
+    (interval * 2)
+        .max(BUSY_INTERVAL)
+        .max(min_interval)
+        .min(max_interval(min_interval))
+}
+
+/// Returns the polling interval after a frame was captured in `capture_time`.
+fn adapted_interval(
+    interval: Duration,
+    capture_time: Duration,
+    min_interval: Duration,
+) -> Duration {
+    let target = capture_time
+        .max(min_interval)
+        .min(max_interval(min_interval));
+
+    if target > interval {
+        // The camera can't keep up, so polling any faster only keeps it busy.
+        target
+    } else {
+        let interval = interval.as_secs_f64();
+        let target = target.as_secs_f64();
+
+        Duration::from_secs_f64(interval + (target - interval) * SMOOTHING)
+    }
+}
+
+impl<'a> Iterator for PreviewStream<'a> {
+    type Item = crate::Result<PooledBuffer>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_frame())
+    }
+}
+
+impl Camera {
+    /// Enables or disables leaving live view before each configuration access.
+    ///
+    /// Some drivers stall or crash when the configuration is read or changed while live view is
+    /// active. With half-duplex enabled, the camera leaves live view before it accesses the
+    /// configuration if a preview was captured since, and the next preview enters live view
+    /// again. This makes configuration access safe for such drivers at the cost of flapping the
+    /// mirror of DSLRs, so it is disabled by default.
+    pub fn set_half_duplex(&mut self, enabled: bool) {
+        self.half_duplex = enabled;
+    }
+
+    /// Returns `true` if the camera leaves live view before each configuration access.
+    pub fn half_duplex(&self) -> bool {
+        self.half_duplex
+    }
+
+    /// Returns `true` if the camera's viewfinder is enabled, i.e., the mirror is up and live view
+    /// is active.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the viewfinder setting could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't have a `viewfinder` setting.
+    pub fn viewfinder(&mut self) -> crate::Result<bool> {
+        let config = self.single_config(VIEWFINDER)?;
+
+        match config.root().value() {
+            Some(WidgetValue::Toggle(enabled)) => Ok(enabled),
+            _ => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+
+    /// Enables or disables the camera's viewfinder.
+    ///
+    /// On Canon EOS and Nikon cameras, enabling the viewfinder raises the mirror and starts live
+    /// view, which some bodies require before previews can be captured, e.g., to get previews
+    /// at full frame rate or to drive the focus. Disabling it lowers the mirror again, so that
+    /// the camera is ready for a capture through the optical viewfinder.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut file = gphoto::FileMedia::create_mem().unwrap();
+    ///
+    /// camera.set_viewfinder(true).unwrap();
+    /// camera.capture_preview(&mut file).unwrap();
+    /// camera.set_viewfinder(false).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the viewfinder could not be changed:
+    ///
+    /// * `NotSupported` if the camera doesn't have a `viewfinder` setting.
+    pub fn set_viewfinder(&mut self, enabled: bool) -> crate::Result<()> {
+        let mut config = self.single_config(VIEWFINDER)?;
+
+        config.set_value(VIEWFINDER, WidgetValue::Toggle(enabled))?;
+        self.set_single_config(&mut config)?;
+
+        self.live_view = enabled;
+
+        Ok(())
+    }
+
+    /// Leaves live view before a configuration access if half-duplex is enabled.
+    pub(crate) fn guard_live_view(&mut self) {
+        if self.half_duplex {
+            self.leave_live_view();
+        }
+    }
+
+    /// Leaves live view if a preview was captured since it was last left.
+    ///
+    /// Leaving live view is best effort: cameras without a viewfinder setting end live view on
+    /// their own.
+    pub(crate) fn leave_live_view(&mut self) {
+        if !self.live_view {
+            return;
+        }
+
+        // Cleared first, so that the configuration access below isn't guarded again.
+        self.live_view = false;
+
+        if let Ok(mut config) = self.single_config(VIEWFINDER) {
+            if config.root().value() == Some(WidgetValue::Toggle(true))
+                && config
+                    .set_value(VIEWFINDER, WidgetValue::Toggle(false))
+                    .is_ok()
+            {
+                let _ = self.set_single_config(&mut config);
+            }
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn from_camera(camera: &mut Camera) -> crate::Result<PreviewStream<'_>> {
+    let media = FileMedia::create_mem()?;
+
+    Ok(PreviewStream {
+        camera,
+        media,
+        pool: BufferPool::default(),
+        min_interval: Duration::from_secs(0),
+        interval: Duration::from_secs(0),
+        last_capture: None,
+        last_frame: None,
+        frames: 0,
+        fps: 0.0,
+        tag_decoder: None,
+        finished: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn converts_frame_rate_limits() {
+        let cases = [
+            (30.0, Duration::from_secs_f64(1.0 / 30.0)),
+            (1.0, ms(1000)),
+            (0.2, ms(5000)),
+            (0.0, ms(0)),
+            (-1.0, ms(0)),
+            (f64::NAN, ms(0)),
+            (1e-30, MAX_FPS_INTERVAL),
+        ];
+
+        for &(fps, interval) in cases.iter() {
+            assert_eq!(fps_interval(fps), interval, "{}", fps);
+        }
+    }
+
+    #[test]
+    fn backs_off_while_busy() {
+        let cases = [
+            (ms(0), ms(0), ms(50)),
+            (ms(100), ms(0), ms(200)),
+            (ms(800), ms(0), ms(1000)),
+            (ms(1000), ms(0), ms(1000)),
+            (ms(10), ms(200), ms(200)),
+            (ms(5000), ms(5000), ms(5000)),
+            (ms(1000), ms(5000), ms(5000)),
+        ];
+
+        for &(interval, min_interval, expected) in cases.iter() {
+            assert_eq!(busy_interval(interval, min_interval), expected);
+        }
+    }
+
+    #[test]
+    fn adapts_to_capture_times() {
+        let cases = [
+            // Slow captures raise the interval at once.
+            (ms(0), ms(100), ms(0), ms(100)),
+            (ms(100), ms(3000), ms(0), ms(1000)),
+            (ms(1000), ms(3000), ms(5000), ms(5000)),
+            // Fast captures lower it gradually, but not below the limit.
+            (ms(100), ms(0), ms(0), ms(80)),
+            (ms(5000), ms(10), ms(5000), ms(5000)),
+            (ms(1000), ms(0), ms(500), ms(900)),
+        ];
+
+        for &(interval, capture_time, min_interval, expected) in cases.iter() {
+            let adapted = adapted_interval(interval, capture_time, min_interval);
+            assert_eq!(adapted.as_millis(), expected.as_millis());
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use cstr_core::CString;
+use std::path::Path;
+
+use crate::abilities::{CameraOperation, DriverStatus};
+use crate::detect::{DetectedCamera, PortInfoList};
+
+/// The result of checking a detected camera before opening it.
+///
+/// Reports are created with `DetectedCamera::probe()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeReport {
+    model: String,
+    port: String,
+    driver_status: DriverStatus,
+    issues: Vec<ProbeIssue>,
+}
+
+/// A reason why a detected camera may not be usable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeIssue {
+    /// The camera's driver is experimental or deprecated.
+    UnstableDriver(DriverStatus),
+
+    /// The camera's driver doesn't support a required operation.
+    MissingOperation(CameraOperation),
+
+    /// The camera's port is no longer known to `libgphoto2`, e.g., because the camera was
+    /// unplugged.
+    PortUnavailable,
+
+    /// The camera's USB device can't be opened for reading and writing, e.g., because of its
+    /// permissions.
+    PortInaccessible,
+}
+
+impl ProbeReport {
+    /// The name of the camera's model.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// The path of the camera's port, e.g., `"usb:001,004"`.
+    pub fn port(&self) -> &str {
+        &self.port
+    }
+
+    /// The stability of the camera's driver.
+    pub fn driver_status(&self) -> DriverStatus {
+        self.driver_status
+    }
+
+    /// The problems that were found, in the order they were checked.
+    pub fn issues(&self) -> &[ProbeIssue] {
+        &self.issues
+    }
+
+    /// Returns `true` if no problems were found.
+    pub fn is_usable(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl DetectedCamera {
+    /// Checks whether the camera is likely to open and support the required operations, without
+    /// initializing it.
+    ///
+    /// Initializing a camera that can't be used, e.g., because its USB device isn't accessible,
+    /// can take seconds before it fails. Probing only checks the driver's abilities and the port,
+    /// so managers of many cameras can skip unusable devices quickly.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::CameraOperation;
+    ///
+    /// let required = [CameraOperation::TriggerCapture, CameraOperation::Config];
+    ///
+    /// for detected in gphoto::detect_cameras().unwrap() {
+    ///     let report = detected.probe(&required).unwrap();
+    ///
+    ///     if report.is_usable() {
+    ///         let camera = detected.open().unwrap();
+    ///     } else {
+    ///         println!("skipping {} on {}: {:?}", report.model(), report.port(), report.issues());
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the ports could not be listed.
+    pub fn probe(&self, required: &[CameraOperation]) -> crate::Result<ProbeReport> {
+        let abilities = self.abilities();
+        let driver_status = abilities.driver_status();
+        let mut issues = Vec::new();
+
+        match driver_status {
+            DriverStatus::Experimental | DriverStatus::Deprecated => {
+                issues.push(ProbeIssue::UnstableDriver(driver_status))
+            }
+            DriverStatus::Production | DriverStatus::Testing => (),
+        }
+
+        let operations = abilities.camera_operations();
+
+        for operation in required {
+            if !operations.contains(operation) {
+                issues.push(ProbeIssue::MissingOperation(*operation));
+            }
+        }
+
+        if PortInfoList::load()?.lookup(self.port()).is_err() {
+            issues.push(ProbeIssue::PortUnavailable);
+        } else if !usb_accessible(self.port()) {
+            issues.push(ProbeIssue::PortInaccessible);
+        }
+
+        Ok(ProbeReport {
+            model: self.model().to_owned(),
+            port: self.port().to_owned(),
+            driver_status,
+            issues,
+        })
+    }
+}
+
+/// Returns `false` if a port is a USB port whose device node can't be opened for reading and
+/// writing.
+fn usb_accessible(port: &str) -> bool {
+    let (bus, device) = match port
+        .strip_prefix("usb:")
+        .and_then(|address| address.split_once(','))
+    {
+        Some(address) => address,
+        None => return true,
+    };
+
+    let node = format!("/dev/bus/usb/{}/{}", bus, device);
+
+    // Without a device node, e.g., on other platforms, accessibility can't be checked.
+    if !Path::new(&node).exists() {
+        return true;
+    }
+
+    let node = match CString::new(node) {
+        Ok(node) => node,
+        Err(_) => return true,
+    };
+
+    unsafe { libc::access(node.as_ptr(), libc::R_OK | libc::W_OK) == 0 }
+}
@@ -0,0 +1,393 @@
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::camera::Camera;
+use crate::numbering::FileNumbering;
+use crate::widget::{Config, WidgetValue};
+
+/// Names of the settings that contain the owner's name in different drivers.
+const OWNER_SETTINGS: &[&str] = &["ownername", "artist"];
+
+/// Names of the settings that set the camera's clock to the host's time when toggled.
+const SYNC_CLOCK_SETTINGS: &[&str] = &["syncdatetimeutc", "syncdatetime"];
+
+/// Names of the settings that contain the camera's clock in different drivers.
+const CLOCK_SETTINGS: &[&str] = &["datetimeutc", "datetime"];
+
+/// A standard configuration for camera bodies.
+///
+/// A profile describes the state that every camera in a fleet should be in. It is applied to a
+/// camera with a `Provisioner`.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{Profile, WidgetValue};
+///
+/// let profile = Profile::new()
+///     .setting("imageformat", WidgetValue::Choice("RAW".to_owned()))
+///     .setting("iso", WidgetValue::Choice("400".to_owned()))
+///     .owner("Example Photo Booths")
+///     .sync_clock();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    settings: Vec<(String, WidgetValue)>,
+    custom_functions: BTreeMap<String, WidgetValue>,
+    file_numbering: Option<FileNumbering>,
+    owner: Option<String>,
+    sync_clock: bool,
+}
+
+impl Profile {
+    /// Creates an empty profile.
+    pub fn new() -> Self {
+        Profile::default()
+    }
+
+    /// Adds a setting to the profile.
+    pub fn setting(mut self, name: &str, value: WidgetValue) -> Self {
+        self.settings.push((name.to_owned(), value));
+        self
+    }
+
+    /// Adds a custom function to the profile.
+    ///
+    /// Names and values are the ones returned by `Camera::custom_functions()`.
+    pub fn custom_function(mut self, name: &str, value: WidgetValue) -> Self {
+        self.custom_functions.insert(name.to_owned(), value);
+        self
+    }
+
+    /// Adds all custom functions of a map to the profile.
+    ///
+    /// This copies the custom functions of a reference body, which were read with
+    /// `Camera::custom_functions()`.
+    pub fn custom_functions(mut self, functions: &BTreeMap<String, WidgetValue>) -> Self {
+        self.custom_functions.extend(
+            functions
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        self
+    }
+
+    /// Sets the file numbering mode.
+    pub fn file_numbering(mut self, numbering: FileNumbering) -> Self {
+        self.file_numbering = Some(numbering);
+        self
+    }
+
+    /// Sets the owner's name that the camera writes into each image.
+    pub fn owner(mut self, owner: &str) -> Self {
+        self.owner = Some(owner.to_owned());
+        self
+    }
+
+    /// Sets the camera's clock to the host's time.
+    pub fn sync_clock(mut self) -> Self {
+        self.sync_clock = true;
+        self
+    }
+}
+
+/// Applies profiles to cameras.
+///
+/// The provisioner reads the camera's configuration, compares it to the profile and changes the
+/// settings that differ. In dry-run mode, it only reports what it would change.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{Profile, Provisioner, WidgetValue};
+///
+/// let profile = Profile::new()
+///     .setting("iso", WidgetValue::Choice("400".to_owned()))
+///     .sync_clock();
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let report = Provisioner::new().dry_run(true).apply(&profile, &mut camera).unwrap();
+///
+/// for item in report.items() {
+///     println!("{}: {:?}", item.name(), item.outcome());
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Provisioner {
+    dry_run: bool,
+}
+
+impl Provisioner {
+    /// Creates a provisioner that changes cameras.
+    pub fn new() -> Self {
+        Provisioner::default()
+    }
+
+    /// Enables or disables dry-run mode.
+    ///
+    /// In dry-run mode, the camera is only read. Items that would be changed are reported as
+    /// `ProvisionOutcome::Changed`.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Applies a profile to a camera.
+    ///
+    /// The settings, owner and clock are changed with a single configuration update and the
+    /// custom functions with a second one. Items that can't be applied, e.g., because the camera
+    /// doesn't have a setting, are reported as failed without affecting the other items.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera's configuration could not be read or
+    /// updated:
+    ///
+    /// * `NotSupported` if the camera can not be configured.
+    pub fn apply(&self, profile: &Profile, camera: &mut Camera) -> crate::Result<ProvisionReport> {
+        let mut config = camera.config()?;
+        let mut items = Vec::new();
+
+        for (name, value) in &profile.settings {
+            items.push(self.change(&mut config, name, value.clone()));
+        }
+
+        if let Some(ref owner) = profile.owner {
+            match first_setting(&config, OWNER_SETTINGS) {
+                Some(name) => {
+                    items.push(self.change(&mut config, &name, WidgetValue::Text(owner.clone())))
+                }
+                None => items.push(ProvisionItem::not_supported("owner")),
+            }
+        }
+
+        if profile.sync_clock {
+            items.push(self.sync_clock(&mut config));
+        }
+
+        let changed = items
+            .iter()
+            .any(|item| matches!(item.outcome, ProvisionOutcome::Changed { .. }));
+
+        if changed && !self.dry_run {
+            camera.set_config(&mut config)?;
+        }
+
+        if let Some(numbering) = profile.file_numbering {
+            items.push(self.file_numbering(camera, numbering));
+        }
+
+        if !profile.custom_functions.is_empty() {
+            items.extend(self.custom_functions(camera, &profile.custom_functions)?);
+        }
+
+        Ok(ProvisionReport {
+            items,
+            dry_run: self.dry_run,
+        })
+    }
+
+    fn change(&self, config: &mut Config, name: &str, value: WidgetValue) -> ProvisionItem {
+        let old = config.value(name);
+
+        let outcome = if old.as_ref() == Some(&value) {
+            ProvisionOutcome::Unchanged
+        } else if self.dry_run && config.get(name).is_some() {
+            ProvisionOutcome::Changed { old, new: value }
+        } else {
+            match config.set_value(name, value.clone()) {
+                Ok(()) => ProvisionOutcome::Changed { old, new: value },
+                Err(err) => ProvisionOutcome::Failed(err),
+            }
+        };
+
+        ProvisionItem {
+            name: name.to_owned(),
+            outcome,
+        }
+    }
+
+    fn sync_clock(&self, config: &mut Config) -> ProvisionItem {
+        if let Some(name) = first_setting(config, SYNC_CLOCK_SETTINGS) {
+            // Toggling the setting is an action, so it changes the clock whatever its value is.
+            let new = WidgetValue::Toggle(true);
+
+            let outcome = if self.dry_run {
+                ProvisionOutcome::Changed { old: None, new }
+            } else {
+                match config.set_value(&name, new.clone()) {
+                    Ok(()) => ProvisionOutcome::Changed { old: None, new },
+                    Err(err) => ProvisionOutcome::Failed(err),
+                }
+            };
+
+            return ProvisionItem { name, outcome };
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs() as i64)
+            .unwrap_or(0);
+
+        match first_setting(config, CLOCK_SETTINGS) {
+            Some(name) => self.change(config, &name, WidgetValue::Date(now)),
+            None => ProvisionItem::not_supported("clock"),
+        }
+    }
+
+    fn file_numbering(&self, camera: &mut Camera, numbering: FileNumbering) -> ProvisionItem {
+        let outcome = match camera.file_numbering() {
+            Ok(old) if old == numbering => ProvisionOutcome::Unchanged,
+            Ok(_) if self.dry_run => ProvisionOutcome::Changed {
+                old: None,
+                new: WidgetValue::Text(format!("{:?}", numbering)),
+            },
+            Ok(_) => match camera.set_file_numbering(numbering) {
+                Ok(()) => ProvisionOutcome::Changed {
+                    old: None,
+                    new: WidgetValue::Text(format!("{:?}", numbering)),
+                },
+                Err(err) => ProvisionOutcome::Failed(err),
+            },
+            Err(err) => ProvisionOutcome::Failed(err),
+        };
+
+        ProvisionItem {
+            name: "file numbering".to_owned(),
+            outcome,
+        }
+    }
+
+    fn custom_functions(
+        &self,
+        camera: &mut Camera,
+        functions: &BTreeMap<String, WidgetValue>,
+    ) -> crate::Result<Vec<ProvisionItem>> {
+        let current = camera.custom_functions()?;
+        let mut items = Vec::new();
+        let mut changes = BTreeMap::new();
+
+        for (name, value) in functions {
+            let old = current.get(name).cloned();
+
+            let outcome = match old {
+                Some(ref old) if old == value => ProvisionOutcome::Unchanged,
+                Some(_) => {
+                    changes.insert(name.clone(), value.clone());
+
+                    ProvisionOutcome::Changed {
+                        old,
+                        new: value.clone(),
+                    }
+                }
+                None => ProvisionOutcome::Failed(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                )),
+            };
+
+            items.push(ProvisionItem {
+                name: name.clone(),
+                outcome,
+            });
+        }
+
+        if !changes.is_empty() && !self.dry_run {
+            camera.set_custom_functions(&changes)?;
+        }
+
+        Ok(items)
+    }
+}
+
+/// The result of applying a profile to a camera.
+#[derive(Debug)]
+pub struct ProvisionReport {
+    items: Vec<ProvisionItem>,
+    dry_run: bool,
+}
+
+impl ProvisionReport {
+    /// Returns the results for each item of the profile.
+    pub fn items(&self) -> &[ProvisionItem] {
+        &self.items
+    }
+
+    /// Returns `true` if the report is from a dry run, so that nothing was changed.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Returns `true` if no item failed.
+    pub fn is_success(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    /// Returns the items that were changed, or would be changed in a dry run.
+    pub fn changed(&self) -> impl Iterator<Item = &ProvisionItem> {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.outcome, ProvisionOutcome::Changed { .. }))
+    }
+
+    /// Returns the items that could not be applied.
+    pub fn failed(&self) -> impl Iterator<Item = &ProvisionItem> {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.outcome, ProvisionOutcome::Failed(_)))
+    }
+}
+
+/// The result of applying one item of a profile.
+#[derive(Debug)]
+pub struct ProvisionItem {
+    name: String,
+    outcome: ProvisionOutcome,
+}
+
+impl ProvisionItem {
+    fn not_supported(name: &str) -> Self {
+        ProvisionItem {
+            name: name.to_owned(),
+            outcome: ProvisionOutcome::Failed(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+
+    /// The name of the setting or custom function that the item changes.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// What happened to the item.
+    pub fn outcome(&self) -> &ProvisionOutcome {
+        &self.outcome
+    }
+}
+
+/// Outcomes of applying an item of a profile.
+#[derive(Debug)]
+pub enum ProvisionOutcome {
+    /// The camera already had the profile's value.
+    Unchanged,
+
+    /// The value was changed, or would be changed in a dry run.
+    Changed {
+        /// The previous value, or `None` if it is not known.
+        old: Option<WidgetValue>,
+
+        /// The profile's value.
+        new: WidgetValue,
+    },
+
+    /// The item could not be applied.
+    Failed(crate::Error),
+}
+
+/// Returns the name of the first of the named settings that the configuration contains.
+fn first_setting(config: &Config, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find(|name| config.get(name).is_some())
+        .map(|name| (*name).to_owned())
+}
@@ -0,0 +1,57 @@
+use crate::camera::Camera;
+use crate::widget::WidgetValue;
+
+/// The maximum number of parameters of a PTP operation.
+const MAX_PARAMS: usize = 5;
+
+impl Camera {
+    /// Runs a PTP operation on the camera.
+    ///
+    /// This invokes operations that the driver doesn't wrap, e.g., vendor extensions, in the
+    /// driver's PTP session. It is only available for cameras that use the `ptp2` driver, which
+    /// exposes the operation through its `opcode` setting. The driver only supports operations
+    /// without a data phase; use `port_io()` for operations that send or receive data.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// // Canon EOS SetRemoteMode(1)
+    /// camera.ptp_transaction(0x9114, &[1]).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the operation failed:
+    ///
+    /// * `NotSupported` if the camera doesn't use the `ptp2` driver.
+    /// * `InvalidInput` if more than five parameters are given.
+    /// * `CameraError` if the camera responded with an error code.
+    pub fn ptp_transaction(&mut self, code: u16, params: &[u32]) -> crate::Result<()> {
+        if params.len() > MAX_PARAMS {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            ));
+        }
+
+        let mut command = format!("0x{:04x}", code);
+
+        for param in params {
+            command.push_str(&format!(",0x{:x}", param));
+        }
+
+        let mut config = match self.single_config("opcode") {
+            Ok(config) => config,
+            Err(ref err) if err.kind() == crate::ErrorKind::InvalidInput => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                ))
+            }
+            Err(err) => return Err(err),
+        };
+
+        config.set_value("opcode", WidgetValue::Text(command))?;
+        self.set_single_config(&mut config)
+    }
+}
@@ -0,0 +1,136 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Limits how often an operation runs.
+///
+/// The camera connection handles one operation at a time, so code that polls the camera too
+/// often delays captures and downloads. A `RateLimiter` enforces a minimum interval between
+/// polls. `Camera::health()` uses one internally.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut limiter = gphoto::RateLimiter::new(Duration::from_secs(2));
+///
+/// loop {
+///     // called by the UI every 100 ms
+///     if limiter.ready() {
+///         println!("{:?}", camera.setting("batterylevel"));
+///     }
+/// #   break;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter that allows one operation per interval.
+    pub fn new(interval: Duration) -> Self {
+        RateLimiter {
+            interval,
+            last: None,
+        }
+    }
+
+    /// Returns the minimum interval between operations.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Sets the minimum interval between operations.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Returns `true` and starts a new interval if the current interval has elapsed.
+    pub fn ready(&mut self) -> bool {
+        self.ready_at(Instant::now())
+    }
+
+    /// Returns `true` and starts a new interval at `now` if the current interval has elapsed.
+    fn ready_at(&mut self, now: Instant) -> bool {
+        match self.last {
+            Some(last) if now.duration_since(last) < self.interval => false,
+            _ => {
+                self.last = Some(now);
+                true
+            }
+        }
+    }
+
+    /// Blocks until the current interval has elapsed and starts a new one.
+    pub fn wait(&mut self) {
+        if let Some(last) = self.last {
+            let elapsed = last.elapsed();
+
+            if elapsed < self.interval {
+                thread::sleep(self.interval - elapsed);
+            }
+        }
+
+        self.last = Some(Instant::now());
+    }
+
+    /// Allows the next operation to run immediately.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_one_operation_per_interval() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(Duration::from_millis(100));
+
+        let cases = [
+            (0, true),
+            (0, false),
+            (99, false),
+            (100, true),
+            (150, false),
+            (250, true),
+            (1000, true),
+        ];
+
+        for &(millis, ready) in cases.iter() {
+            let now = start + Duration::from_millis(millis);
+            assert_eq!(limiter.ready_at(now), ready, "{} ms", millis);
+        }
+    }
+
+    #[test]
+    fn resets_the_interval() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(Duration::from_secs(1));
+
+        assert!(limiter.ready_at(start));
+        assert!(!limiter.ready_at(start));
+
+        limiter.reset();
+        assert!(limiter.ready_at(start));
+
+        limiter.set_interval(Duration::from_secs(0));
+        assert!(limiter.ready_at(start));
+    }
+
+    #[test]
+    fn waits_for_the_interval() {
+        let interval = Duration::from_millis(20);
+        let mut limiter = RateLimiter::new(interval);
+
+        let start = Instant::now();
+        limiter.wait();
+        limiter.wait();
+        assert!(start.elapsed() >= interval);
+    }
+}
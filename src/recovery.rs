@@ -0,0 +1,139 @@
+use crate::camera::Camera;
+use crate::lifecycle::CameraState;
+
+use crate::handle::prelude::*;
+
+/// What to do after an operation failed.
+///
+/// Returned by the `on_error` callback of `Camera::run_with_recovery()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Recovery {
+    /// Runs the operation again.
+    Retry,
+
+    /// Reinitializes the connection to the camera and runs the operation again.
+    Reinit,
+
+    /// Gives up on the operation, but not on the caller's loop.
+    Skip,
+
+    /// Gives up and returns the error.
+    Abort,
+}
+
+impl Recovery {
+    /// Returns the default recovery from an error.
+    ///
+    /// Disconnects are recovered by reinitializing the camera, other retryable errors by retrying,
+    /// and all other errors abort. See `Error::is_retryable()` and `Error::is_disconnect()`.
+    pub fn for_error(err: &crate::Error) -> Recovery {
+        if err.is_disconnect() {
+            Recovery::Reinit
+        } else if err.is_retryable() {
+            Recovery::Retry
+        } else {
+            Recovery::Abort
+        }
+    }
+}
+
+impl Camera {
+    /// Closes and reopens the connection to the camera.
+    ///
+    /// This recovers from many transient errors, e.g., a PTP session that got out of sync after
+    /// an interrupted transfer.
+    ///
+    /// The new connection starts without live view, the cached configuration is discarded, and
+    /// the capture options are applied again by the next capture.
+    pub fn reinit(&mut self) -> crate::Result<()> {
+        self.set_state(CameraState::Reinitializing);
+
+        unsafe {
+            crate::gphoto2::gp_camera_exit(self.camera, self.context.as_mut_ptr());
+        }
+
+        // The camera may have been reset, or changed by hand, while the connection was down.
+        self.config_cache.invalidate_all();
+        self.live_view = false;
+        self.capture_options_applied = false;
+
+        match unsafe { crate::gphoto2::gp_camera_init(self.camera, self.context.as_mut_ptr()) } {
+            crate::gphoto2::GP_OK => (),
+            err => {
+                let err = crate::error::from_libgphoto2(err);
+                self.lifecycle
+                    .transition(CameraState::Error, Some(err.kind()));
+
+                return Err(err);
+            }
+        }
+
+        self.set_state(CameraState::Initialized);
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_reconnect();
+
+        Ok(())
+    }
+
+    /// Runs an operation and lets a callback decide how to recover from failures.
+    ///
+    /// `on_error` is called with each error and the number of the failed attempt, starting at 1.
+    /// Returns the operation's result, `None` if the callback chose to skip it, or the error if
+    /// the callback chose to abort. Errors from reinitializing the camera are passed to the
+    /// callback like errors from the operation.
+    ///
+    /// This allows loops like time-lapses to encode domain-specific recovery, e.g., skipping a
+    /// frame when autofocus fails at night, but aborting when the card is full.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::{ErrorKind, Recovery};
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// let file = camera.run_with_recovery(
+    ///     |camera| camera.capture_image(),
+    ///     |err, attempt| match err.kind() {
+    ///         ErrorKind::NoSpace => Recovery::Abort,
+    ///         ErrorKind::CameraBusy if attempt < 5 => Recovery::Retry,
+    ///         ErrorKind::Timeout | ErrorKind::OSFailure if attempt < 3 => Recovery::Reinit,
+    ///         _ if attempt < 3 => Recovery::for_error(err),
+    ///         _ => Recovery::Skip,
+    ///     },
+    /// ).unwrap();
+    /// ```
+    pub fn run_with_recovery<T, F, E>(
+        &mut self,
+        mut operation: F,
+        mut on_error: E,
+    ) -> crate::Result<Option<T>>
+    where
+        F: FnMut(&mut Camera) -> crate::Result<T>,
+        E: FnMut(&crate::Error, u32) -> Recovery,
+    {
+        let mut attempt = 0;
+        let mut reinit = false;
+
+        loop {
+            attempt += 1;
+
+            let result = if reinit {
+                self.reinit().and_then(|()| operation(self))
+            } else {
+                operation(self)
+            };
+
+            match result {
+                Ok(value) => return Ok(Some(value)),
+                Err(err) => match on_error(&err, attempt) {
+                    Recovery::Retry => reinit = false,
+                    Recovery::Reinit => reinit = true,
+                    Recovery::Skip => return Ok(None),
+                    Recovery::Abort => return Err(err),
+                },
+            }
+        }
+    }
+}
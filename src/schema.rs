@@ -0,0 +1,432 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::camera::Camera;
+use crate::widget::{leaf_name, Config, WidgetType, WidgetValue};
+
+/// The extension of schema files in the schema cache.
+const EXTENSION: &str = "schema";
+
+/// The immutable parts of a camera's configuration: the widgets' paths, types, labels and
+/// choices, but not their values.
+///
+/// Fetching the whole configuration tree is slow on many drivers, but its shape only depends on
+/// the camera model. Schemas are read with `Camera::config_schema()`, which keeps them on disk
+/// when a schema cache is enabled with `Camera::set_schema_cache()`, so that a UI can be built
+/// right after connecting. The current values are then read with `Camera::schema_values()`.
+///
+/// Only widgets with a value and buttons are part of the schema; windows and sections are only
+/// represented by the paths of their widgets.
+///
+/// A schema is formatted by `Display` like the output of `gphoto2 --list-all-config`, without the
+/// current values. This is also the format of the files in the schema cache.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSchema {
+    widgets: Vec<WidgetSchema>,
+}
+
+/// The immutable parts of a configuration widget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetSchema {
+    path: String,
+    label: String,
+    widget_type: WidgetType,
+    readonly: bool,
+    choices: Vec<String>,
+    range: Option<(f32, f32, f32)>,
+}
+
+impl ConfigSchema {
+    /// Extracts the schema of a configuration.
+    pub fn from_config(config: &Config) -> Self {
+        let widgets = config
+            .dump()
+            .widgets()
+            .into_iter()
+            .filter(|widget| widget.value().is_some() || widget.widget_type() == WidgetType::Button)
+            .map(|widget| WidgetSchema {
+                path: widget.path().to_owned(),
+                label: widget.label().to_owned(),
+                widget_type: widget.widget_type(),
+                readonly: widget.is_readonly(),
+                choices: widget.choices().to_vec(),
+                range: widget.range(),
+            })
+            .collect();
+
+        ConfigSchema { widgets }
+    }
+
+    /// Returns the widgets of the schema, in the order of the configuration tree.
+    pub fn widgets(&self) -> &[WidgetSchema] {
+        &self.widgets
+    }
+
+    /// Looks up a widget by name or path.
+    pub fn get(&self, name: &str) -> Option<&WidgetSchema> {
+        if name.contains('/') {
+            self.widgets.iter().find(|widget| widget.path == name)
+        } else {
+            self.widgets.iter().find(|widget| widget.name() == name)
+        }
+    }
+
+    /// Returns `true` if exactly one widget has a name.
+    fn is_unique(&self, name: &str) -> bool {
+        self.widgets
+            .iter()
+            .filter(|widget| widget.name() == name)
+            .count()
+            == 1
+    }
+
+    /// Parses a schema in the format of `Display`.
+    fn parse(text: &str) -> Option<Self> {
+        let mut widgets = Vec::new();
+        let mut current: Option<WidgetSchema> = None;
+        let mut range = (None, None, None);
+
+        for line in text.lines() {
+            if line.starts_with('/') {
+                current = Some(WidgetSchema {
+                    path: line.to_owned(),
+                    label: String::new(),
+                    widget_type: WidgetType::Text,
+                    readonly: false,
+                    choices: Vec::new(),
+                    range: None,
+                });
+                range = (None, None, None);
+                continue;
+            }
+
+            let widget = current.as_mut()?;
+
+            if line == "END" {
+                if let (Some(min), Some(max), Some(increment)) = range {
+                    widget.range = Some((min, max, increment));
+                }
+
+                widgets.push(current.take()?);
+                continue;
+            }
+
+            let (key, value) = line.split_once(": ")?;
+
+            match key {
+                "Label" => widget.label = value.to_owned(),
+                "Readonly" => widget.readonly = value == "1",
+                "Type" => widget.widget_type = parse_type(value)?,
+                "Choice" => {
+                    let choice = value.split_once(' ').map_or("", |(_, choice)| choice);
+                    widget.choices.push(choice.to_owned());
+                }
+                "Bottom" => range.0 = value.parse().ok(),
+                "Top" => range.1 = value.parse().ok(),
+                "Step" => range.2 = value.parse().ok(),
+                _ => (),
+            }
+        }
+
+        if current.is_some() {
+            return None;
+        }
+
+        Some(ConfigSchema { widgets })
+    }
+}
+
+impl fmt::Display for ConfigSchema {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for widget in &self.widgets {
+            writeln!(fmt, "{}", widget.path)?;
+            writeln!(fmt, "Label: {}", widget.label)?;
+            writeln!(fmt, "Readonly: {}", widget.readonly as u8)?;
+            writeln!(fmt, "Type: {}", crate::dump::type_name(widget.widget_type))?;
+
+            if let Some((min, max, increment)) = widget.range {
+                writeln!(fmt, "Bottom: {}", min)?;
+                writeln!(fmt, "Top: {}", max)?;
+                writeln!(fmt, "Step: {}", increment)?;
+            }
+
+            for (index, choice) in widget.choices.iter().enumerate() {
+                writeln!(fmt, "Choice: {} {}", index, choice)?;
+            }
+
+            writeln!(fmt, "END")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl WidgetSchema {
+    /// The widget's path, e.g., `"/main/imgsettings/iso"`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The widget's name, e.g., `"iso"`.
+    pub fn name(&self) -> &str {
+        leaf_name(&self.path)
+    }
+
+    /// The widget's human-readable label, e.g., `"ISO Speed"`.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The type of the widget.
+    pub fn widget_type(&self) -> WidgetType {
+        self.widget_type
+    }
+
+    /// Returns `true` if the widget's value can not be changed.
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// The choices of a radio or menu widget.
+    pub fn choices(&self) -> &[String] {
+        &self.choices
+    }
+
+    /// The minimum, maximum, and increment of a range widget.
+    pub fn range(&self) -> Option<(f32, f32, f32)> {
+        self.range
+    }
+}
+
+impl Camera {
+    /// Enables or disables the on-disk cache of configuration schemas.
+    ///
+    /// With a cache directory, `config_schema()` stores one schema file per camera model in the
+    /// directory, and reads it instead of fetching the configuration tree the next time a camera
+    /// of that model is connected. The cache is disabled by default.
+    ///
+    /// The cached schema of a model must be deleted if its firmware changes the configuration
+    /// tree, e.g., after a firmware update.
+    pub fn set_schema_cache(&mut self, dir: Option<&Path>) {
+        self.schema_cache = dir.map(Path::to_path_buf);
+    }
+
+    /// Returns the directory of the schema cache, if it is enabled.
+    pub fn schema_cache(&self) -> Option<&Path> {
+        self.schema_cache.as_deref()
+    }
+
+    /// Returns the schema of the camera's configuration.
+    ///
+    /// The schema is read from the schema cache if it contains the camera's model. Otherwise, the
+    /// configuration is fetched from the camera, and its schema is stored in the cache. Failing to
+    /// write to the cache doesn't cause an error.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// camera.set_schema_cache(Some("/var/cache/camera-schemas".as_ref()));
+    ///
+    /// let schema = camera.config_schema().unwrap();
+    ///
+    /// for (path, value) in camera.schema_values(&schema).unwrap() {
+    ///     let widget = schema.get(&path).unwrap();
+    ///     println!("{} = {:?} (choices: {:?})", widget.label(), value, widget.choices());
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the schema isn't cached and the configuration could not
+    /// be read:
+    ///
+    /// * `NotSupported` if the camera can not be configured.
+    pub fn config_schema(&mut self) -> crate::Result<ConfigSchema> {
+        let path = self.schema_path();
+
+        if let Some(ref path) = path {
+            if let Some(schema) = fs::read_to_string(path)
+                .ok()
+                .and_then(|text| ConfigSchema::parse(&text))
+            {
+                return Ok(schema);
+            }
+        }
+
+        let schema = ConfigSchema::from_config(&self.config()?);
+
+        if let Some(ref path) = path {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+
+            let _ = fs::write(path, schema.to_string());
+        }
+
+        Ok(schema)
+    }
+
+    /// Reads the current values of the widgets of a schema.
+    ///
+    /// The values are read widget by widget if the driver supports it, which avoids rebuilding
+    /// the whole configuration tree, and from the whole configuration otherwise. Widgets whose
+    /// name isn't unique are read from the whole configuration by their path. Returns the
+    /// values by path; widgets that the camera doesn't report, e.g., because they depend on the
+    /// camera's mode, are left out.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the values could not be read:
+    ///
+    /// * `NotSupported` if the camera can not be configured.
+    pub fn schema_values(
+        &mut self,
+        schema: &ConfigSchema,
+    ) -> crate::Result<Vec<(String, WidgetValue)>> {
+        let mut values = Vec::new();
+        let mut config: Option<Config> = None;
+
+        for widget in &schema.widgets {
+            if widget.widget_type == WidgetType::Button {
+                continue;
+            }
+
+            // Single widgets are looked up by name, which only identifies widgets with a unique
+            // name.
+            if config.is_none() && schema.is_unique(widget.name()) {
+                match self.single_config(widget.name()) {
+                    Ok(single) => {
+                        if let Some(value) = single.root().value() {
+                            values.push((widget.path.clone(), value));
+                        }
+
+                        continue;
+                    }
+                    Err(ref err) if err.kind() != crate::ErrorKind::NotSupported => continue,
+                    Err(_) => (),
+                }
+            }
+
+            if config.is_none() {
+                config = Some(self.config()?);
+            }
+
+            if let Some(value) = config
+                .as_ref()
+                .and_then(|config| config.value(&widget.path))
+            {
+                values.push((widget.path.clone(), value));
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Returns the path of the cached schema of the camera's model.
+    fn schema_path(&self) -> Option<PathBuf> {
+        let dir = self.schema_cache.as_ref()?;
+
+        let model: String = self
+            .abilities()
+            .model()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        Some(dir.join(format!("{}.{}", model, EXTENSION)))
+    }
+}
+
+fn parse_type(name: &str) -> Option<WidgetType> {
+    Some(match name {
+        "WINDOW" => WidgetType::Window,
+        "SECTION" => WidgetType::Section,
+        "TEXT" => WidgetType::Text,
+        "RANGE" => WidgetType::Range,
+        "TOGGLE" => WidgetType::Toggle,
+        "RADIO" => WidgetType::Radio,
+        "MENU" => WidgetType::Menu,
+        "BUTTON" => WidgetType::Button,
+        "DATE" => WidgetType::Date,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn widget(path: &str, widget_type: WidgetType) -> WidgetSchema {
+        WidgetSchema {
+            path: path.to_owned(),
+            label: String::new(),
+            widget_type,
+            readonly: false,
+            choices: Vec::new(),
+            range: None,
+        }
+    }
+
+    #[test]
+    fn round_trips_display() {
+        let schema = ConfigSchema {
+            widgets: vec![
+                WidgetSchema {
+                    label: "ISO Speed".to_owned(),
+                    choices: vec!["Auto".to_owned(), "100".to_owned(), "".to_owned()],
+                    ..widget("/main/imgsettings/iso", WidgetType::Radio)
+                },
+                WidgetSchema {
+                    label: "Exposure Compensation".to_owned(),
+                    range: Some((-3.0, 3.0, 0.333)),
+                    ..widget(
+                        "/main/capturesettings/exposurecompensation",
+                        WidgetType::Range,
+                    )
+                },
+                WidgetSchema {
+                    label: "Serial Number".to_owned(),
+                    readonly: true,
+                    ..widget("/main/status/serialnumber", WidgetType::Text)
+                },
+                widget("/main/actions/autofocusdrive", WidgetType::Toggle),
+                widget("/main/actions/uilock", WidgetType::Button),
+                widget("/main/settings/datetime", WidgetType::Date),
+                widget("/main/other/d002", WidgetType::Menu),
+            ],
+        };
+
+        assert_eq!(
+            Some(schema.clone()),
+            ConfigSchema::parse(&schema.to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_schemas() {
+        let schema = ConfigSchema {
+            widgets: vec![widget("/main/imgsettings/iso", WidgetType::Radio)],
+        };
+        let text = schema.to_string();
+
+        assert_eq!(None, ConfigSchema::parse(text.trim_end_matches("END\n")));
+        assert_eq!(None, ConfigSchema::parse("Label: ISO Speed\n"));
+    }
+
+    #[test]
+    fn finds_unique_names() {
+        let schema = ConfigSchema {
+            widgets: vec![
+                widget("/main/imgsettings/iso", WidgetType::Radio),
+                widget("/main/capturesettings/focusmode", WidgetType::Radio),
+                widget("/main/other/focusmode", WidgetType::Radio),
+            ],
+        };
+
+        assert!(schema.is_unique("iso"));
+        assert!(!schema.is_unique("focusmode"));
+        assert!(!schema.is_unique("shutterspeed"));
+    }
+}
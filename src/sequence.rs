@@ -0,0 +1,315 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::camera::{Camera, CameraFile};
+use crate::widget::WidgetValue;
+
+/// A scripted shoot, built from settings changes, captures and waits.
+///
+/// Sequences are built step by step; `repeat()` repeats all steps that were added before it, so
+/// loops are nested by repeating a sequence that already contains a repeat. `then()` appends
+/// another sequence. A sequence is compiled to a `Plan`, which knows its total number of steps
+/// and runs them against a camera while reporting progress.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use gphoto::{Sequence, SequenceEvent};
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+///
+/// let plan = Sequence::new()
+///     .set("iso", "100")
+///     .capture()
+///     .wait(Duration::from_secs(5))
+///     .repeat(10)
+///     .compile();
+///
+/// let files = plan
+///     .run(&mut camera, |event| {
+///         if let SequenceEvent::Started { step, total, .. } = event {
+///             println!("step {}/{}", step + 1, total);
+///         }
+///     })
+///     .unwrap();
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Sequence {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Step {
+    Action(Action),
+    Repeat(usize, Vec<Step>),
+}
+
+/// A single step of a compiled `Plan`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Action {
+    /// Changes a setting to a value that is converted to the setting's type, e.g., `"100"`.
+    Set(String, String),
+
+    /// Changes a setting to a value.
+    SetValue(String, WidgetValue),
+
+    /// Captures an image.
+    Capture,
+
+    /// Waits for a duration.
+    Wait(Duration),
+}
+
+/// Progress of a running `Plan`.
+#[derive(Debug)]
+pub enum SequenceEvent<'a> {
+    /// A step was started. Steps are numbered from 0 to `total - 1`.
+    Started {
+        /// The number of the step.
+        step: usize,
+
+        /// The total number of steps of the plan.
+        total: usize,
+
+        /// The step's action.
+        action: &'a Action,
+    },
+
+    /// An image was captured.
+    Captured {
+        /// The number of the step.
+        step: usize,
+
+        /// The captured file.
+        file: &'a CameraFile,
+    },
+}
+
+impl Sequence {
+    /// Creates an empty sequence.
+    pub fn new() -> Self {
+        Sequence::default()
+    }
+
+    /// Changes a setting.
+    ///
+    /// The value is converted to the type of the setting when the step runs: `"1"`, `"true"` or
+    /// `"on"` for toggles, numbers for ranges and dates (as a Unix timestamp), and text for text
+    /// and choice settings.
+    pub fn set(self, name: &str, value: &str) -> Self {
+        self.action(Action::Set(name.to_owned(), value.to_owned()))
+    }
+
+    /// Changes a setting to a typed value.
+    pub fn set_value(self, name: &str, value: WidgetValue) -> Self {
+        self.action(Action::SetValue(name.to_owned(), value))
+    }
+
+    /// Captures an image.
+    pub fn capture(self) -> Self {
+        self.action(Action::Capture)
+    }
+
+    /// Waits for a duration.
+    pub fn wait(self, duration: Duration) -> Self {
+        self.action(Action::Wait(duration))
+    }
+
+    /// Repeats all steps so far `count` times.
+    pub fn repeat(mut self, count: usize) -> Self {
+        let steps = std::mem::take(&mut self.steps);
+        self.steps.push(Step::Repeat(count, steps));
+        self
+    }
+
+    /// Appends the steps of another sequence.
+    pub fn then(mut self, other: Sequence) -> Self {
+        self.steps.extend(other.steps);
+        self
+    }
+
+    /// Compiles the sequence to a plan.
+    pub fn compile(&self) -> Plan {
+        let mut actions = Vec::new();
+        flatten(&self.steps, &mut actions);
+
+        Plan { actions }
+    }
+
+    fn action(mut self, action: Action) -> Self {
+        self.steps.push(Step::Action(action));
+        self
+    }
+}
+
+fn flatten(steps: &[Step], actions: &mut Vec<Action>) {
+    for step in steps {
+        match *step {
+            Step::Action(ref action) => actions.push(action.clone()),
+            Step::Repeat(count, ref steps) => {
+                for _ in 0..count {
+                    flatten(steps, actions);
+                }
+            }
+        }
+    }
+}
+
+/// A compiled `Sequence`: the flat list of actions that it runs.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Plan {
+    actions: Vec<Action>,
+}
+
+impl Plan {
+    /// Returns the plan's actions in order.
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// Returns the number of images that the plan captures.
+    pub fn captures(&self) -> usize {
+        self.actions
+            .iter()
+            .filter(|action| **action == Action::Capture)
+            .count()
+    }
+
+    /// Returns the total time that the plan waits, excluding the time of the other actions.
+    pub fn wait_time(&self) -> Duration {
+        self.actions
+            .iter()
+            .map(|action| match *action {
+                Action::Wait(duration) => duration,
+                _ => Duration::from_secs(0),
+            })
+            .sum()
+    }
+
+    /// Runs the plan against a camera.
+    ///
+    /// `on_event` is called at the start of each step and after each capture. Returns the captured
+    /// files. The plan stops at the first step that fails.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the error of the step that failed, e.g.:
+    ///
+    /// * `InvalidInput` if a value can't be converted to the type of its setting.
+    pub fn run<F>(&self, camera: &mut Camera, mut on_event: F) -> crate::Result<Vec<CameraFile>>
+    where
+        F: FnMut(&SequenceEvent),
+    {
+        let total = self.actions.len();
+        let mut files = Vec::new();
+
+        for (step, action) in self.actions.iter().enumerate() {
+            on_event(&SequenceEvent::Started {
+                step,
+                total,
+                action,
+            });
+
+            match *action {
+                Action::Set(ref name, ref value) => {
+                    let mut config = camera.single_config(name)?;
+                    let value = parse_value(config.value(name), value)?;
+
+                    config.set_value(name, value)?;
+                    camera.set_single_config(&mut config)?;
+                }
+                Action::SetValue(ref name, ref value) => {
+                    let mut config = camera.single_config(name)?;
+
+                    config.set_value(name, value.clone())?;
+                    camera.set_single_config(&mut config)?;
+                }
+                Action::Capture => {
+                    let file = camera.capture_image()?;
+
+                    on_event(&SequenceEvent::Captured { step, file: &file });
+                    files.push(file);
+                }
+                Action::Wait(duration) => thread::sleep(duration),
+            }
+        }
+
+        Ok(files)
+    }
+}
+
+/// Converts a text value to the type of a setting's current value.
+pub(crate) fn parse_value(current: Option<WidgetValue>, value: &str) -> crate::Result<WidgetValue> {
+    let parsed = match current {
+        Some(WidgetValue::Toggle(_)) => match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "on" => Some(WidgetValue::Toggle(true)),
+            "0" | "false" | "off" => Some(WidgetValue::Toggle(false)),
+            _ => None,
+        },
+        Some(WidgetValue::Range(_)) => value.parse().ok().map(WidgetValue::Range),
+        Some(WidgetValue::Date(_)) => value.parse().ok().map(WidgetValue::Date),
+        Some(WidgetValue::Text(_)) => Some(WidgetValue::Text(value.to_owned())),
+        Some(WidgetValue::Choice(_)) | None => Some(WidgetValue::Choice(value.to_owned())),
+    };
+
+    match parsed {
+        Some(value) => Ok(value),
+        None => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values_like_current_value() {
+        let text = || Some(WidgetValue::Text(String::new()));
+        let range = || Some(WidgetValue::Range(0.0));
+        let toggle = || Some(WidgetValue::Toggle(false));
+        let choice = || Some(WidgetValue::Choice(String::new()));
+        let date = || Some(WidgetValue::Date(0));
+
+        let cases = [
+            (toggle(), "1", Some(WidgetValue::Toggle(true))),
+            (toggle(), "On", Some(WidgetValue::Toggle(true))),
+            (toggle(), "TRUE", Some(WidgetValue::Toggle(true))),
+            (toggle(), "0", Some(WidgetValue::Toggle(false))),
+            (toggle(), "off", Some(WidgetValue::Toggle(false))),
+            (toggle(), "false", Some(WidgetValue::Toggle(false))),
+            (toggle(), "yes", None),
+            (range(), "-0.5", Some(WidgetValue::Range(-0.5))),
+            (range(), "1/3", None),
+            (date(), "1700000000", Some(WidgetValue::Date(1_700_000_000))),
+            (date(), "now", None),
+            (text(), " a b ", Some(WidgetValue::Text(" a b ".to_owned()))),
+            (
+                choice(),
+                "1/250",
+                Some(WidgetValue::Choice("1/250".to_owned())),
+            ),
+            (None, "400", Some(WidgetValue::Choice("400".to_owned()))),
+        ];
+
+        for (current, value, expected) in cases {
+            let description = format!("{:?} {:?}", current, value);
+
+            match (parse_value(current, value), expected) {
+                (Ok(parsed), Some(expected)) => assert_eq!(expected, parsed, "{}", description),
+                (Err(err), None) => {
+                    assert_eq!(
+                        crate::ErrorKind::InvalidInput,
+                        err.kind(),
+                        "{}",
+                        description
+                    )
+                }
+                (result, _) => panic!("{}: {:?}", description, result),
+            }
+        }
+    }
+}
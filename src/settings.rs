@@ -0,0 +1,81 @@
+use cstr_core::{CStr, CString};
+use libc::c_char;
+
+/// The size of the buffers that `libgphoto2` stores setting IDs, keys and values in.
+const VALUE_SIZE: usize = 256;
+
+/// Reads a value from `libgphoto2`'s settings store.
+///
+/// The settings store is a small key-value store that is persisted in the user's `~/.gphoto`
+/// directory and shared with the `gphoto2` command line tools. Values are grouped by an ID, which
+/// is usually the name of the application or library that owns them.
+///
+/// Returns `None` if the setting doesn't exist.
+///
+/// ## Example
+///
+/// ```no_run
+/// if let Some(port) = gphoto::library_setting("gphoto2", "port").unwrap() {
+///     println!("preferred port: {}", port);
+/// }
+/// ```
+///
+/// ## Errors
+///
+/// This function returns an error if the setting could not be read:
+///
+/// * `InvalidInput` if the ID or key contains a NUL byte.
+pub fn library_setting(id: &str, key: &str) -> crate::Result<Option<String>> {
+    let id = to_cstring(id)?;
+    let key = to_cstring(key)?;
+    let mut value = [0 as c_char; VALUE_SIZE];
+
+    match unsafe { crate::gphoto2::gp_setting_get(id.as_ptr(), key.as_ptr(), value.as_mut_ptr()) } {
+        crate::gphoto2::GP_OK => Ok(Some(unsafe {
+            String::from_utf8_lossy(CStr::from_ptr(value.as_ptr()).to_bytes()).into_owned()
+        })),
+        // libgphoto2 reports a missing setting as a generic error.
+        crate::gphoto2::GP_ERROR => Ok(None),
+        err => Err(crate::error::from_libgphoto2(err)),
+    }
+}
+
+/// Stores a value in `libgphoto2`'s settings store.
+///
+/// The value is written to disk immediately. See `library_setting()` for details about the
+/// settings store.
+///
+/// ## Errors
+///
+/// This function returns an error if the setting could not be stored:
+///
+/// * `InvalidInput` if the ID, key, or value contains a NUL byte, or is too long.
+pub fn set_library_setting(id: &str, key: &str, value: &str) -> crate::Result<()> {
+    // libgphoto2 copies all three into fixed-size buffers without checking their lengths.
+    if id.len() >= VALUE_SIZE || key.len() >= VALUE_SIZE || value.len() >= VALUE_SIZE {
+        return Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+        ));
+    }
+
+    let id = to_cstring(id)?;
+    let key = to_cstring(key)?;
+    let value = to_cstring(value)?;
+
+    try_unsafe!(crate::gphoto2::gp_setting_set(
+        id.as_ptr(),
+        key.as_ptr(),
+        value.as_ptr()
+    ));
+
+    Ok(())
+}
+
+fn to_cstring(s: &str) -> crate::Result<CString> {
+    match CString::new(s) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+        )),
+    }
+}
@@ -0,0 +1,339 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::camera::{Camera, CameraFile};
+use crate::context::CancelHandle;
+use crate::media::Media;
+use crate::snapshot::StateSnapshot;
+use crate::threading::SerializedGuard;
+use crate::widget::{Config, WidgetValue};
+
+/// A camera that can be shared between threads.
+///
+/// `SharedCamera` wraps a `Camera` in a mutex. Its methods lock the camera for the duration of
+/// the call, so they can be called from any thread. Each method has a `try_` variant that fails
+/// with a `CameraBusy` error instead of blocking if the camera is in use by another thread.
+///
+/// Cloning a `SharedCamera` returns another handle to the same camera.
+///
+/// Waiting threads are admitted by `Priority`: captures are admitted before downloads and
+/// configuration reads that were queued earlier.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::thread;
+///
+/// let camera = gphoto::SharedCamera::autodetect().unwrap();
+///
+/// let preview = camera.clone();
+/// thread::spawn(move || {
+///     let mut file = gphoto::FileMedia::create("preview.jpg".as_ref()).unwrap();
+///     preview.capture_preview(&mut file).unwrap();
+/// });
+///
+/// match camera.try_capture_image() {
+///     Ok(file) => println!("captured {}", file.path()),
+///     Err(ref err) if err.kind() == gphoto::ErrorKind::CameraBusy => println!("camera busy"),
+///     Err(err) => panic!("{}", err),
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SharedCamera {
+    camera: Arc<Mutex<Camera>>,
+    gate: Arc<Gate>,
+    cancel: CancelHandle,
+}
+
+/// Priorities of operations on a `SharedCamera`.
+///
+/// When the camera becomes available, threads waiting with `High` priority get it before threads
+/// waiting with `Normal` priority. Captures use `High` priority, so that a capture isn't delayed
+/// by a queue of downloads and configuration reads.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Priority {
+    /// Housekeeping operations like downloads and configuration reads.
+    Normal,
+
+    /// Time-critical operations like captures.
+    High,
+}
+
+/// A locked `SharedCamera`.
+///
+/// The camera is unlocked when the guard is dropped.
+pub struct CameraGuard<'a> {
+    camera: MutexGuard<'a, Camera>,
+    gate: &'a Gate,
+    _serialized: SerializedGuard,
+}
+
+impl<'a> Drop for CameraGuard<'a> {
+    fn drop(&mut self) {
+        self.gate.leave();
+    }
+}
+
+impl<'a> Deref for CameraGuard<'a> {
+    type Target = Camera;
+
+    fn deref(&self) -> &Camera {
+        &self.camera
+    }
+}
+
+impl<'a> DerefMut for CameraGuard<'a> {
+    fn deref_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+}
+
+impl SharedCamera {
+    /// Wraps a camera to share it between threads.
+    pub fn new(camera: Camera) -> Self {
+        SharedCamera {
+            cancel: camera.cancel_handle(),
+            camera: Arc::new(Mutex::new(camera)),
+            gate: Arc::new(Gate::new()),
+        }
+    }
+
+    /// Opens the first detected camera.
+    pub fn autodetect() -> crate::Result<Self> {
+        Camera::autodetect().map(SharedCamera::new)
+    }
+
+    /// Returns a handle that cancels the camera's in-flight operation.
+    ///
+    /// Unlike `lock().cancel_handle()`, this doesn't wait for the operation to complete.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    /// Locks the camera with `Normal` priority, blocking until it is available.
+    pub fn lock(&self) -> CameraGuard<'_> {
+        self.lock_with_priority(Priority::Normal)
+    }
+
+    /// Locks the camera, blocking until it is available.
+    pub fn lock_with_priority(&self, priority: Priority) -> CameraGuard<'_> {
+        self.gate.enter(priority);
+        self.guard()
+    }
+
+    /// Locks the camera with `Normal` priority if it isn't in use by another thread.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera is in use:
+    ///
+    /// * `CameraBusy` if the camera is locked by another thread or a thread with `High` priority
+    ///   is waiting for it.
+    pub fn try_lock(&self) -> crate::Result<CameraGuard<'_>> {
+        self.try_lock_with_priority(Priority::Normal)
+    }
+
+    /// Locks the camera if it isn't in use by another thread.
+    pub fn try_lock_with_priority(&self, priority: Priority) -> crate::Result<CameraGuard<'_>> {
+        if self.gate.try_enter(priority) {
+            Ok(self.guard())
+        } else {
+            Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_CAMERA_BUSY,
+            ))
+        }
+    }
+
+    /// Calls a function with the camera locked with `Normal` priority.
+    pub fn with<F, T>(&self, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Camera) -> crate::Result<T>,
+    {
+        f(&mut self.lock())
+    }
+
+    /// Calls a function with the camera locked with the given priority.
+    pub fn with_priority<F, T>(&self, priority: Priority, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Camera) -> crate::Result<T>,
+    {
+        f(&mut self.lock_with_priority(priority))
+    }
+
+    /// Calls a function with the locked camera if it isn't in use by another thread.
+    pub fn try_with<F, T>(&self, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Camera) -> crate::Result<T>,
+    {
+        f(&mut *self.try_lock()?)
+    }
+
+    fn guard(&self) -> CameraGuard<'_> {
+        // The gate admits one thread at a time, so the mutex is never contended. A panic while the
+        // camera was locked doesn't leave the camera in an invalid state.
+        let camera = match self.camera.lock() {
+            Ok(camera) => camera,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        CameraGuard {
+            camera,
+            gate: &self.gate,
+            _serialized: crate::threading::serialize(),
+        }
+    }
+
+    /// Captures an image.
+    ///
+    /// The capture has `High` priority.
+    pub fn capture_image(&self) -> crate::Result<CameraFile> {
+        self.with_priority(Priority::High, |camera| camera.capture_image())
+    }
+
+    /// Captures an image if the camera isn't in use by another thread.
+    pub fn try_capture_image(&self) -> crate::Result<CameraFile> {
+        let mut camera = self.try_lock_with_priority(Priority::High)?;
+        camera.capture_image()
+    }
+
+    /// Captures a preview image.
+    pub fn capture_preview<T: Media>(&self, destination: &mut T) -> crate::Result<()> {
+        self.with(|camera| camera.capture_preview(destination))
+    }
+
+    /// Captures a preview image if the camera isn't in use by another thread.
+    pub fn try_capture_preview<T: Media>(&self, destination: &mut T) -> crate::Result<()> {
+        self.try_with(|camera| camera.capture_preview(destination))
+    }
+
+    /// Downloads a file from the camera.
+    pub fn download<T: Media>(
+        &self,
+        source: &CameraFile,
+        destination: &mut T,
+        file_type: Option<crate::CameraFileType>,
+    ) -> crate::Result<()> {
+        self.with(|camera| camera.download(source, destination, file_type))
+    }
+
+    /// Downloads a file from the camera if it isn't in use by another thread.
+    pub fn try_download<T: Media>(
+        &self,
+        source: &CameraFile,
+        destination: &mut T,
+        file_type: Option<crate::CameraFileType>,
+    ) -> crate::Result<()> {
+        self.try_with(|camera| camera.download(source, destination, file_type))
+    }
+
+    /// Retrieves the camera's configuration.
+    pub fn config(&self) -> crate::Result<Config> {
+        self.with(|camera| camera.config())
+    }
+
+    /// Retrieves the camera's configuration if the camera isn't in use by another thread.
+    pub fn try_config(&self) -> crate::Result<Config> {
+        self.try_with(|camera| camera.config())
+    }
+
+    /// Returns the current value of a setting.
+    pub fn setting(&self, name: &str) -> crate::Result<WidgetValue> {
+        self.with(|camera| camera.setting(name))
+    }
+
+    /// Returns the current value of a setting if the camera isn't in use by another thread.
+    pub fn try_setting(&self, name: &str) -> crate::Result<WidgetValue> {
+        self.try_with(|camera| camera.setting(name))
+    }
+
+    /// Changes several settings at once.
+    pub fn apply_config(&self, changes: &[(&str, WidgetValue)]) -> crate::Result<()> {
+        self.with(|camera| camera.apply_config(changes))
+    }
+
+    /// Changes several settings at once if the camera isn't in use by another thread.
+    pub fn try_apply_config(&self, changes: &[(&str, WidgetValue)]) -> crate::Result<()> {
+        self.try_with(|camera| camera.apply_config(changes))
+    }
+
+    /// Reads a consistent snapshot of the camera's state.
+    ///
+    /// The camera stays locked for the whole snapshot, so no other thread's operation is
+    /// interleaved with it.
+    pub fn state_snapshot(&self) -> crate::Result<StateSnapshot> {
+        self.with(|camera| camera.state_snapshot())
+    }
+}
+
+impl From<Camera> for SharedCamera {
+    fn from(camera: Camera) -> Self {
+        SharedCamera::new(camera)
+    }
+}
+
+/// Admits one thread at a time, preferring threads with `High` priority.
+struct Gate {
+    state: Mutex<GateState>,
+    available: Condvar,
+}
+
+struct GateState {
+    busy: bool,
+    high_waiting: usize,
+}
+
+impl Gate {
+    fn new() -> Self {
+        Gate {
+            state: Mutex::new(GateState {
+                busy: false,
+                high_waiting: 0,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, GateState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn enter(&self, priority: Priority) {
+        let mut state = self.state();
+
+        if priority == Priority::High {
+            state.high_waiting += 1;
+        }
+
+        while state.busy || (priority == Priority::Normal && state.high_waiting > 0) {
+            state = match self.available.wait(state) {
+                Ok(state) => state,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+
+        if priority == Priority::High {
+            state.high_waiting -= 1;
+        }
+
+        state.busy = true;
+    }
+
+    fn try_enter(&self, priority: Priority) -> bool {
+        let mut state = self.state();
+
+        if state.busy || (priority == Priority::Normal && state.high_waiting > 0) {
+            false
+        } else {
+            state.busy = true;
+            true
+        }
+    }
+
+    fn leave(&self) {
+        self.state().busy = false;
+        self.available.notify_all();
+    }
+}
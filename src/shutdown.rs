@@ -0,0 +1,244 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::lifecycle::CameraState;
+use crate::shared::{Priority, SharedCamera};
+
+use crate::handle::prelude::*;
+
+/// The default time that in-flight operations are given to complete.
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// The default time after which cameras that haven't exited are abandoned.
+const DEADLINE: Duration = Duration::from_secs(10);
+
+/// How often a camera that is in use is checked while shutting down.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Coordinates a graceful shutdown of several cameras.
+///
+/// Shutting down proceeds in parallel for all cameras:
+///
+/// 1. The shutdown is signalled to all `ShutdownSignal`s, so that worker loops stop starting new
+///    operations.
+/// 2. In-flight operations, e.g., downloads, are given a grace period to complete.
+/// 3. Operations that are still running after the grace period are cancelled.
+/// 4. The connection to each camera is closed.
+///
+/// Cameras that haven't exited by the deadline are abandoned, so that a service that is being
+/// stopped isn't killed while it waits for a camera that doesn't respond.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use gphoto::{SharedCamera, Shutdown};
+///
+/// let camera = SharedCamera::autodetect().unwrap();
+/// let shutdown = Shutdown::new()
+///     .camera(&camera)
+///     .grace_period(Duration::from_secs(3))
+///     .deadline(Duration::from_secs(8));
+///
+/// let signal = shutdown.signal();
+/// let worker = thread::spawn(move || {
+///     while !signal.is_requested() {
+///         let _ = camera.capture_image();
+///     }
+/// });
+///
+/// // on SIGTERM
+/// let report = shutdown.run();
+///
+/// if !report.is_clean() {
+///     println!("cameras {:?} didn't exit in time", report.timed_out());
+/// }
+/// # worker.join().unwrap();
+/// ```
+pub struct Shutdown {
+    cameras: Vec<SharedCamera>,
+    grace_period: Duration,
+    deadline: Duration,
+    requested: Arc<AtomicBool>,
+}
+
+/// Tells worker threads that a shutdown was requested.
+///
+/// Signals are created with `Shutdown::signal()`.
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownSignal {
+    /// Returns `true` if the shutdown was started.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+/// The result of a shutdown.
+///
+/// Cameras are identified by the order in which they were added to the `Shutdown`, starting at 0.
+#[derive(Debug, Clone)]
+pub struct ShutdownReport {
+    cancelled: Vec<usize>,
+    timed_out: Vec<usize>,
+}
+
+impl ShutdownReport {
+    /// Returns `true` if all cameras exited before the deadline.
+    pub fn is_clean(&self) -> bool {
+        self.timed_out.is_empty()
+    }
+
+    /// The cameras whose in-flight operation was cancelled after the grace period.
+    pub fn cancelled(&self) -> &[usize] {
+        &self.cancelled
+    }
+
+    /// The cameras that didn't exit before the deadline.
+    pub fn timed_out(&self) -> &[usize] {
+        &self.timed_out
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown::new()
+    }
+}
+
+impl Shutdown {
+    /// Creates a coordinator without cameras.
+    pub fn new() -> Self {
+        Shutdown {
+            cameras: Vec::new(),
+            grace_period: GRACE_PERIOD,
+            deadline: DEADLINE,
+            requested: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Adds a camera to shut down.
+    pub fn camera(mut self, camera: &SharedCamera) -> Self {
+        self.cameras.push(camera.clone());
+        self
+    }
+
+    /// Sets the time that in-flight operations are given to complete before they are cancelled.
+    ///
+    /// The default is five seconds.
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Sets the time after which cameras that haven't exited are abandoned.
+    ///
+    /// The deadline is measured from the start of the shutdown. The default is ten seconds.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Returns a signal that tells worker threads when the shutdown starts.
+    pub fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            requested: self.requested.clone(),
+        }
+    }
+
+    /// Shuts down all cameras and waits until they exited or the deadline passed.
+    ///
+    /// Cameras that are abandoned at the deadline are closed by their thread if their operation
+    /// completes later.
+    pub fn run(&self) -> ShutdownReport {
+        self.requested.store(true, Ordering::SeqCst);
+
+        let start = Instant::now();
+        let grace_end = start + self.grace_period;
+        let deadline = start + self.deadline;
+
+        let (sender, receiver) = mpsc::channel();
+
+        for (index, camera) in self.cameras.iter().enumerate() {
+            let camera = camera.clone();
+            let sender = sender.clone();
+
+            thread::spawn(move || {
+                let cancelled = shut_down(&camera, grace_end);
+                let _ = sender.send((index, cancelled));
+            });
+        }
+
+        drop(sender);
+
+        let mut exited = vec![false; self.cameras.len()];
+        let mut cancelled = Vec::new();
+
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            match receiver.recv_timeout(timeout) {
+                Ok((index, was_cancelled)) => {
+                    exited[index] = true;
+
+                    if was_cancelled {
+                        cancelled.push(index);
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+
+        cancelled.sort_unstable();
+
+        ShutdownReport {
+            cancelled,
+            timed_out: (0..exited.len()).filter(|&index| !exited[index]).collect(),
+        }
+    }
+}
+
+/// Waits for a camera's in-flight operation, cancelling it after `grace_end`, and exits the camera.
+///
+/// Returns `true` if the operation was cancelled.
+fn shut_down(camera: &SharedCamera, grace_end: Instant) -> bool {
+    let cancel = camera.cancel_handle();
+    let mut cancelled = false;
+
+    let mut guard = loop {
+        if let Ok(guard) = camera.try_lock_with_priority(Priority::High) {
+            break guard;
+        }
+
+        if !cancelled && Instant::now() >= grace_end {
+            cancel.cancel();
+            cancelled = true;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    // A request that wasn't consumed by the operation would cancel closing the connection.
+    cancel.reset();
+    guard.exit();
+
+    cancelled
+}
+
+impl Camera {
+    /// Closes the connection to the camera.
+    pub(crate) fn exit(&mut self) {
+        unsafe {
+            crate::gphoto2::gp_camera_exit(self.camera, self.context.as_mut_ptr());
+        }
+
+        self.set_state(CameraState::Detected);
+    }
+}
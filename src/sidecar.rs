@@ -0,0 +1,696 @@
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::camera::{Camera, CameraFile};
+use crate::clock::civil_date;
+use crate::exposure::{ISO_SETTINGS, SHUTTER_SETTINGS};
+use crate::json::JsonObject;
+use crate::snapshot::{APERTURE_SETTINGS, COMPENSATION_SETTINGS, MODE_SETTINGS};
+use crate::status::find_text;
+use crate::widget::Config;
+
+/// Names of the settings that contain the camera's serial number in different drivers.
+const SERIAL_SETTINGS: &[&str] = &["serialnumber", "eosserialnumber"];
+
+/// Names of the settings that contain the latitude of the camera's GPS fix in different drivers.
+const LATITUDE_SETTINGS: &[&str] = &["gpslatitude", "latitude"];
+
+/// Names of the settings that contain the longitude of the camera's GPS fix in different drivers.
+const LONGITUDE_SETTINGS: &[&str] = &["gpslongitude", "longitude"];
+
+/// Names of the settings that contain the altitude of the camera's GPS fix in different drivers.
+const ALTITUDE_SETTINGS: &[&str] = &["gpsaltitude", "altitude"];
+
+/// The time within which added files belong to the same capture, e.g., the files of a RAW+JPEG
+/// capture, if the camera doesn't report the end of the capture.
+const CAPTURE_WINDOW: Duration = Duration::from_secs(2);
+
+/// The number of recently captured files whose settings are kept for their sidecars.
+const MAX_CAPTURED_FILES: usize = 256;
+
+/// Formats of metadata sidecars.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SidecarFormat {
+    /// A JSON object, written to `<file>.json`, e.g., `IMG_0001.JPG.json`.
+    Json,
+
+    /// An XMP packet, written to `<file>.xmp`, e.g., `IMG_0001.JPG.xmp`, so that the RAW and JPEG
+    /// files of a capture get sidecars of their own.
+    Xmp,
+}
+
+/// A position reported by a camera's GPS receiver.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GpsFix {
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<f64>,
+}
+
+impl GpsFix {
+    /// The latitude in degrees, positive to the north.
+    pub fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    /// The longitude in degrees, positive to the east.
+    pub fn longitude(&self) -> f64 {
+        self.longitude
+    }
+
+    /// The altitude in meters above sea level, if the camera reports it.
+    pub fn altitude(&self) -> Option<f64> {
+        self.altitude
+    }
+}
+
+/// The serial number, exposure settings and GPS fix that were read when a file was captured.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct CaptureSettings {
+    serial_number: Option<String>,
+    shutter: Option<String>,
+    aperture: Option<String>,
+    iso: Option<String>,
+    exposure_compensation: Option<String>,
+    mode: Option<String>,
+    gps: Option<GpsFix>,
+}
+
+/// The settings of recent captures, by the paths of the files they produced.
+#[derive(Debug, Default)]
+pub(crate) struct CaptureSnapshots {
+    enabled: bool,
+    current: Option<(Arc<CaptureSettings>, Instant)>,
+    files: VecDeque<(String, Arc<CaptureSettings>)>,
+}
+
+impl CaptureSnapshots {
+    pub(crate) fn new() -> Self {
+        CaptureSnapshots::default()
+    }
+
+    /// Ends the current capture, so that the next added file is snapshotted again.
+    pub(crate) fn capture_complete(&mut self) {
+        self.current = None;
+    }
+
+    fn settings(&self, path: &str) -> Option<&CaptureSettings> {
+        self.files
+            .iter()
+            .rev()
+            .find(|(file, _)| file == path)
+            .map(|(_, settings)| &**settings)
+    }
+}
+
+/// Metadata about a captured file that is written next to the file when it is downloaded.
+///
+/// A sidecar records the camera's identity, the time the file was taken, and, for files that were
+/// captured while recording was enabled with `Camera::record_capture_settings()`, the camera's
+/// exposure settings and GPS fix at the time of the capture. Cataloging tools can ingest them
+/// without parsing the file's EXIF data, which not all formats and cameras provide.
+///
+/// Sidecars are created with `Camera::sidecar()`, and written for each downloaded file when
+/// enabled with `DownloadOptions::sidecar()`. Files that were already on the card, e.g., in an
+/// offload, only get the camera's identity and the time from the file's information, because
+/// the settings they were taken with are unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sidecar {
+    taken_at: Option<SystemTime>,
+    model: String,
+    stable_id: String,
+    settings: Option<CaptureSettings>,
+}
+
+impl Sidecar {
+    /// The time at which the file was taken, from the file's modification time on the camera.
+    pub fn taken_at(&self) -> Option<SystemTime> {
+        self.taken_at
+    }
+
+    /// The name of the camera's model.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// The camera's stable ID. See `Camera::stable_id()`.
+    pub fn stable_id(&self) -> &str {
+        &self.stable_id
+    }
+
+    /// Returns `true` if the camera's settings were recorded when the file was captured.
+    pub fn has_settings(&self) -> bool {
+        self.settings.is_some()
+    }
+
+    /// The camera's serial number, if it was recorded at capture time.
+    pub fn serial_number(&self) -> Option<&str> {
+        self.setting(|settings| &settings.serial_number)
+    }
+
+    /// The shutter speed at capture time, e.g., `"1/250"`.
+    pub fn shutter(&self) -> Option<&str> {
+        self.setting(|settings| &settings.shutter)
+    }
+
+    /// The aperture at capture time, e.g., `"5.6"`.
+    pub fn aperture(&self) -> Option<&str> {
+        self.setting(|settings| &settings.aperture)
+    }
+
+    /// The ISO speed at capture time, e.g., `"400"`.
+    pub fn iso(&self) -> Option<&str> {
+        self.setting(|settings| &settings.iso)
+    }
+
+    /// The exposure compensation at capture time, e.g., `"-0.3"`.
+    pub fn exposure_compensation(&self) -> Option<&str> {
+        self.setting(|settings| &settings.exposure_compensation)
+    }
+
+    /// The exposure mode at capture time, e.g., `"Manual"`.
+    pub fn mode(&self) -> Option<&str> {
+        self.setting(|settings| &settings.mode)
+    }
+
+    /// The camera's GPS fix at capture time, if it has a GPS receiver with a fix.
+    pub fn gps(&self) -> Option<GpsFix> {
+        self.settings.as_ref().and_then(|settings| settings.gps)
+    }
+
+    /// Formats the sidecar as a JSON object.
+    pub fn to_json(&self) -> String {
+        let gps = match self.gps() {
+            Some(gps) => {
+                let mut object = JsonObject::new()
+                    .raw("latitude", &gps.latitude.to_string())
+                    .raw("longitude", &gps.longitude.to_string());
+
+                object = match gps.altitude {
+                    Some(altitude) => object.raw("altitude", &altitude.to_string()),
+                    None => object.null("altitude"),
+                };
+
+                object.finish()
+            }
+            None => String::from("null"),
+        };
+
+        JsonObject::new()
+            .optional_string("taken_at", self.taken_at.map(iso_8601).as_deref())
+            .string("model", &self.model)
+            .optional_string("serial_number", self.serial_number())
+            .string("stable_id", &self.stable_id)
+            .optional_string("shutter", self.shutter())
+            .optional_string("aperture", self.aperture())
+            .optional_string("iso", self.iso())
+            .optional_string("exposure_compensation", self.exposure_compensation())
+            .optional_string("mode", self.mode())
+            .raw("gps", &gps)
+            .finish()
+    }
+
+    /// Formats the sidecar as an XMP packet with TIFF, EXIF and EXIF auxiliary properties.
+    ///
+    /// Settings that the camera doesn't report are left out.
+    pub fn to_xmp(&self) -> String {
+        let mut properties = Vec::new();
+
+        properties.push(format!("tiff:Model=\"{}\"", xml_escape(&self.model)));
+
+        if let Some(taken_at) = self.taken_at {
+            properties.push(format!("exif:DateTimeOriginal=\"{}\"", iso_8601(taken_at)));
+        }
+
+        if let Some(serial_number) = self.serial_number() {
+            properties.push(format!(
+                "aux:SerialNumber=\"{}\"",
+                xml_escape(serial_number)
+            ));
+        }
+
+        if let Some(shutter) = self.shutter() {
+            properties.push(format!("exif:ExposureTime=\"{}\"", xml_escape(shutter)));
+        }
+
+        if let Some(aperture) = self.aperture() {
+            let aperture = aperture.trim_start_matches("f/");
+            properties.push(format!("exif:FNumber=\"{}\"", xml_escape(aperture)));
+        }
+
+        if let Some(compensation) = self.exposure_compensation() {
+            properties.push(format!(
+                "exif:ExposureBiasValue=\"{}\"",
+                xml_escape(compensation)
+            ));
+        }
+
+        if let Some(gps) = self.gps() {
+            properties.push(format!(
+                "exif:GPSLatitude=\"{}\"",
+                gps_coordinate(gps.latitude, 'N', 'S')
+            ));
+            properties.push(format!(
+                "exif:GPSLongitude=\"{}\"",
+                gps_coordinate(gps.longitude, 'E', 'W')
+            ));
+
+            if let Some(altitude) = gps.altitude {
+                properties.push(format!(
+                    "exif:GPSAltitude=\"{}/100\"",
+                    (altitude.abs() * 100.0).round() as u64
+                ));
+                properties.push(format!(
+                    "exif:GPSAltitudeRef=\"{}\"",
+                    if altitude < 0.0 { 1 } else { 0 }
+                ));
+            }
+        }
+
+        let iso = match self.iso() {
+            Some(iso) => format!(
+                "\n   <exif:ISOSpeedRatings>\n    <rdf:Seq>\n     <rdf:li>{}</rdf:li>\n    \
+                 </rdf:Seq>\n   </exif:ISOSpeedRatings>\n  ",
+                xml_escape(iso)
+            ),
+            None => String::new(),
+        };
+
+        format!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+             <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+             <rdf:Description rdf:about=\"\"\n   \
+             xmlns:tiff=\"http://ns.adobe.com/tiff/1.0/\"\n   \
+             xmlns:exif=\"http://ns.adobe.com/exif/1.0/\"\n   \
+             xmlns:aux=\"http://ns.adobe.com/exif/1.0/aux/\"\n   \
+             {}>{}</rdf:Description>\n \
+             </rdf:RDF>\n\
+             </x:xmpmeta>\n\
+             <?xpacket end=\"w\"?>\n",
+            properties.join("\n   "),
+            iso
+        )
+    }
+
+    /// Writes the sidecar next to a file and returns the sidecar's path.
+    ///
+    /// The sidecar is written to `<file>.xmp` or `<file>.json`. An existing sidecar, e.g., one
+    /// with edits from a cataloging tool, is never replaced.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the sidecar could not be written:
+    ///
+    /// * `FileExists` if the sidecar already exists.
+    /// * `OSFailure` if the file could not be written.
+    pub fn write_for(&self, file: &Path, format: SidecarFormat) -> crate::Result<PathBuf> {
+        self.write(file, format, false)
+    }
+
+    /// Writes the sidecar next to a file, replacing an existing sidecar if `overwrite` is set.
+    pub(crate) fn write(
+        &self,
+        file: &Path,
+        format: SidecarFormat,
+        overwrite: bool,
+    ) -> crate::Result<PathBuf> {
+        let (extension, contents) = match format {
+            SidecarFormat::Json => (".json", self.to_json()),
+            SidecarFormat::Xmp => (".xmp", self.to_xmp()),
+        };
+
+        let mut path = file.as_os_str().to_owned();
+        path.push(extension);
+        let path = PathBuf::from(path);
+
+        let mut options = OpenOptions::new();
+        options.write(true);
+
+        if overwrite {
+            options.create(true).truncate(true);
+        } else {
+            options.create_new(true);
+        }
+
+        match options
+            .open(&path)
+            .and_then(|mut sidecar| sidecar.write_all(contents.as_bytes()))
+        {
+            Ok(()) => Ok(path),
+            Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => Err(
+                crate::error::from_libgphoto2(crate::gphoto2::GP_ERROR_FILE_EXISTS),
+            ),
+            Err(_) => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            )),
+        }
+    }
+
+    fn setting<F>(&self, field: F) -> Option<&str>
+    where
+        F: Fn(&CaptureSettings) -> &Option<String>,
+    {
+        self.settings
+            .as_ref()
+            .and_then(|settings| field(settings).as_deref())
+    }
+}
+
+impl Camera {
+    /// Enables or disables recording the camera's settings when files are captured, for their
+    /// sidecars.
+    ///
+    /// While enabled, the camera's serial number, exposure settings and GPS fix are read once per
+    /// capture: when `capture_image()` returns, or when the first file of a capture is reported
+    /// by a `FileAdded` event. The other files of the capture, e.g., the JPEG of a RAW+JPEG
+    /// capture, share the snapshot. Sidecars of the files then include the settings, so they
+    /// describe the capture even if the files are downloaded later. Recording is disabled by
+    /// default, because reading the settings delays each capture.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::{DownloadOptions, SidecarFormat};
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// camera.record_capture_settings(true);
+    ///
+    /// let file = camera.capture_image().unwrap();
+    /// let options = DownloadOptions::new().sidecar(SidecarFormat::Xmp);
+    ///
+    /// camera.download_to_dir(&file, "photos".as_ref(), &options).unwrap();
+    /// ```
+    pub fn record_capture_settings(&mut self, enabled: bool) {
+        self.capture_snapshots.enabled = enabled;
+
+        if !enabled {
+            self.capture_snapshots.current = None;
+        }
+    }
+
+    /// Creates the sidecar of a file on the camera.
+    ///
+    /// The sidecar contains the camera's identity, the time from the file's information, and the
+    /// settings that were recorded when the file was captured, if any. See `Sidecar` for details.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the file's information could not be read:
+    ///
+    /// * `FileNotFound` if the file doesn't exist on the camera.
+    pub fn sidecar(&mut self, file: &CameraFile) -> crate::Result<Sidecar> {
+        let info = self.file_info(file)?;
+
+        Ok(Sidecar {
+            taken_at: info.mtime(),
+            model: self.abilities().model().into_owned(),
+            stable_id: self.stable_id().to_string(),
+            settings: self.capture_snapshots.settings(&file.path()).cloned(),
+        })
+    }
+
+    /// Records the settings for a file that was added by a capture, if recording is enabled.
+    ///
+    /// The settings are read for the first file of a capture and shared by the other files.
+    pub(crate) fn capture_file_added(&mut self, file: &CameraFile) {
+        if !self.capture_snapshots.enabled {
+            return;
+        }
+
+        let now = Instant::now();
+
+        let settings = match self.capture_snapshots.current {
+            Some((ref settings, last)) if now.duration_since(last) < CAPTURE_WINDOW => {
+                settings.clone()
+            }
+            _ => Arc::new(self.capture_settings()),
+        };
+
+        let snapshots = &mut self.capture_snapshots;
+        snapshots.current = Some((settings.clone(), now));
+        snapshots.files.push_back((file.path(), settings));
+
+        if snapshots.files.len() > MAX_CAPTURED_FILES {
+            snapshots.files.pop_front();
+        }
+    }
+
+    /// Reads the settings for the sidecars of a capture.
+    ///
+    /// Settings that can't be read are left out, so that recording never fails a capture.
+    fn capture_settings(&mut self) -> CaptureSettings {
+        let config = match self.config() {
+            Ok(config) => config,
+            Err(_) => return CaptureSettings::default(),
+        };
+
+        let text = |names: &[&str]| find_text(&config, names);
+
+        CaptureSettings {
+            serial_number: text(SERIAL_SETTINGS),
+            shutter: text(SHUTTER_SETTINGS),
+            aperture: text(APERTURE_SETTINGS),
+            iso: text(ISO_SETTINGS),
+            exposure_compensation: text(COMPENSATION_SETTINGS),
+            mode: text(MODE_SETTINGS),
+            gps: gps_fix(&config),
+        }
+    }
+}
+
+/// Reads the GPS fix from a configuration, if it contains coordinates.
+fn gps_fix(config: &Config) -> Option<GpsFix> {
+    let coordinate = |names: &[&str]| {
+        find_text(config, names)
+            .and_then(|text| text.trim().parse::<f64>().ok())
+            .filter(|value| value.is_finite())
+    };
+
+    let latitude = coordinate(LATITUDE_SETTINGS)?;
+    let longitude = coordinate(LONGITUDE_SETTINGS)?;
+
+    // Receivers without a fix report the origin.
+    if latitude == 0.0 && longitude == 0.0 {
+        return None;
+    }
+
+    Some(GpsFix {
+        latitude,
+        longitude,
+        altitude: coordinate(ALTITUDE_SETTINGS),
+    })
+}
+
+/// Formats a coordinate in the XMP format `DDD,MM.mmmmk`, e.g., `48,8.4000N`.
+fn gps_coordinate(degrees: f64, positive: char, negative: char) -> String {
+    let direction = if degrees < 0.0 { negative } else { positive };
+    let degrees = degrees.abs();
+
+    format!(
+        "{},{:.4}{}",
+        degrees.trunc() as u32,
+        degrees.fract() * 60.0,
+        direction
+    )
+}
+
+/// Formats a time as an ISO 8601 timestamp in UTC, e.g., `2024-05-01T12:30:00Z`.
+fn iso_8601(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0);
+
+    let (year, month, day) = civil_date((seconds / 86400) as i64);
+    let seconds_of_day = seconds % 86400;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sidecar(model: &str, gps: Option<GpsFix>) -> Sidecar {
+        Sidecar {
+            taken_at: Some(UNIX_EPOCH + Duration::from_secs(1_714_566_600)),
+            model: model.to_owned(),
+            stable_id: String::from("usb:001,004"),
+            settings: Some(CaptureSettings {
+                iso: Some(String::from("400")),
+                gps,
+                ..CaptureSettings::default()
+            }),
+        }
+    }
+
+    fn gps(latitude: f64, longitude: f64, altitude: Option<f64>) -> Option<GpsFix> {
+        Some(GpsFix {
+            latitude,
+            longitude,
+            altitude,
+        })
+    }
+
+    #[test]
+    fn formats_xmp() {
+        let cases: &[(Sidecar, &[&str], &[&str])] = &[
+            (
+                sidecar("EOS R5", gps(48.14, 11.58, Some(519.0))),
+                &[
+                    "tiff:Model=\"EOS R5\"",
+                    "exif:DateTimeOriginal=\"2024-05-01T12:30:00Z\"",
+                    "exif:GPSLatitude=\"48,8.4000N\"",
+                    "exif:GPSLongitude=\"11,34.8000E\"",
+                    "exif:GPSAltitude=\"51900/100\"",
+                    "exif:GPSAltitudeRef=\"0\"",
+                    "<rdf:li>400</rdf:li>",
+                ],
+                &["aux:SerialNumber", "exif:FNumber"],
+            ),
+            (
+                sidecar("A&B \"1\" <x>", gps(-33.8568, -70.6483, Some(-12.5))),
+                &[
+                    "tiff:Model=\"A&amp;B &quot;1&quot; &lt;x&gt;\"",
+                    "exif:GPSLatitude=\"33,51.4080S\"",
+                    "exif:GPSLongitude=\"70,38.8980W\"",
+                    "exif:GPSAltitude=\"1250/100\"",
+                    "exif:GPSAltitudeRef=\"1\"",
+                ],
+                &["A&B"],
+            ),
+            (
+                sidecar("EOS R5", gps(-33.8568, 151.2093, None)),
+                &["exif:GPSLongitude=\"151,12.5580E\""],
+                &["exif:GPSAltitude"],
+            ),
+            (
+                sidecar("EOS R5", None),
+                &["tiff:Model=\"EOS R5\""],
+                &["exif:GPSLatitude", "exif:GPSLongitude"],
+            ),
+        ];
+
+        for (sidecar, present, absent) in cases {
+            let xmp = sidecar.to_xmp();
+
+            assert!(xmp.starts_with("<?xpacket begin="), "{}", xmp);
+            assert!(xmp.ends_with("<?xpacket end=\"w\"?>\n"), "{}", xmp);
+
+            for property in present.iter() {
+                assert!(xmp.contains(property), "{:?} in {}", property, xmp);
+            }
+
+            for property in absent.iter() {
+                assert!(!xmp.contains(property), "{:?} in {}", property, xmp);
+            }
+        }
+    }
+
+    #[test]
+    fn formats_json() {
+        let cases = [
+            (
+                sidecar("EOS R5", gps(48.14, 11.58, Some(519.0))),
+                "{\"taken_at\":\"2024-05-01T12:30:00Z\",\"model\":\"EOS R5\",\
+                 \"serial_number\":null,\"stable_id\":\"usb:001,004\",\"shutter\":null,\
+                 \"aperture\":null,\"iso\":\"400\",\"exposure_compensation\":null,\"mode\":null,\
+                 \"gps\":{\"latitude\":48.14,\"longitude\":11.58,\"altitude\":519}}",
+            ),
+            (
+                sidecar("A&B \"1\"", gps(-33.8568, -70.6483, Some(-12.5))),
+                "{\"taken_at\":\"2024-05-01T12:30:00Z\",\"model\":\"A&B \\\"1\\\"\",\
+                 \"serial_number\":null,\"stable_id\":\"usb:001,004\",\"shutter\":null,\
+                 \"aperture\":null,\"iso\":\"400\",\"exposure_compensation\":null,\"mode\":null,\
+                 \"gps\":{\"latitude\":-33.8568,\"longitude\":-70.6483,\"altitude\":-12.5}}",
+            ),
+            (
+                sidecar("EOS R5", gps(-33.8568, 151.2093, None)),
+                "{\"taken_at\":\"2024-05-01T12:30:00Z\",\"model\":\"EOS R5\",\
+                 \"serial_number\":null,\"stable_id\":\"usb:001,004\",\"shutter\":null,\
+                 \"aperture\":null,\"iso\":\"400\",\"exposure_compensation\":null,\"mode\":null,\
+                 \"gps\":{\"latitude\":-33.8568,\"longitude\":151.2093,\"altitude\":null}}",
+            ),
+            (
+                sidecar("EOS R5", None),
+                "{\"taken_at\":\"2024-05-01T12:30:00Z\",\"model\":\"EOS R5\",\
+                 \"serial_number\":null,\"stable_id\":\"usb:001,004\",\"shutter\":null,\
+                 \"aperture\":null,\"iso\":\"400\",\"exposure_compensation\":null,\"mode\":null,\
+                 \"gps\":null}",
+            ),
+        ];
+
+        for &(ref sidecar, json) in cases.iter() {
+            assert_eq!(sidecar.to_json(), json, "{:?}", sidecar);
+        }
+    }
+
+    #[test]
+    fn formats_gps_coordinates() {
+        let cases = [
+            (48.14, 'N', 'S', "48,8.4000N"),
+            (-33.8568, 'N', 'S', "33,51.4080S"),
+            (-122.4194, 'E', 'W', "122,25.1640W"),
+            (151.2093, 'E', 'W', "151,12.5580E"),
+            (0.5, 'E', 'W', "0,30.0000E"),
+            (-0.25, 'N', 'S', "0,15.0000S"),
+        ];
+
+        for &(degrees, positive, negative, expected) in cases.iter() {
+            assert_eq!(
+                gps_coordinate(degrees, positive, negative),
+                expected,
+                "{:?}",
+                degrees
+            );
+        }
+    }
+
+    #[test]
+    fn formats_iso_8601() {
+        let cases = [
+            (UNIX_EPOCH, "1970-01-01T00:00:00Z"),
+            (
+                UNIX_EPOCH + Duration::from_secs(86_399),
+                "1970-01-01T23:59:59Z",
+            ),
+            (
+                UNIX_EPOCH + Duration::from_secs(951_782_400),
+                "2000-02-29T00:00:00Z",
+            ),
+            (
+                UNIX_EPOCH + Duration::from_secs(1_714_566_600),
+                "2024-05-01T12:30:00Z",
+            ),
+            (
+                UNIX_EPOCH + Duration::from_millis(1_500),
+                "1970-01-01T00:00:01Z",
+            ),
+            (UNIX_EPOCH - Duration::from_secs(1), "1970-01-01T00:00:00Z"),
+        ];
+
+        for &(time, expected) in cases.iter() {
+            assert_eq!(iso_8601(time), expected, "{:?}", time);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fileinfo::FileInfo;
+use crate::hooks::{Hook, HookError};
+
+/// A post-download sink that copies files to another directory, e.g., a backup drive.
+///
+/// Files keep their names; if a name is already taken in the directory, the copy gets a numeric
+/// suffix like with `Collision::Rename`. A partially written copy is removed if copying fails.
+///
+/// `DirSink` is a `Hook`, so each downloaded file can be fanned out to several destinations by
+/// adding one sink per destination to a pipeline with `Hooks::add_channel()`, or to a `Tether`
+/// with `TetherOptions::channel()`. Each destination fails on its own: a full backup drive is
+/// recorded as a failure of its channel, while the other channels still receive the file.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{DirSink, DownloadOptions, Hooks};
+///
+/// let mut hooks = Hooks::new();
+/// hooks.add_channel("backup", DirSink::new("/mnt/backup/photos".as_ref()));
+/// hooks.add_channel("archive", DirSink::new("/mnt/archive/photos".as_ref()));
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let options = DownloadOptions::new();
+///
+/// camera
+///     .fs()
+///     .download_matching_with_hooks("/", |_| true, "photos".as_ref(), &options, &mut hooks)
+///     .unwrap();
+///
+/// for failure in hooks.take_failures() {
+///     println!("{}", failure);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DirSink {
+    dir: PathBuf,
+}
+
+impl DirSink {
+    /// Creates a sink that copies files to a directory.
+    ///
+    /// The directory is created when the first file is copied, if it doesn't exist.
+    pub fn new(dir: &Path) -> Self {
+        DirSink {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Returns the directory that files are copied to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Hook for DirSink {
+    fn run(&mut self, path: &Path, _info: &FileInfo) -> Result<(), HookError> {
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => return Err("file name is not valid UTF-8".into()),
+        };
+
+        fs::create_dir_all(&self.dir)?;
+
+        let mut destination = self.dir.join(name);
+
+        if destination.exists() {
+            destination = crate::download::free_path(&self.dir, name)?;
+        }
+
+        if let Err(err) = fs::copy(path, &destination) {
+            let _ = fs::remove_file(&destination);
+
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "dir"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_copies_on_collision() {
+        let root = std::env::temp_dir().join(format!("gphoto-sink-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let source = root.join("IMG_0001.JPG");
+        let backup = root.join("backup");
+        fs::write(&source, b"first").unwrap();
+
+        let info = FileInfo::empty(crate::camera::file_from_parts("/DCIM", "IMG_0001.JPG"));
+        let mut sink = DirSink::new(&backup);
+
+        let first = sink.run(&source, &info);
+        fs::write(&source, b"second").unwrap();
+        let second = sink.run(&source, &info);
+
+        let copies = [
+            fs::read(backup.join("IMG_0001.JPG")),
+            fs::read(backup.join("IMG_0001_1.JPG")),
+        ];
+        let _ = fs::remove_dir_all(&root);
+
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert_eq!(copies[0].as_ref().unwrap(), b"first");
+        assert_eq!(copies[1].as_ref().unwrap(), b"second");
+    }
+}
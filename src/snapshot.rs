@@ -0,0 +1,133 @@
+use std::time::SystemTime;
+
+use crate::camera::Camera;
+use crate::exposure::{ISO_SETTINGS, SHUTTER_SETTINGS};
+use crate::status::{find_text, CameraStatus};
+use crate::storage::Storage;
+use crate::widget::{Config, WidgetValue};
+
+/// Names of the settings that contain the aperture in different drivers.
+pub(crate) const APERTURE_SETTINGS: &[&str] = &["aperture", "f-number", "eos-aperture"];
+
+/// Names of the settings that contain the exposure compensation in different drivers.
+pub(crate) const COMPENSATION_SETTINGS: &[&str] =
+    &["exposurecompensation", "exposurecompensation2"];
+
+/// Names of the settings that contain the exposure mode in different drivers.
+pub(crate) const MODE_SETTINGS: &[&str] = &["autoexposuremode", "expprogram", "exposureprogram"];
+
+/// A consistent view of a camera's state.
+///
+/// State snapshots are read with `Camera::state_snapshot()`. All settings are read from a single
+/// configuration tree, together with the camera's storages, so the values belong together rather
+/// than being read seconds apart. Each setting is optional, because drivers expose different
+/// subsets of them.
+pub struct StateSnapshot {
+    taken_at: SystemTime,
+    shutter: Option<String>,
+    aperture: Option<String>,
+    iso: Option<String>,
+    exposure_compensation: Option<String>,
+    mode: Option<String>,
+    battery_level: Option<String>,
+    storage: Vec<Storage>,
+    status: CameraStatus,
+}
+
+impl StateSnapshot {
+    /// The time at which the snapshot was taken.
+    pub fn taken_at(&self) -> SystemTime {
+        self.taken_at
+    }
+
+    /// The shutter speed, e.g., `"1/250"`.
+    pub fn shutter(&self) -> Option<&str> {
+        self.shutter.as_deref()
+    }
+
+    /// The aperture, e.g., `"5.6"`.
+    pub fn aperture(&self) -> Option<&str> {
+        self.aperture.as_deref()
+    }
+
+    /// The ISO speed, e.g., `"400"`.
+    pub fn iso(&self) -> Option<&str> {
+        self.iso.as_deref()
+    }
+
+    /// The exposure compensation, e.g., `"-0.3"`.
+    pub fn exposure_compensation(&self) -> Option<&str> {
+        self.exposure_compensation.as_deref()
+    }
+
+    /// The exposure mode, e.g., `"Manual"` or `"AV"`.
+    pub fn mode(&self) -> Option<&str> {
+        self.mode.as_deref()
+    }
+
+    /// The battery level as reported by the camera, e.g., `"50%"`.
+    pub fn battery_level(&self) -> Option<&str> {
+        self.battery_level.as_deref()
+    }
+
+    /// The camera's storages.
+    pub fn storage(&self) -> &[Storage] {
+        &self.storage
+    }
+
+    /// The camera's status.
+    pub fn status(&self) -> &CameraStatus {
+        &self.status
+    }
+}
+
+impl Camera {
+    /// Reads a consistent snapshot of the camera's exposure settings, battery, storage and mode.
+    ///
+    /// The configuration and the storages are read back to back with no other operation in
+    /// between. Use `SharedCamera::state_snapshot()` to keep other threads from interleaving
+    /// their operations.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let snapshot = camera.state_snapshot().unwrap();
+    ///
+    /// println!("shutter  = {:?}", snapshot.shutter());
+    /// println!("aperture = {:?}", snapshot.aperture());
+    /// println!("iso      = {:?}", snapshot.iso());
+    /// println!("battery  = {:?}", snapshot.battery_level());
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera's configuration or storages could not be
+    /// read:
+    ///
+    /// * `NotSupported` if the camera can not be configured.
+    pub fn state_snapshot(&mut self) -> crate::Result<StateSnapshot> {
+        let config = self.config()?;
+        let storage = self.storage()?;
+
+        Ok(StateSnapshot {
+            taken_at: SystemTime::now(),
+            shutter: find_text(&config, SHUTTER_SETTINGS),
+            aperture: find_text(&config, APERTURE_SETTINGS),
+            iso: find_text(&config, ISO_SETTINGS),
+            exposure_compensation: find_text(&config, COMPENSATION_SETTINGS),
+            mode: find_text(&config, MODE_SETTINGS),
+            battery_level: battery_level(&config),
+            storage,
+            status: CameraStatus::from_config(&config),
+        })
+    }
+}
+
+fn battery_level(config: &Config) -> Option<String> {
+    match config.value("batterylevel")? {
+        WidgetValue::Text(level) | WidgetValue::Choice(level) => Some(level),
+        WidgetValue::Range(level) => Some(format!("{}%", level)),
+        _ => None,
+    }
+}
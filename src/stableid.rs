@@ -0,0 +1,152 @@
+use std::fmt;
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+
+use crate::camera::Camera;
+use crate::detect::{detect_cameras, DetectedCamera};
+
+/// The directory that lists the USB devices in sysfs.
+#[cfg(target_os = "linux")]
+const SYSFS_USB_DEVICES: &str = "/sys/bus/usb/devices";
+
+/// An identifier of a camera that doesn't change when the camera is reconnected.
+///
+/// USB port paths like `usb:020,007` contain the device number, which changes each time the camera
+/// is plugged in. A stable ID is derived from the most stable information that is available:
+///
+/// 1. The camera's model and its USB serial number, e.g.,
+///    `Canon EOS 5D Mark III@serial:0123456789`. This identifies the camera on any port.
+/// 2. On Linux, the model and the USB topology path of the port, e.g.,
+///    `Canon EOS 5D Mark III@usb:1-1.2`. This identifies the port the camera is plugged into,
+///    for cameras that don't report a serial number.
+/// 3. The model and the port path, e.g., `Nikon DSC D750@ptpip:192.168.1.10`. This is stable for
+///    network cameras, but not for USB cameras on other systems.
+///
+/// Stable IDs can be stored in configuration files and used to open the camera later with
+/// `Camera::open_stable()`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StableId {
+    id: String,
+}
+
+impl StableId {
+    /// Derives the stable ID of a camera model on a port.
+    pub(crate) fn new(model: &str, port: &str) -> Self {
+        let suffix = usb_stable_suffix(port).unwrap_or_else(|| port.to_owned());
+
+        StableId {
+            id: format!("{}@{}", model, suffix),
+        }
+    }
+
+    /// Returns the ID as a string.
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+}
+
+impl From<&str> for StableId {
+    fn from(id: &str) -> Self {
+        StableId { id: id.to_owned() }
+    }
+}
+
+impl fmt::Display for StableId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.id)
+    }
+}
+
+impl DetectedCamera {
+    /// Returns an identifier of the camera that doesn't change when it is reconnected.
+    pub fn stable_id(&self) -> StableId {
+        StableId::new(self.model(), self.port())
+    }
+}
+
+impl Camera {
+    /// Returns an identifier of the camera that doesn't change when it is reconnected.
+    ///
+    /// The ID is the same as the `DetectedCamera::stable_id()` of the detected camera.
+    pub fn stable_id(&self) -> StableId {
+        StableId::new(&self.abilities().model(), &self.port().path())
+    }
+
+    /// Opens a detected camera by its stable ID.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::{Camera, StableId};
+    ///
+    /// let id = StableId::from("Canon EOS 5D Mark III@serial:0123456789");
+    /// let mut camera = Camera::open_stable(&id).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera could not be opened:
+    ///
+    /// * `ModelNotFound` if no detected camera has the ID.
+    pub fn open_stable(id: &StableId) -> crate::Result<Self> {
+        match detect_cameras()?
+            .iter()
+            .find(|camera| camera.stable_id() == *id)
+        {
+            Some(camera) => camera.open(),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_MODEL_NOT_FOUND,
+            )),
+        }
+    }
+}
+
+/// Returns the serial number or topology part of the stable ID of a USB port.
+#[cfg(target_os = "linux")]
+fn usb_stable_suffix(port: &str) -> Option<String> {
+    let device = usb_device(port)?;
+
+    if let Some(serial) = read_attribute(&device, "serial") {
+        if !serial.is_empty() {
+            return Some(format!("serial:{}", serial));
+        }
+    }
+
+    let topology = device.file_name()?.to_str()?;
+
+    Some(format!("usb:{}", topology))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn usb_stable_suffix(_port: &str) -> Option<String> {
+    None
+}
+
+/// Finds the sysfs directory of the USB device on a port like `usb:020,007`.
+#[cfg(target_os = "linux")]
+fn usb_device(port: &str) -> Option<PathBuf> {
+    let mut numbers = port.strip_prefix("usb:")?.split(',');
+    let bus: u32 = numbers.next()?.parse().ok()?;
+    let device: u32 = numbers.next()?.parse().ok()?;
+
+    fs::read_dir(SYSFS_USB_DEVICES)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            let number = |name: &str| -> Option<u32> {
+                read_attribute(path, name).and_then(|value| value.parse().ok())
+            };
+
+            number("busnum") == Some(bus) && number("devnum") == Some(device)
+        })
+}
+
+#[cfg(target_os = "linux")]
+fn read_attribute(device: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(device.join(name))
+        .ok()
+        .map(|value| value.trim().to_owned())
+}
@@ -0,0 +1,223 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::widget::{Config, WidgetValue};
+
+/// Names of the settings that indicate movie recording in different drivers.
+const RECORDING_SETTINGS: &[&str] = &["movie", "movierecording"];
+
+/// Names of the settings that contain the movie recording target in different drivers.
+const RECORD_TARGET_SETTINGS: &[&str] = &["movierecordtarget"];
+
+/// Names of the settings that contain the autofocus status in different drivers.
+const FOCUS_SETTINGS: &[&str] = &["focusinfo", "afstatus", "focusfound"];
+
+/// Names of the settings that contain the remaining recording time in different drivers.
+const REMAINING_TIME_SETTINGS: &[&str] = &["movieremainingtime", "remainingrecordingtime"];
+
+/// Names of the settings that contain the number of shots that fit on the card in different
+/// drivers.
+const REMAINING_SHOTS_SETTINGS: &[&str] = &["availableshots", "shotsremaining"];
+
+/// Names of the settings that contain the number of shots that fit into the camera's buffer in
+/// different drivers.
+const BUFFER_SETTINGS: &[&str] = &["bufferspace", "remainingbuffer", "buffercapacity"];
+
+/// How often `wait_for_buffer()` polls the buffer space.
+const BUFFER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A snapshot of a camera's status.
+///
+/// Status snapshots are read with `Camera::status()`. Each field is optional, because drivers
+/// expose different subsets of the status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraStatus {
+    recording: Option<bool>,
+    record_target: Option<String>,
+    focus: Option<String>,
+    remaining_recording_time: Option<Duration>,
+}
+
+impl CameraStatus {
+    /// Returns `true` if the camera is recording a movie.
+    pub fn is_recording(&self) -> Option<bool> {
+        self.recording
+    }
+
+    /// Where movies are recorded to, e.g., `"Card"` or `"None"`.
+    pub fn record_target(&self) -> Option<&str> {
+        self.record_target.as_deref()
+    }
+
+    /// The autofocus status as reported by the driver.
+    ///
+    /// The format of the status is specific to the driver.
+    pub fn focus(&self) -> Option<&str> {
+        self.focus.as_deref()
+    }
+
+    /// The time that can still be recorded.
+    pub fn remaining_recording_time(&self) -> Option<Duration> {
+        self.remaining_recording_time
+    }
+}
+
+/// The camera's counters of remaining shots.
+///
+/// Counters are read with `Camera::capture_counters()`. Each counter is optional, because
+/// drivers expose different subsets of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureCounters {
+    remaining_shots: Option<u32>,
+    buffer_space: Option<u32>,
+}
+
+impl CaptureCounters {
+    /// The number of shots that still fit on the memory card.
+    pub fn remaining_shots(&self) -> Option<u32> {
+        self.remaining_shots
+    }
+
+    /// The number of shots that still fit into the camera's internal buffer before a burst
+    /// slows down to the speed of the memory card.
+    pub fn buffer_space(&self) -> Option<u32> {
+        self.buffer_space
+    }
+}
+
+impl Camera {
+    /// Reads the camera's counters of remaining shots.
+    ///
+    /// The counters are read setting by setting, which is much faster than reading the whole
+    /// configuration, so they can be polled between the shots of a burst. Counters that can't be
+    /// read are `None`.
+    pub fn capture_counters(&mut self) -> CaptureCounters {
+        CaptureCounters {
+            remaining_shots: self.counter(REMAINING_SHOTS_SETTINGS),
+            buffer_space: self.counter(BUFFER_SETTINGS),
+        }
+    }
+
+    /// Waits until the camera's buffer has space for at least `shots` shots.
+    ///
+    /// Triggering captures faster than the camera can empty its buffer makes it report busy
+    /// errors. Waiting for buffer space throttles a burst to the rate that the camera sustains.
+    /// Returns `true` if there is enough space, and `false` if the timeout expired first. Cameras
+    /// that don't report their buffer space are assumed to have enough.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// for _ in 0..50 {
+    ///     camera.wait_for_buffer(1, Duration::from_secs(5));
+    ///     camera.trigger_capture().unwrap();
+    /// }
+    /// ```
+    pub fn wait_for_buffer(&mut self, shots: u32, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            match self.capture_counters().buffer_space {
+                Some(space) if space < shots => (),
+                _ => return true,
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return false;
+            }
+
+            thread::sleep(BUFFER_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    fn counter(&mut self, names: &[&str]) -> Option<u32> {
+        names.iter().find_map(|name| {
+            let config = self.single_config(name).ok()?;
+
+            match config.root().value()? {
+                WidgetValue::Range(count) if count >= 0.0 => Some(count as u32),
+                WidgetValue::Text(count) | WidgetValue::Choice(count) => count.trim().parse().ok(),
+                _ => None,
+            }
+        })
+    }
+
+    /// Reads a snapshot of the camera's status.
+    ///
+    /// The status is read from the camera's read-only status settings, so it reflects the
+    /// camera's state at the time of the call, including changes made on the camera body that
+    /// weren't reported as events.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let status = camera.status().unwrap();
+    ///
+    /// if status.is_recording() == Some(true) {
+    ///     println!("recording, {:?} left", status.remaining_recording_time());
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera's configuration could not be read:
+    ///
+    /// * `NotSupported` if the camera can not be configured.
+    pub fn status(&mut self) -> crate::Result<CameraStatus> {
+        Ok(CameraStatus::from_config(&self.config()?))
+    }
+}
+
+impl CameraStatus {
+    /// Reads the status from a camera's configuration.
+    pub(crate) fn from_config(config: &Config) -> Self {
+        let recording = find_value(config, RECORDING_SETTINGS).and_then(|value| match value {
+            WidgetValue::Toggle(recording) => Some(recording),
+            WidgetValue::Range(value) => Some(value != 0.0),
+            WidgetValue::Text(value) | WidgetValue::Choice(value) => {
+                match value.to_ascii_lowercase().as_str() {
+                    "on" | "1" | "recording" => Some(true),
+                    "off" | "0" => Some(false),
+                    _ => None,
+                }
+            }
+            _ => None,
+        });
+
+        let remaining_recording_time = find_value(config, REMAINING_TIME_SETTINGS)
+            .and_then(|value| match value {
+                WidgetValue::Range(seconds) if seconds >= 0.0 => Some(seconds as u64),
+                WidgetValue::Text(value) | WidgetValue::Choice(value) => value.trim().parse().ok(),
+                _ => None,
+            })
+            .map(Duration::from_secs);
+
+        CameraStatus {
+            recording,
+            record_target: find_text(config, RECORD_TARGET_SETTINGS),
+            focus: find_text(config, FOCUS_SETTINGS),
+            remaining_recording_time,
+        }
+    }
+}
+
+/// Returns the value of the first of the named settings that has a value.
+fn find_value(config: &Config, names: &[&str]) -> Option<WidgetValue> {
+    names.iter().find_map(|name| config.value(name))
+}
+
+/// Returns the value of the first of the named settings that has a text value.
+pub(crate) fn find_text(config: &Config, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| match config.value(name) {
+        Some(WidgetValue::Text(value)) | Some(WidgetValue::Choice(value)) => Some(value),
+        _ => None,
+    })
+}
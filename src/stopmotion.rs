@@ -0,0 +1,325 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::camera::Camera;
+use crate::media::FileMedia;
+#[cfg(feature = "image")]
+use crate::overlay::Overlay;
+
+/// The prefix of the names of stop-motion frames.
+const PREFIX: &str = "frame_";
+
+/// The width that frames are scaled down to for onion skinning.
+#[cfg(feature = "image")]
+const ONION_SKIN_WIDTH: u32 = 1024;
+
+/// A stop-motion session that captures numbered frames into a directory.
+///
+/// Frames are downloaded as `frame_0001.jpg`, `frame_0002.jpg`, etc. A session that is started
+/// in a directory that already contains frames continues the sequence, so an animation can be
+/// shot over several sessions.
+///
+/// With the `image` feature enabled, `preview()` blends the last frame into the live view
+/// (onion skinning), which helps to position the next pose.
+///
+/// Sessions are started with `Camera::stop_motion()`.
+///
+/// ## Example
+///
+/// ```no_run
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut session = camera.stop_motion("animation".as_ref()).unwrap();
+///
+/// for _ in 0..24 {
+///     // show session.preview() to the animator, wait for them to pose the scene...
+///     let path = session.capture().unwrap();
+///     println!("captured {}", path.display());
+/// }
+///
+/// // the last pose was wrong
+/// session.retake().unwrap();
+/// ```
+pub struct StopMotion<'a> {
+    camera: &'a mut Camera,
+    dir: PathBuf,
+    frames: Vec<PathBuf>,
+    media: FileMedia,
+    #[cfg(feature = "image")]
+    overlay: Overlay,
+    #[cfg(feature = "image")]
+    onion_skin: bool,
+}
+
+impl<'a> StopMotion<'a> {
+    /// Returns the paths of the frames, in order.
+    pub fn frames(&self) -> &[PathBuf] {
+        &self.frames
+    }
+
+    /// Returns the directory that frames are captured into.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Enables or disables blending the last frame into previews.
+    ///
+    /// Onion skinning is enabled by default.
+    ///
+    /// This function is only available with the `image` feature enabled.
+    #[cfg(feature = "image")]
+    pub fn set_onion_skin(&mut self, enabled: bool) {
+        self.onion_skin = enabled;
+        self.load_onion_skin();
+    }
+
+    /// Replaces the overlay that is drawn onto previews, e.g., to add a grid.
+    ///
+    /// The overlay's ghost image is replaced by the last frame, or removed if onion skinning is
+    /// disabled.
+    ///
+    /// This function is only available with the `image` feature enabled.
+    #[cfg(feature = "image")]
+    pub fn set_overlay(&mut self, overlay: Overlay) {
+        self.overlay = overlay;
+        self.load_onion_skin();
+    }
+
+    /// Captures a live view frame.
+    ///
+    /// With the `image` feature enabled, the frame is composited with the session's overlay and,
+    /// if onion skinning is enabled, the last frame, and re-encoded as JPEG.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be captured:
+    ///
+    /// * `NotSupported` if the camera does not support capturing previews.
+    /// * `CorruptedData` if the frame isn't a valid JPEG image (only with the `image` feature).
+    pub fn preview(&mut self) -> crate::Result<Vec<u8>> {
+        self.camera.capture_preview(&mut self.media)?;
+        let frame = self.media.get_data();
+
+        #[cfg(feature = "image")]
+        let frame = self.overlay.apply_jpeg(&frame, 85)?;
+
+        Ok(frame)
+    }
+
+    /// Captures the next frame and downloads it into the session's directory.
+    ///
+    /// Returns the path of the frame. Its number follows the highest number in the sequence, so
+    /// that a directory with gaps in its numbering doesn't have existing frames overwritten.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be captured or downloaded.
+    pub fn capture(&mut self) -> crate::Result<PathBuf> {
+        let file = self.camera.capture_image()?;
+        let number = self
+            .frames
+            .last()
+            .and_then(|path| frame_number(path))
+            .map_or(1, |number| number + 1);
+        let path = self.frame_path(number, &file.basename());
+
+        self.camera.download_to_path(&file, &path, None)?;
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_download();
+        self.frames.push(path.clone());
+
+        #[cfg(feature = "image")]
+        self.load_onion_skin();
+
+        Ok(path)
+    }
+
+    /// Deletes the last frame and captures it again.
+    ///
+    /// Returns the path of the new frame.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be deleted, captured or downloaded.
+    pub fn retake(&mut self) -> crate::Result<PathBuf> {
+        if !self.frames.is_empty() {
+            self.delete(self.frames.len() - 1)?;
+        }
+
+        self.capture()
+    }
+
+    /// Deletes a frame by its index, starting at 0.
+    ///
+    /// Each of the following frames is renamed to the number of the frame before it, so that the
+    /// deleted frame doesn't leave a gap in the sequence.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be deleted:
+    ///
+    /// * `InvalidInput` if there is no frame with the index.
+    /// * `OSFailure` if a file could not be deleted or renamed.
+    pub fn delete(&mut self, index: usize) -> crate::Result<()> {
+        if index >= self.frames.len() {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            ));
+        }
+
+        let numbers: Vec<usize> = self
+            .frames
+            .iter()
+            .map(|path| frame_number(path).unwrap_or(0))
+            .collect();
+
+        if fs::remove_file(&self.frames[index]).is_err() {
+            return Err(os_failure());
+        }
+
+        self.frames.remove(index);
+
+        // The frame at each index takes the number of the frame that was there before.
+        for (following, &number) in numbers
+            .iter()
+            .enumerate()
+            .take(self.frames.len())
+            .skip(index)
+        {
+            let name = file_name(&self.frames[following]);
+            let path = self.frame_path(number, &name);
+
+            if fs::rename(&self.frames[following], &path).is_err() {
+                return Err(os_failure());
+            }
+
+            self.frames[following] = path;
+        }
+
+        #[cfg(feature = "image")]
+        self.load_onion_skin();
+
+        Ok(())
+    }
+
+    /// Returns the path of the frame with a number, keeping the extension of `name`.
+    fn frame_path(&self, number: usize, name: &str) -> PathBuf {
+        let name = match name.rfind('.') {
+            Some(dot) => format!(
+                "{}{:04}{}",
+                PREFIX,
+                number,
+                name[dot..].to_ascii_lowercase()
+            ),
+            None => format!("{}{:04}", PREFIX, number),
+        };
+
+        self.dir.join(name)
+    }
+
+    /// Makes the last frame the ghost image of the overlay, or removes the ghost image if onion
+    /// skinning is disabled.
+    ///
+    /// Frames that can't be decoded, e.g., RAW files, don't change the ghost image.
+    #[cfg(feature = "image")]
+    fn load_onion_skin(&mut self) {
+        if !self.onion_skin || self.frames.is_empty() {
+            self.overlay.clear_ghost();
+        } else if let Some(image) = onion_skin(&self.frames) {
+            self.overlay.set_ghost(image);
+        }
+    }
+}
+
+impl Camera {
+    /// Starts a stop-motion session that captures frames into a directory.
+    ///
+    /// The directory is created if it doesn't exist. Frames that it already contains are
+    /// continued. See `StopMotion` for details.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the directory could not be prepared:
+    ///
+    /// * `OSFailure` if the directory could not be created or read.
+    pub fn stop_motion(&mut self, dir: &Path) -> crate::Result<StopMotion<'_>> {
+        if fs::create_dir_all(dir).is_err() {
+            return Err(os_failure());
+        }
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return Err(os_failure()),
+        };
+
+        let mut frames: Vec<(usize, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter_map(|path| frame_number(&path).map(|number| (number, path)))
+            .collect();
+
+        frames.sort();
+
+        let frames: Vec<PathBuf> = frames.into_iter().map(|(_, path)| path).collect();
+
+        #[cfg(feature = "image")]
+        let mut overlay = Overlay::new();
+
+        #[cfg(feature = "image")]
+        {
+            if let Some(image) = onion_skin(&frames) {
+                overlay.set_ghost(image);
+            }
+        }
+
+        Ok(StopMotion {
+            camera: self,
+            dir: dir.to_owned(),
+            frames,
+            media: FileMedia::create_mem()?,
+            #[cfg(feature = "image")]
+            overlay,
+            #[cfg(feature = "image")]
+            onion_skin: true,
+        })
+    }
+}
+
+/// Decodes the last frame, scaled down for onion skinning.
+///
+/// Returns `None` if the frame can't be decoded, e.g., if it is a RAW file.
+#[cfg(feature = "image")]
+fn onion_skin(frames: &[PathBuf]) -> Option<image::RgbImage> {
+    let image = image::open(frames.last()?).ok()?;
+
+    Some(
+        image
+            .thumbnail(ONION_SKIN_WIDTH, ONION_SKIN_WIDTH)
+            .to_rgb8(),
+    )
+}
+
+/// Returns the number of a frame file like `frame_0042.jpg`.
+///
+/// Numbers have at least four digits; frames after `frame_9999` have more.
+fn frame_number(path: &Path) -> Option<usize> {
+    let name = path.file_name()?.to_str()?;
+    let rest = name.strip_prefix(PREFIX)?;
+    let digits = rest.split('.').next()?;
+
+    if digits.len() >= 4 && digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn os_failure() -> crate::Error {
+    crate::error::from_libgphoto2(crate::gphoto2::GP_ERROR_OS_FAILURE)
+}
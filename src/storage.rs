@@ -1,5 +1,6 @@
 #[cfg(not(feature = "std"))]
 use alloc::borrow::Cow;
+use core::ops::BitOr;
 use cstr_core::CStr;
 #[cfg(feature = "std")]
 use std::borrow::Cow;
@@ -11,10 +12,9 @@ use std::borrow::Cow;
 /// A `Storage` object can be used to retrieve information about a camera's storage:
 ///
 /// ```no_run
-/// let mut context = gphoto::Context::new().unwrap();
-/// let mut camera = gphoto::Camera::autodetect(&mut context).unwrap();
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
 ///
-/// for storage in camera.storage(&mut context).unwrap() {
+/// for storage in camera.storage().unwrap() {
 ///     println!("       base dir = {:?}", storage.base_dir());
 ///     println!("          label = {:?}", storage.label());
 ///     println!("    description = {:?}", storage.description());
@@ -46,7 +46,30 @@ pub struct Storage {
 }
 
 impl Storage {
+    /// The fields that the camera reported for the storage.
+    ///
+    /// The accessors of fields that weren't reported return `None`. This distinguishes, e.g., a
+    /// full card from a camera that doesn't report the free space:
+    ///
+    /// ```no_run
+    /// use gphoto::StorageFields;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// for storage in camera.storage().unwrap() {
+    ///     if !storage.reported_fields().contains(StorageFields::FREE_KBYTES) {
+    ///         println!("{:?}: free space unknown", storage.label());
+    ///     }
+    /// }
+    /// ```
+    pub fn reported_fields(&self) -> StorageFields {
+        StorageFields(self.inner.fields as u32)
+    }
+
     /// Base directory of the storage.
+    ///
+    /// This is the root folder of the storage's files, e.g., `/store_00010001`. Cameras with
+    /// several storages have a separate base directory for each of them.
     pub fn base_dir(&self) -> Option<Cow<str>> {
         if self.inner.fields & crate::gphoto2::GP_STORAGEINFO_BASE != 0 {
             Some(unsafe {
@@ -151,10 +174,123 @@ impl Storage {
             None
         }
     }
+
+    /// Estimates the number of images that fit in the storage's remaining space.
+    ///
+    /// The estimate is computed from the free space and the average size of an image in
+    /// kilobytes, which depends on the current image format and can be measured from previous
+    /// captures. If the storage doesn't report its free space or `average_kbytes` is zero, the
+    /// camera's own estimate from `free_images()` is returned instead.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// for storage in camera.storage().unwrap() {
+    ///     // RAW files of about 25 MB
+    ///     println!("{:?}", storage.estimated_remaining_shots(25_000));
+    /// }
+    /// ```
+    pub fn estimated_remaining_shots(&self, average_kbytes: u64) -> Option<u64> {
+        match self.free_kbytes() {
+            Some(free_kbytes) if average_kbytes > 0 => Some(free_kbytes / average_kbytes),
+            _ => self.free_images(),
+        }
+    }
+
+    /// Returns `true` if the storage is a removable card.
+    ///
+    /// Returns `None` if the storage doesn't report its hardware type.
+    pub fn is_removable(&self) -> Option<bool> {
+        self.storage_type().map(|storage_type| match storage_type {
+            StorageType::RemovableRom | StorageType::RemoveableRam => true,
+            StorageType::FixedRom | StorageType::FixedRam | StorageType::Unknown => false,
+        })
+    }
+
+    /// Returns `true` if files can be written to the storage.
+    ///
+    /// Returns `None` if the storage doesn't report its access permissions.
+    pub fn is_writable(&self) -> Option<bool> {
+        self.access_type()
+            .map(|access_type| access_type == AccessType::ReadWrite)
+    }
+}
+
+/// A set of storage information fields.
+///
+/// The fields that a camera reported for a storage are returned by `Storage::reported_fields()`.
+/// Sets can be combined with `|`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct StorageFields(u32);
+
+impl StorageFields {
+    /// The base directory.
+    pub const BASE_DIR: StorageFields = StorageFields(crate::gphoto2::GP_STORAGEINFO_BASE as u32);
+
+    /// The label.
+    pub const LABEL: StorageFields = StorageFields(crate::gphoto2::GP_STORAGEINFO_LABEL as u32);
+
+    /// The description.
+    pub const DESCRIPTION: StorageFields =
+        StorageFields(crate::gphoto2::GP_STORAGEINFO_DESCRIPTION as u32);
+
+    /// The access permissions.
+    pub const ACCESS_TYPE: StorageFields =
+        StorageFields(crate::gphoto2::GP_STORAGEINFO_ACCESS as u32);
+
+    /// The hardware type.
+    pub const STORAGE_TYPE: StorageFields =
+        StorageFields(crate::gphoto2::GP_STORAGEINFO_STORAGETYPE as u32);
+
+    /// The filesystem hierarchy type.
+    pub const FILESYSTEM_TYPE: StorageFields =
+        StorageFields(crate::gphoto2::GP_STORAGEINFO_FILESYSTEMTYPE as u32);
+
+    /// The total capacity.
+    pub const CAPACITY_KBYTES: StorageFields =
+        StorageFields(crate::gphoto2::GP_STORAGEINFO_MAXCAPACITY as u32);
+
+    /// The free space.
+    pub const FREE_KBYTES: StorageFields =
+        StorageFields(crate::gphoto2::GP_STORAGEINFO_FREESPACEKBYTES as u32);
+
+    /// The estimated number of free images.
+    pub const FREE_IMAGES: StorageFields =
+        StorageFields(crate::gphoto2::GP_STORAGEINFO_FREESPACEIMAGES as u32);
+
+    /// Returns an empty set.
+    pub fn empty() -> Self {
+        StorageFields(0)
+    }
+
+    /// Returns the raw bitmask of `libgphoto2`'s `CameraStorageInfoFields`.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if the set contains all fields of `other`.
+    pub fn contains(&self, other: StorageFields) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if the set doesn't contain any fields.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for StorageFields {
+    type Output = StorageFields;
+
+    fn bitor(self, other: StorageFields) -> StorageFields {
+        StorageFields(self.0 | other.0)
+    }
 }
 
 /// Types of storage hardware.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum StorageType {
     /// A fixed ROM storage.
     FixedRom,
@@ -175,7 +311,7 @@ pub enum StorageType {
 }
 
 /// Types of filesystem hierarchies.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum FilesystemType {
     /// All files stored in one directory.
     Flat,
@@ -195,7 +331,7 @@ pub enum FilesystemType {
 }
 
 /// Types of access permissions.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum AccessType {
     /// Read and write operations are allowed.
     ReadWrite,
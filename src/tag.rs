@@ -0,0 +1,62 @@
+use crate::camera::Camera;
+
+/// Decodes a session tag, e.g., a barcode or QR code, from a preview frame.
+///
+/// Decoders are installed on a `PreviewStream` with `set_tag_decoder()`. Each frame is passed to
+/// the decoder, and a decoded value becomes the camera's session tag, which is prepended to the
+/// names of files that are downloaded with `Camera::download_to_dir()`. In event photography,
+/// this attributes the following shots to the guest who showed their ticket to the camera.
+///
+/// Closures that take the JPEG data of a frame and return the decoded value are decoders.
+pub trait TagDecoder {
+    /// Decodes a tag from the JPEG data of a preview frame.
+    ///
+    /// Returns `None` if the frame doesn't contain a tag; the current session tag is kept.
+    fn decode(&mut self, frame: &[u8]) -> Option<String>;
+}
+
+impl<F> TagDecoder for F
+where
+    F: FnMut(&[u8]) -> Option<String>,
+{
+    fn decode(&mut self, frame: &[u8]) -> Option<String> {
+        self(frame)
+    }
+}
+
+impl Camera {
+    /// Returns the session tag that is prepended to the names of downloaded files.
+    pub fn session_tag(&self) -> Option<&str> {
+        self.session_tag.as_deref()
+    }
+
+    /// Sets or clears the session tag that is prepended to the names of downloaded files.
+    ///
+    /// Files are downloaded by `download_to_dir()` as `<tag>_<name>`, e.g., `A1234_IMG_0001.JPG`.
+    /// Characters other than ASCII letters, digits, `-` and `_` are replaced by `_`, so that tags
+    /// can't name other directories.
+    pub fn set_session_tag(&mut self, tag: Option<&str>) {
+        self.session_tag = tag.map(sanitize).filter(|tag| !tag.is_empty());
+    }
+}
+
+/// Returns the local name of a downloaded file with the session tag prepended.
+pub(crate) fn tagged_name(tag: Option<&str>, name: &str) -> String {
+    match tag {
+        Some(tag) => format!("{}_{}", tag, name),
+        None => name.to_owned(),
+    }
+}
+
+fn sanitize(tag: &str) -> String {
+    tag.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
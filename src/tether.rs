@@ -0,0 +1,999 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::camera::{file_from_parts, Camera, CameraFile};
+use crate::clock::{Clock, SystemClock};
+use crate::context::CancelHandle;
+use crate::download::{Collision, DownloadOptions};
+use crate::event::CameraEvent;
+use crate::fileinfo::FileInfo;
+use crate::hooks::{Hook, HookFailure, Hooks};
+use crate::journal::{journal_error, Journal, JournalEntry, TransferState};
+use crate::recovery::Recovery;
+use crate::tag::tagged_name;
+
+/// How long the tether waits for an event before it checks whether it was stopped.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long the tether waits before it retries a failed operation.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How many times the default recovery policy runs a failed operation.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// A policy that decides how a tether recovers from a failure.
+type OnError = Box<dyn FnMut(&crate::Error, u32) -> Recovery + Send>;
+
+/// Options for a `Tether`.
+#[derive(Default)]
+pub struct TetherOptions {
+    journal: Option<Box<dyn Journal + Send>>,
+    download: DownloadOptions,
+    hooks: Hooks,
+    on_error: Option<OnError>,
+    clock: Option<Box<dyn Clock + Send>>,
+}
+
+impl TetherOptions {
+    /// Creates the default options, which don't record the tether's downloads.
+    pub fn new() -> Self {
+        TetherOptions::default()
+    }
+
+    /// Records the tether's downloads in a journal, like `Camera::wait_event_journaled()`.
+    ///
+    /// When a tether is started with a journal of an earlier session, e.g., one that ended with a
+    /// crash or a power loss, it first downloads the files whose downloads hadn't completed, like
+    /// `Camera::resume_journaled()`, and then the files that the camera added to the storage of
+    /// the last recorded file while no tether was running. Added files are found by their
+    /// modification time, so files in a new folder, e.g., after the camera rolled over from
+    /// `100CANON` to `101CANON`, and files whose number wrapped around aren't missed.
+    pub fn journal<J: Journal + Send + 'static>(mut self, journal: J) -> Self {
+        self.journal = Some(Box::new(journal));
+        self
+    }
+
+    /// Sets the options that added files are downloaded with.
+    ///
+    /// The options' `Collision` policy decides what happens when a file's name is already taken
+    /// in the destination directory, e.g., because the camera reused `IMG_0001.JPG` after its
+    /// file counter was reset. With the default options, such a download fails with `FileExists`
+    /// and the file is left on the camera.
+    pub fn download_options(mut self, options: DownloadOptions) -> Self {
+        self.download = options;
+        self
+    }
+
+    /// Runs a pipeline of hooks for each downloaded file, e.g., to generate thumbnails or to
+    /// upload files.
+    ///
+    /// The hooks run on the tether's thread after each download, before the file is passed to
+    /// `recv()`. A failing hook doesn't stop the tether: its failure is collected and returned by
+    /// `Tether::take_hook_failures()`. Downloads that are skipped by the `Collision` policy don't
+    /// run the hooks.
+    pub fn hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Adds a named channel that delivers each downloaded file to another destination, e.g., a
+    /// backup directory with `DirSink`.
+    ///
+    /// Channels are added to the end of the tether's hooks with `Hooks::add_channel()`. Each
+    /// channel fails on its own, so a full backup drive is reported by
+    /// `Tether::take_hook_failures()` with the channel's name, while the download to the main
+    /// directory and the other channels carry on.
+    pub fn channel<H: Hook + Send + 'static>(mut self, name: &str, hook: H) -> Self {
+        self.hooks.add_channel(name, hook);
+        self
+    }
+
+    /// Decides how the tether recovers from each failure, like the `on_error` callback of
+    /// `Camera::run_with_recovery()`.
+    ///
+    /// `on_error` is called with each error of waiting for an event or of downloading a file, and
+    /// the number of the failed attempt, starting at 1:
+    ///
+    /// * `Retry` waits a second on the tether's clock and runs the operation again.
+    /// * `Reinit` reinitializes the camera and runs the operation again. Errors from
+    ///   reinitializing the camera are passed to `on_error` like errors from the operation.
+    /// * `Skip` passes the error to `recv()` and carries on with the next event.
+    /// * `Abort` passes the error to `recv()` and stops the tether.
+    ///
+    /// By default, errors are recovered with `Recovery::for_error()` for up to three attempts.
+    /// After that, or if an error can't be recovered, a failed download is skipped, and a failed
+    /// wait for an event stops the tether.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::{ErrorKind, Recovery, TetherOptions};
+    ///
+    /// let options = TetherOptions::new().on_error(|err, attempt| match err.kind() {
+    ///     ErrorKind::NoSpace => Recovery::Abort,
+    ///     _ if attempt < 5 => Recovery::for_error(err),
+    ///     _ => Recovery::Skip,
+    /// });
+    /// ```
+    pub fn on_error<F>(mut self, on_error: F) -> Self
+    where
+        F: FnMut(&crate::Error, u32) -> Recovery + Send + 'static,
+    {
+        self.on_error = Some(Box::new(on_error));
+        self
+    }
+
+    /// Sets the clock that the tether waits with, e.g., a `SimulatedClock`.
+    ///
+    /// The clock times the pause before an operation is retried, and the rest of the poll
+    /// interval after a wait for an event failed. `SystemClock` is used by default.
+    pub fn clock<C: Clock + Send + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+}
+
+/// Downloads the files that a camera adds, e.g., when its shutter is released, on a background
+/// thread.
+///
+/// The tether owns the camera and waits for its `FileAdded` events. Each added file is
+/// downloaded to a local directory with `Camera::download_to_dir()` and the tether's
+/// `DownloadOptions`, and the result of each download is passed to the tether's owner through
+/// `recv()`. Downloads are written to a temporary file first, so an interrupted download never
+/// leaves a partial file under the file's name, and existing files in the directory are never
+/// removed.
+///
+/// Each downloaded file can be fanned out to more destinations, e.g., a backup drive, with
+/// `TetherOptions::channel()`.
+///
+/// `shutdown()` stops the tether and closes the camera. Dropping the tether does the same.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::{Collision, DirSink, DownloadOptions, FileJournal, Tether, TetherOptions};
+///
+/// let camera = gphoto::Camera::autodetect().unwrap();
+/// let journal = FileJournal::open("tether.journal".as_ref()).unwrap();
+/// let download = DownloadOptions::new().collision(Collision::Rename);
+/// let options = TetherOptions::new()
+///     .journal(journal)
+///     .download_options(download)
+///     .channel("backup", DirSink::new("/mnt/backup/photos".as_ref()));
+///
+/// let tether = Tether::start(camera, "photos".as_ref(), options).unwrap();
+///
+/// // Downloads ten files, and stops.
+/// for result in std::iter::from_fn(|| tether.recv()).take(10) {
+///     match result {
+///         Ok(path) => println!("downloaded {}", path.display()),
+///         Err(err) => println!("download failed: {}", err),
+///     }
+/// }
+///
+/// for failure in tether.take_hook_failures() {
+///     println!("{}", failure);
+/// }
+///
+/// tether.shutdown().unwrap();
+/// ```
+pub struct Tether {
+    files: Receiver<crate::Result<PathBuf>>,
+    hook_failures: Arc<Mutex<Vec<HookFailure>>>,
+    stop: Arc<AtomicBool>,
+    cancel: CancelHandle,
+    worker: Option<JoinHandle<Camera>>,
+}
+
+impl Drop for Tether {
+    fn drop(&mut self) {
+        let _ = self.stop_worker();
+    }
+}
+
+impl Tether {
+    /// Starts downloading the files that a camera adds to a local directory.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the tether could not be started:
+    ///
+    /// * `OSFailure` if the journal could not be read, or the operating system refused to start a
+    ///   thread.
+    pub fn start(camera: Camera, dest_dir: &Path, options: TetherOptions) -> crate::Result<Tether> {
+        Tether::spawn(camera, dest_dir, options, Vec::new())
+    }
+
+    /// Starts a tether on a simulated camera that serves the files in a local directory, e.g., to
+    /// develop the storage or the user interface of a tethering application without hardware.
+    ///
+    /// The camera is opened with `Camera::simulated()`. The files in the directory are passed
+    /// through the whole pipeline, i.e., downloaded to `dest_dir`, run through the hooks and
+    /// recorded in the journal, in the order of their modification time, as if the camera had
+    /// added them. Files that the journal records from an earlier session aren't passed again.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::{Tether, TetherOptions};
+    ///
+    /// let source = "testdata/DCIM".as_ref();
+    /// let tether = Tether::simulated(source, "photos".as_ref(), TetherOptions::new()).unwrap();
+    ///
+    /// while let Some(result) = tether.recv() {
+    ///     println!("{:?}", result);
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the errors of `Camera::simulated()`, `CameraFs::walk()` and
+    /// `start()`.
+    pub fn simulated(
+        source_dir: &Path,
+        dest_dir: &Path,
+        options: TetherOptions,
+    ) -> crate::Result<Tether> {
+        let mut camera = Camera::simulated(source_dir)?;
+        let files = simulated_files(&mut camera)?;
+
+        Tether::spawn(camera, dest_dir, options, files)
+    }
+
+    /// Starts a tether that passes `replay` through the pipeline before it waits for events.
+    fn spawn(
+        camera: Camera,
+        dest_dir: &Path,
+        options: TetherOptions,
+        replay: Vec<CameraFile>,
+    ) -> crate::Result<Tether> {
+        let mut journal = options.journal.unwrap_or_else(|| Box::new(NoJournal));
+        let entries = journal.entries().map_err(|_| journal_error())?;
+
+        let (sender, files) = mpsc::channel();
+        let hook_failures = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let cancel = camera.cancel_handle();
+
+        let session = Session {
+            camera,
+            dest_dir: dest_dir.to_owned(),
+            download: options.download,
+            hooks: options.hooks,
+            on_error: options.on_error,
+            clock: options.clock.unwrap_or_else(|| Box::new(SystemClock)),
+            journal,
+            entries,
+            replay,
+            files: sender,
+            hook_failures: hook_failures.clone(),
+            stop: stop.clone(),
+        };
+
+        let worker = thread::Builder::new()
+            .name("gphoto-tether".to_owned())
+            .spawn(move || session.run())
+            .map_err(|_| os_failure())?;
+
+        Ok(Tether {
+            files,
+            hook_failures,
+            stop,
+            cancel,
+            worker: Some(worker),
+        })
+    }
+
+    /// Blocks until the next file was downloaded, and returns its local path.
+    ///
+    /// Files whose download is skipped by the `Collision` policy aren't returned.
+    /// Returns an error for each download that failed, and `None` when the tether has stopped,
+    /// e.g., because the camera was disconnected. The error that stopped the tether is returned
+    /// before `None`.
+    pub fn recv(&self) -> Option<crate::Result<PathBuf>> {
+        self.files.recv().ok()
+    }
+
+    /// Returns the next downloaded file if one is available, without blocking.
+    pub fn try_recv(&self) -> Option<crate::Result<PathBuf>> {
+        self.files.try_recv().ok()
+    }
+
+    /// Returns the failures of the tether's hooks since the last call, and clears them.
+    ///
+    /// See `TetherOptions::hooks()`.
+    pub fn take_hook_failures(&self) -> Vec<HookFailure> {
+        match self.hook_failures.lock() {
+            Ok(mut failures) => std::mem::take(&mut *failures),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        }
+    }
+
+    /// Stops the tether and closes the camera.
+    ///
+    /// The download in progress, if any, is cancelled. It stays unfinished in the journal, so that
+    /// the next tether with the same journal downloads it again.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the tether could not be stopped cleanly:
+    ///
+    /// * `OSFailure` if the tether's thread panicked.
+    pub fn shutdown(mut self) -> crate::Result<()> {
+        self.stop_worker()
+    }
+
+    fn stop_worker(&mut self) -> crate::Result<()> {
+        let worker = match self.worker.take() {
+            Some(worker) => worker,
+            None => return Ok(()),
+        };
+
+        self.stop.store(true, Ordering::SeqCst);
+        self.cancel.cancel();
+
+        let result = worker.join();
+
+        // A request that wasn't consumed by an operation would cancel closing the connection.
+        self.cancel.reset();
+
+        match result {
+            Ok(camera) => {
+                drop(camera);
+                Ok(())
+            }
+            Err(_) => Err(os_failure()),
+        }
+    }
+}
+
+impl Camera {
+    /// Waits for an event from the camera, and downloads the file if the camera reports an added
+    /// file, recording the transfer in a journal.
+    ///
+    /// This is a step of a tethered session that survives crashes: an added file is recorded as
+    /// planned before it is downloaded, and as completed or failed afterwards. After a crash or a
+    /// power loss, `resume_journaled()` downloads the files that were planned but not completed,
+    /// so no file is missed or downloaded twice. The journal's last entry records the last event
+    /// that was processed. See `Journal` for details.
+    ///
+    /// Files are downloaded to `dest_dir` with `download_to_dir()`, and the pipeline of hooks is
+    /// run for each of them. The event is returned in any case, so that the caller can handle
+    /// the other events.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use gphoto::{DownloadOptions, FileJournal, Hooks};
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut journal = FileJournal::open("tether.journal".as_ref()).unwrap();
+    /// let mut hooks = Hooks::new();
+    /// let options = DownloadOptions::new();
+    /// let dir = "photos".as_ref();
+    ///
+    /// // Files that were added before a crash, but not downloaded, are downloaded first.
+    /// camera.resume_journaled(dir, &options, &mut hooks, &mut journal).unwrap();
+    ///
+    /// loop {
+    ///     let timeout = Duration::from_secs(1);
+    ///     camera
+    ///         .wait_event_journaled(timeout, dir, &options, &mut hooks, &mut journal)
+    ///         .unwrap();
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the errors of `wait_event()` and `download_to_dir()`, and:
+    ///
+    /// * `OSFailure` if the journal could not be written.
+    pub fn wait_event_journaled(
+        &mut self,
+        timeout: Duration,
+        dest_dir: &Path,
+        options: &DownloadOptions,
+        hooks: &mut Hooks,
+        journal: &mut dyn Journal,
+    ) -> crate::Result<CameraEvent> {
+        let event = self.wait_event(timeout)?;
+
+        if let CameraEvent::FileAdded(ref file) = event {
+            let info = self.file_info(file)?;
+            self.download_journaled(&info, dest_dir, options, hooks, journal, None)?;
+        }
+
+        Ok(event)
+    }
+
+    /// Downloads the files that a journal records as planned or failed, e.g., after a crash.
+    ///
+    /// The files are downloaded in the order they were first recorded, and their transfers are
+    /// recorded in the journal like with `wait_event_journaled()`. A file whose download was
+    /// interrupted is downloaded again to the local path that was planned for it, regardless of
+    /// the `Collision` policy, so a copy that was completed but not recorded is replaced instead
+    /// of kept twice. Files that no longer exist on the camera are recorded as failed once and
+    /// skipped.
+    ///
+    /// Returns the paths of the files that were downloaded by this call.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the errors of `download_to_dir()`, and:
+    ///
+    /// * `OSFailure` if the journal could not be read or written.
+    pub fn resume_journaled(
+        &mut self,
+        dest_dir: &Path,
+        options: &DownloadOptions,
+        hooks: &mut Hooks,
+        journal: &mut dyn Journal,
+    ) -> crate::Result<Vec<PathBuf>> {
+        let entries = journal.entries().map_err(|_| journal_error())?;
+        let mut downloaded = Vec::new();
+
+        for (path, planned) in unfinished_transfers(&entries) {
+            let resumed =
+                self.resume_file(&path, planned.as_deref(), dest_dir, options, hooks, journal)?;
+            downloaded.extend(resumed);
+        }
+
+        Ok(downloaded)
+    }
+
+    /// Downloads a file that a journal records as unfinished, and records its transfer.
+    ///
+    /// Returns `None` if the file no longer exists on the camera, or if the `Collision` policy
+    /// skipped its download.
+    fn resume_file(
+        &mut self,
+        path: &str,
+        planned: Option<&Path>,
+        dest_dir: &Path,
+        options: &DownloadOptions,
+        hooks: &mut Hooks,
+        journal: &mut dyn Journal,
+    ) -> crate::Result<Option<PathBuf>> {
+        let (directory, name) = split_path(path);
+
+        let info = match self.file_info(&file_from_parts(directory, name)) {
+            Ok(info) => info,
+            Err(ref err) if err.kind() == crate::ErrorKind::FileNotFound => {
+                // The last entry of an unfinished file that wasn't interrupted is a failure.
+                if planned.is_some() {
+                    let entry =
+                        JournalEntry::new(path, None, TransferState::Failed(err.to_string()));
+                    journal.append(&entry).map_err(|_| journal_error())?;
+                }
+
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        };
+
+        self.download_journaled(&info, dest_dir, options, hooks, journal, planned)
+    }
+
+    /// Downloads a file and records its transfer in a journal.
+    ///
+    /// The file is recorded as planned with its local path before it's downloaded. If the file's
+    /// transfer was interrupted, it's downloaded again to the `planned` path of that transfer.
+    ///
+    /// Returns the file's local path, or `None` if the `Collision` policy skipped the download. A
+    /// skipped file is recorded as completed with the path of the file that it was skipped for,
+    /// but the hooks aren't run for it.
+    pub(crate) fn download_journaled(
+        &mut self,
+        info: &FileInfo,
+        dest_dir: &Path,
+        options: &DownloadOptions,
+        hooks: &mut Hooks,
+        journal: &mut dyn Journal,
+        planned: Option<&Path>,
+    ) -> crate::Result<Option<PathBuf>> {
+        let file = info.file().path();
+
+        let result = match planned {
+            // The download may have completed before it was recorded, and replacing that copy
+            // includes its sidecar.
+            Some(path) => {
+                let options = options.clone().collision(Collision::Overwrite);
+                self.download_planned(info, path, &options, journal)
+                    .map(|path| (path, true))
+            }
+            None => match self.download_destination(info.file(), dest_dir, options) {
+                Ok(Some(path)) => self
+                    .download_planned(info, &path, options, journal)
+                    .map(|path| (path, true)),
+                // A skipped download leaves a file with the same size in the destination.
+                Ok(None) => {
+                    let name = tagged_name(self.session_tag(), &info.file().basename());
+                    Ok((dest_dir.join(name), false))
+                }
+                Err(err) => Err(err),
+            },
+        };
+
+        match result {
+            Ok((path, downloaded)) => {
+                let entry =
+                    JournalEntry::new(&file, info.size(), TransferState::Completed(path.clone()));
+                journal.append(&entry).map_err(|_| journal_error())?;
+
+                if !downloaded {
+                    return Ok(None);
+                }
+
+                hooks.run(&path, info);
+
+                Ok(Some(path))
+            }
+            Err(err) => {
+                let entry =
+                    JournalEntry::new(&file, info.size(), TransferState::Failed(err.to_string()));
+                let _ = journal.append(&entry);
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Records a file as planned for a local path in a journal, and downloads it to that path.
+    fn download_planned(
+        &mut self,
+        info: &FileInfo,
+        path: &Path,
+        options: &DownloadOptions,
+        journal: &mut dyn Journal,
+    ) -> crate::Result<PathBuf> {
+        let planned = TransferState::Planned(path.to_path_buf());
+        let entry = JournalEntry::new(&info.file().path(), info.size(), planned);
+        journal.append(&entry).map_err(|_| journal_error())?;
+
+        self.download_to_destination(info.file(), path, options)
+    }
+}
+
+/// The files that a journal records as unfinished, in the order they were first recorded, with
+/// the local path that their interrupted transfer was planned for.
+fn unfinished_transfers(entries: &[JournalEntry]) -> Vec<(String, Option<PathBuf>)> {
+    let interrupted = crate::journal::interrupted(entries);
+
+    crate::journal::unfinished(entries)
+        .into_iter()
+        .map(|file| {
+            (
+                file.to_owned(),
+                interrupted.get(file).map(|path| path.to_path_buf()),
+            )
+        })
+        .collect()
+}
+
+/// The journal of a tether whose downloads aren't recorded.
+struct NoJournal;
+
+impl Journal for NoJournal {
+    fn append(&mut self, _entry: &JournalEntry) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn entries(&mut self) -> io::Result<Vec<JournalEntry>> {
+        Ok(Vec::new())
+    }
+}
+
+/// The state of a tether's thread.
+struct Session {
+    camera: Camera,
+    dest_dir: PathBuf,
+    download: DownloadOptions,
+    hooks: Hooks,
+    on_error: Option<OnError>,
+    clock: Box<dyn Clock + Send>,
+    journal: Box<dyn Journal + Send>,
+    entries: Vec<JournalEntry>,
+    /// The files of a simulated camera, which are processed as if the camera had added them.
+    replay: Vec<CameraFile>,
+    files: Sender<crate::Result<PathBuf>>,
+    hook_failures: Arc<Mutex<Vec<HookFailure>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Session {
+    /// Runs the session until it's stopped or fails, and returns the camera to be closed.
+    fn run(mut self) -> Camera {
+        if let Err(err) = self.serve() {
+            let _ = self.files.send(Err(err));
+        }
+
+        self.camera
+    }
+
+    fn serve(&mut self) -> crate::Result<()> {
+        self.resume()?;
+
+        while !self.stopped() {
+            let deadline = self.clock.now() + POLL_INTERVAL;
+
+            match self.recover(false, |session| session.camera.wait_event(POLL_INTERVAL))? {
+                Ok(CameraEvent::FileAdded(file)) => {
+                    let result = self.recover(true, |session| session.process(&file))?;
+                    self.send(result);
+                }
+                Ok(_) => (),
+                Err(err) => {
+                    let _ = self.files.send(Err(err));
+
+                    // A wait that fails at once would otherwise poll the camera without a pause.
+                    self.clock.sleep_until(deadline);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Downloads the files that an earlier session with the same journal didn't download, and
+    /// the files that were added while no session was running.
+    fn resume(&mut self) -> crate::Result<()> {
+        let entries = std::mem::take(&mut self.entries);
+
+        for (path, planned) in unfinished_transfers(&entries) {
+            if self.stopped() {
+                return Ok(());
+            }
+
+            let result = self.recover(true, |session| {
+                let result = session.camera.resume_file(
+                    &path,
+                    planned.as_deref(),
+                    &session.dest_dir,
+                    &session.download,
+                    &mut session.hooks,
+                    &mut *session.journal,
+                );
+                session.collect_hook_failures();
+                result
+            })?;
+
+            self.send(result);
+        }
+
+        let added = match entries.last() {
+            _ if !self.replay.is_empty() => std::mem::take(&mut self.replay),
+            Some(entry) => self.added_since(entry.file()),
+            None => return Ok(()),
+        };
+
+        let recorded: HashSet<&str> = entries.iter().map(|entry| entry.file()).collect();
+
+        for file in added {
+            if self.stopped() {
+                return Ok(());
+            }
+
+            if !recorded.contains(file.path().as_str()) {
+                let result = self.recover(true, |session| session.process(&file))?;
+                self.send(result);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs an operation, and recovers from its failures with the tether's `on_error` policy.
+    ///
+    /// Returns the operation's result, or its last error if the policy skipped it. Returns an
+    /// error if the policy aborted.
+    fn recover<T, F>(&mut self, download: bool, mut operation: F) -> crate::Result<crate::Result<T>>
+    where
+        F: FnMut(&mut Session) -> crate::Result<T>,
+    {
+        let mut attempt = 0;
+        let mut reinit = false;
+
+        loop {
+            attempt += 1;
+
+            let result = if reinit {
+                self.camera.reinit().and_then(|()| operation(self))
+            } else {
+                operation(self)
+            };
+
+            let err = match result {
+                Ok(value) => return Ok(Ok(value)),
+                // A stopped tether cancels its operation, which isn't a failure to recover from.
+                Err(err) if self.stopped() => return Ok(Err(err)),
+                Err(err) => err,
+            };
+
+            let recovery = match self.on_error {
+                Some(ref mut on_error) => on_error(&err, attempt),
+                None => default_recovery(&err, attempt, download),
+            };
+
+            match recovery {
+                Recovery::Retry => {
+                    reinit = false;
+                    self.clock.sleep(RETRY_DELAY);
+                }
+                Recovery::Reinit => reinit = true,
+                Recovery::Skip => return Ok(Err(err)),
+                Recovery::Abort => return Err(err),
+            }
+        }
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    /// Returns the files on the storage of the last recorded file that were added after it, in
+    /// the order they were added.
+    fn added_since(&mut self, last: &str) -> Vec<CameraFile> {
+        let (folder, name) = split_path(last);
+
+        // Without the last file's time, e.g., because it was deleted, files are ordered by name.
+        let last_time = match self.camera.file_info(&file_from_parts(folder, name)) {
+            Ok(info) => info.mtime(),
+            Err(_) => None,
+        };
+
+        let files = match self.camera.fs().walk(storage_root(last)) {
+            Ok(files) => files,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut added = Vec::new();
+
+        for file in files {
+            let path = file.path();
+
+            let time = match last_time {
+                Some(_) => match self.camera.file_info(&file) {
+                    Ok(info) => info.mtime(),
+                    Err(_) => None,
+                },
+                None => None,
+            };
+
+            if is_later((&path, time), (last, last_time)) {
+                added.push((time, path, file));
+            }
+        }
+
+        in_added_order(added)
+    }
+
+    /// Downloads an added file, records its transfer in the journal, and runs the hooks for it.
+    ///
+    /// Returns the file's local path, or `None` if the `Collision` policy skipped its download.
+    fn process(&mut self, file: &CameraFile) -> crate::Result<Option<PathBuf>> {
+        // The hooks receive the information that the camera reported before the download.
+        let info = match self.camera.file_info(file) {
+            Ok(info) => info,
+            Err(err) => {
+                // The file is downloaded again when the journal is resumed.
+                let entry =
+                    JournalEntry::new(&file.path(), None, TransferState::Failed(err.to_string()));
+                let _ = self.journal.append(&entry);
+
+                return Err(err);
+            }
+        };
+
+        let result = self.camera.download_journaled(
+            &info,
+            &self.dest_dir,
+            &self.download,
+            &mut self.hooks,
+            &mut *self.journal,
+            None,
+        );
+        self.collect_hook_failures();
+
+        result
+    }
+
+    /// Passes a download's result to `Tether::recv()`, unless nothing was downloaded.
+    fn send(&self, result: crate::Result<Option<PathBuf>>) {
+        match result {
+            Ok(Some(path)) => {
+                let _ = self.files.send(Ok(path));
+            }
+            Ok(None) => (),
+            Err(err) => {
+                let _ = self.files.send(Err(err));
+            }
+        }
+    }
+
+    fn collect_hook_failures(&mut self) {
+        let failures = self.hooks.take_failures();
+
+        match self.hook_failures.lock() {
+            Ok(mut collected) => collected.extend(failures),
+            Err(poisoned) => poisoned.into_inner().extend(failures),
+        }
+    }
+}
+
+/// Lists the files of a simulated camera in the order of their modification time.
+pub(crate) fn simulated_files(camera: &mut Camera) -> crate::Result<Vec<CameraFile>> {
+    let mut files = Vec::new();
+
+    for file in camera.fs().walk("/")? {
+        let time = camera.file_info(&file).ok().and_then(|info| info.mtime());
+        files.push((time, file.path(), file));
+    }
+
+    Ok(in_added_order(files))
+}
+
+/// Sorts files with their modification time and their path in the order they were added.
+fn in_added_order(mut files: Vec<(Option<SystemTime>, String, CameraFile)>) -> Vec<CameraFile> {
+    files.sort_by(|(a_time, a_path, _), (b_time, b_path, _)| {
+        a_time
+            .cmp(b_time)
+            .then_with(|| split_path(a_path).cmp(&split_path(b_path)))
+    });
+
+    files.into_iter().map(|(_, _, file)| file).collect()
+}
+
+/// Recovers from a failure of a tether's operation if no `on_error` policy was set.
+///
+/// Failed downloads are skipped instead of aborted, so that one file doesn't stop the tether.
+fn default_recovery(err: &crate::Error, attempt: u32, download: bool) -> Recovery {
+    let recovery = if attempt < MAX_ATTEMPTS {
+        Recovery::for_error(err)
+    } else {
+        Recovery::Abort
+    };
+
+    match recovery {
+        Recovery::Abort if download => Recovery::Skip,
+        recovery => recovery,
+    }
+}
+
+/// Splits a camera path into its folder and its name.
+fn split_path(path: &str) -> (&str, &str) {
+    match path.rfind('/') {
+        Some(index) => (&path[..index.max(1)], &path[index + 1..]),
+        None => ("/", path),
+    }
+}
+
+/// Returns the root folder of the storage that a camera path is on, e.g., `/store_00010001`, or
+/// `/` if the camera doesn't name its storages.
+fn storage_root(path: &str) -> &str {
+    let rest = match path.strip_prefix('/') {
+        Some(rest) if rest.starts_with("store_") => rest,
+        _ => return "/",
+    };
+
+    match rest.find('/') {
+        Some(end) => &path[..end + 1],
+        None => path,
+    }
+}
+
+/// Returns `true` if a file was added after the last processed file.
+///
+/// Files are ordered by their modification time, and by their folder and name if their times are
+/// equal or unknown. Names alone would miss files in a new folder whose names restarted, or files
+/// whose number wrapped around.
+fn is_later(file: (&str, Option<SystemTime>), last: (&str, Option<SystemTime>)) -> bool {
+    let by_name = split_path(file.0) > split_path(last.0);
+
+    match (file.1, last.1) {
+        (Some(time), Some(last_time)) if time != last_time => time > last_time,
+        _ => by_name,
+    }
+}
+
+fn os_failure() -> crate::Error {
+    crate::error::from_libgphoto2(crate::gphoto2::GP_ERROR_OS_FAILURE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovers_by_default() {
+        use crate::gphoto2::{GP_ERROR_CAMERA_BUSY, GP_ERROR_FILE_EXISTS, GP_ERROR_IO};
+
+        let cases = [
+            (GP_ERROR_CAMERA_BUSY, 1, false, Recovery::Retry),
+            (GP_ERROR_CAMERA_BUSY, 2, true, Recovery::Retry),
+            (GP_ERROR_CAMERA_BUSY, 3, false, Recovery::Abort),
+            (GP_ERROR_CAMERA_BUSY, 3, true, Recovery::Skip),
+            (GP_ERROR_IO, 1, false, Recovery::Reinit),
+            (GP_ERROR_IO, 1, true, Recovery::Reinit),
+            (GP_ERROR_FILE_EXISTS, 1, false, Recovery::Abort),
+            (GP_ERROR_FILE_EXISTS, 1, true, Recovery::Skip),
+        ];
+
+        for &(code, attempt, download, recovery) in cases.iter() {
+            let err = crate::error::from_libgphoto2(code);
+
+            assert_eq!(
+                default_recovery(&err, attempt, download),
+                recovery,
+                "{:?} {} {}",
+                err.kind(),
+                attempt,
+                download
+            );
+        }
+    }
+
+    #[test]
+    fn splits_paths() {
+        let cases = [
+            (
+                "/DCIM/100CANON/IMG_0001.JPG",
+                ("/DCIM/100CANON", "IMG_0001.JPG"),
+            ),
+            ("/IMG_0001.JPG", ("/", "IMG_0001.JPG")),
+            ("IMG_0001.JPG", ("/", "IMG_0001.JPG")),
+        ];
+
+        for &(path, expected) in cases.iter() {
+            assert_eq!(split_path(path), expected, "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn finds_storage_roots() {
+        let cases = [
+            (
+                "/store_00010001/DCIM/100CANON/IMG_0001.JPG",
+                "/store_00010001",
+            ),
+            ("/store_00020001", "/store_00020001"),
+            ("/DCIM/100CANON/IMG_0001.JPG", "/"),
+            ("/IMG_0001.JPG", "/"),
+        ];
+
+        for &(path, root) in cases.iter() {
+            assert_eq!(storage_root(path), root, "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn orders_added_files() {
+        let at = |secs: u64| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+        let last = ("/DCIM/100CANON/IMG_9999.JPG", at(100));
+
+        let cases = [
+            (("/DCIM/101CANON/IMG_0001.JPG", at(101)), true),
+            (("/DCIM/100CANON/IMG_0001.JPG", at(101)), true),
+            (("/DCIM/100CANON/IMG_9998.JPG", at(99)), false),
+            (("/DCIM/100CANON/IMG_9999.JPG", at(100)), false),
+            (("/DCIM/101CANON/IMG_0001.JPG", at(100)), true),
+            (("/DCIM/100CANON/IMG_0001.JPG", at(100)), false),
+            (("/DCIM/101CANON/IMG_0001.JPG", None), true),
+            (("/DCIM/099CANON/IMG_9999.JPG", None), false),
+        ];
+
+        for &(file, later) in cases.iter() {
+            assert_eq!(is_later(file, last), later, "{:?}", file);
+        }
+
+        let unknown = ("/DCIM/100CANON/IMG_0001.JPG", None);
+        assert!(is_later(("/DCIM/100CANON/IMG_0002.JPG", at(1)), unknown));
+    }
+}
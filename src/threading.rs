@@ -0,0 +1,104 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Whether the threading model is `Serialized`.
+static SERIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Serializes the calls into `libgphoto2` in the `Serialized` threading model.
+static FFI_LOCK: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    /// Whether the current thread holds `FFI_LOCK`.
+    static HELD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// How calls into `libgphoto2` are synchronized between threads.
+///
+/// `libgphoto2` allows calls for different cameras from different threads, but some drivers and
+/// port libraries share global state, which has caused crashes with many cameras on some
+/// versions. The threading model trades parallelism for safety:
+///
+/// * `PerCamera` (the default) only serializes the calls for each camera, so operations on
+///   different cameras run in parallel.
+/// * `Serialized` serializes all calls of `SharedCamera`s and camera detection, so only one
+///   operation runs at a time across all cameras. `RemoteCamera`s share a single `Executor`
+///   thread, which makes all of their calls from one thread.
+///
+/// The threading model is selected with `set_threading_model()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ThreadingModel {
+    /// Calls for different cameras run in parallel.
+    #[default]
+    PerCamera,
+
+    /// Calls for all cameras are serialized.
+    Serialized,
+}
+
+/// Returns the threading model.
+pub fn threading_model() -> ThreadingModel {
+    if SERIALIZED.load(Ordering::SeqCst) {
+        ThreadingModel::Serialized
+    } else {
+        ThreadingModel::PerCamera
+    }
+}
+
+/// Selects the threading model.
+///
+/// The threading model should be selected before any camera is opened. Operations that are in
+/// progress when the model is changed complete under the previous model.
+///
+/// In the `Serialized` model, a thread must not lock a `SharedCamera` while it holds the lock of
+/// another one.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::ThreadingModel;
+///
+/// gphoto::set_threading_model(ThreadingModel::Serialized);
+///
+/// let cameras: Vec<_> = gphoto::detect_cameras()
+///     .unwrap()
+///     .iter()
+///     .map(|camera| gphoto::SharedCamera::new(camera.open().unwrap()))
+///     .collect();
+/// ```
+pub fn set_threading_model(model: ThreadingModel) {
+    SERIALIZED.store(model == ThreadingModel::Serialized, Ordering::SeqCst);
+}
+
+/// Acquires the global lock if the threading model is `Serialized`.
+///
+/// The lock is reentrant: a thread that already holds it, e.g., the executor's thread while it
+/// runs a job that detects cameras, doesn't acquire it again.
+pub(crate) fn serialize() -> SerializedGuard {
+    if threading_model() != ThreadingModel::Serialized || HELD.with(Cell::get) {
+        return SerializedGuard { guard: None };
+    }
+
+    // The lock doesn't protect any data, so a panic while it was held doesn't matter.
+    let guard = match FFI_LOCK.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    HELD.with(|held| held.set(true));
+
+    SerializedGuard { guard: Some(guard) }
+}
+
+/// Holds the global lock of the `Serialized` threading model, if it was acquired.
+pub(crate) struct SerializedGuard {
+    guard: Option<MutexGuard<'static, ()>>,
+}
+
+impl Drop for SerializedGuard {
+    fn drop(&mut self) {
+        if self.guard.is_some() {
+            HELD.with(|held| held.set(false));
+        }
+    }
+}
@@ -0,0 +1,540 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::vec;
+
+use crate::camera::{Camera, CameraFile};
+use crate::clock::{Clock, SimulatedClock, SystemClock};
+use crate::exposure::{ExposureController, ExposureRamp, ExposureStep};
+
+/// Schedules the frames of a timelapse at fixed intervals.
+///
+/// Frames are scheduled against absolute targets, `start + index * interval`, instead of
+/// sleeping for the interval after each frame. The time that is spent capturing and downloading
+/// therefore doesn't accumulate: a frame that starts late doesn't delay the following frames.
+/// When a frame takes longer than the interval, the targets that have already passed are skipped,
+/// so that the sequence stays aligned to the schedule.
+///
+/// The lateness of each frame is recorded in the timelapse's `JitterStats`.
+///
+/// For day-to-night timelapses, `capture_ramped()` adjusts the exposure with an `ExposureRamp`
+/// before each frame.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use gphoto::Timelapse;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut timelapse = Timelapse::new(Duration::from_secs(10)).frames(2880);
+///
+/// while let Some(frame) = timelapse.next_frame() {
+///     camera.capture_image().unwrap();
+///     println!("frame {} ({:?} late)", frame.index(), frame.lateness());
+/// }
+///
+/// println!("{:?}", timelapse.stats());
+/// ```
+pub struct Timelapse<C = SystemClock> {
+    clock: C,
+    interval: Duration,
+    frames: Option<u64>,
+    start: Option<Instant>,
+    next: u64,
+    stats: JitterStats,
+}
+
+/// A frame of a timelapse that is due.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Frame {
+    index: u64,
+    target: Instant,
+    started: Instant,
+}
+
+impl Frame {
+    /// The frame's index in the schedule, starting at 0.
+    ///
+    /// Indices of skipped frames aren't returned, so the index also tells when frames were missed.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// The time at which the frame was scheduled.
+    pub fn target(&self) -> Instant {
+        self.target
+    }
+
+    /// The time at which the frame was returned to the caller.
+    pub fn started(&self) -> Instant {
+        self.started
+    }
+
+    /// How late the frame started.
+    pub fn lateness(&self) -> Duration {
+        self.started - self.target
+    }
+}
+
+/// A frame of a timelapse that was captured with `Timelapse::capture_ramped()`.
+#[derive(Debug)]
+pub struct RampedFrame {
+    frame: Frame,
+    step: ExposureStep,
+    file: CameraFile,
+}
+
+impl RampedFrame {
+    /// The frame of the schedule.
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// The exposure that the frame was captured with.
+    pub fn step(&self) -> &ExposureStep {
+        &self.step
+    }
+
+    /// The file that the capture produced.
+    pub fn file(&self) -> &CameraFile {
+        &self.file
+    }
+}
+
+/// A timelapse that replays the files of a simulated camera on its schedule.
+///
+/// Created with `Timelapse::simulated()`.
+pub struct SimulatedTimelapse {
+    camera: Camera,
+    files: vec::IntoIter<CameraFile>,
+    timelapse: Timelapse<SimulatedClock>,
+}
+
+/// A frame of a `SimulatedTimelapse`.
+#[derive(Debug)]
+pub struct SimulatedFrame {
+    frame: Frame,
+    file: CameraFile,
+}
+
+impl SimulatedFrame {
+    /// The frame of the schedule.
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// The file that the simulated camera produced for the frame.
+    pub fn file(&self) -> &CameraFile {
+        &self.file
+    }
+}
+
+/// Statistics about how accurately the frames of a timelapse were started.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct JitterStats {
+    frames: u64,
+    skipped: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl JitterStats {
+    /// The number of frames that were started.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// The number of frames that were skipped because their target had passed.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// The mean lateness of the started frames.
+    pub fn mean(&self) -> Duration {
+        if self.frames == 0 {
+            Duration::from_secs(0)
+        } else {
+            self.total.div_f64(self.frames as f64)
+        }
+    }
+
+    /// The largest lateness of the started frames.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    fn record(&mut self, lateness: Duration) {
+        self.frames += 1;
+        self.total += lateness;
+        self.max = self.max.max(lateness);
+    }
+}
+
+impl Timelapse<SystemClock> {
+    /// Creates an endless timelapse with the given interval between frames.
+    pub fn new(interval: Duration) -> Self {
+        Timelapse::with_clock(interval, SystemClock)
+    }
+}
+
+impl Timelapse<SimulatedClock> {
+    /// Creates a timelapse on a simulated camera that produces the files in a local directory on
+    /// the schedule, e.g., to develop the storage or the user interface of a timelapse
+    /// application without hardware.
+    ///
+    /// The camera is opened with `Camera::simulated()`, and each frame produces the next file of
+    /// the directory in the order of their modification time, as if the camera had captured it.
+    /// The timelapse is scheduled with a `SimulatedClock`, so it runs faster than real time, and
+    /// ends after the last file.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    ///
+    /// use gphoto::{DownloadOptions, Timelapse};
+    ///
+    /// let source = Path::new("testdata/DCIM");
+    /// let mut timelapse = Timelapse::simulated(source, Duration::from_secs(10)).unwrap();
+    /// let options = DownloadOptions::new();
+    ///
+    /// while let Some(frame) = timelapse.next_frame() {
+    ///     let camera = timelapse.camera();
+    ///     let path = camera.download_to_dir(frame.file(), "frames".as_ref(), &options);
+    ///
+    ///     println!("frame {}: {:?}", frame.frame().index(), path);
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the errors of `Camera::simulated()` and `CameraFs::walk()`.
+    pub fn simulated(source_dir: &Path, interval: Duration) -> crate::Result<SimulatedTimelapse> {
+        let mut camera = Camera::simulated(source_dir)?;
+        let files = crate::tether::simulated_files(&mut camera)?;
+
+        Ok(SimulatedTimelapse {
+            camera,
+            files: files.into_iter(),
+            timelapse: Timelapse::with_clock(interval, SimulatedClock::new()),
+        })
+    }
+}
+
+impl SimulatedTimelapse {
+    /// Returns the simulated camera, e.g., to download the files of the frames.
+    pub fn camera(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    /// Returns the clock that schedules the timelapse.
+    ///
+    /// Advancing the clock simulates the time that processing a frame takes.
+    pub fn clock(&self) -> &SimulatedClock {
+        &self.timelapse.clock
+    }
+
+    /// Returns the statistics of the frames that were started so far.
+    pub fn stats(&self) -> JitterStats {
+        self.timelapse.stats()
+    }
+
+    /// Waits until the next frame is due and returns it with the next file.
+    ///
+    /// Returns `None` when all files have been produced.
+    pub fn next_frame(&mut self) -> Option<SimulatedFrame> {
+        self.timelapse.replay_frame(&mut self.files)
+    }
+}
+
+impl<C: Clock> Timelapse<C> {
+    /// Creates an endless timelapse that is scheduled with a clock, e.g., a `SimulatedClock`.
+    pub fn with_clock(interval: Duration, clock: C) -> Self {
+        Timelapse {
+            clock,
+            interval,
+            frames: None,
+            start: None,
+            next: 0,
+            stats: JitterStats::default(),
+        }
+    }
+
+    /// Limits the timelapse to a number of frames, including skipped frames.
+    pub fn frames(mut self, frames: u64) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    /// Sets the time of the first frame.
+    ///
+    /// By default, the first frame is due when `next_frame()` is first called.
+    pub fn start_at(mut self, start: Instant) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Returns the interval between frames.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the statistics of the frames that were started so far.
+    pub fn stats(&self) -> JitterStats {
+        self.stats
+    }
+
+    /// Waits until the next frame is due and returns it.
+    ///
+    /// Returns `None` when all frames have been scheduled.
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        let (index, target) = self.schedule()?;
+
+        Some(self.start_frame(index, target))
+    }
+
+    /// Adjusts the exposure with a ramp, waits until the next frame is due and captures it.
+    ///
+    /// The exposure is adjusted before waiting, so that the time it takes doesn't delay the frame.
+    /// Returns `None` when all frames have been scheduled.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use gphoto::{ExposureRamp, Timelapse};
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut timelapse = Timelapse::new(Duration::from_secs(30)).frames(1440);
+    ///
+    /// // Meter with the application's light sensor: keep the scene at 1000 lux.
+    /// let read_lux = || 250.0_f64;
+    /// let mut ramp = ExposureRamp::new(move |_: &mut gphoto::Camera| -> gphoto::Result<f64> {
+    ///     Ok((1000.0 / read_lux()).log2())
+    /// })
+    /// .max_shutter(Duration::from_secs(20));
+    ///
+    /// while let Some(frame) = timelapse.capture_ramped(&mut camera, &mut ramp) {
+    ///     let frame = frame.unwrap();
+    ///     println!("{} ISO {}", frame.step().shutter(), frame.step().iso());
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the errors of `ExposureRamp::step()` and `Camera::capture_image()`.
+    /// The frame isn't scheduled again if its capture failed.
+    pub fn capture_ramped<E: ExposureController>(
+        &mut self,
+        camera: &mut Camera,
+        ramp: &mut ExposureRamp<E>,
+    ) -> Option<crate::Result<RampedFrame>> {
+        let (index, target) = self.schedule()?;
+
+        let step = match ramp.step(camera) {
+            Ok(step) => step,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let frame = self.start_frame(index, target);
+
+        Some(
+            camera
+                .capture_image()
+                .map(|file| RampedFrame { frame, step, file }),
+        )
+    }
+
+    /// Waits until the next frame is due and returns it with the next of the replayed files.
+    fn replay_frame<I>(&mut self, files: &mut I) -> Option<SimulatedFrame>
+    where
+        I: Iterator<Item = CameraFile>,
+    {
+        let file = files.next()?;
+        let (index, target) = self.schedule()?;
+        let frame = self.start_frame(index, target);
+
+        Some(SimulatedFrame { frame, file })
+    }
+
+    /// Returns the index and target of the next frame, skipping frames whose target has passed.
+    fn schedule(&mut self) -> Option<(u64, Instant)> {
+        let start = match self.start {
+            Some(start) => start,
+            None => *self.start.insert(self.clock.now()),
+        };
+
+        if self.interval > Duration::from_secs(0) {
+            // Skip the frames whose target is at least an interval in the past.
+            let elapsed = self.clock.now().saturating_duration_since(start);
+            let mut due = (elapsed.as_nanos() / self.interval.as_nanos()) as u64;
+
+            if let Some(frames) = self.frames {
+                due = due.min(frames);
+            }
+
+            if due > self.next {
+                self.stats.skipped += due - self.next;
+                self.next = due;
+            }
+        }
+
+        if let Some(frames) = self.frames {
+            if self.next >= frames {
+                return None;
+            }
+        }
+
+        let index = self.next;
+        let target = start + self.interval.mul_f64(index as f64);
+
+        Some((index, target))
+    }
+
+    /// Waits until the target of a frame and records it as started.
+    fn start_frame(&mut self, index: u64, target: Instant) -> Frame {
+        self.clock.sleep_until(target);
+
+        let started = self.clock.now();
+        let frame = Frame {
+            index,
+            target,
+            started,
+        };
+
+        self.stats.record(frame.lateness());
+        self.next += 1;
+
+        frame
+    }
+}
+
+impl<C: Clock> Iterator for Timelapse<C> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        self.next_frame()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::file_from_parts;
+
+    const INTERVAL: Duration = Duration::from_secs(10);
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn schedules_absolute_targets() {
+        let clock = SimulatedClock::new();
+        let start = clock.now();
+        let mut timelapse = Timelapse::with_clock(INTERVAL, &clock);
+
+        for index in 0..5 {
+            let frame = timelapse.next_frame().unwrap();
+
+            assert_eq!(frame.index(), index);
+            assert_eq!(frame.target(), start + INTERVAL * index as u32);
+            assert_eq!(frame.lateness(), secs(0));
+
+            // The time that the capture takes doesn't delay the next frame.
+            clock.advance(secs(3));
+        }
+
+        assert_eq!(timelapse.stats().skipped(), 0);
+    }
+
+    #[test]
+    fn replays_files_on_schedule() {
+        let clock = SimulatedClock::new();
+        let start = clock.now();
+        let mut timelapse = Timelapse::with_clock(INTERVAL, &clock);
+
+        let names = ["IMG_0001.JPG", "IMG_0002.JPG", "IMG_0003.JPG"];
+        let mut files = names
+            .iter()
+            .map(|name| file_from_parts("/DCIM", name))
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        for (index, name) in names.iter().enumerate() {
+            let frame = timelapse.replay_frame(&mut files).unwrap();
+
+            assert_eq!(frame.file().basename(), *name);
+            assert_eq!(frame.frame().index(), index as u64);
+            assert_eq!(clock.now(), start + INTERVAL * index as u32);
+
+            clock.advance(secs(3));
+        }
+
+        // The timelapse ends after the last file.
+        assert!(timelapse.replay_frame(&mut files).is_none());
+        assert_eq!(timelapse.stats().frames(), 3);
+    }
+
+    #[test]
+    fn skips_passed_targets() {
+        let clock = SimulatedClock::new();
+        let start = clock.now();
+        let mut timelapse = Timelapse::with_clock(INTERVAL, &clock);
+
+        assert_eq!(timelapse.next_frame().unwrap().index(), 0);
+
+        // The first frame overruns the second frame's target by more than an interval.
+        clock.advance(secs(25));
+
+        let frame = timelapse.next_frame().unwrap();
+        assert_eq!(frame.index(), 2);
+        assert_eq!(frame.target(), start + secs(20));
+        assert_eq!(frame.lateness(), secs(5));
+
+        let frame = timelapse.next_frame().unwrap();
+        assert_eq!(frame.index(), 3);
+        assert_eq!(frame.lateness(), secs(0));
+
+        let stats = timelapse.stats();
+        assert_eq!(stats.frames(), 3);
+        assert_eq!(stats.skipped(), 1);
+    }
+
+    #[test]
+    fn stops_at_frame_limit() {
+        let clock = SimulatedClock::new();
+        let timelapse = Timelapse::with_clock(INTERVAL, &clock).frames(3);
+
+        let indices: Vec<u64> = timelapse.map(|frame| frame.index()).collect();
+        assert_eq!(indices, [0, 1, 2]);
+
+        // Skipped frames count towards the limit.
+        let mut timelapse = Timelapse::with_clock(INTERVAL, &clock).frames(3);
+        assert_eq!(timelapse.next_frame().unwrap().index(), 0);
+
+        clock.advance(secs(35));
+
+        assert_eq!(timelapse.next_frame(), None);
+        assert_eq!(timelapse.stats().frames(), 1);
+        assert_eq!(timelapse.stats().skipped(), 2);
+    }
+
+    #[test]
+    fn computes_jitter_stats() {
+        let mut stats = JitterStats::default();
+        assert_eq!(stats.mean(), secs(0));
+        assert_eq!(stats.max(), secs(0));
+
+        for &lateness in [secs(1), secs(3), secs(2)].iter() {
+            stats.record(lateness);
+        }
+
+        assert_eq!(stats.frames(), 3);
+        assert_eq!(stats.mean(), secs(2));
+        assert_eq!(stats.max(), secs(3));
+    }
+}
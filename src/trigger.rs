@@ -0,0 +1,424 @@
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::capture::CaptureId;
+use crate::widget::WidgetValue;
+
+/// A source of external trigger signals.
+///
+/// Triggers are used with `Camera::capture_on()` to fire a capture when an external signal
+/// arrives, e.g., from a light barrier, a motion sensor, or a sound trigger.
+///
+/// Closures of type `FnMut(Duration) -> bool` implement `Trigger`. The closure is called with the
+/// remaining timeout and returns `true` if the signal arrived.
+pub trait Trigger {
+    /// Blocks until the trigger fires or the timeout expires.
+    ///
+    /// Returns `true` if the trigger fired and `false` if the timeout expired.
+    fn wait(&mut self, timeout: Duration) -> crate::Result<bool>;
+}
+
+impl<F> Trigger for F
+where
+    F: FnMut(Duration) -> bool,
+{
+    fn wait(&mut self, timeout: Duration) -> crate::Result<bool> {
+        Ok(self(timeout))
+    }
+}
+
+impl Camera {
+    /// Prepares the camera for a capture with minimal shutter lag.
+    ///
+    /// This presses the shutter button half-way, which pre-focuses and meters the scene, so that
+    /// a following capture fires immediately. Canon EOS cameras are armed with the
+    /// `eosremoterelease` setting, other cameras by starting autofocus with `autofocusdrive`.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the camera could not be armed:
+    ///
+    /// * `NotSupported` if the camera doesn't have a setting to arm it.
+    pub fn arm_capture(&mut self) -> crate::Result<()> {
+        let mut config = self.config()?;
+
+        if config.get("eosremoterelease").is_some() {
+            config.set_value(
+                "eosremoterelease",
+                WidgetValue::Choice("Press Half".to_owned()),
+            )?;
+        } else if config.get("autofocusdrive").is_some() {
+            config.set_value("autofocusdrive", WidgetValue::Toggle(true))?;
+        } else {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            ));
+        }
+
+        self.set_config(&mut config)
+    }
+
+    /// Releases a half-pressed shutter button after `arm_capture()`.
+    pub fn disarm_capture(&mut self) -> crate::Result<()> {
+        let mut config = self.config()?;
+
+        if config.get("eosremoterelease").is_some() {
+            config.set_value(
+                "eosremoterelease",
+                WidgetValue::Choice("Release Half".to_owned()),
+            )?;
+            self.set_config(&mut config)?;
+        }
+
+        Ok(())
+    }
+
+    /// Arms the camera and triggers a capture when an external trigger fires.
+    ///
+    /// Returns the ID of the triggered capture, or `None` if the trigger didn't fire before the
+    /// timeout expired. The camera is disarmed in either case. Use `wait_capture_complete()` to
+    /// retrieve the captured files.
+    ///
+    /// Cameras that can't be armed are triggered without arming them first.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// let trigger = |timeout: Duration| {
+    ///     // wait for a signal from a sensor...
+    ///     true
+    /// };
+    ///
+    /// if let Some(id) = camera.capture_on(trigger, Duration::from_secs(60)).unwrap() {
+    ///     let capture = camera.wait_capture_complete(Duration::from_secs(10)).unwrap();
+    ///     println!("{:?}: {:?}", id, capture.files());
+    /// }
+    /// ```
+    pub fn capture_on<T: Trigger>(
+        &mut self,
+        mut trigger: T,
+        timeout: Duration,
+    ) -> crate::Result<Option<CaptureId>> {
+        let deadline = Instant::now() + timeout;
+
+        let armed = match self.arm_capture() {
+            Ok(()) => true,
+            Err(ref err) if err.kind() == crate::ErrorKind::NotSupported => false,
+            Err(err) => return Err(err),
+        };
+
+        let result = match trigger.wait(deadline.saturating_duration_since(Instant::now())) {
+            Ok(true) => self.trigger_capture().map(Some),
+            Ok(false) => Ok(None),
+            Err(err) => Err(err),
+        };
+
+        if armed {
+            let _ = self.disarm_capture();
+        }
+
+        result
+    }
+}
+
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+pub use self::gpio::{Edge, GpioTrigger};
+
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+mod gpio {
+    use std::fs::{self, File};
+    use std::io::{Read, Seek, SeekFrom};
+    use std::os::unix::io::AsRawFd;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use libc::c_int;
+
+    /// Signal edges that fire a GPIO trigger.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum Edge {
+        /// The line changes from low to high.
+        Rising,
+
+        /// The line changes from high to low.
+        Falling,
+
+        /// The line changes in either direction.
+        Both,
+    }
+
+    /// A trigger that fires on a signal edge of a GPIO line.
+    ///
+    /// The line is accessed through the sysfs GPIO interface. It is exported and configured as an
+    /// input when the trigger is opened.
+    ///
+    /// This type is only available on Linux with the `gpio` feature enabled.
+    pub struct GpioTrigger {
+        value: File,
+    }
+
+    impl GpioTrigger {
+        /// Opens a GPIO line as a trigger.
+        ///
+        /// ## Errors
+        ///
+        /// This function returns an error if the line could not be configured:
+        ///
+        /// * `OSFailure` if the line could not be exported or configured, e.g., due to missing
+        ///   permissions.
+        pub fn open(line: u32, edge: Edge) -> crate::Result<Self> {
+            let dir = PathBuf::from(format!("/sys/class/gpio/gpio{}", line));
+
+            if !dir.exists() {
+                write("/sys/class/gpio/export".into(), &line.to_string())?;
+            }
+
+            write(dir.join("direction"), "in")?;
+            write(
+                dir.join("edge"),
+                match edge {
+                    Edge::Rising => "rising",
+                    Edge::Falling => "falling",
+                    Edge::Both => "both",
+                },
+            )?;
+
+            match File::open(dir.join("value")) {
+                Ok(value) => Ok(GpioTrigger { value }),
+                Err(_) => Err(os_failure()),
+            }
+        }
+
+        fn clear(&mut self) -> crate::Result<()> {
+            let mut buffer = [0; 8];
+
+            match self
+                .value
+                .seek(SeekFrom::Start(0))
+                .and_then(|_| self.value.read(&mut buffer))
+            {
+                Ok(_) => Ok(()),
+                Err(_) => Err(os_failure()),
+            }
+        }
+    }
+
+    impl super::Trigger for GpioTrigger {
+        fn wait(&mut self, timeout: Duration) -> crate::Result<bool> {
+            // Reading the value acknowledges previous edges.
+            self.clear()?;
+
+            let mut fd = libc::pollfd {
+                fd: self.value.as_raw_fd(),
+                events: libc::POLLPRI | libc::POLLERR,
+                revents: 0,
+            };
+
+            let timeout = timeout.as_millis().min(c_int::MAX as u128) as c_int;
+
+            match unsafe { libc::poll(&mut fd, 1, timeout) } {
+                n if n < 0 => Err(os_failure()),
+                0 => Ok(false),
+                _ => {
+                    self.clear()?;
+                    Ok(true)
+                }
+            }
+        }
+    }
+
+    fn write(path: PathBuf, value: &str) -> crate::Result<()> {
+        match fs::write(path, value) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(os_failure()),
+        }
+    }
+
+    fn os_failure() -> crate::Error {
+        crate::error::from_libgphoto2(crate::gphoto2::GP_ERROR_OS_FAILURE)
+    }
+}
+
+#[cfg(feature = "image")]
+pub use self::motion::MotionTrigger;
+
+#[cfg(feature = "image")]
+mod motion {
+    use std::time::{Duration, Instant};
+
+    use image::imageops::{self, FilterType};
+    use image::{GrayImage, ImageFormat};
+
+    use crate::camera::{Camera, CameraFile};
+    use crate::media::FileMedia;
+
+    /// Detects motion in a camera's preview frames by frame differencing.
+    ///
+    /// Each preview frame is decoded, converted to grayscale and scaled down, which suppresses
+    /// sensor noise and JPEG artifacts. A pixel has changed if its brightness differs from the
+    /// previous frame by more than the sensitivity. Motion is detected when the fraction of changed
+    /// pixels exceeds the threshold.
+    ///
+    /// This type is only available with the `image` feature enabled.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use gphoto::MotionTrigger;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut motion = MotionTrigger::new().threshold(0.05);
+    ///
+    /// loop {
+    ///     if let Some(file) = camera.capture_on_motion(&mut motion, Duration::from_secs(60)).unwrap() {
+    ///         println!("motion: {}", file.path());
+    ///     }
+    /// }
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct MotionTrigger {
+        threshold: f64,
+        sensitivity: u8,
+        width: u32,
+        previous: Option<GrayImage>,
+    }
+
+    impl Default for MotionTrigger {
+        fn default() -> Self {
+            MotionTrigger {
+                threshold: 0.02,
+                sensitivity: 25,
+                width: 64,
+                previous: None,
+            }
+        }
+    }
+
+    impl MotionTrigger {
+        /// Creates a trigger that fires when 2 % of the pixels change.
+        pub fn new() -> Self {
+            MotionTrigger::default()
+        }
+
+        /// Sets the fraction of pixels, from 0 to 1, that must change to detect motion.
+        pub fn threshold(mut self, threshold: f64) -> Self {
+            self.threshold = threshold;
+            self
+        }
+
+        /// Sets the brightness difference, from 0 to 255, above which a pixel has changed.
+        ///
+        /// The default is 25.
+        pub fn sensitivity(mut self, sensitivity: u8) -> Self {
+            self.sensitivity = sensitivity;
+            self
+        }
+
+        /// Sets the width that frames are scaled down to before they are compared.
+        ///
+        /// Smaller widths ignore smaller objects and are faster to compare. The default is 64
+        /// pixels.
+        pub fn width(mut self, width: u32) -> Self {
+            self.width = width.max(1);
+            self
+        }
+
+        /// Forgets the previous frame, e.g., after the camera was moved.
+        pub fn reset(&mut self) {
+            self.previous = None;
+        }
+
+        /// Compares a JPEG preview frame to the previous frame.
+        ///
+        /// Returns the fraction of pixels that changed. The first frame, and a frame whose size
+        /// differs from the previous frame, are only remembered and return 0.
+        ///
+        /// ## Errors
+        ///
+        /// This function returns an error if the frame could not be decoded:
+        ///
+        /// * `CorruptedData` if the frame isn't a valid JPEG image.
+        pub fn compare(&mut self, frame: &[u8]) -> crate::Result<f64> {
+            let image = match image::load_from_memory_with_format(frame, ImageFormat::Jpeg) {
+                Ok(image) => image.to_luma8(),
+                Err(_) => {
+                    return Err(crate::error::from_libgphoto2(
+                        crate::gphoto2::GP_ERROR_CORRUPTED_DATA,
+                    ))
+                }
+            };
+
+            let height = (u64::from(image.height()) * u64::from(self.width)
+                / u64::from(image.width().max(1)))
+            .max(1) as u32;
+            let image = imageops::resize(&image, self.width, height, FilterType::Triangle);
+
+            let changed = match self.previous {
+                Some(ref previous) if previous.dimensions() == image.dimensions() => {
+                    let changed = previous
+                        .pixels()
+                        .zip(image.pixels())
+                        .filter(|(before, after)| before[0].abs_diff(after[0]) > self.sensitivity)
+                        .count();
+
+                    changed as f64 / (image.width() * image.height()) as f64
+                }
+                _ => 0.0,
+            };
+
+            self.previous = Some(image);
+
+            Ok(changed)
+        }
+
+        /// Compares a JPEG preview frame to the previous frame and returns `true` on motion.
+        pub fn is_motion(&mut self, frame: &[u8]) -> crate::Result<bool> {
+            Ok(self.compare(frame)? > self.threshold)
+        }
+    }
+
+    impl Camera {
+        /// Watches the camera's preview frames and captures a full-resolution image on motion.
+        ///
+        /// Returns the captured file, or `None` if no motion was detected before the timeout
+        /// expired. The trigger keeps its previous frame, so it can be passed to consecutive calls.
+        ///
+        /// This function is only available with the `image` feature enabled.
+        ///
+        /// ## Errors
+        ///
+        /// This function returns an error if a preview frame or the image could not be captured:
+        ///
+        /// * `NotSupported` if the camera does not support capturing previews.
+        /// * `CorruptedData` if a preview frame isn't a valid JPEG image.
+        pub fn capture_on_motion(
+            &mut self,
+            trigger: &mut MotionTrigger,
+            timeout: Duration,
+        ) -> crate::Result<Option<CameraFile>> {
+            let deadline = Instant::now() + timeout;
+            let mut media = FileMedia::create_mem()?;
+            let mut frame = Vec::new();
+
+            while Instant::now() < deadline {
+                self.capture_preview(&mut media)?;
+                media.get_data_into(&mut frame);
+
+                if trigger.is_motion(&frame)? {
+                    // The scene changes after the capture, e.g., the camera's mirror flips.
+                    trigger.reset();
+
+                    return self.capture_image().map(Some);
+                }
+            }
+
+            Ok(None)
+        }
+    }
+}
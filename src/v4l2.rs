@@ -0,0 +1,178 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use libc::{c_ulong, c_void};
+
+const VIDEO_OUTPUT: u32 = 2;
+const FIELD_NONE: u32 = 1;
+const COLORSPACE_JPEG: u32 = 7;
+const PIX_FMT_MJPEG: u32 =
+    (b'M' as u32) | ((b'J' as u32) << 8) | ((b'P' as u32) << 16) | ((b'G' as u32) << 24);
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PixFormat {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    private: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+// Mirrors the union in `struct v4l2_format`. Some members of the union contain pointers, which
+// determines its alignment (and therefore the size of the struct that is encoded in the ioctl).
+#[repr(C)]
+#[derive(Clone, Copy)]
+union FormatData {
+    pix: PixFormat,
+    raw_data: [u8; 200],
+    _align: [*mut c_void; 0],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Format {
+    buffer_type: u32,
+    fmt: FormatData,
+}
+
+/// `_IOWR('V', 5, struct v4l2_format)`
+fn vidioc_s_fmt() -> c_ulong {
+    (3 << 30) | ((mem::size_of::<Format>() as c_ulong) << 16) | ((b'V' as c_ulong) << 8) | 5
+}
+
+/// A sink that writes preview frames to a `v4l2loopback` video device.
+///
+/// A `V4l2Sink` turns a camera into a webcam: every frame written to the sink is exposed by the
+/// loopback device as a Motion-JPEG video stream, which can be opened by any application that
+/// supports V4L2 capture devices.
+///
+/// This type is only available on Linux with the `v4l2` feature enabled.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut preview = gphoto::FileMedia::create_mem().unwrap();
+/// let mut sink = gphoto::V4l2Sink::open(Path::new("/dev/video0"), 1024, 680).unwrap();
+///
+/// loop {
+///     camera.capture_preview(&mut preview).unwrap();
+///     sink.write_frame(&preview.get_data()).unwrap();
+/// }
+/// ```
+pub struct V4l2Sink {
+    device: File,
+    width: u32,
+    height: u32,
+}
+
+impl V4l2Sink {
+    /// Opens a loopback device and configures it for frames of the given size.
+    ///
+    /// The dimensions must match the size of the preview frames produced by the camera.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the device can not be configured:
+    ///
+    /// * `InvalidInput` if the size of a frame in bytes doesn't fit in 32 bits.
+    /// * `OSFailure` if the device could not be opened or does not accept the output format.
+    pub fn open(path: &Path, width: u32, height: u32) -> crate::Result<Self> {
+        // MJPEG frames are smaller than two bytes per pixel.
+        let size = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(2));
+
+        let size = match size {
+            Some(size) => size,
+            None => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        };
+
+        let device = match OpenOptions::new().write(true).open(path) {
+            Ok(device) => device,
+            Err(_) => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_OS_FAILURE,
+                ))
+            }
+        };
+
+        let mut format = Format {
+            buffer_type: VIDEO_OUTPUT,
+            fmt: FormatData { raw_data: [0; 200] },
+        };
+
+        format.fmt.pix = PixFormat {
+            width,
+            height,
+            pixelformat: PIX_FMT_MJPEG,
+            field: FIELD_NONE,
+            bytesperline: 0,
+            sizeimage: size,
+            colorspace: COLORSPACE_JPEG,
+            private: 0,
+            flags: 0,
+            ycbcr_enc: 0,
+            quantization: 0,
+            xfer_func: 0,
+        };
+
+        let result =
+            unsafe { libc::ioctl(device.as_raw_fd(), vidioc_s_fmt() as _, &mut format as *mut _) };
+
+        if result < 0 {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            ));
+        }
+
+        Ok(V4l2Sink {
+            device,
+            width,
+            height,
+        })
+    }
+
+    /// Returns the width of the frames accepted by the device.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the frames accepted by the device.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Writes a JPEG-encoded frame to the device.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be written:
+    ///
+    /// * `OSFailure` if writing to the device failed.
+    pub fn write_frame(&mut self, frame: &[u8]) -> crate::Result<()> {
+        match self.device.write_all(frame) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_OS_FAILURE,
+            )),
+        }
+    }
+}
@@ -0,0 +1,534 @@
+use std::time::{Duration, Instant};
+
+use crate::camera::{Camera, CameraFile};
+use crate::event::CameraEvent;
+use crate::exposure::{find_config, set_choice};
+use crate::media::Media;
+use crate::widget::{Config, WidgetValue};
+
+/// How long to wait for the recorded clip to be reported after recording stopped.
+const CLIP_TIMEOUT: Duration = Duration::from_secs(30);
+
+const VIDEO_EXTENSIONS: &[&str] = &["mov", "mp4", "avi", "mts", "m4v", "mkv"];
+
+/// The setting that switches Canon EOS cameras between photo and movie mode.
+const EOS_MOVIE_MODE: &str = "eosmoviemode";
+
+/// The setting that starts and stops recording to the memory card on Canon EOS cameras.
+const MOVIE_RECORD_TARGET: &str = "movierecordtarget";
+
+/// Choices of the movie record target that mean that the camera doesn't record.
+const NO_RECORD_TARGET_CHOICES: &[&str] = &["none", "off"];
+
+/// The setting that selects the capture mode of other cameras, e.g., Sony.
+const CAPTURE_MODE: &str = "capturemode";
+
+/// Words in the choices of the capture mode setting that select movie mode.
+const MOVIE_CHOICES: &[&str] = &["movie", "video"];
+
+/// Names of the settings that contain the microphone's recording level in different drivers.
+const AUDIO_LEVEL_SETTINGS: &[&str] = &[
+    "audiolevel",
+    "movierecordinglevel",
+    "microphonelevel",
+    "soundrecordinglevel",
+];
+
+/// Names of the settings that enable the wind filter in different drivers.
+const WIND_FILTER_SETTINGS: &[&str] = &[
+    "windfilter",
+    "moviewindnoisereduction",
+    "windnoisereduction",
+];
+
+/// Names of the settings that enable continuous autofocus during movie recording in different
+/// drivers.
+const MOVIE_SERVO_AF_SETTINGS: &[&str] = &["movieservoaf", "moviecontinuousaf"];
+
+/// Values of on/off settings that mean off.
+const OFF_CHOICES: &[&str] = &["off", "0", "disable", "disabled"];
+
+/// Capture modes of a camera.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Mode {
+    /// The camera captures still images.
+    Photo,
+
+    /// The camera records movies.
+    Movie,
+}
+
+impl Camera {
+    /// Returns the camera's capture mode.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the mode could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't have a setting for the mode.
+    pub fn mode(&mut self) -> crate::Result<Mode> {
+        if let Ok(config) = self.single_config(EOS_MOVIE_MODE) {
+            if let Some(WidgetValue::Toggle(movie)) = config.root().value() {
+                return Ok(if movie { Mode::Movie } else { Mode::Photo });
+            }
+        }
+
+        if let Ok(config) = self.single_config(CAPTURE_MODE) {
+            if let Some(WidgetValue::Choice(value)) = config.root().value() {
+                return Ok(if is_movie_choice(&value) {
+                    Mode::Movie
+                } else {
+                    Mode::Photo
+                });
+            }
+        }
+
+        Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        ))
+    }
+
+    /// Switches the camera between photo and movie mode.
+    ///
+    /// Many movie-related operations fail silently unless the camera is in movie mode first, and
+    /// captures fail in movie mode. Canon EOS cameras are switched with the `eosmoviemode`
+    /// setting. Other cameras are switched with the `capturemode` setting, by the first choice
+    /// that mentions movies or video, or the first choice that doesn't for photo mode. The mode
+    /// isn't changed if the camera is already in it.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use gphoto::Mode;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// camera.set_mode(Mode::Movie).unwrap();
+    /// camera.start_recording().unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the mode could not be changed:
+    ///
+    /// * `NotSupported` if the camera doesn't have a setting for the mode, or the setting doesn't
+    ///   have a choice for it.
+    pub fn set_mode(&mut self, mode: Mode) -> crate::Result<()> {
+        if let Ok(mut config) = self.single_config(EOS_MOVIE_MODE) {
+            let movie = mode == Mode::Movie;
+
+            if config.root().value() == Some(WidgetValue::Toggle(movie)) {
+                return Ok(());
+            }
+
+            config.set_value(EOS_MOVIE_MODE, WidgetValue::Toggle(movie))?;
+            return self.set_single_config(&mut config);
+        }
+
+        if let Ok(mut config) = self.single_config(CAPTURE_MODE) {
+            let wanted = |choice: &str| is_movie_choice(choice) == (mode == Mode::Movie);
+
+            if let Some(WidgetValue::Choice(value)) = config.root().value() {
+                if wanted(&value) {
+                    return Ok(());
+                }
+            }
+
+            let choice = match config
+                .root()
+                .choices()
+                .into_iter()
+                .find(|choice| wanted(choice))
+            {
+                Some(choice) => choice,
+                None => {
+                    return Err(crate::error::from_libgphoto2(
+                        crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                    ))
+                }
+            };
+
+            config.set_value(CAPTURE_MODE, WidgetValue::Choice(choice))?;
+            return self.set_single_config(&mut config);
+        }
+
+        Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        ))
+    }
+
+    /// Returns the microphone's recording level.
+    ///
+    /// The level is in the units of the camera, e.g., 0 to 64 on Canon EOS cameras.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the level could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't have a numeric recording level setting.
+    pub fn audio_level(&mut self) -> crate::Result<f32> {
+        let config = find_config(self, AUDIO_LEVEL_SETTINGS)?;
+
+        match config.root().value() {
+            Some(WidgetValue::Range(level)) => Ok(level),
+            Some(WidgetValue::Choice(level)) | Some(WidgetValue::Text(level)) => {
+                match level.trim().parse() {
+                    Ok(level) => Ok(level),
+                    Err(_) => Err(crate::error::from_libgphoto2(
+                        crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+                    )),
+                }
+            }
+            _ => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+
+    /// Changes the microphone's recording level.
+    ///
+    /// Levels are clamped to the setting's range, or rounded to the nearest of its choices.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    ///
+    /// camera.set_audio_level(40.0).unwrap();
+    /// camera.set_wind_filter(true).unwrap();
+    /// camera.set_movie_servo_af(true).unwrap();
+    /// camera.start_recording().unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the level could not be changed:
+    ///
+    /// * `NotSupported` if the camera doesn't have a numeric recording level setting.
+    pub fn set_audio_level(&mut self, level: f32) -> crate::Result<()> {
+        let mut config = find_config(self, AUDIO_LEVEL_SETTINGS)?;
+
+        if let Some((min, max, _)) = config.root().range() {
+            let name = config.root().name().into_owned();
+
+            config.set_value(&name, WidgetValue::Range(level.max(min).min(max)))?;
+            return self.set_single_config(&mut config);
+        }
+
+        let nearest = config
+            .root()
+            .choices()
+            .into_iter()
+            .filter_map(|choice| {
+                let value: f32 = choice.trim().parse().ok()?;
+                Some(((value - level).abs(), choice))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        match nearest {
+            Some((_, choice)) => set_choice(self, &mut config, &choice),
+            None => Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            )),
+        }
+    }
+
+    /// Returns `true` if the microphone's wind filter is enabled.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the wind filter could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't have a wind filter setting.
+    pub fn wind_filter(&mut self) -> crate::Result<bool> {
+        switch(self, WIND_FILTER_SETTINGS)
+    }
+
+    /// Enables or disables the microphone's wind filter.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the wind filter could not be changed:
+    ///
+    /// * `NotSupported` if the camera doesn't have a wind filter setting.
+    pub fn set_wind_filter(&mut self, enabled: bool) -> crate::Result<()> {
+        set_switch(self, WIND_FILTER_SETTINGS, enabled)
+    }
+
+    /// Returns `true` if the camera focuses continuously while recording movies (Movie Servo AF).
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the setting could not be read:
+    ///
+    /// * `NotSupported` if the camera doesn't have a movie servo AF setting.
+    pub fn movie_servo_af(&mut self) -> crate::Result<bool> {
+        switch(self, MOVIE_SERVO_AF_SETTINGS)
+    }
+
+    /// Enables or disables continuous autofocus while recording movies (Movie Servo AF).
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the setting could not be changed:
+    ///
+    /// * `NotSupported` if the camera doesn't have a movie servo AF setting.
+    pub fn set_movie_servo_af(&mut self, enabled: bool) -> crate::Result<()> {
+        set_switch(self, MOVIE_SERVO_AF_SETTINGS, enabled)
+    }
+
+    /// Starts recording a movie.
+    ///
+    /// Canon EOS cameras are switched to movie mode and record to the memory card through the
+    /// `movierecordtarget` setting, by its first choice that isn't `None`. Other cameras are
+    /// controlled with the `movie` setting.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if recording could not be started:
+    ///
+    /// * `NotSupported` if the camera can't record movies remotely.
+    pub fn start_recording(&mut self) -> crate::Result<()> {
+        let mut config = self.config()?;
+
+        if config.get(MOVIE_RECORD_TARGET).is_some() {
+            if config.get(EOS_MOVIE_MODE).is_some() {
+                self.set_mode(Mode::Movie)?;
+
+                config = self.config()?;
+            }
+
+            let target = record_target(&config, true)?;
+            config.set_value(MOVIE_RECORD_TARGET, WidgetValue::Choice(target))?;
+        } else if config.get("movie").is_some() {
+            config.set_value("movie", WidgetValue::Toggle(true))?;
+        } else {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            ));
+        }
+
+        self.set_config(&mut config)
+    }
+
+    /// Stops recording a movie.
+    ///
+    /// The recorded clip is reported by a `FileAdded` event.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if recording could not be stopped:
+    ///
+    /// * `NotSupported` if the camera can't record movies remotely.
+    pub fn stop_recording(&mut self) -> crate::Result<()> {
+        let mut config = self.config()?;
+
+        if config.get(MOVIE_RECORD_TARGET).is_some() {
+            let target = record_target(&config, false)?;
+            config.set_value(MOVIE_RECORD_TARGET, WidgetValue::Choice(target))?;
+        } else if config.get("movie").is_some() {
+            config.set_value("movie", WidgetValue::Toggle(false))?;
+        } else {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+            ));
+        }
+
+        self.set_config(&mut config)
+    }
+
+    /// Records a movie of the given duration and downloads it.
+    ///
+    /// This starts recording, waits for `duration`, stops recording, and then waits for the
+    /// camera to report the recorded clip, which is downloaded to `destination`. Returns the
+    /// clip's location on the camera.
+    ///
+    /// Recording is stopped even if waiting fails or panics, so that the camera isn't left
+    /// recording. Events that the camera reports in the meantime, e.g., a still that was captured
+    /// during recording, are kept for `wait_event()`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    ///
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let mut file = gphoto::FileMedia::create_mem().unwrap();
+    ///
+    /// let clip = camera.record_video(Duration::from_secs(10), &mut file).unwrap();
+    /// println!("recorded {} ({} bytes)", clip.basename(), file.get_data().len());
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the movie could not be recorded:
+    ///
+    /// * `NotSupported` if the camera can't record movies remotely.
+    /// * `Timeout` if the camera didn't report a video file in time.
+    ///
+    /// If both waiting and stopping fail, the error of waiting is returned.
+    pub fn record_video<T: Media>(
+        &mut self,
+        duration: Duration,
+        destination: &mut T,
+    ) -> crate::Result<CameraFile> {
+        self.start_recording()?;
+
+        let recording = Recording {
+            camera: self,
+            stopped: false,
+        };
+
+        let waited = recording.camera.wait_recording(duration);
+        let stopped = recording.stop();
+        waited.and(stopped)?;
+
+        let clip = self.wait_for_clip()?;
+        self.download(&clip, destination, None)?;
+
+        Ok(clip)
+    }
+
+    /// Waits while the camera records, keeping the events that it reports for `wait_event()`.
+    fn wait_recording(&mut self, duration: Duration) -> crate::Result<()> {
+        let deadline = Instant::now() + duration;
+
+        loop {
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Ok(());
+            }
+
+            let event = self.receive_event(deadline - now)?;
+            self.queue_event(event);
+        }
+    }
+
+    /// Waits for the camera to report a video file.
+    ///
+    /// Other events, e.g., a still that was captured during recording, are kept for
+    /// `wait_event()`.
+    fn wait_for_clip(&mut self) -> crate::Result<CameraFile> {
+        let deadline = Instant::now() + CLIP_TIMEOUT;
+
+        loop {
+            let now = Instant::now();
+
+            if now >= deadline {
+                break;
+            }
+
+            match self.receive_event(deadline - now)? {
+                CameraEvent::FileAdded(ref file) if is_video(file) => return Ok(file.clone()),
+                event => self.queue_event(event),
+            }
+        }
+
+        Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_TIMEOUT,
+        ))
+    }
+}
+
+/// A movie recording that is stopped when it's dropped, e.g., by a panic.
+struct Recording<'a> {
+    camera: &'a mut Camera,
+    stopped: bool,
+}
+
+impl<'a> Recording<'a> {
+    fn stop(mut self) -> crate::Result<()> {
+        self.stopped = true;
+        self.camera.stop_recording()
+    }
+}
+
+impl<'a> Drop for Recording<'a> {
+    fn drop(&mut self) {
+        if !self.stopped {
+            let _ = self.camera.stop_recording();
+        }
+    }
+}
+
+/// Returns the choice of the movie record target that starts or stops recording.
+fn record_target(config: &Config, recording: bool) -> crate::Result<String> {
+    let choice = config
+        .get(MOVIE_RECORD_TARGET)
+        .into_iter()
+        .flat_map(|widget| widget.choices())
+        .find(|choice| is_no_record_target(choice) != recording);
+
+    match choice {
+        Some(choice) => Ok(choice),
+        None => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        )),
+    }
+}
+
+fn is_no_record_target(choice: &str) -> bool {
+    NO_RECORD_TARGET_CHOICES.contains(&choice.trim().to_ascii_lowercase().as_str())
+}
+
+/// Reads an on/off setting, which is either a toggle or a choice like `"On"` and `"Off"`.
+fn switch(camera: &mut Camera, names: &[&str]) -> crate::Result<bool> {
+    let config = find_config(camera, names)?;
+
+    match config.root().value() {
+        Some(WidgetValue::Toggle(enabled)) => Ok(enabled),
+        Some(WidgetValue::Choice(value)) | Some(WidgetValue::Text(value)) => Ok(!is_off(&value)),
+        _ => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        )),
+    }
+}
+
+/// Changes an on/off setting. Choices are switched on by the first choice that isn't off.
+fn set_switch(camera: &mut Camera, names: &[&str], enabled: bool) -> crate::Result<()> {
+    let mut config = find_config(camera, names)?;
+
+    if let Some(WidgetValue::Toggle(_)) = config.root().value() {
+        let name = config.root().name().into_owned();
+
+        config.set_value(&name, WidgetValue::Toggle(enabled))?;
+        return camera.set_single_config(&mut config);
+    }
+
+    let choice = config
+        .root()
+        .choices()
+        .into_iter()
+        .find(|choice| is_off(choice) != enabled);
+
+    match choice {
+        Some(choice) => set_choice(camera, &mut config, &choice),
+        None => Err(crate::error::from_libgphoto2(
+            crate::gphoto2::GP_ERROR_NOT_SUPPORTED,
+        )),
+    }
+}
+
+fn is_off(value: &str) -> bool {
+    OFF_CHOICES.contains(&value.trim().to_ascii_lowercase().as_str())
+}
+
+fn is_movie_choice(choice: &str) -> bool {
+    let choice = choice.to_ascii_lowercase();
+
+    MOVIE_CHOICES.iter().any(|word| choice.contains(word))
+}
+
+fn is_video(file: &CameraFile) -> bool {
+    let name = file.basename();
+
+    match name.rfind('.') {
+        Some(index) => {
+            let extension = name[index + 1..].to_ascii_lowercase();
+            VIDEO_EXTENSIONS.contains(&extension.as_str())
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::camera::CameraFile;
+use crate::fileinfo::FileInfo;
+use crate::hooks::{Hook, HookError};
+use crate::json::JsonObject;
+
+/// The default timeout of webhook requests.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A notifier that posts capture and download events to a URL.
+///
+/// Each event is sent as a JSON object in the body of a `POST` request:
+///
+/// ```text
+/// {"event":"download","camera":"booth-1","timestamp":1700000000,
+///  "camera_path":"/store_00010001/DCIM/100CANON/IMG_0001.JPG",
+///  "local_path":"photos/IMG_0001.JPG","size":6291456,"mtime":1699999990}
+/// ```
+///
+/// Timestamps are seconds since the Unix epoch. Fields that aren't known are `null`.
+///
+/// `Webhook` is a `Hook`, which notifies about downloads when it is added to a pipeline of
+/// post-download hooks. It is only available with the `webhook` feature.
+///
+/// ## Example
+///
+/// ```no_run
+/// use gphoto::Webhook;
+///
+/// let webhook = Webhook::new("http://automation.local/camera-events").camera("booth-1");
+///
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let file = camera.capture_image().unwrap();
+///
+/// webhook.capture(&file).unwrap();
+/// ```
+pub struct Webhook {
+    url: String,
+    camera: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl Webhook {
+    /// Creates a notifier that posts to a URL.
+    pub fn new(url: &str) -> Self {
+        Webhook {
+            url: url.to_owned(),
+            camera: None,
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+        }
+    }
+
+    /// Sets the camera identity that is included in every event.
+    pub fn camera(mut self, identity: &str) -> Self {
+        self.camera = Some(identity.to_owned());
+        self
+    }
+
+    /// Posts a capture event.
+    pub fn capture(&self, file: &CameraFile) -> Result<(), HookError> {
+        let event = self
+            .event("capture")
+            .string("camera_path", &file.path())
+            .finish();
+
+        self.post(&event)
+    }
+
+    /// Posts a download event.
+    pub fn download(&self, path: &Path, info: &FileInfo) -> Result<(), HookError> {
+        let event = self
+            .event("download")
+            .string("camera_path", &info.file().path())
+            .string("local_path", &path.to_string_lossy())
+            .optional_number("size", info.size())
+            .optional_number("mtime", info.mtime().map(unix_seconds))
+            .finish();
+
+        self.post(&event)
+    }
+
+    fn event(&self, event: &str) -> JsonObject {
+        JsonObject::new()
+            .string("event", event)
+            .optional_string("camera", self.camera.as_deref())
+            .number("timestamp", unix_seconds(SystemTime::now()))
+    }
+
+    fn post(&self, event: &str) -> Result<(), HookError> {
+        self.agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(event)?;
+
+        Ok(())
+    }
+}
+
+impl Hook for Webhook {
+    fn run(&mut self, path: &Path, info: &FileInfo) -> Result<(), HookError> {
+        self.download(path, info)
+    }
+
+    fn name(&self) -> &str {
+        "webhook"
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
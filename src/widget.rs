@@ -0,0 +1,787 @@
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use cstr_core::CString;
+use libc::{c_char, c_float, c_int, c_void};
+
+use crate::handle::{Handle, HandleMut};
+
+/// A camera's configuration.
+///
+/// The configuration is a tree of widgets. The root of the tree is a window, which contains
+/// sections, which in turn contain the widgets for the camera's settings. Widgets are usually
+/// looked up by name.
+///
+/// Some cameras have widgets with the same name in different sections. These are told apart by
+/// their path, which names the sections that lead to the widget, e.g., `/main/imgsettings/iso`.
+/// Every function that looks up widgets by name also accepts paths.
+///
+/// Changes made to a `Config` are only applied to the camera by `Camera::set_config()`.
+///
+/// ## Example
+///
+/// ```no_run
+/// let mut camera = gphoto::Camera::autodetect().unwrap();
+/// let mut config = camera.config().unwrap();
+///
+/// if let Some(iso) = config.get("iso") {
+///     println!("iso = {:?}", iso.value());
+///     println!("choices = {:?}", iso.choices());
+/// }
+///
+/// config.set_value("iso", gphoto::WidgetValue::Choice("800".to_owned())).unwrap();
+/// camera.set_config(&mut config).unwrap();
+/// ```
+pub struct Config {
+    root: *mut crate::gphoto2::CameraWidget,
+}
+
+impl Drop for Config {
+    fn drop(&mut self) {
+        unsafe {
+            crate::gphoto2::gp_widget_free(self.root);
+        }
+    }
+}
+
+impl Config {
+    /// Returns the root widget of the configuration tree.
+    pub fn root(&self) -> Widget<'_> {
+        from_libgphoto2(self.root)
+    }
+
+    /// Looks up a widget by name or path.
+    ///
+    /// The whole tree is searched for a name, including the root widget. If several widgets have
+    /// the same name, the first one is returned.
+    ///
+    /// A path starting with `/` is resolved from the root widget, whose name is the first part of
+    /// the path, e.g., `/main/imgsettings/iso`. A path without a leading `/` is resolved from the
+    /// root's children, e.g., `imgsettings/iso`.
+    pub fn get(&self, name: &str) -> Option<Widget<'_>> {
+        if name.contains('/') {
+            return self.get_path(name);
+        }
+
+        let root = self.root();
+
+        if root.name() == name {
+            return Some(root);
+        }
+
+        let name = CString::new(name).ok()?;
+        let mut ptr = MaybeUninit::uninit();
+
+        unsafe {
+            match crate::gphoto2::gp_widget_get_child_by_name(
+                self.root,
+                name.as_ptr(),
+                &mut *ptr.as_mut_ptr(),
+            ) {
+                crate::gphoto2::GP_OK => Some(from_libgphoto2(ptr.assume_init())),
+                _ => None,
+            }
+        }
+    }
+
+    fn get_path(&self, path: &str) -> Option<Widget<'_>> {
+        let mut parts = path.split('/').filter(|part| !part.is_empty());
+        let mut widget = self.root();
+
+        if path.starts_with('/') && parts.next()? != widget.name() {
+            return None;
+        }
+
+        for part in parts {
+            widget = widget
+                .children()
+                .into_iter()
+                .find(|child| child.name() == part)?;
+        }
+
+        Some(widget)
+    }
+
+    /// Looks up a widget by its numeric ID.
+    ///
+    /// IDs are assigned by the driver and are only stable while the configuration is in use, so
+    /// they are useful to refer to widgets from a UI that was built from this configuration.
+    pub fn get_by_id(&self, id: i32) -> Option<Widget<'_>> {
+        self.widgets().into_iter().find(|widget| widget.id() == id)
+    }
+
+    /// Looks up a widget by name, path or label, tolerating differences in spelling.
+    ///
+    /// Drivers name the same setting differently, e.g., `"shutterspeed"` and `"shutterspeed2"`,
+    /// or `"Shutter Speed"` as a label. The candidates are tried in this order, and the first
+    /// widget that matches is returned:
+    ///
+    /// 1. a widget with that name or path, as with `get()`,
+    /// 2. a widget whose name or label matches, ignoring case,
+    /// 3. a widget whose name or label matches, ignoring case, spaces and punctuation,
+    /// 4. the widget with the shortest name or label that starts with the query, ignoring case,
+    ///    spaces and punctuation.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// let mut camera = gphoto::Camera::autodetect().unwrap();
+    /// let config = camera.config().unwrap();
+    ///
+    /// if let Some(shutter) = config.find("Shutter Speed") {
+    ///     println!("{} = {:?}", shutter.name(), shutter.value());
+    /// }
+    /// ```
+    pub fn find(&self, query: &str) -> Option<Widget<'_>> {
+        if let Some(widget) = self.get(query) {
+            return Some(widget);
+        }
+
+        let mut widgets = self.widgets();
+
+        if let Some(index) = widgets.iter().position(|widget| {
+            widget.name().eq_ignore_ascii_case(query) || widget.label().eq_ignore_ascii_case(query)
+        }) {
+            return Some(widgets.swap_remove(index));
+        }
+
+        let query = normalize(query);
+
+        if query.is_empty() {
+            return None;
+        }
+
+        if let Some(index) = widgets.iter().position(|widget| {
+            normalize(&widget.name()) == query || normalize(&widget.label()) == query
+        }) {
+            return Some(widgets.swap_remove(index));
+        }
+
+        widgets
+            .into_iter()
+            .filter_map(|widget| {
+                let length = [normalize(&widget.name()), normalize(&widget.label())]
+                    .iter()
+                    .filter(|candidate| candidate.starts_with(query.as_str()))
+                    .map(|candidate| candidate.len())
+                    .min()?;
+
+                Some((length, widget))
+            })
+            .min_by_key(|&(length, _)| length)
+            .map(|(_, widget)| widget)
+    }
+
+    /// Returns all widgets in the tree, in depth-first order, starting with the root widget.
+    pub fn widgets(&self) -> Vec<Widget<'_>> {
+        let mut widgets = Vec::new();
+        let mut pending = vec![self.root()];
+
+        while let Some(widget) = pending.pop() {
+            pending.extend(widget.children().into_iter().rev());
+            widgets.push(widget);
+        }
+
+        widgets
+    }
+
+    /// Returns the current value of the named widget.
+    ///
+    /// Returns `None` if there is no widget with that name or if the widget doesn't have a value.
+    pub fn value(&self, name: &str) -> Option<WidgetValue> {
+        self.get(name).and_then(|widget| widget.value())
+    }
+
+    /// Changes the value of the named widget.
+    ///
+    /// The widget is marked as changed, so that the new value is sent to the camera by
+    /// `Camera::set_config()`.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the value could not be changed:
+    ///
+    /// * `InvalidInput` if there is no widget with that name or if the value doesn't match the
+    ///   widget's type.
+    pub fn set_value(&mut self, name: &str, value: WidgetValue) -> crate::Result<()> {
+        let widget = match self.get(name) {
+            Some(widget) => widget.ptr,
+            None => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        };
+
+        set_widget_value(widget, &value)
+    }
+
+    /// Updates the named widget's value without marking it as changed.
+    pub(crate) fn update_value(&mut self, name: &str, value: &WidgetValue) -> crate::Result<()> {
+        let widget = match self.get(name) {
+            Some(widget) => widget.ptr,
+            None => {
+                return Err(crate::error::from_libgphoto2(
+                    crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                ))
+            }
+        };
+
+        set_widget_value(widget, value)?;
+
+        unsafe {
+            crate::gphoto2::gp_widget_set_changed(widget, 0);
+        }
+
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+impl Handle<crate::gphoto2::CameraWidget> for Config {
+    unsafe fn as_ptr(&self) -> *const crate::gphoto2::CameraWidget {
+        self.root
+    }
+}
+
+#[doc(hidden)]
+impl HandleMut<crate::gphoto2::CameraWidget> for Config {
+    unsafe fn as_mut_ptr(&mut self) -> *mut crate::gphoto2::CameraWidget {
+        self.root
+    }
+}
+
+/// A widget in a camera's configuration tree.
+pub struct Widget<'a> {
+    // The widget is owned by the tree of the Config that it was retrieved from.
+    ptr: *mut crate::gphoto2::CameraWidget,
+    __phantom: PhantomData<&'a Config>,
+}
+
+impl<'a> Widget<'a> {
+    /// Returns the widget's name.
+    ///
+    /// The name identifies the widget, e.g., `"iso"` or `"shutterspeed"`.
+    pub fn name(&self) -> Cow<'a, str> {
+        let mut name = MaybeUninit::uninit();
+
+        unsafe {
+            match crate::gphoto2::gp_widget_get_name(self.ptr, &mut *name.as_mut_ptr()) {
+                crate::gphoto2::GP_OK => util::to_cow(name.assume_init()),
+                _ => Cow::Borrowed(""),
+            }
+        }
+    }
+
+    /// Returns the widget's human-readable label.
+    pub fn label(&self) -> Cow<'a, str> {
+        let mut label = MaybeUninit::uninit();
+
+        unsafe {
+            match crate::gphoto2::gp_widget_get_label(self.ptr, &mut *label.as_mut_ptr()) {
+                crate::gphoto2::GP_OK => util::to_cow(label.assume_init()),
+                _ => Cow::Borrowed(""),
+            }
+        }
+    }
+
+    /// Returns the widget's help text.
+    pub fn info(&self) -> Cow<'a, str> {
+        let mut info = MaybeUninit::uninit();
+
+        unsafe {
+            match crate::gphoto2::gp_widget_get_info(self.ptr, &mut *info.as_mut_ptr()) {
+                crate::gphoto2::GP_OK => util::to_cow(info.assume_init()),
+                _ => Cow::Borrowed(""),
+            }
+        }
+    }
+
+    /// Returns the widget's numeric ID.
+    pub fn id(&self) -> i32 {
+        let mut id: c_int = 0;
+
+        unsafe {
+            crate::gphoto2::gp_widget_get_id(self.ptr, &mut id);
+        }
+
+        id as i32
+    }
+
+    /// Returns the type of the widget.
+    pub fn widget_type(&self) -> WidgetType {
+        widget_type(self.ptr)
+    }
+
+    /// Returns `true` if the widget's value can not be changed.
+    pub fn is_readonly(&self) -> bool {
+        let mut readonly: c_int = 0;
+
+        unsafe {
+            crate::gphoto2::gp_widget_get_readonly(self.ptr, &mut readonly);
+        }
+
+        readonly != 0
+    }
+
+    /// Returns the widget's current value.
+    ///
+    /// Returns `None` for widgets that don't have a value, such as windows, sections, and buttons.
+    pub fn value(&self) -> Option<WidgetValue> {
+        unsafe {
+            match self.widget_type() {
+                WidgetType::Text | WidgetType::Radio | WidgetType::Menu => {
+                    let mut value: *const c_char = core::ptr::null();
+
+                    if crate::gphoto2::gp_widget_get_value(
+                        self.ptr,
+                        &mut value as *mut _ as *mut c_void,
+                    ) != crate::gphoto2::GP_OK
+                        || value.is_null()
+                    {
+                        return None;
+                    }
+
+                    let value = util::to_cow(value).into_owned();
+
+                    if self.widget_type() == WidgetType::Text {
+                        Some(WidgetValue::Text(value))
+                    } else {
+                        Some(WidgetValue::Choice(value))
+                    }
+                }
+                WidgetType::Range => {
+                    let mut value: c_float = 0.0;
+
+                    match crate::gphoto2::gp_widget_get_value(
+                        self.ptr,
+                        &mut value as *mut _ as *mut c_void,
+                    ) {
+                        crate::gphoto2::GP_OK => Some(WidgetValue::Range(value as f32)),
+                        _ => None,
+                    }
+                }
+                WidgetType::Toggle => {
+                    let mut value: c_int = 0;
+
+                    match crate::gphoto2::gp_widget_get_value(
+                        self.ptr,
+                        &mut value as *mut _ as *mut c_void,
+                    ) {
+                        crate::gphoto2::GP_OK => Some(WidgetValue::Toggle(value != 0)),
+                        _ => None,
+                    }
+                }
+                WidgetType::Date => {
+                    let mut value: c_int = 0;
+
+                    match crate::gphoto2::gp_widget_get_value(
+                        self.ptr,
+                        &mut value as *mut _ as *mut c_void,
+                    ) {
+                        crate::gphoto2::GP_OK => Some(WidgetValue::Date(value as i64)),
+                        _ => None,
+                    }
+                }
+                WidgetType::Window | WidgetType::Section | WidgetType::Button => None,
+            }
+        }
+    }
+
+    /// Returns the choices of a radio or menu widget.
+    ///
+    /// Returns an empty `Vec` for other types of widgets.
+    pub fn choices(&self) -> Vec<String> {
+        let count = unsafe { crate::gphoto2::gp_widget_count_choices(self.ptr) };
+
+        (0..count.max(0))
+            .filter_map(|i| {
+                let mut choice = MaybeUninit::uninit();
+
+                unsafe {
+                    match crate::gphoto2::gp_widget_get_choice(
+                        self.ptr,
+                        i,
+                        &mut *choice.as_mut_ptr(),
+                    ) {
+                        crate::gphoto2::GP_OK => {
+                            Some(util::to_cow(choice.assume_init()).into_owned())
+                        }
+                        _ => None,
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the minimum, maximum, and increment of a range widget.
+    ///
+    /// Returns `None` for other types of widgets.
+    pub fn range(&self) -> Option<(f32, f32, f32)> {
+        if self.widget_type() != WidgetType::Range {
+            return None;
+        }
+
+        let mut min: c_float = 0.0;
+        let mut max: c_float = 0.0;
+        let mut increment: c_float = 0.0;
+
+        unsafe {
+            match crate::gphoto2::gp_widget_get_range(self.ptr, &mut min, &mut max, &mut increment)
+            {
+                crate::gphoto2::GP_OK => Some((min as f32, max as f32, increment as f32)),
+                _ => None,
+            }
+        }
+    }
+
+    /// Returns the widget's children.
+    pub fn children(&self) -> Vec<Widget<'a>> {
+        let count = unsafe { crate::gphoto2::gp_widget_count_children(self.ptr) };
+
+        (0..count.max(0))
+            .filter_map(|i| {
+                let mut child = MaybeUninit::uninit();
+
+                unsafe {
+                    match crate::gphoto2::gp_widget_get_child(self.ptr, i, &mut *child.as_mut_ptr())
+                    {
+                        crate::gphoto2::GP_OK => Some(from_libgphoto2(child.assume_init())),
+                        _ => None,
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Types of configuration widgets.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WidgetType {
+    /// The root of a configuration tree.
+    Window,
+
+    /// A group of related widgets.
+    Section,
+
+    /// A text value.
+    Text,
+
+    /// A number within a range.
+    Range,
+
+    /// An on/off switch.
+    Toggle,
+
+    /// A choice among a list of values, usually displayed as radio buttons.
+    Radio,
+
+    /// A choice among a list of values, usually displayed as a drop-down menu.
+    Menu,
+
+    /// An action that is triggered when the widget's value is set.
+    Button,
+
+    /// A date and time.
+    Date,
+}
+
+/// The value of a configuration widget.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WidgetValue {
+    /// The value of a text widget.
+    Text(String),
+
+    /// The value of a range widget.
+    Range(f32),
+
+    /// The value of a toggle widget.
+    Toggle(bool),
+
+    /// The selected choice of a radio or menu widget.
+    Choice(String),
+
+    /// The value of a date widget, in seconds since the Unix epoch.
+    Date(i64),
+}
+
+fn widget_type(ptr: *mut crate::gphoto2::CameraWidget) -> WidgetType {
+    let mut widget_type = MaybeUninit::uninit();
+
+    let widget_type = unsafe {
+        assert_eq!(
+            crate::gphoto2::GP_OK,
+            crate::gphoto2::gp_widget_get_type(ptr, &mut *widget_type.as_mut_ptr())
+        );
+        widget_type.assume_init()
+    };
+
+    match widget_type {
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_WINDOW => WidgetType::Window,
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_SECTION => WidgetType::Section,
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_TEXT => WidgetType::Text,
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_RANGE => WidgetType::Range,
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_TOGGLE => WidgetType::Toggle,
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_RADIO => WidgetType::Radio,
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_MENU => WidgetType::Menu,
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_BUTTON => WidgetType::Button,
+        crate::gphoto2::CameraWidgetType::GP_WIDGET_DATE => WidgetType::Date,
+    }
+}
+
+fn set_widget_value(
+    ptr: *mut crate::gphoto2::CameraWidget,
+    value: &WidgetValue,
+) -> crate::Result<()> {
+    match (widget_type(ptr), value) {
+        (WidgetType::Text, WidgetValue::Text(s))
+        | (WidgetType::Text, WidgetValue::Choice(s))
+        | (WidgetType::Radio, WidgetValue::Choice(s))
+        | (WidgetType::Radio, WidgetValue::Text(s))
+        | (WidgetType::Menu, WidgetValue::Choice(s))
+        | (WidgetType::Menu, WidgetValue::Text(s)) => {
+            let value = match CString::new(s.as_str()) {
+                Ok(value) => value,
+                Err(_) => {
+                    return Err(crate::error::from_libgphoto2(
+                        crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+                    ))
+                }
+            };
+
+            try_unsafe!(crate::gphoto2::gp_widget_set_value(
+                ptr,
+                value.as_ptr() as *const c_void
+            ));
+        }
+        (WidgetType::Range, WidgetValue::Range(n)) => {
+            let value = *n as c_float;
+
+            try_unsafe!(crate::gphoto2::gp_widget_set_value(
+                ptr,
+                &value as *const _ as *const c_void
+            ));
+        }
+        (WidgetType::Toggle, WidgetValue::Toggle(b)) => {
+            let value = *b as c_int;
+
+            try_unsafe!(crate::gphoto2::gp_widget_set_value(
+                ptr,
+                &value as *const _ as *const c_void
+            ));
+        }
+        (WidgetType::Date, WidgetValue::Date(t)) => {
+            let value = *t as c_int;
+
+            try_unsafe!(crate::gphoto2::gp_widget_set_value(
+                ptr,
+                &value as *const _ as *const c_void
+            ));
+        }
+        _ => {
+            return Err(crate::error::from_libgphoto2(
+                crate::gphoto2::GP_ERROR_BAD_PARAMETERS,
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+/// Lowercases a name or label and removes everything but letters and digits.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Returns the name of the widget that a name or path refers to.
+pub(crate) fn leaf_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[doc(hidden)]
+pub fn from_libgphoto2<'a>(ptr: *mut crate::gphoto2::CameraWidget) -> Widget<'a> {
+    Widget {
+        ptr,
+        __phantom: PhantomData,
+    }
+}
+
+#[doc(hidden)]
+pub fn config_from_libgphoto2(root: *mut crate::gphoto2::CameraWidget) -> Config {
+    Config { root }
+}
+
+mod util {
+    #[cfg(not(feature = "std"))]
+    use alloc::borrow::Cow;
+    #[cfg(feature = "std")]
+    use std::borrow::Cow;
+
+    use cstr_core::CStr;
+    use libc::c_char;
+
+    pub unsafe fn to_cow<'a>(ptr: *const c_char) -> Cow<'a, str> {
+        if ptr.is_null() {
+            Cow::Borrowed("")
+        } else {
+            String::from_utf8_lossy(CStr::from_ptr(ptr).to_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::gphoto2::CameraWidgetType::{
+        GP_WIDGET_RADIO, GP_WIDGET_SECTION, GP_WIDGET_TEXT, GP_WIDGET_WINDOW,
+    };
+
+    /// Creates a widget with a name and a label, and appends it to a parent.
+    fn widget(
+        parent: *mut crate::gphoto2::CameraWidget,
+        kind: crate::gphoto2::CameraWidgetType,
+        name: &str,
+        label: &str,
+    ) -> *mut crate::gphoto2::CameraWidget {
+        let name = CString::new(name).unwrap();
+        let label = CString::new(label).unwrap();
+        let mut ptr = MaybeUninit::uninit();
+
+        unsafe {
+            assert_eq!(
+                crate::gphoto2::gp_widget_new(kind, label.as_ptr(), &mut *ptr.as_mut_ptr()),
+                crate::gphoto2::GP_OK
+            );
+
+            let widget = ptr.assume_init();
+            crate::gphoto2::gp_widget_set_name(widget, name.as_ptr());
+
+            if !parent.is_null() {
+                crate::gphoto2::gp_widget_append(parent, widget);
+            }
+
+            widget
+        }
+    }
+
+    /// Builds a configuration like the ones of PTP cameras, with an `iso` widget in two sections.
+    fn config() -> Config {
+        let root = widget(
+            core::ptr::null_mut(),
+            GP_WIDGET_WINDOW,
+            "main",
+            "Camera and Driver Configuration",
+        );
+
+        let settings = widget(root, GP_WIDGET_SECTION, "settings", "Camera Settings");
+        widget(settings, GP_WIDGET_TEXT, "artist", "Artist");
+
+        let image = widget(root, GP_WIDGET_SECTION, "imgsettings", "Image Settings");
+        widget(image, GP_WIDGET_RADIO, "iso", "ISO Speed");
+        widget(image, GP_WIDGET_RADIO, "imageformat", "Image Format");
+
+        let capture = widget(
+            root,
+            GP_WIDGET_SECTION,
+            "capturesettings",
+            "Capture Settings",
+        );
+        widget(capture, GP_WIDGET_RADIO, "shutterspeed2", "Shutter Speed 2");
+        widget(capture, GP_WIDGET_RADIO, "shutterspeed", "Shutter Speed");
+        widget(capture, GP_WIDGET_RADIO, "f-number", "F-Number");
+
+        let other = widget(
+            root,
+            GP_WIDGET_SECTION,
+            "other",
+            "Other PTP Device Properties",
+        );
+        widget(other, GP_WIDGET_RADIO, "iso", "ISO");
+
+        config_from_libgphoto2(root)
+    }
+
+    #[test]
+    fn resolves_paths() {
+        let config = config();
+
+        let cases = [
+            ("/main", Some("Camera and Driver Configuration")),
+            ("/main/imgsettings/iso", Some("ISO Speed")),
+            ("/main/other/iso", Some("ISO")),
+            ("/main/imgsettings/iso/", Some("ISO Speed")),
+            ("imgsettings/iso", Some("ISO Speed")),
+            ("other/iso", Some("ISO")),
+            // The root's name is only part of absolute paths.
+            ("main/imgsettings/iso", None),
+            ("/other/iso", None),
+            ("/imgsettings/iso", None),
+            ("/main/imgsettings/shutterspeed", None),
+            ("/main/missing", None),
+        ];
+
+        for &(path, label) in cases.iter() {
+            let found = config.get(path).map(|widget| widget.label().into_owned());
+            assert_eq!(found.as_deref(), label, "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn finds_widgets() {
+        let config = config();
+
+        let cases = [
+            // Names and paths.
+            ("shutterspeed", Some("shutterspeed")),
+            ("other/iso", Some("iso")),
+            // Names and labels, ignoring case.
+            ("ShutterSpeed2", Some("shutterspeed2")),
+            ("image format", Some("imageformat")),
+            ("SHUTTER SPEED", Some("shutterspeed")),
+            // Names and labels, ignoring case, spaces and punctuation.
+            ("fnumber", Some("f-number")),
+            ("F Number", Some("f-number")),
+            ("Shutter-Speed-2", Some("shutterspeed2")),
+            // The shortest name or label with the query as a prefix.
+            ("shutter", Some("shutterspeed")),
+            ("Image", Some("imageformat")),
+            ("art", Some("artist")),
+            ("zoom", None),
+            ("", None),
+            ("--", None),
+        ];
+
+        for &(query, name) in cases.iter() {
+            let found = config.find(query).map(|widget| widget.name().into_owned());
+            assert_eq!(found.as_deref(), name, "{:?}", query);
+        }
+
+        let iso = config.find("iso speed").unwrap();
+        assert_eq!(iso.label(), "ISO Speed");
+    }
+
+    #[test]
+    fn normalizes_names() {
+        let cases = [
+            ("shutterspeed", "shutterspeed"),
+            ("Shutter Speed", "shutterspeed"),
+            ("F-Number", "fnumber"),
+            ("ISO (Auto)", "isoauto"),
+            ("White Balance 2", "whitebalance2"),
+            ("\u{c9}clairage", "\u{e9}clairage"),
+            (" - ", ""),
+        ];
+
+        for &(name, normalized) in cases.iter() {
+            assert_eq!(normalize(name), normalized, "{:?}", name);
+        }
+    }
+}